@@ -0,0 +1,26 @@
+//! Build script
+//!
+//! Embeds the git commit the binary was built from as `SAFELYNX_GIT_HASH`.
+//! Builds outside a checkout, e.g. in a container, can pass it through the
+//! environment instead.
+
+use std::process::Command;
+
+fn main() {
+    println!("cargo:rerun-if-env-changed=SAFELYNX_GIT_HASH");
+    println!("cargo:rerun-if-changed=../.git/HEAD");
+    println!("cargo:rerun-if-changed=../.git/refs/heads");
+
+    let hash = std::env::var("SAFELYNX_GIT_HASH").ok().or_else(|| {
+        Command::new("git")
+            .args(["rev-parse", "--short", "HEAD"])
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .and_then(|output| String::from_utf8(output.stdout).ok())
+    });
+
+    if let Some(hash) = hash {
+        println!("cargo:rustc-env=SAFELYNX_GIT_HASH={}", hash.trim());
+    }
+}
@@ -0,0 +1,4 @@
+-- Migration: 003_add_thumbnail_quality
+-- Stores the quality score of each profile's thumbnail so better crops can replace it
+
+ALTER TABLE profiles ADD COLUMN IF NOT EXISTS thumbnail_quality REAL;
@@ -0,0 +1,5 @@
+-- Migration: 004_add_settings_version
+-- Adds a version counter so concurrent settings updates cannot overwrite each other
+
+ALTER TABLE settings ADD COLUMN IF NOT EXISTS version BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE settings ADD COLUMN IF NOT EXISTS created_at TIMESTAMPTZ NOT NULL DEFAULT NOW();
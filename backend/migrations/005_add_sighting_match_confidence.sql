@@ -0,0 +1,4 @@
+-- Migration: 005_add_sighting_match_confidence
+-- Stores how confidently a sighting's face was matched to its profile
+
+ALTER TABLE sightings ADD COLUMN IF NOT EXISTS match_confidence REAL;
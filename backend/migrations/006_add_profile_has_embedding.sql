@@ -0,0 +1,9 @@
+-- Migration: 006_add_profile_has_embedding
+-- Marks profiles created without a face embedding so they are left out of matching
+
+ALTER TABLE profiles ADD COLUMN IF NOT EXISTS has_embedding BOOLEAN NOT NULL DEFAULT TRUE;
+
+-- Placeholder embeddings are stored as all-zero bytes
+UPDATE profiles
+SET has_embedding = FALSE
+WHERE embedding = decode(repeat('00', length(embedding)), 'hex');
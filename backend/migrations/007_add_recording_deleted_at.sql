@@ -0,0 +1,5 @@
+-- Migration: 007_add_recording_deleted_at
+-- Keeps deleted recordings restorable for a grace period before their files are purged
+
+ALTER TABLE recordings ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMPTZ;
+ALTER TABLE recordings ADD COLUMN IF NOT EXISTS status_before_deletion recording_status;
@@ -0,0 +1,12 @@
+-- Migration: 008_add_profile_embeddings
+-- Extra face samples per profile, so matching copes with changes in lighting and pose
+
+CREATE TABLE IF NOT EXISTS profile_embeddings (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    profile_id UUID NOT NULL REFERENCES profiles(id) ON DELETE CASCADE,
+    position INT NOT NULL,
+    embedding BYTEA NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_profile_embeddings_profile ON profile_embeddings(profile_id, position);
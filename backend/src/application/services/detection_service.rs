@@ -3,13 +3,24 @@
 //! Orchestrates face detection, embedding extraction, and profile matching.
 
 use chrono::Utc;
+use image::{DynamicImage, ImageFormat, ImageResult};
+use serde::{Deserialize, Serialize};
+use std::io::Cursor;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
-use tokio::sync::RwLock;
-use tracing::{debug, info};
+use std::time::{Duration, Instant};
+use tokio::sync::{Mutex, RwLock};
+use tracing::{debug, info, warn};
 use uuid::Uuid;
 
-use crate::application::services::{EventBus, FaceMatcher};
-use crate::domain::entities::{FrameDetections, Profile, Sighting};
+use crate::application::services::{
+    decode_jpeg_upright, face_quality, EventBus, FaceMatcher, ProfileClassifier,
+};
+use crate::domain::entities::{
+    DetectionSettings, FrameDetections, Profile, ProfileClassification, Recording, Sighting,
+    SightingCooldownScope,
+};
 use crate::domain::events::{
     DomainEvent, FaceDetectedEvent, ProfileCreatedEvent, ProfileSightedEvent,
 };
@@ -25,6 +36,29 @@ pub struct DetectionConfig {
     pub match_threshold: f32,
     /// Cooldown between sightings of the same profile (seconds).
     pub sighting_cooldown_secs: i64,
+    /// Whether the cooldown is shared across cameras or kept per camera.
+    pub sighting_cooldown_scope: SightingCooldownScope,
+    /// Detections with a smaller bounding box area (pixels) are skipped before
+    /// matching; 0 keeps all. Independent of the detector's minimum face size.
+    pub min_face_area_px: u32,
+    /// New profiles allowed per camera per minute; excess faces are dropped.
+    pub max_new_profiles_per_minute: u32,
+    /// Sightings recorded per camera per minute; excess sightings are dropped.
+    pub max_sightings_per_minute: u32,
+    /// Image format thumbnails and snapshots are written in.
+    pub snapshot_format: SnapshotFormat,
+    /// Fraction the face box is grown by before cropping a thumbnail, so
+    /// foreheads and chins are kept. `0.0` crops to the box exactly.
+    pub thumbnail_margin: f32,
+    /// Faces are detected, matched and published, but no profiles, sightings
+    /// or recordings are created. For tuning detector thresholds.
+    pub detection_only: bool,
+    /// IoU a face's box must have with a track's predicted box to be
+    /// attached to the track's profile rather than creating a new one.
+    pub track_iou_threshold: f32,
+    /// How long a face track outlives the last frame it was seen in
+    /// (milliseconds); 0 disables tracking.
+    pub track_lifetime_ms: u64,
 }
 
 impl Default for DetectionConfig {
@@ -33,36 +67,403 @@ impl Default for DetectionConfig {
             min_confidence: 0.7,
             match_threshold: 0.6,
             sighting_cooldown_secs: 30,
+            sighting_cooldown_scope: SightingCooldownScope::default(),
+            min_face_area_px: 0,
+            max_new_profiles_per_minute: 30,
+            max_sightings_per_minute: 120,
+            snapshot_format: SnapshotFormat::default(),
+            thumbnail_margin: DEFAULT_THUMBNAIL_MARGIN,
+            detection_only: false,
+            track_iou_threshold: DEFAULT_TRACK_IOU_THRESHOLD,
+            track_lifetime_ms: DEFAULT_TRACK_LIFETIME_MS,
+        }
+    }
+}
+
+impl DetectionConfig {
+    /// Returns this config with the values tunable from persisted settings
+    /// replaced by `settings`.
+    pub fn with_settings(self, settings: &DetectionSettings) -> Self {
+        Self {
+            min_confidence: settings.min_confidence,
+            match_threshold: settings.match_threshold,
+            sighting_cooldown_secs: settings.sighting_cooldown_secs as i64,
+            sighting_cooldown_scope: settings.sighting_cooldown_scope,
+            min_face_area_px: settings.min_face_area_px,
+            detection_only: settings.detection_only,
+            ..self
+        }
+    }
+}
+
+/// JPEG quality used unless configured, matching the image crate's encoder.
+pub const DEFAULT_JPEG_QUALITY: u8 = 75;
+
+/// Image format for face thumbnails and snapshots.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "format", rename_all = "lowercase")]
+pub enum SnapshotFormat {
+    /// Lossy JPEG at a quality from 1 (smallest) to 100 (best).
+    Jpeg { quality: u8 },
+    /// Lossless WebP.
+    WebP,
+    /// Lossless PNG.
+    Png,
+}
+
+impl Default for SnapshotFormat {
+    fn default() -> Self {
+        Self::Jpeg {
+            quality: DEFAULT_JPEG_QUALITY,
+        }
+    }
+}
+
+impl SnapshotFormat {
+    /// Parses a format name (`jpeg`, `webp` or `png`); `quality` applies to JPEG.
+    pub fn from_name(name: &str, quality: u8) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "jpeg" | "jpg" => Some(Self::Jpeg {
+                quality: quality.clamp(1, 100),
+            }),
+            "webp" => Some(Self::WebP),
+            "png" => Some(Self::Png),
+            _ => None,
+        }
+    }
+
+    /// File extension for images in this format.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Jpeg { .. } => "jpg",
+            Self::WebP => "webp",
+            Self::Png => "png",
+        }
+    }
+
+    /// Encodes an image in this format.
+    pub fn encode(&self, img: &DynamicImage) -> ImageResult<Vec<u8>> {
+        let mut buffer = Cursor::new(Vec::new());
+        match *self {
+            Self::Jpeg { quality } => {
+                // JPEG has no alpha channel
+                let rgb = DynamicImage::ImageRgb8(img.to_rgb8());
+                rgb.write_with_encoder(image::codecs::jpeg::JpegEncoder::new_with_quality(
+                    &mut buffer,
+                    quality,
+                ))?
+            }
+            Self::WebP => img.write_to(&mut buffer, ImageFormat::WebP)?,
+            Self::Png => img.write_to(&mut buffer, ImageFormat::Png)?,
+        }
+        Ok(buffer.into_inner())
+    }
+}
+
+/// Token bucket refilled continuously at `per_minute / 60` tokens a second.
+struct TokenBucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    fn new(per_minute: u32, now: Instant) -> Self {
+        Self {
+            tokens: per_minute as f64,
+            last_refill: now,
+        }
+    }
+
+    fn try_take(&mut self, per_minute: u32, now: Instant) -> bool {
+        let capacity = per_minute as f64;
+        let elapsed = now
+            .saturating_duration_since(self.last_refill)
+            .as_secs_f64();
+        self.tokens = (self.tokens + elapsed * capacity / 60.0).min(capacity);
+        self.last_refill = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// What a processed frame's detections resolved to.
+#[derive(Debug, Clone, Default)]
+pub struct FrameOutcome {
+    /// Profiles created for faces seen for the first time.
+    pub created_profiles: Vec<Uuid>,
+    /// Existing profiles that were recognised, each listed once.
+    pub matched_profiles: Vec<Uuid>,
+    /// One entry per detection that resolved to a profile, in frame order.
+    /// Detections skipped for low confidence, size or rate limits have none.
+    pub detections: Vec<DetectionResult>,
+}
+
+/// The profile one detection resolved to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DetectionResult {
+    /// Index of the detection in the frame.
+    pub index: usize,
+    pub profile_id: Uuid,
+    /// Whether the profile was created for this detection.
+    pub is_new: bool,
+    /// Confidence of the match; 0 for new profiles.
+    pub match_confidence: f32,
+}
+
+/// The profile a detection resolved to, keyed by its index in the frame.
+struct DetectionOutcome {
+    index: usize,
+    profile_id: Uuid,
+    profile_name: Option<String>,
+    classification: ProfileClassification,
+    is_new: bool,
+    distance: f32,
+    /// Confidence of the match to an existing profile; unused for new ones.
+    match_confidence: f32,
+    /// Embedding of the detected face, if one was extracted.
+    embedding: Option<FaceEmbedding>,
+    bbox: BoundingBox,
+    confidence: f32,
+}
+
+/// Matches at least this confident may add their embedding as another
+/// sample of the profile.
+const SAMPLE_MIN_CONFIDENCE: f32 = 0.5;
+
+/// A new sample must be this far from the profile's others, as a fraction of
+/// the match threshold, so samples cover different lighting and poses rather
+/// than repeating one.
+const SAMPLE_MIN_SPREAD: f32 = 0.25;
+
+/// How much a crop's quality must beat the current thumbnail to replace it.
+const THUMBNAIL_UPGRADE_MARGIN: f32 = 0.05;
+
+/// Thumbnail crop margin used unless configured.
+pub const DEFAULT_THUMBNAIL_MARGIN: f32 = 0.2;
+
+/// A face cropped from a frame and encoded as a thumbnail.
+struct FaceCrop {
+    encoded: Vec<u8>,
+    quality: f32,
+}
+
+/// Grows `bbox` by `margin` around its center and clips it to the image.
+/// Returns `(x, y, width, height)`, or `None` if nothing of it is inside.
+fn crop_bounds(
+    bbox: &BoundingBox,
+    margin: f32,
+    image_width: u32,
+    image_height: u32,
+) -> Option<(u32, u32, u32, u32)> {
+    // Worked in i64 so boxes far outside the frame can't overflow
+    let factor = 1.0 + f64::from(margin.max(0.0));
+    let (width, height) = (
+        i64::from(bbox.width().max(0)),
+        i64::from(bbox.height().max(0)),
+    );
+    let center_x = i64::from(bbox.x()) + width / 2;
+    let center_y = i64::from(bbox.y()) + height / 2;
+    let grown_width = (width as f64 * factor) as i64;
+    let grown_height = (height as f64 * factor) as i64;
+
+    let x1 = (center_x - grown_width / 2).clamp(0, i64::from(image_width));
+    let y1 = (center_y - grown_height / 2).clamp(0, i64::from(image_height));
+    let x2 = (center_x - grown_width / 2 + grown_width).clamp(0, i64::from(image_width));
+    let y2 = (center_y - grown_height / 2 + grown_height).clamp(0, i64::from(image_height));
+
+    (x2 > x1 && y2 > y1).then(|| (x1 as u32, y1 as u32, (x2 - x1) as u32, (y2 - y1) as u32))
+}
+
+/// How long a newly created profile stays in the pending buffer.
+const PENDING_PROFILE_WINDOW: Duration = Duration::from_secs(5);
+
+/// Profiles created moments ago, matched against before creating another.
+///
+/// Covers the gap where concurrent frames of the same new face both miss the
+/// matcher cache before either profile has been added to it.
+#[derive(Default)]
+struct PendingProfiles {
+    entries: Vec<(Uuid, FaceEmbedding, Instant)>,
+}
+
+impl PendingProfiles {
+    /// Returns the closest pending profile within `threshold`.
+    fn find_near(
+        &mut self,
+        embedding: &FaceEmbedding,
+        threshold: f32,
+        now: Instant,
+    ) -> Option<(Uuid, f32)> {
+        self.entries.retain(|(_, _, created_at)| {
+            now.saturating_duration_since(*created_at) < PENDING_PROFILE_WINDOW
+        });
+
+        self.entries
+            .iter()
+            .map(|(id, stored, _)| (*id, embedding.distance(stored)))
+            .filter(|(_, distance)| *distance < threshold)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+    }
+
+    fn insert(&mut self, profile_id: Uuid, embedding: FaceEmbedding, now: Instant) {
+        self.entries.push((profile_id, embedding, now));
+    }
+}
+
+/// Track IoU threshold used unless configured.
+pub const DEFAULT_TRACK_IOU_THRESHOLD: f32 = 0.3;
+
+/// Track lifetime used unless configured, in milliseconds.
+pub const DEFAULT_TRACK_LIFETIME_MS: u64 = 1500;
+
+/// A face followed across a camera's consecutive frames.
+struct FaceTrack {
+    profile_id: Uuid,
+    bbox: BoundingBox,
+    /// How far the box center moved between the last two frames it was in.
+    velocity: (i32, i32),
+    last_seen: Instant,
+}
+
+impl FaceTrack {
+    /// Where the box should be next if the face keeps moving the same way.
+    fn predicted(&self) -> BoundingBox {
+        BoundingBox::new(
+            self.bbox.x().saturating_add(self.velocity.0),
+            self.bbox.y().saturating_add(self.velocity.1),
+            self.bbox.width(),
+            self.bbox.height(),
+        )
+    }
+}
+
+/// Faces seen in each camera's recent frames.
+///
+/// A face whose embedding misses every profile, say mid head-turn or when
+/// none was extracted, is attached to the track its box continues instead
+/// of becoming another profile.
+#[derive(Default)]
+struct FaceTracks {
+    cameras: std::collections::HashMap<Uuid, Vec<FaceTrack>>,
+}
+
+impl FaceTracks {
+    /// Drops tracks not seen within `lifetime`.
+    fn expire(&mut self, lifetime: Duration, now: Instant) {
+        self.cameras.retain(|_, tracks| {
+            tracks.retain(|track| now.saturating_duration_since(track.last_seen) < lifetime);
+            !tracks.is_empty()
+        });
+    }
+
+    /// Returns the profile of the track on `camera_id` whose predicted box
+    /// overlaps `bbox` most, if by at least `min_iou`. Profiles in `claimed`
+    /// are skipped.
+    fn find(
+        &self,
+        camera_id: Uuid,
+        bbox: &BoundingBox,
+        min_iou: f32,
+        claimed: &[Uuid],
+    ) -> Option<Uuid> {
+        self.cameras
+            .get(&camera_id)?
+            .iter()
+            .filter(|track| !claimed.contains(&track.profile_id))
+            .map(|track| (track.profile_id, track.predicted().iou(bbox)))
+            .filter(|(_, iou)| *iou >= min_iou)
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(profile_id, _)| profile_id)
+    }
+
+    /// Moves the profile's track on `camera_id` to `bbox`, starting one if
+    /// there is none.
+    fn observe(&mut self, camera_id: Uuid, profile_id: Uuid, bbox: BoundingBox, now: Instant) {
+        let tracks = self.cameras.entry(camera_id).or_default();
+        match tracks
+            .iter_mut()
+            .find(|track| track.profile_id == profile_id)
+        {
+            Some(track) => {
+                let (from_x, from_y) = track.bbox.center();
+                let (to_x, to_y) = bbox.center();
+                track.velocity = (to_x.saturating_sub(from_x), to_y.saturating_sub(from_y));
+                track.bbox = bbox;
+                track.last_seen = now;
+            }
+            None => tracks.push(FaceTrack {
+                profile_id,
+                bbox,
+                velocity: (0, 0),
+                last_seen: now,
+            }),
         }
     }
 }
 
+/// Per-camera token buckets.
+#[derive(Default)]
+struct CameraRateLimiter {
+    buckets: std::collections::HashMap<Uuid, TokenBucket>,
+}
+
+impl CameraRateLimiter {
+    fn allow(&mut self, camera_id: Uuid, per_minute: u32, now: Instant) -> bool {
+        self.buckets
+            .entry(camera_id)
+            .or_insert_with(|| TokenBucket::new(per_minute, now))
+            .try_take(per_minute, now)
+    }
+}
+
 /// Tracks recent sightings to prevent duplicates.
 struct SightingTracker {
-    recent: std::collections::HashMap<Uuid, chrono::DateTime<Utc>>,
+    /// Last sighting per profile, and per camera under `PerProfilePerCamera`.
+    recent: std::collections::HashMap<(Uuid, Option<Uuid>), chrono::DateTime<Utc>>,
     cooldown_secs: i64,
+    scope: SightingCooldownScope,
 }
 
 impl SightingTracker {
-    fn new(cooldown_secs: i64) -> Self {
+    fn new(cooldown_secs: i64, scope: SightingCooldownScope) -> Self {
         Self {
             recent: std::collections::HashMap::new(),
             cooldown_secs,
+            scope,
         }
     }
 
-    fn should_record(&mut self, profile_id: Uuid) -> bool {
-        let now = Utc::now();
+    /// Changes the cooldown scope, forgetting sightings tracked under the old one.
+    fn set_scope(&mut self, scope: SightingCooldownScope) {
+        if self.scope != scope {
+            self.scope = scope;
+            self.recent.clear();
+        }
+    }
 
-        if let Some(last_seen) = self.recent.get(&profile_id) {
-            let elapsed = (now - *last_seen).num_seconds();
-            if elapsed < self.cooldown_secs {
-                return false;
-            }
+    fn key(&self, profile_id: Uuid, camera_id: Uuid) -> (Uuid, Option<Uuid>) {
+        match self.scope {
+            SightingCooldownScope::PerProfile => (profile_id, None),
+            SightingCooldownScope::PerProfilePerCamera => (profile_id, Some(camera_id)),
         }
+    }
+
+    /// Whether a sighting was recorded for the profile within the cooldown.
+    fn is_cooling_down(&self, profile_id: Uuid, camera_id: Uuid) -> bool {
+        self.recent
+            .get(&self.key(profile_id, camera_id))
+            .is_some_and(|last_seen| (Utc::now() - *last_seen).num_seconds() < self.cooldown_secs)
+    }
 
-        self.recent.insert(profile_id, now);
-        true
+    /// Starts the cooldown for a recorded sighting.
+    fn record(&mut self, profile_id: Uuid, camera_id: Uuid) {
+        let key = self.key(profile_id, camera_id);
+        self.recent.insert(key, Utc::now());
     }
 
     fn cleanup(&mut self) {
@@ -81,6 +482,14 @@ pub struct DetectionService {
     config: RwLock<DetectionConfig>,
     sighting_tracker: RwLock<SightingTracker>,
     current_location: RwLock<Option<GeoLocation>>,
+    pending_profiles: Mutex<PendingProfiles>,
+    face_tracks: Mutex<FaceTracks>,
+    profile_limiter: RwLock<CameraRateLimiter>,
+    sighting_limiter: RwLock<CameraRateLimiter>,
+    profiles_created: AtomicU64,
+    sightings_recorded: AtomicU64,
+    rate_limited: AtomicU64,
+    classifier: Option<Arc<ProfileClassifier>>,
 }
 
 impl DetectionService {
@@ -92,62 +501,164 @@ impl DetectionService {
         event_bus: Arc<EventBus>,
         config: DetectionConfig,
     ) -> Self {
-        let cooldown = config.sighting_cooldown_secs;
+        let tracker = SightingTracker::new(
+            config.sighting_cooldown_secs,
+            config.sighting_cooldown_scope,
+        );
         Self {
             profile_repo,
             sighting_repo,
             face_matcher,
             event_bus,
             config: RwLock::new(config),
-            sighting_tracker: RwLock::new(SightingTracker::new(cooldown)),
+            sighting_tracker: RwLock::new(tracker),
             current_location: RwLock::new(None),
+            pending_profiles: Mutex::new(PendingProfiles::default()),
+            face_tracks: Mutex::new(FaceTracks::default()),
+            profile_limiter: RwLock::new(CameraRateLimiter::default()),
+            sighting_limiter: RwLock::new(CameraRateLimiter::default()),
+            profiles_created: AtomicU64::new(0),
+            sightings_recorded: AtomicU64::new(0),
+            rate_limited: AtomicU64::new(0),
+            classifier: None,
         }
     }
 
+    /// Applies classification rules to profiles each time they are sighted.
+    pub fn with_classifier(mut self, classifier: Arc<ProfileClassifier>) -> Self {
+        self.classifier = Some(classifier);
+        self
+    }
+
+    /// Returns the number of profiles created since startup.
+    pub fn profiles_created_count(&self) -> u64 {
+        self.profiles_created.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of sightings recorded since startup.
+    pub fn sightings_recorded_count(&self) -> u64 {
+        self.sightings_recorded.load(Ordering::Relaxed)
+    }
+
+    /// Total profile creations and sightings dropped by the rate limiter.
+    pub fn rate_limited_count(&self) -> u64 {
+        self.rate_limited.load(Ordering::Relaxed)
+    }
+
     /// Updates the current device location.
     pub async fn set_location(&self, location: GeoLocation) {
         *self.current_location.write().await = Some(location);
     }
 
     /// Updates the detection configuration.
+    ///
+    /// The match threshold is forwarded to the face matcher.
     pub async fn update_config(&self, config: DetectionConfig) {
         let cooldown = config.sighting_cooldown_secs;
+        let scope = config.sighting_cooldown_scope;
+        self.face_matcher
+            .set_threshold(config.match_threshold)
+            .await;
         *self.config.write().await = config;
-        self.sighting_tracker.write().await.cooldown_secs = cooldown;
+        let mut tracker = self.sighting_tracker.write().await;
+        tracker.cooldown_secs = cooldown;
+        tracker.set_scope(scope);
+    }
+
+    /// Applies persisted detection settings, keeping the rest of the config.
+    pub async fn apply_settings(&self, settings: &DetectionSettings) {
+        let config = self.config().await.with_settings(settings);
+        self.update_config(config).await;
+    }
+
+    /// Gets the current config.
+    pub async fn config(&self) -> DetectionConfig {
+        self.config.read().await.clone()
+    }
+
+    /// Takes a new-profile token for the camera, counting the drop if none is left.
+    async fn admit_new_profile(&self, camera_id: Uuid, config: &DetectionConfig) -> bool {
+        let allowed = self.profile_limiter.write().await.allow(
+            camera_id,
+            config.max_new_profiles_per_minute,
+            Instant::now(),
+        );
+        if !allowed {
+            self.rate_limited.fetch_add(1, Ordering::Relaxed);
+            warn!(
+                "Dropping new face from camera {}: profile creation rate limit reached",
+                camera_id
+            );
+        }
+        allowed
+    }
+
+    /// Returns the active profile whose face track on `camera_id` `bbox`
+    /// continues, skipping profiles already resolved in this frame.
+    async fn find_tracked_profile(
+        &self,
+        camera_id: Uuid,
+        bbox: &BoundingBox,
+        config: &DetectionConfig,
+        resolved: &[DetectionOutcome],
+    ) -> RepoResult<Option<Profile>> {
+        let claimed: Vec<Uuid> = resolved.iter().map(|o| o.profile_id).collect();
+        let tracked = self.face_tracks.lock().await.find(
+            camera_id,
+            bbox,
+            config.track_iou_threshold,
+            &claimed,
+        );
+        let Some(profile_id) = tracked else {
+            return Ok(None);
+        };
+        let profile = self.profile_repo.find_by_id(profile_id).await?;
+        Ok(profile.filter(|p| p.is_active()))
     }
 
     /// Processes a frame with face detections.
+    ///
+    /// Sightings are linked to `recording`, the camera's active recording,
+    /// at the frame's offset into it.
     pub async fn process_frame(
         &self,
         frame: &mut FrameDetections,
         snapshot_dir: &str,
-    ) -> RepoResult<Vec<Uuid>> {
+        recording: Option<&Recording>,
+    ) -> RepoResult<FrameOutcome> {
         let config = self.config.read().await.clone();
+        if config.detection_only {
+            return self.match_without_saving(frame, &config).await;
+        }
         let location = self.current_location.read().await.clone();
         let mut created_profiles = Vec::new();
+        let mut matched_profiles = Vec::new();
+        let mut results = Vec::new();
+        self.face_tracks.lock().await.expire(
+            Duration::from_millis(config.track_lifetime_ms),
+            Instant::now(),
+        );
 
         // Get frame data before iterating
         let camera_id = frame.camera_id();
         let frame_number = frame.frame_number();
         let frame_data = frame.frame_data().map(|d| d.to_vec());
+        let (frame_width, frame_height) = frame.frame_size().unwrap_or_default();
+        let recording_link = recording.map(|r| {
+            let offset_ms = frame.timestamp_ms() - r.started_at().timestamp_millis();
+            (r.id(), offset_ms.max(0))
+        });
+
+        // First pass: resolve a profile for each confident detection
+        let mut outcomes: Vec<DetectionOutcome> = Vec::with_capacity(frame.detections().len());
 
-        // First pass: collect processing results for each detection
-        let detection_count = frame.detections().len();
-        let mut results: Vec<
-            Option<(
-                Uuid,
-                Option<String>,
-                crate::domain::entities::ProfileClassification,
-                bool,
-                f32,
-                BoundingBox,
-                f32,
-            )>,
-        > = Vec::with_capacity(detection_count);
-
-        for detection in frame.detections() {
+        for (index, detection) in frame.detections().iter().enumerate() {
             if detection.confidence() < config.min_confidence {
-                results.push(None);
+                continue;
+            }
+            // Tiny, distant faces give embeddings too poor to match reliably
+            let area = detection.bounding_box().area().max(0) as u64;
+            if area < u64::from(config.min_face_area_px) {
                 continue;
             }
 
@@ -167,54 +678,127 @@ impl DetectionService {
                             .await?;
 
                         match profile {
-                            Some(p) => Some((
-                                p.id(),
-                                p.name().map(String::from),
-                                p.classification(),
-                                false,
-                                match_result.distance,
+                            Some(p) => Some(DetectionOutcome {
+                                index,
+                                profile_id: p.id(),
+                                profile_name: p.name().map(String::from),
+                                classification: p.classification(),
+                                is_new: false,
+                                distance: match_result.distance,
+                                match_confidence: match_result.confidence,
+                                embedding: Some(emb.clone()),
                                 bbox,
                                 confidence,
-                            )),
+                            }),
                             None => None,
                         }
                     }
                     None => {
-                        // No match found, create new profile
-                        let profile = self
-                            .create_profile_from_detection(
-                                emb.clone(),
-                                &bbox,
-                                camera_id,
-                                frame_number,
-                                frame_data.as_deref(),
-                                snapshot_dir,
-                            )
-                            .await?;
-
-                        let profile_id = profile.id();
-                        created_profiles.push(profile_id);
-
-                        self.event_bus
-                            .publish(DomainEvent::ProfileCreated(ProfileCreatedEvent {
+                        // Hold the lock through creation so a concurrent frame of
+                        // the same face attaches to this profile instead
+                        let mut pending = self.pending_profiles.lock().await;
+                        let threshold = self.face_matcher.threshold().await;
+
+                        if let Some((profile_id, distance)) =
+                            pending.find_near(emb, threshold, Instant::now())
+                        {
+                            debug!("Attaching face to just-created profile {}", profile_id);
+                            Some(DetectionOutcome {
+                                index,
+                                profile_id,
+                                profile_name: None,
+                                classification: ProfileClassification::default(),
+                                is_new: false,
+                                distance,
+                                match_confidence: FaceMatcher::distance_to_confidence(
+                                    distance, threshold,
+                                ),
+                                embedding: Some(emb.clone()),
+                                bbox,
+                                confidence,
+                            })
+                        } else if let Some(p) = self
+                            .find_tracked_profile(camera_id, &bbox, &config, &outcomes)
+                            .await?
+                        {
+                            debug!("Attaching face to tracked profile {}", p.id());
+                            // Matched by position, not appearance, so the
+                            // embedding isn't kept as a sample
+                            Some(DetectionOutcome {
+                                index,
+                                profile_id: p.id(),
+                                profile_name: p.name().map(String::from),
+                                classification: p.classification(),
+                                is_new: false,
+                                distance: threshold,
+                                match_confidence: 0.0,
+                                embedding: None,
+                                bbox,
+                                confidence,
+                            })
+                        } else if !self.admit_new_profile(camera_id, &config).await {
+                            None
+                        } else {
+                            // No match found, create new profile
+                            let profile = self
+                                .create_profile_from_detection(
+                                    emb.clone(),
+                                    &bbox,
+                                    camera_id,
+                                    frame_number,
+                                    frame_data.as_deref(),
+                                    snapshot_dir,
+                                )
+                                .await?;
+
+                            let profile_id = profile.id();
+                            created_profiles.push(profile_id);
+                            pending.insert(profile_id, emb.clone(), Instant::now());
+
+                            self.event_bus.publish(DomainEvent::ProfileCreated(
+                                ProfileCreatedEvent {
+                                    profile_id,
+                                    thumbnail_path: profile.thumbnail_path().map(String::from),
+                                    camera_id,
+                                    location: location.clone(),
+                                    timestamp: Utc::now(),
+                                },
+                            ));
+
+                            Some(DetectionOutcome {
+                                index,
                                 profile_id,
-                                thumbnail_path: profile.thumbnail_path().map(String::from),
-                                camera_id,
-                                location: location.clone(),
-                                timestamp: Utc::now(),
-                            }));
-
-                        Some((
-                            profile_id,
-                            None,
-                            profile.classification(),
-                            true,
-                            0.0,
-                            bbox,
-                            confidence,
-                        ))
+                                profile_name: None,
+                                classification: profile.classification(),
+                                is_new: true,
+                                distance: 0.0,
+                                match_confidence: 0.0,
+                                embedding: None,
+                                bbox,
+                                confidence,
+                            })
+                        }
                     }
                 }
+            } else if let Some(p) = self
+                .find_tracked_profile(camera_id, &bbox, &config, &outcomes)
+                .await?
+            {
+                debug!("Attaching face to tracked profile {}", p.id());
+                Some(DetectionOutcome {
+                    index,
+                    profile_id: p.id(),
+                    profile_name: p.name().map(String::from),
+                    classification: p.classification(),
+                    is_new: false,
+                    distance: config.match_threshold,
+                    match_confidence: 0.0,
+                    embedding: None,
+                    bbox,
+                    confidence,
+                })
+            } else if !self.admit_new_profile(camera_id, &config).await {
+                None
             } else {
                 // No embedding - for MVP, create profile without embedding
                 // This allows the system to at least record detections
@@ -240,67 +824,148 @@ impl DetectionService {
                         timestamp: Utc::now(),
                     }));
 
-                Some((
+                Some(DetectionOutcome {
+                    index,
                     profile_id,
-                    None,
-                    profile.classification(),
-                    true,
-                    0.0,
+                    profile_name: None,
+                    classification: profile.classification(),
+                    is_new: true,
+                    distance: 0.0,
+                    match_confidence: 0.0,
+                    embedding: None,
                     bbox,
                     confidence,
-                ))
+                })
             };
-            results.push(result);
+            outcomes.extend(result);
+        }
+
+        // Faces in this frame continue their tracks in the next
+        {
+            let mut tracks = self.face_tracks.lock().await;
+            let now = Instant::now();
+            for outcome in &outcomes {
+                tracks.observe(camera_id, outcome.profile_id, outcome.bbox.clone(), now);
+            }
         }
 
         // Second pass: update detections and process sightings
-        for (i, detection) in frame.detections_mut().iter_mut().enumerate() {
-            if let Some(Some((
-                profile_id,
-                profile_name,
-                classification,
-                is_new,
-                distance,
-                ref bbox,
-                confidence,
-            ))) = results.get(i)
-            {
-                if !*is_new {
-                    detection.set_match(*profile_id, *distance);
+        for outcome in outcomes {
+            let Some(detection) = frame.detections_mut().get_mut(outcome.index) else {
+                continue;
+            };
+            if !outcome.is_new {
+                detection.set_match(
+                    outcome.profile_id,
+                    outcome.distance,
+                    outcome.match_confidence,
+                );
+                if !matched_profiles.contains(&outcome.profile_id) {
+                    matched_profiles.push(outcome.profile_id);
                 }
+            }
+            results.push(DetectionResult {
+                index: outcome.index,
+                profile_id: outcome.profile_id,
+                is_new: outcome.is_new,
+                match_confidence: outcome.match_confidence,
+            });
+
+            self.event_bus
+                .publish(DomainEvent::FaceDetected(FaceDetectedEvent {
+                    camera_id,
+                    frame_number,
+                    bounding_box: outcome.bbox.clone(),
+                    frame_width,
+                    frame_height,
+                    confidence: outcome.confidence,
+                    profile_id: Some(outcome.profile_id),
+                    profile_name: outcome.profile_name.clone(),
+                    classification: Some(outcome.classification),
+                    timestamp: Utc::now(),
+                }));
+
+            if !outcome.is_new {
+                self.record_sighting_data(
+                    outcome,
+                    camera_id,
+                    frame_data.as_deref(),
+                    snapshot_dir,
+                    location.clone(),
+                    recording_link,
+                )
+                .await?;
+            }
+        }
 
-                self.event_bus
-                    .publish(DomainEvent::FaceDetected(FaceDetectedEvent {
-                        camera_id,
-                        frame_number,
-                        bounding_box: bbox.clone(),
-                        confidence: *confidence,
-                        profile_id: Some(*profile_id),
-                        profile_name: profile_name.clone(),
-                        classification: Some(*classification),
-                        timestamp: Utc::now(),
-                    }));
+        self.cleanup_tracker().await;
 
-                if !*is_new {
-                    self.record_sighting_data(
-                        *profile_id,
-                        profile_name.clone(),
-                        *classification,
-                        camera_id,
-                        bbox,
-                        *confidence,
-                        frame_data.as_deref(),
-                        snapshot_dir,
-                        location.clone(),
-                    )
-                    .await?;
+        Ok(FrameOutcome {
+            created_profiles,
+            matched_profiles,
+            detections: results,
+        })
+    }
+
+    /// Matches a frame's faces against known profiles and publishes them,
+    /// without creating profiles or sightings. Unmatched faces are published
+    /// without a profile.
+    async fn match_without_saving(
+        &self,
+        frame: &mut FrameDetections,
+        config: &DetectionConfig,
+    ) -> RepoResult<FrameOutcome> {
+        let camera_id = frame.camera_id();
+        let frame_number = frame.frame_number();
+        let (frame_width, frame_height) = frame.frame_size().unwrap_or_default();
+        let mut outcome = FrameOutcome::default();
+
+        for (index, detection) in frame.detections_mut().iter_mut().enumerate() {
+            let area = detection.bounding_box().area().max(0) as u64;
+            if detection.confidence() < config.min_confidence
+                || area < u64::from(config.min_face_area_px)
+            {
+                continue;
+            }
+
+            let matched = match detection.embedding() {
+                Some(emb) => self.face_matcher.find_match(emb).await,
+                None => None,
+            };
+            let profile = match &matched {
+                Some(m) => self.profile_repo.find_by_id(m.profile_id).await?,
+                None => None,
+            };
+
+            if let (Some(m), Some(p)) = (&matched, &profile) {
+                detection.set_match(p.id(), m.distance, m.confidence);
+                if !outcome.matched_profiles.contains(&p.id()) {
+                    outcome.matched_profiles.push(p.id());
                 }
+                outcome.detections.push(DetectionResult {
+                    index,
+                    profile_id: p.id(),
+                    is_new: false,
+                    match_confidence: m.confidence,
+                });
             }
-        }
 
-        self.cleanup_tracker().await;
+            self.event_bus
+                .publish(DomainEvent::FaceDetected(FaceDetectedEvent {
+                    camera_id,
+                    frame_number,
+                    bounding_box: detection.bounding_box().clone(),
+                    frame_width,
+                    frame_height,
+                    confidence: detection.confidence(),
+                    profile_id: profile.as_ref().map(|p| p.id()),
+                    profile_name: profile.as_ref().and_then(|p| p.name().map(String::from)),
+                    classification: profile.as_ref().map(|p| p.classification()),
+                    timestamp: Utc::now(),
+                }));
+        }
 
-        Ok(created_profiles)
+        Ok(outcome)
     }
 
     async fn create_profile_from_detection(
@@ -312,12 +977,16 @@ impl DetectionService {
         image_data: Option<&[u8]>,
         snapshot_dir: &str,
     ) -> RepoResult<Profile> {
-        let thumbnail_path = self
+        let (thumbnail_path, quality) = self
             .save_thumbnail_from_data(image_data, bbox, snapshot_dir)
             .await;
-        let profile = Profile::new(embedding.clone(), thumbnail_path);
+        let mut profile = Profile::new(embedding.clone(), None);
+        if let Some(path) = thumbnail_path {
+            profile.set_thumbnail(path, quality);
+        }
 
         self.profile_repo.save(&profile).await?;
+        self.profiles_created.fetch_add(1, Ordering::Relaxed);
         self.face_matcher
             .add_to_cache(profile.id(), embedding)
             .await;
@@ -340,15 +1009,18 @@ impl DetectionService {
         image_data: Option<&[u8]>,
         snapshot_dir: &str,
     ) -> RepoResult<Profile> {
-        let thumbnail_path = self
+        let (thumbnail_path, quality) = self
             .save_thumbnail_from_data(image_data, bbox, snapshot_dir)
             .await;
 
-        // Create a dummy embedding (all zeros) - won't be used for matching
-        let dummy_embedding = FaceEmbedding::zeros(128);
-        let profile = Profile::new(dummy_embedding, thumbnail_path);
+        // Holds a placeholder embedding and stays out of matching
+        let mut profile = Profile::without_embedding(None);
+        if let Some(path) = thumbnail_path {
+            profile.set_thumbnail(path, quality);
+        }
 
         self.profile_repo.save(&profile).await?;
+        self.profiles_created.fetch_add(1, Ordering::Relaxed);
 
         info!(
             "Created new profile (no embedding): {} from camera {}",
@@ -361,21 +1033,46 @@ impl DetectionService {
 
     async fn record_sighting_data(
         &self,
-        profile_id: Uuid,
-        profile_name: Option<String>,
-        classification: crate::domain::entities::ProfileClassification,
+        outcome: DetectionOutcome,
         camera_id: Uuid,
-        bbox: &BoundingBox,
-        confidence: f32,
         image_data: Option<&[u8]>,
         snapshot_dir: &str,
         location: Option<GeoLocation>,
+        recording_link: Option<(Uuid, i64)>,
     ) -> RepoResult<()> {
+        let DetectionOutcome {
+            profile_id,
+            profile_name,
+            mut classification,
+            match_confidence,
+            embedding,
+            bbox,
+            confidence,
+            ..
+        } = outcome;
+
+        let config = self.config.read().await.clone();
         let mut tracker = self.sighting_tracker.write().await;
-        if !tracker.should_record(profile_id) {
+        if tracker.is_cooling_down(profile_id, camera_id) {
             debug!("Skipping sighting for {} (cooldown active)", profile_id);
             return Ok(());
         }
+
+        let allowed = self.sighting_limiter.write().await.allow(
+            camera_id,
+            config.max_sightings_per_minute,
+            Instant::now(),
+        );
+        if !allowed {
+            self.rate_limited.fetch_add(1, Ordering::Relaxed);
+            warn!(
+                "Dropping sighting of {} from camera {}: rate limit reached",
+                profile_id, camera_id
+            );
+            return Ok(());
+        }
+        // Only sightings that are written start the cooldown
+        tracker.record(profile_id, camera_id);
         drop(tracker);
 
         let snapshot_path = self
@@ -383,7 +1080,7 @@ impl DetectionService {
             .await
             .unwrap_or_else(|| "unknown".to_string());
 
-        let sighting = Sighting::new(
+        let mut sighting = Sighting::new(
             profile_id,
             camera_id,
             snapshot_path,
@@ -391,12 +1088,36 @@ impl DetectionService {
             confidence,
             location.clone(),
         );
+        sighting.set_match_confidence(match_confidence);
+        if let Some((recording_id, offset_ms)) = recording_link {
+            sighting.link_to_recording(recording_id, offset_ms);
+        }
 
         self.sighting_repo.save(&sighting).await?;
+        self.sightings_recorded.fetch_add(1, Ordering::Relaxed);
 
         if let Some(mut profile) = self.profile_repo.find_by_id(profile_id).await? {
             profile.record_sighting();
+            let sampled = match embedding {
+                Some(embedding) if match_confidence >= SAMPLE_MIN_CONFIDENCE => {
+                    let min_spread = self.face_matcher.threshold().await * SAMPLE_MIN_SPREAD;
+                    profile.add_embedding_sample(embedding, min_spread)
+                }
+                _ => false,
+            };
+            if let Some(classifier) = &self.classifier {
+                if let Some(classified) = classifier.apply(&mut profile).await? {
+                    classification = classified;
+                }
+            }
+            self.upgrade_thumbnail(&mut profile, image_data, &bbox, snapshot_dir)
+                .await;
             self.profile_repo.update(&profile).await?;
+            if sampled {
+                debug!("Added embedding sample to profile {}", profile_id);
+                self.profile_repo.save_embedding_samples(&profile).await?;
+                self.face_matcher.cache_profile(&profile).await;
+            }
         }
 
         self.event_bus
@@ -419,103 +1140,239 @@ impl DetectionService {
         image_data: Option<&[u8]>,
         bbox: &BoundingBox,
         snapshot_dir: &str,
-    ) -> Option<String> {
+    ) -> (Option<String>, Option<f32>) {
+        let (format, margin) = self.thumbnail_settings().await;
         // Store only the filename, not the full path
-        let filename = format!("thumb_{}.jpg", Uuid::new_v4());
-        let full_path = format!("{}/{}", snapshot_dir, filename);
+        let filename = format!("thumb_{}.{}", Uuid::new_v4(), format.extension());
+        let full_path = Path::new(snapshot_dir).join(&filename);
 
         // Ensure directory exists
         if let Err(e) = tokio::fs::create_dir_all(snapshot_dir).await {
             tracing::warn!("Failed to create snapshot directory: {}", e);
-            return Some(filename); // Still return filename for DB
+            return (Some(filename), None); // Still return filename for DB
         }
 
+        let mut quality = None;
+
         // If we have image data, save it
         if let Some(data) = image_data {
             // Try to extract face region from full frame
-            if let Some(cropped) = Self::crop_face_region(data, bbox) {
-                if let Err(e) = tokio::fs::write(&full_path, &cropped).await {
-                    tracing::warn!("Failed to write thumbnail {}: {}", full_path, e);
+            match Self::crop_face_blocking(data, bbox, format, margin).await {
+                Ok(crop) => {
+                    quality = Some(crop.quality);
+                    if let Err(e) = tokio::fs::write(&full_path, &crop.encoded).await {
+                        tracing::warn!("Failed to write thumbnail {}: {}", full_path.display(), e);
+                    }
                 }
-            } else {
-                // If cropping fails, try to save the raw data as-is (it might be JPEG already)
-                if let Err(e) = tokio::fs::write(&full_path, data).await {
-                    tracing::warn!("Failed to write thumbnail {}: {}", full_path, e);
+                Err(e) => {
+                    debug!("Could not crop face for thumbnail: {}", e);
+                    // Save the raw data as-is instead (it might be JPEG already)
+                    if let Err(e) = tokio::fs::write(&full_path, data).await {
+                        tracing::warn!("Failed to write thumbnail {}: {}", full_path.display(), e);
+                    }
                 }
             }
         }
 
-        Some(filename)
+        (Some(filename), quality)
     }
 
-    /// Crop face region from full frame and encode as JPEG
-    /// The frame_data can be either:
-    /// 1. Raw RGB data (width * height * 3 bytes)
-    /// 2. Already encoded JPEG
-    fn crop_face_region(frame_data: &[u8], bbox: &BoundingBox) -> Option<Vec<u8>> {
-        use image::{DynamicImage, ImageFormat, ImageBuffer, Rgb};
-        use std::io::Cursor;
+    /// Replaces the profile's thumbnail when this frame has a clearly better crop.
+    async fn upgrade_thumbnail(
+        &self,
+        profile: &mut Profile,
+        image_data: Option<&[u8]>,
+        bbox: &BoundingBox,
+        snapshot_dir: &str,
+    ) {
+        let (format, margin) = self.thumbnail_settings().await;
+        let Some(data) = image_data else {
+            return;
+        };
+        let Ok(crop) = Self::crop_face_blocking(data, bbox, format, margin).await else {
+            return;
+        };
+        let current = profile.thumbnail_quality().unwrap_or(0.0);
+        if crop.quality < current + THUMBNAIL_UPGRADE_MARGIN {
+            return;
+        }
 
-        // First, try to decode as JPEG (if it's already encoded)
-        let img: DynamicImage = if let Ok(img) = image::load_from_memory_with_format(frame_data, ImageFormat::Jpeg) {
-            img
-        } else {
-            // Try to interpret as raw RGB data
-            // Common resolutions to try
-            let common_resolutions = [
-                (1920, 1080),
-                (1280, 720),
-                (640, 480),
-                (800, 600),
-            ];
-
-            let expected_bytes: Vec<(u32, u32, usize)> = common_resolutions
-                .iter()
-                .map(|(w, h)| (*w, *h, (w * h * 3) as usize))
-                .collect();
-
-            if let Some(&(width, height, _)) = expected_bytes.iter().find(|(_, _, size)| *size == frame_data.len()) {
-                // Create RGB image buffer from raw data
-                let rgb_buf: ImageBuffer<Rgb<u8>, _> = ImageBuffer::from_raw(width, height, frame_data.to_vec())?;
-                DynamicImage::ImageRgb8(rgb_buf)
-            } else {
-                // Unknown format
-                tracing::debug!("Unknown frame format: {} bytes", frame_data.len());
-                return None;
+        let filename = format!("thumb_{}.{}", Uuid::new_v4(), format.extension());
+        let full_path = Path::new(snapshot_dir).join(&filename);
+        if let Err(e) = tokio::fs::write(&full_path, &crop.encoded).await {
+            tracing::warn!("Failed to write thumbnail {}: {}", full_path.display(), e);
+            return;
+        }
+
+        // Only the file name is trusted, so a stored path cannot escape the directory
+        let previous = profile
+            .thumbnail_path()
+            .and_then(|p| Path::new(p).file_name())
+            .map(|name| Path::new(snapshot_dir).join(name));
+        if let Some(previous) = previous {
+            if let Err(e) = tokio::fs::remove_file(&previous).await {
+                if e.kind() != std::io::ErrorKind::NotFound {
+                    tracing::warn!("Failed to remove thumbnail {}: {}", previous.display(), e);
+                }
             }
-        };
+        }
 
-        // Crop and resize face region
-        let x = bbox.x().max(0) as u32;
-        let y = bbox.y().max(0) as u32;
-        let width = (bbox.width() as u32).min(img.width().saturating_sub(x));
-        let height = (bbox.height() as u32).min(img.height().saturating_sub(y));
+        debug!(
+            "Upgrading thumbnail of {} (quality {:.2} -> {:.2})",
+            profile.id(),
+            current,
+            crop.quality
+        );
+        profile.set_thumbnail(filename, Some(crop.quality));
+    }
 
-        if width == 0 || height == 0 {
-            return None;
+    /// Returns the snapshot format and crop margin thumbnails are made with.
+    async fn thumbnail_settings(&self) -> (SnapshotFormat, f32) {
+        let config = self.config.read().await;
+        (config.snapshot_format, config.thumbnail_margin)
+    }
+
+    /// Decodes frame data: an encoded JPEG, turned upright by its EXIF
+    /// orientation, or raw RGB/RGBA at one of the common resolutions.
+    fn decode_frame(frame_data: &[u8]) -> Result<DynamicImage, String> {
+        use image::{ImageBuffer, Rgb, Rgba};
+
+        // First, try to decode as JPEG (if it's already encoded)
+        if let Ok(img) = decode_jpeg_upright(frame_data) {
+            return Ok(img);
         }
 
+        // Try to interpret as raw RGB data
+        // Common resolutions to try
+        let common_resolutions = [(1920, 1080), (1280, 720), (640, 480), (800, 600)];
+
+        if let Some(&(width, height)) = common_resolutions
+            .iter()
+            .find(|(w, h)| (w * h * 3) as usize == frame_data.len())
+        {
+            // Create RGB image buffer from raw data
+            let rgb_buf: ImageBuffer<Rgb<u8>, _> =
+                ImageBuffer::from_raw(width, height, frame_data.to_vec())
+                    .ok_or("Failed to create RGB image buffer")?;
+            Ok(DynamicImage::ImageRgb8(rgb_buf))
+        } else if let Some(&(width, height)) = common_resolutions
+            .iter()
+            .find(|(w, h)| (w * h * 4) as usize == frame_data.len())
+        {
+            // RGBA, as in encode_jpeg; the encoder drops alpha where needed
+            let rgba_buf: ImageBuffer<Rgba<u8>, _> =
+                ImageBuffer::from_raw(width, height, frame_data.to_vec())
+                    .ok_or("Failed to create RGBA image buffer")?;
+            Ok(DynamicImage::ImageRgba8(rgba_buf))
+        } else {
+            Err(format!("Unknown frame format: {} bytes", frame_data.len()))
+        }
+    }
+
+    /// Crop face region from full frame, grown by `margin`, score it and
+    /// encode in `format`
+    /// The frame_data can be either:
+    /// 1. Raw RGB data (width * height * 3 bytes)
+    /// 2. Raw RGBA data (width * height * 4 bytes), as macOS cameras deliver
+    /// 3. Already encoded JPEG, turned upright by its EXIF orientation
+    ///
+    /// The box is clipped to the frame; an error describes why no crop could
+    /// be made.
+    fn crop_face_region(
+        frame_data: &[u8],
+        bbox: &BoundingBox,
+        format: SnapshotFormat,
+        margin: f32,
+    ) -> Result<FaceCrop, String> {
+        let img = Self::decode_frame(frame_data)?;
+
+        if img.width() == 0 || img.height() == 0 {
+            return Err(format!(
+                "Frame has no pixels: {}x{}",
+                img.width(),
+                img.height()
+            ));
+        }
+
+        // Crop and resize face region
+        let (x, y, width, height) = crop_bounds(bbox, margin, img.width(), img.height())
+            .ok_or_else(|| {
+                format!(
+                    "Face box at ({}, {}) sized {}x{} is outside the {}x{} frame",
+                    bbox.x(),
+                    bbox.y(),
+                    bbox.width(),
+                    bbox.height(),
+                    img.width(),
+                    img.height()
+                )
+            })?;
         let cropped = img.crop_imm(x, y, width, height);
+        let quality = face_quality(&cropped);
         // Resize to thumbnail size
         let thumbnail = cropped.thumbnail(128, 128);
 
-        let mut buffer = Cursor::new(Vec::new());
-        if thumbnail.write_to(&mut buffer, ImageFormat::Jpeg).is_ok() {
-            return Some(buffer.into_inner());
-        }
+        let encoded = format
+            .encode(&thumbnail)
+            .map_err(|e| format!("Failed to encode thumbnail: {}", e))?;
+        Ok(FaceCrop { encoded, quality })
+    }
 
-        None
+    /// Runs [`Self::crop_face_region`] on the blocking pool, so decoding and
+    /// encoding a frame doesn't hold up the async executor.
+    async fn crop_face_blocking(
+        frame_data: &[u8],
+        bbox: &BoundingBox,
+        format: SnapshotFormat,
+        margin: f32,
+    ) -> Result<FaceCrop, String> {
+        let data = frame_data.to_vec();
+        let bbox = bbox.clone();
+        tokio::task::spawn_blocking(move || Self::crop_face_region(&data, &bbox, format, margin))
+            .await
+            .map_err(|e| format!("Thumbnail task failed: {}", e))?
     }
 
+    /// Writes the full frame to `snapshot_dir` and returns its file name, or
+    /// `None` when there is no frame or it cannot be written.
     async fn save_snapshot_from_data(
         &self,
-        _image_data: Option<&[u8]>,
+        image_data: Option<&[u8]>,
         snapshot_dir: &str,
     ) -> Option<String> {
+        let data = image_data?.to_vec();
+        let format = self.config.read().await.snapshot_format;
         // Store only the filename, not the full path
-        let filename = format!("snap_{}.jpg", Uuid::new_v4());
-        let _full_path = format!("{}/{}", snapshot_dir, filename);
-        // TODO: Actually save the image data to _full_path
+        let filename = format!("snap_{}.{}", Uuid::new_v4(), format.extension());
+        let full_path = Path::new(snapshot_dir).join(&filename);
+
+        let encoded = tokio::task::spawn_blocking(move || {
+            let img = Self::decode_frame(&data)?;
+            format
+                .encode(&img)
+                .map_err(|e| format!("Failed to encode snapshot: {}", e))
+        })
+        .await;
+        let encoded = match encoded {
+            Ok(Ok(encoded)) => encoded,
+            Ok(Err(e)) => {
+                debug!("Could not save snapshot: {}", e);
+                return None;
+            }
+            Err(e) => {
+                tracing::warn!("Snapshot encoding failed: {}", e);
+                return None;
+            }
+        };
+
+        if let Err(e) = tokio::fs::create_dir_all(snapshot_dir).await {
+            tracing::warn!("Failed to create snapshot directory: {}", e);
+            return None;
+        }
+        if let Err(e) = tokio::fs::write(&full_path, &encoded).await {
+            tracing::warn!("Failed to write snapshot {}: {}", full_path.display(), e);
+            return None;
+        }
         Some(filename)
     }
 
@@ -528,14 +1385,68 @@ impl DetectionService {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::domain::entities::Settings;
+    use crate::domain::repositories::fakes::{
+        InMemoryProfileRepo, InMemorySettingsRepo, InMemorySightingRepo,
+    };
+    use crate::domain::repositories::SettingsRepository;
+    use crate::domain::value_objects::embedding_dimension;
+
+    /// A service over `profile_repo` and `sighting_repo`, with the matcher
+    /// cache loaded from `profile_repo`.
+    async fn test_service(
+        profile_repo: Arc<InMemoryProfileRepo>,
+        sighting_repo: Arc<InMemorySightingRepo>,
+        config: DetectionConfig,
+    ) -> (DetectionService, Arc<FaceMatcher>) {
+        let matcher = Arc::new(FaceMatcher::new(profile_repo.clone(), 0.6));
+        matcher.load_cache().await.unwrap();
+        let service = DetectionService::new(
+            profile_repo,
+            sighting_repo,
+            matcher.clone(),
+            Arc::new(EventBus::new()),
+            config,
+        );
+        (service, matcher)
+    }
+
+    /// A temporary snapshot directory and its path.
+    fn snapshot_dir() -> (tempfile::TempDir, String) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().to_string_lossy().to_string();
+        (dir, path)
+    }
 
     #[test]
     fn sighting_tracker_respects_cooldown() {
-        let mut tracker = SightingTracker::new(30);
+        let mut tracker = SightingTracker::new(30, SightingCooldownScope::PerProfile);
+        let (profile_id, camera_id) = (Uuid::new_v4(), Uuid::new_v4());
+
+        assert!(!tracker.is_cooling_down(profile_id, camera_id));
+        tracker.record(profile_id, camera_id);
+        assert!(tracker.is_cooling_down(profile_id, camera_id));
+    }
+
+    #[test]
+    fn per_profile_cooldown_is_shared_across_cameras() {
+        let mut tracker = SightingTracker::new(30, SightingCooldownScope::PerProfile);
         let profile_id = Uuid::new_v4();
 
-        assert!(tracker.should_record(profile_id));
-        assert!(!tracker.should_record(profile_id));
+        tracker.record(profile_id, Uuid::new_v4());
+        assert!(tracker.is_cooling_down(profile_id, Uuid::new_v4()));
+    }
+
+    #[test]
+    fn per_camera_cooldown_records_each_camera_once() {
+        let mut tracker = SightingTracker::new(30, SightingCooldownScope::PerProfilePerCamera);
+        let (profile_id, front, back) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+
+        tracker.record(profile_id, front);
+        assert!(!tracker.is_cooling_down(profile_id, back));
+        tracker.record(profile_id, back);
+        assert!(tracker.is_cooling_down(profile_id, front));
+        assert!(tracker.is_cooling_down(profile_id, back));
     }
 
     #[test]
@@ -545,4 +1456,637 @@ mod tests {
         assert!(config.match_threshold > 0.0);
         assert!(config.sighting_cooldown_secs > 0);
     }
+
+    #[tokio::test]
+    async fn update_config_changes_matcher_threshold() {
+        let (service, matcher) = test_service(
+            Arc::new(InMemoryProfileRepo::default()),
+            Arc::new(InMemorySightingRepo::default()),
+            DetectionConfig::default(),
+        )
+        .await;
+
+        service
+            .update_config(DetectionConfig {
+                match_threshold: 0.35,
+                ..Default::default()
+            })
+            .await;
+
+        assert_eq!(matcher.threshold().await, 0.35);
+    }
+
+    #[tokio::test]
+    async fn stored_match_threshold_is_applied_at_startup() {
+        let mut stored = Settings::default();
+        stored.detection.match_threshold = 0.42;
+        let settings_repo = InMemorySettingsRepo::new(stored);
+
+        // As AppState::new does
+        let settings = settings_repo.get().await.unwrap();
+        let (service, matcher) = test_service(
+            Arc::new(InMemoryProfileRepo::default()),
+            Arc::new(InMemorySightingRepo::default()),
+            DetectionConfig {
+                thumbnail_margin: 0.5,
+                ..Default::default()
+            },
+        )
+        .await;
+        service.apply_settings(&settings.detection).await;
+
+        assert_eq!(matcher.threshold().await, 0.42);
+        let config = service.config().await;
+        assert_eq!(config.match_threshold, 0.42);
+        // Values that do not come from settings are kept
+        assert_eq!(config.thumbnail_margin, 0.5);
+    }
+
+    #[test]
+    fn lower_jpeg_quality_gives_smaller_thumbnail() {
+        // Noisy 640x480 raw RGB frame, so the encoder has detail to discard
+        let frame: Vec<u8> = (0..640u32 * 480 * 3)
+            .map(|i| (i.wrapping_mul(2_654_435_761) >> 24) as u8)
+            .collect();
+        let bbox = BoundingBox::new(100, 100, 200, 200);
+
+        let crop = |quality| {
+            DetectionService::crop_face_region(&frame, &bbox, SnapshotFormat::Jpeg { quality }, 0.0)
+                .unwrap()
+        };
+        let high = crop(95);
+        let low = crop(30);
+
+        assert!(low.encoded.len() < high.encoded.len());
+        assert_eq!(low.quality, high.quality);
+    }
+
+    #[test]
+    fn raw_rgba_frame_produces_a_thumbnail() {
+        let frame: Vec<u8> = (0..640u32 * 480)
+            .flat_map(|i| [(i % 251) as u8, (i % 241) as u8, (i % 239) as u8, 255])
+            .collect();
+        let bbox = BoundingBox::new(100, 100, 200, 200);
+
+        let crop = DetectionService::crop_face_region(&frame, &bbox, SnapshotFormat::Png, 0.0)
+            .expect("RGBA frame should be cropped");
+
+        let thumbnail = image::load_from_memory(&crop.encoded).unwrap();
+        assert_eq!((thumbnail.width(), thumbnail.height()), (128, 128));
+    }
+
+    #[test]
+    fn crop_margin_grows_the_crop_within_the_image() {
+        let bbox = BoundingBox::new(100, 100, 200, 200);
+
+        assert_eq!(
+            crop_bounds(&bbox, 0.0, 640, 480),
+            Some((100, 100, 200, 200))
+        );
+        assert_eq!(crop_bounds(&bbox, 0.2, 640, 480), Some((80, 80, 240, 240)));
+
+        // A margin past the frame edge is clipped to it
+        let corner = BoundingBox::new(0, 0, 100, 100);
+        assert_eq!(crop_bounds(&corner, 0.2, 640, 480), Some((0, 0, 110, 110)));
+        let outside = BoundingBox::new(700, 0, 50, 50);
+        assert_eq!(crop_bounds(&outside, 0.2, 640, 480), None);
+
+        // Boxes at the edge of the coordinate range don't overflow
+        let huge = BoundingBox::new(i32::MAX - 10, i32::MIN, i32::MAX, i32::MAX);
+        assert_eq!(crop_bounds(&huge, 0.2, 640, 480), None);
+        assert_eq!(crop_bounds(&bbox, 0.2, 0, 0), None);
+    }
+
+    #[test]
+    fn oversized_face_box_is_clipped_to_the_frame() {
+        let frame = vec![128u8; 640 * 480 * 3];
+
+        let larger = BoundingBox::new(-100, -100, 1000, 1000);
+        let crop = DetectionService::crop_face_region(&frame, &larger, SnapshotFormat::Png, 0.2)
+            .expect("box overlapping the frame should be cropped");
+        let thumbnail = image::load_from_memory(&crop.encoded).unwrap();
+        assert_eq!(thumbnail.width(), 128);
+
+        let outside = BoundingBox::new(1000, 1000, 50, 50);
+        let err = DetectionService::crop_face_region(&frame, &outside, SnapshotFormat::Png, 0.2)
+            .err()
+            .unwrap();
+        assert!(err.contains("outside the 640x480 frame"), "{}", err);
+    }
+
+    #[test]
+    fn snapshot_format_parses_names() {
+        assert_eq!(
+            SnapshotFormat::from_name("JPEG", 0),
+            Some(SnapshotFormat::Jpeg { quality: 1 })
+        );
+        assert_eq!(
+            SnapshotFormat::from_name("webp", 80),
+            Some(SnapshotFormat::WebP)
+        );
+        assert_eq!(SnapshotFormat::from_name("gif", 80), None);
+        assert_eq!(SnapshotFormat::Png.extension(), "png");
+    }
+
+    #[test]
+    fn token_bucket_refills_over_time() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(60, start);
+
+        for _ in 0..60 {
+            assert!(bucket.try_take(60, start));
+        }
+        assert!(!bucket.try_take(60, start));
+
+        let later = start + std::time::Duration::from_secs(2);
+        assert!(bucket.try_take(60, later));
+        assert!(bucket.try_take(60, later));
+        assert!(!bucket.try_take(60, later));
+    }
+
+    #[test]
+    fn rate_limiter_tracks_cameras_independently() {
+        let now = Instant::now();
+        let mut limiter = CameraRateLimiter::default();
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+
+        assert!(limiter.allow(a, 1, now));
+        assert!(!limiter.allow(a, 1, now));
+        assert!(limiter.allow(b, 1, now));
+    }
+
+    #[tokio::test]
+    async fn profile_creation_is_capped_per_camera() {
+        let (service, _) = test_service(
+            Arc::new(InMemoryProfileRepo::default()),
+            Arc::new(InMemorySightingRepo::default()),
+            DetectionConfig {
+                max_new_profiles_per_minute: 5,
+                ..Default::default()
+            },
+        )
+        .await;
+        let (_snapshots, snapshot_dir) = snapshot_dir();
+
+        let mut frame = FrameDetections::new(Uuid::new_v4(), 1, 0);
+        for i in 0..20 {
+            frame.add_detection(crate::domain::entities::Detection::new(
+                BoundingBox::new(i * 10, 0, 10, 10),
+                0.9,
+            ));
+        }
+
+        let created = service
+            .process_frame(&mut frame, &snapshot_dir, None)
+            .await
+            .unwrap()
+            .created_profiles;
+
+        assert_eq!(created.len(), 5);
+        assert_eq!(service.profiles_created_count(), 5);
+        assert_eq!(service.rate_limited_count(), 15);
+    }
+
+    #[tokio::test]
+    async fn faces_below_min_area_are_skipped() {
+        let (service, _) = test_service(
+            Arc::new(InMemoryProfileRepo::default()),
+            Arc::new(InMemorySightingRepo::default()),
+            DetectionConfig {
+                min_face_area_px: 40 * 40,
+                ..Default::default()
+            },
+        )
+        .await;
+        let (_snapshots, snapshot_dir) = snapshot_dir();
+
+        let mut frame = FrameDetections::new(Uuid::new_v4(), 1, 0);
+        for size in [20, 40] {
+            frame.add_detection(crate::domain::entities::Detection::new(
+                BoundingBox::new(0, 0, size, size),
+                0.9,
+            ));
+        }
+
+        let created = service
+            .process_frame(&mut frame, &snapshot_dir, None)
+            .await
+            .unwrap()
+            .created_profiles;
+
+        assert_eq!(created.len(), 1);
+        assert_eq!(service.profiles_created_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn match_data_lands_on_the_right_detection() {
+        let sighting_repo = Arc::new(InMemorySightingRepo::default());
+        let (service, _) = test_service(
+            Arc::new(InMemoryProfileRepo::default()),
+            sighting_repo.clone(),
+            DetectionConfig::default(),
+        )
+        .await;
+        let (_snapshots, snapshot_dir) = snapshot_dir();
+
+        // A skipped low-confidence face, a new face, then the same face again,
+        // which matches the new profile through the matcher cache
+        let embedding = FaceEmbedding::new(vec![0.1; embedding_dimension()]);
+        let mut frame = FrameDetections::new(Uuid::new_v4(), 1, 0);
+        for (x, confidence) in [(0, 0.2), (20, 0.9), (40, 0.9)] {
+            let mut detection =
+                crate::domain::entities::Detection::new(BoundingBox::new(x, 0, 10, 10), confidence);
+            detection.set_embedding(embedding.clone());
+            frame.add_detection(detection);
+        }
+
+        let created = service
+            .process_frame(&mut frame, &snapshot_dir, None)
+            .await
+            .unwrap()
+            .created_profiles;
+
+        assert_eq!(created.len(), 1);
+        let detections = frame.detections();
+        assert_eq!(detections[0].matched_profile_id(), None);
+        assert_eq!(detections[1].matched_profile_id(), None);
+        assert_eq!(detections[2].matched_profile_id(), Some(created[0]));
+        assert_eq!(detections[2].match_distance(), Some(0.0));
+        assert!(detections.iter().all(|d| d.has_embedding()));
+
+        // Only the match is a sighting; the new profile's first face isn't
+        let sightings = sighting_repo.all();
+        assert_eq!(sightings.len(), 1);
+        assert_eq!(sightings[0].profile_id(), created[0]);
+    }
+
+    #[tokio::test]
+    async fn known_face_is_reported_as_matched_not_created() {
+        let known = Profile::new(FaceEmbedding::new(vec![0.1; embedding_dimension()]), None);
+        let (service, _) = test_service(
+            Arc::new(InMemoryProfileRepo::with([known.clone()])),
+            Arc::new(InMemorySightingRepo::default()),
+            DetectionConfig::default(),
+        )
+        .await;
+        let (_snapshots, snapshot_dir) = snapshot_dir();
+
+        // The known face, and a stranger
+        let mut frame = FrameDetections::new(Uuid::new_v4(), 1, 0);
+        for (x, value) in [(0, 0.1), (40, 0.9)] {
+            let mut detection =
+                crate::domain::entities::Detection::new(BoundingBox::new(x, 0, 10, 10), 0.9);
+            detection.set_embedding(FaceEmbedding::new(vec![value; embedding_dimension()]));
+            frame.add_detection(detection);
+        }
+
+        let outcome = service
+            .process_frame(&mut frame, &snapshot_dir, None)
+            .await
+            .unwrap();
+
+        assert_eq!(outcome.matched_profiles, vec![known.id()]);
+        assert_eq!(outcome.created_profiles.len(), 1);
+        assert!(!outcome.created_profiles.contains(&known.id()));
+
+        let results: Vec<(usize, bool)> = outcome
+            .detections
+            .iter()
+            .map(|d| (d.index, d.is_new))
+            .collect();
+        assert_eq!(results, vec![(0, false), (1, true)]);
+        assert_eq!(outcome.detections[0].profile_id, known.id());
+        assert!(outcome.detections[0].match_confidence > 0.9);
+    }
+
+    #[tokio::test]
+    async fn detection_only_mode_creates_no_profiles_or_sightings() {
+        let known = Profile::new(FaceEmbedding::new(vec![0.1; embedding_dimension()]), None);
+        let profile_repo = Arc::new(InMemoryProfileRepo::with([known.clone()]));
+        let sighting_repo = Arc::new(InMemorySightingRepo::default());
+        let (service, _) = test_service(
+            profile_repo.clone(),
+            sighting_repo.clone(),
+            DetectionConfig {
+                detection_only: true,
+                ..Default::default()
+            },
+        )
+        .await;
+        let mut events = service.event_bus.subscribe();
+        let (_snapshots, snapshot_dir) = snapshot_dir();
+
+        // The known face, a stranger, and a face without an embedding
+        let mut frame = FrameDetections::new(Uuid::new_v4(), 1, 0);
+        for (x, value) in [(0, Some(0.1)), (40, Some(0.9)), (80, None)] {
+            let mut detection =
+                crate::domain::entities::Detection::new(BoundingBox::new(x, 0, 10, 10), 0.9);
+            if let Some(value) = value {
+                detection.set_embedding(FaceEmbedding::new(vec![value; embedding_dimension()]));
+            }
+            frame.add_detection(detection);
+        }
+
+        let outcome = service
+            .process_frame(&mut frame, &snapshot_dir, None)
+            .await
+            .unwrap();
+
+        assert!(outcome.created_profiles.is_empty());
+        assert_eq!(outcome.matched_profiles, vec![known.id()]);
+        assert_eq!(frame.detections()[0].matched_profile_id(), Some(known.id()));
+        assert_eq!(profile_repo.all().len(), 1);
+        assert!(sighting_repo.all().is_empty());
+        assert_eq!(service.profiles_created_count(), 0);
+        assert_eq!(service.sightings_recorded_count(), 0);
+
+        // Every face is still published for the live view
+        let mut published = 0;
+        while let Some(event) = events.try_recv() {
+            assert!(matches!(&*event, DomainEvent::FaceDetected(_)));
+            published += 1;
+        }
+        assert_eq!(published, 3);
+    }
+
+    #[tokio::test]
+    async fn sightings_during_a_recording_link_to_it() {
+        let sighting_repo = Arc::new(InMemorySightingRepo::default());
+        let (service, _) = test_service(
+            Arc::new(InMemoryProfileRepo::default()),
+            sighting_repo.clone(),
+            DetectionConfig::default(),
+        )
+        .await;
+        let (_snapshots, snapshot_dir) = snapshot_dir();
+
+        // The first sighting of a face creates its profile, the second is recorded
+        let camera_id = Uuid::new_v4();
+        let recording = Recording::new(camera_id, "/recordings/clip.mp4".to_string());
+        let frame_ms = recording.started_at().timestamp_millis() + 1_500;
+        let embedding = FaceEmbedding::new(vec![0.1; embedding_dimension()]);
+        let mut frame = FrameDetections::new(camera_id, 1, frame_ms);
+        for x in [0, 40] {
+            let mut detection =
+                crate::domain::entities::Detection::new(BoundingBox::new(x, 0, 10, 10), 0.9);
+            detection.set_embedding(embedding.clone());
+            frame.add_detection(detection);
+        }
+
+        service
+            .process_frame(&mut frame, &snapshot_dir, Some(&recording))
+            .await
+            .unwrap();
+
+        let sightings = sighting_repo.all();
+        assert_eq!(sightings.len(), 1);
+        assert_eq!(sightings[0].recording_id(), Some(recording.id()));
+        assert_eq!(sightings[0].recording_timestamp_ms(), Some(1_500));
+    }
+
+    #[tokio::test]
+    async fn only_new_embedding_samples_are_saved() {
+        let known = FaceEmbedding::new(vec![0.1; embedding_dimension()]);
+        let profile = Profile::new(known.clone(), None);
+        let profile_id = profile.id();
+        let profile_repo = Arc::new(InMemoryProfileRepo::with([profile]));
+        let (service, _) = test_service(
+            profile_repo.clone(),
+            Arc::new(InMemorySightingRepo::default()),
+            DetectionConfig {
+                sighting_cooldown_secs: 0,
+                track_lifetime_ms: 0,
+                ..Default::default()
+            },
+        )
+        .await;
+        let (_snapshots, snapshot_dir) = snapshot_dir();
+        let camera_id = Uuid::new_v4();
+        let sight = |frame_number: u64, embedding: FaceEmbedding| {
+            let mut frame = FrameDetections::new(camera_id, frame_number, 0);
+            let mut detection =
+                crate::domain::entities::Detection::new(BoundingBox::new(0, 0, 10, 10), 0.9);
+            detection.set_embedding(embedding);
+            frame.add_detection(detection);
+            frame
+        };
+
+        // The stored face itself adds no sample, so only the profile row is written
+        service
+            .process_frame(&mut sight(1, known), &snapshot_dir, None)
+            .await
+            .unwrap();
+        assert_eq!(profile_repo.get(profile_id).unwrap().sighting_count(), 2);
+        assert_eq!(
+            profile_repo
+                .save_embedding_samples_calls
+                .load(Ordering::SeqCst),
+            0
+        );
+
+        // A confident match far enough from it is kept as a sample
+        let mut variant = vec![0.1; embedding_dimension()];
+        variant[0] += 0.2;
+        let variant = FaceEmbedding::new(variant);
+        service
+            .process_frame(&mut sight(2, variant.clone()), &snapshot_dir, None)
+            .await
+            .unwrap();
+        assert_eq!(
+            profile_repo
+                .save_embedding_samples_calls
+                .load(Ordering::SeqCst),
+            1
+        );
+        assert_eq!(
+            profile_repo.get(profile_id).unwrap().embeddings(),
+            [variant]
+        );
+    }
+
+    #[tokio::test]
+    async fn matched_sightings_carry_match_confidence() {
+        let sighting_repo = Arc::new(InMemorySightingRepo::default());
+        let (service, _) = test_service(
+            Arc::new(InMemoryProfileRepo::default()),
+            sighting_repo.clone(),
+            DetectionConfig::default(),
+        )
+        .await;
+        let (_snapshots, snapshot_dir) = snapshot_dir();
+
+        // A new face, then a close variant of it that is recognised
+        let mut frame = FrameDetections::new(Uuid::new_v4(), 1, 0);
+        for (x, value) in [(0, 0.1), (40, 0.11)] {
+            let mut detection =
+                crate::domain::entities::Detection::new(BoundingBox::new(x, 0, 10, 10), 0.9);
+            detection.set_embedding(FaceEmbedding::new(vec![value; embedding_dimension()]));
+            frame.add_detection(detection);
+        }
+
+        let created = service
+            .process_frame(&mut frame, &snapshot_dir, None)
+            .await
+            .unwrap()
+            .created_profiles;
+
+        assert_eq!(created.len(), 1);
+        let detections = frame.detections();
+        assert_eq!(detections[0].match_confidence(), None);
+        let confidence = detections[1].match_confidence().unwrap();
+        assert!(confidence > 0.0 && confidence <= 1.0);
+
+        let sightings = sighting_repo.all();
+        assert_eq!(sightings.len(), 1);
+        assert_eq!(sightings[0].match_confidence(), Some(confidence));
+        assert_eq!(sightings[0].confidence(), 0.9);
+    }
+
+    #[tokio::test]
+    async fn rate_limited_sightings_do_not_start_the_cooldown() {
+        let (front, back) = (
+            Profile::new(FaceEmbedding::new(vec![0.1; embedding_dimension()]), None),
+            Profile::new(FaceEmbedding::new(vec![0.9; embedding_dimension()]), None),
+        );
+        let sighting_repo = Arc::new(InMemorySightingRepo::default());
+        let (service, _) = test_service(
+            Arc::new(InMemoryProfileRepo::with([front.clone(), back.clone()])),
+            sighting_repo.clone(),
+            DetectionConfig {
+                max_sightings_per_minute: 1,
+                ..Default::default()
+            },
+        )
+        .await;
+        let (_snapshots, snapshot_dir) = snapshot_dir();
+
+        // Both known faces in one frame; only one sighting fits the limit
+        let camera_id = Uuid::new_v4();
+        let mut frame = FrameDetections::new(camera_id, 1, 0);
+        for (x, value) in [(0, 0.1), (40, 0.9)] {
+            let mut detection =
+                crate::domain::entities::Detection::new(BoundingBox::new(x, 0, 10, 10), 0.9);
+            detection.set_embedding(FaceEmbedding::new(vec![value; embedding_dimension()]));
+            frame.add_detection(detection);
+        }
+        service
+            .process_frame(&mut frame, &snapshot_dir, None)
+            .await
+            .unwrap();
+
+        let sightings = sighting_repo.all();
+        assert_eq!(sightings.len(), 1);
+        assert_eq!(service.rate_limited_count(), 1);
+        let dropped = if sightings[0].profile_id() == front.id() {
+            back.id()
+        } else {
+            front.id()
+        };
+        let tracker = service.sighting_tracker.read().await;
+        assert!(tracker.is_cooling_down(sightings[0].profile_id(), camera_id));
+        assert!(!tracker.is_cooling_down(dropped, camera_id));
+    }
+
+    #[test]
+    fn tracks_follow_moving_faces_and_expire() {
+        let camera_id = Uuid::new_v4();
+        let profile_id = Uuid::new_v4();
+        let start = Instant::now();
+        let mut tracks = FaceTracks::default();
+
+        tracks.observe(camera_id, profile_id, BoundingBox::new(0, 0, 40, 40), start);
+        tracks.observe(
+            camera_id,
+            profile_id,
+            BoundingBox::new(30, 0, 40, 40),
+            start,
+        );
+
+        // Predicted a further 30px right, where the unmoved box barely overlaps
+        let ahead = BoundingBox::new(60, 0, 40, 40);
+        assert_eq!(tracks.find(camera_id, &ahead, 0.3, &[]), Some(profile_id));
+        assert_eq!(tracks.find(camera_id, &ahead, 0.3, &[profile_id]), None);
+        assert_eq!(tracks.find(Uuid::new_v4(), &ahead, 0.3, &[]), None);
+        let elsewhere = BoundingBox::new(300, 300, 40, 40);
+        assert_eq!(tracks.find(camera_id, &elsewhere, 0.3, &[]), None);
+
+        tracks.expire(Duration::from_millis(500), start + Duration::from_secs(1));
+        assert_eq!(tracks.find(camera_id, &ahead, 0.3, &[]), None);
+    }
+
+    #[tokio::test]
+    async fn overlapping_faces_in_consecutive_frames_share_a_profile() {
+        let (service, _) = test_service(
+            Arc::new(InMemoryProfileRepo::default()),
+            Arc::new(InMemorySightingRepo::default()),
+            DetectionConfig::default(),
+        )
+        .await;
+        let (_snapshots, snapshot_dir) = snapshot_dir();
+        let camera_id = Uuid::new_v4();
+
+        // Embeddings too far apart to match, as when a head turns
+        let frame = |number: u64, x: i32, value: f32| {
+            let mut frame = FrameDetections::new(camera_id, number, 0);
+            let mut detection =
+                crate::domain::entities::Detection::new(BoundingBox::new(x, 100, 80, 80), 0.9);
+            detection.set_embedding(FaceEmbedding::new(vec![value; embedding_dimension()]));
+            frame.add_detection(detection);
+            frame
+        };
+
+        let mut first = frame(1, 100, 0.1);
+        let created = service
+            .process_frame(&mut first, &snapshot_dir, None)
+            .await
+            .unwrap()
+            .created_profiles;
+        assert_eq!(created.len(), 1);
+
+        let mut second = frame(2, 110, -0.1);
+        let outcome = service
+            .process_frame(&mut second, &snapshot_dir, None)
+            .await
+            .unwrap();
+        assert!(outcome.created_profiles.is_empty());
+        assert_eq!(outcome.matched_profiles, created);
+        assert_eq!(
+            second.detections()[0].matched_profile_id(),
+            Some(created[0])
+        );
+        assert_eq!(service.profiles_created_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn concurrent_frames_of_same_new_face_create_one_profile() {
+        let (service, _) = test_service(
+            Arc::new(InMemoryProfileRepo::default()),
+            Arc::new(InMemorySightingRepo::default()),
+            DetectionConfig::default(),
+        )
+        .await;
+        let (_snapshots, snapshot_dir) = snapshot_dir();
+
+        let frame = |value: f32| {
+            let mut frame = FrameDetections::new(Uuid::new_v4(), 1, 0);
+            let mut detection =
+                crate::domain::entities::Detection::new(BoundingBox::new(0, 0, 10, 10), 0.9);
+            detection.set_embedding(FaceEmbedding::new(vec![value; embedding_dimension()]));
+            frame.add_detection(detection);
+            frame
+        };
+        let mut first = frame(0.5);
+        let mut second = frame(0.501);
+
+        let (a, b) = tokio::join!(
+            service.process_frame(&mut first, &snapshot_dir, None),
+            service.process_frame(&mut second, &snapshot_dir, None),
+        );
+
+        assert_eq!(
+            a.unwrap().created_profiles.len() + b.unwrap().created_profiles.len(),
+            1
+        );
+        assert_eq!(service.profiles_created_count(), 1);
+    }
 }
@@ -5,8 +5,10 @@
 //!
 //! Reference: https://refactoring.guru/design-patterns/observer
 
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
 use tracing::debug;
 
 use crate::domain::events::DomainEvent;
@@ -15,9 +17,17 @@ use crate::domain::events::DomainEvent;
 const CHANNEL_CAPACITY: usize = 1024;
 
 /// Event bus for publishing and subscribing to domain events.
+///
+/// Publishing never waits on subscribers and cannot fail. A subscriber that
+/// falls more than the channel capacity behind skips the oldest events: each
+/// event evicted unread is counted in [`EventBus::dropped_count`] when it is
+/// pushed out, and the events a subscriber skipped are counted in
+/// [`EventBus::lagged_count`] once it reads again.
 #[derive(Clone)]
 pub struct EventBus {
     sender: broadcast::Sender<Arc<DomainEvent>>,
+    lagged: Arc<AtomicU64>,
+    dropped: Arc<AtomicU64>,
 }
 
 impl Default for EventBus {
@@ -30,14 +40,24 @@ impl EventBus {
     /// Creates a new event bus.
     pub fn new() -> Self {
         let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
-        Self { sender }
+        Self {
+            sender,
+            lagged: Arc::new(AtomicU64::new(0)),
+            dropped: Arc::new(AtomicU64::new(0)),
+        }
     }
 
-    /// Publishes an event to all subscribers.
+    /// Publishes an event to all subscribers without waiting on any of them.
     pub fn publish(&self, event: DomainEvent) {
         let event_type = event.event_type();
         let event = Arc::new(event);
 
+        // A full channel evicts its oldest event, unread by some subscriber.
+        // Racing publishers may each see room, so this can undercount slightly.
+        if self.sender.len() >= CHANNEL_CAPACITY {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+
         match self.sender.send(event) {
             Ok(count) => {
                 debug!("Published {} event to {} subscribers", event_type, count);
@@ -52,9 +72,35 @@ impl EventBus {
     pub fn subscribe(&self) -> EventSubscriber {
         EventSubscriber {
             receiver: self.sender.subscribe(),
+            lagged: self.lagged.clone(),
         }
     }
 
+    /// Runs `handler` on every event published from now on, in a spawned task.
+    /// The task ends when the bus is dropped or the handle is aborted.
+    pub fn register_handler<F>(&self, handler: F) -> JoinHandle<()>
+    where
+        F: Fn(Arc<DomainEvent>) + Send + 'static,
+    {
+        let mut subscriber = self.subscribe();
+        tokio::spawn(async move {
+            while let Some(event) = subscriber.recv().await {
+                handler(event);
+            }
+        })
+    }
+
+    /// Returns the number of events skipped by subscribers that fell behind.
+    pub fn lagged_count(&self) -> u64 {
+        self.lagged.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of events evicted before every subscriber read
+    /// them, including subscribers that have stopped reading altogether.
+    pub fn dropped_count(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
     /// Returns the number of active subscribers.
     pub fn subscriber_count(&self) -> usize {
         self.sender.receiver_count()
@@ -64,6 +110,7 @@ impl EventBus {
 /// A subscriber to domain events.
 pub struct EventSubscriber {
     receiver: broadcast::Receiver<Arc<DomainEvent>>,
+    lagged: Arc<AtomicU64>,
 }
 
 impl EventSubscriber {
@@ -73,15 +120,14 @@ impl EventSubscriber {
     ) -> std::pin::Pin<Box<dyn std::future::Future<Output = Option<Arc<DomainEvent>>> + Send + '_>>
     {
         Box::pin(async move {
-            match self.receiver.recv().await {
-                Ok(event) => Some(event),
-                Err(broadcast::error::RecvError::Closed) => None,
-                Err(broadcast::error::RecvError::Lagged(count)) => {
-                    tracing::warn!("Event subscriber lagged by {} events", count);
-                    // Try to get the next event without recursion
-                    match self.receiver.recv().await {
-                        Ok(event) => Some(event),
-                        Err(_) => None,
+            loop {
+                match self.receiver.recv().await {
+                    Ok(event) => return Some(event),
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                    Err(broadcast::error::RecvError::Lagged(count)) => {
+                        // Skip what was missed and carry on with the oldest event left
+                        tracing::warn!("Event subscriber lagged by {} events", count);
+                        self.lagged.fetch_add(count, Ordering::Relaxed);
                     }
                 }
             }
@@ -137,6 +183,78 @@ mod tests {
         assert!(sub2.recv().await.is_some());
     }
 
+    fn settings_changed(category: &str) -> DomainEvent {
+        DomainEvent::SettingsChanged(SettingsChangedEvent {
+            category: category.to_string(),
+            timestamp: Utc::now(),
+        })
+    }
+
+    #[tokio::test]
+    async fn registered_handler_observes_published_event() {
+        let bus = EventBus::new();
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let handle = bus.register_handler(move |event| {
+            let _ = tx.send(event.event_type());
+        });
+
+        bus.publish(settings_changed("detection"));
+
+        let observed = tokio::time::timeout(std::time::Duration::from_secs(1), rx.recv())
+            .await
+            .unwrap();
+        assert_eq!(observed, Some("settings_changed"));
+        handle.abort();
+    }
+
+    #[tokio::test]
+    async fn slow_subscriber_does_not_block_publisher() {
+        let bus = EventBus::new();
+        let mut subscriber = bus.subscribe();
+
+        for _ in 0..CHANNEL_CAPACITY + 10 {
+            bus.publish(settings_changed("test"));
+        }
+
+        assert!(subscriber.recv().await.is_some());
+        assert_eq!(bus.lagged_count(), 10);
+    }
+
+    #[tokio::test]
+    async fn stalled_subscriber_does_not_stall_publishers() {
+        let bus = EventBus::new();
+        let _stalled = bus.subscribe();
+        let mut reader = bus.subscribe();
+
+        let publishers: Vec<_> = (0..4)
+            .map(|_| {
+                let bus = bus.clone();
+                tokio::spawn(async move {
+                    for _ in 0..CHANNEL_CAPACITY {
+                        bus.publish(settings_changed("test"));
+                    }
+                })
+            })
+            .collect();
+        tokio::time::timeout(std::time::Duration::from_secs(5), async {
+            for publisher in publishers {
+                publisher.await.unwrap();
+            }
+        })
+        .await
+        .expect("publishers stalled");
+
+        assert_eq!(bus.dropped_count(), 3 * CHANNEL_CAPACITY as u64);
+
+        // A reader that fell behind resumes with the oldest event left
+        assert!(reader.recv().await.is_some());
+        assert_eq!(bus.lagged_count(), 3 * CHANNEL_CAPACITY as u64);
+        bus.publish(settings_changed("later"));
+        for _ in 0..CHANNEL_CAPACITY {
+            assert!(reader.recv().await.is_some());
+        }
+    }
+
     #[test]
     fn subscriber_count_tracks_active_subscribers() {
         let bus = EventBus::new();
@@ -3,10 +3,15 @@
 //! Matches detected faces against known profiles using embedding similarity.
 //! Reference: https://arxiv.org/abs/1503.03832 (FaceNet: A Unified Embedding for Face Recognition)
 
-use std::sync::Arc;
+use rayon::prelude::*;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
 use uuid::Uuid;
 
+use crate::domain::entities::Profile;
 use crate::domain::repositories::{ProfileRepository, RepoResult};
 use crate::domain::value_objects::FaceEmbedding;
 
@@ -18,13 +23,141 @@ pub struct MatchResult {
     pub confidence: f32,
 }
 
+/// Default number of profile embeddings kept in memory.
+pub const DEFAULT_CACHE_CAPACITY: usize = 10_000;
+
+/// Caches at least this large are matched in parallel.
+const PARALLEL_MATCH_MIN: usize = 2_048;
+
+/// A cached profile's embedding samples, primary embedding first.
+type CachedEmbedding = (Uuid, Vec<FaceEmbedding>);
+
+/// Minimum time between database searches for profiles evicted from the
+/// cache. Misses in between only search the cache.
+const REPO_SEARCH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Where a cached profile is and when it was last used.
+struct CacheSlot {
+    index: usize,
+    used: u64,
+}
+
+/// Profile embeddings held in memory, evicting the least recently matched
+/// entry once the capacity is exceeded.
+///
+/// Embeddings sit behind an `Arc` so matching can take a snapshot and release
+/// the lock before comparing.
+struct EmbeddingCache {
+    embeddings: Arc<Vec<CachedEmbedding>>,
+    slots: HashMap<Uuid, CacheSlot>,
+    /// Cached profiles by when they were last used, least recent first.
+    recency: BTreeMap<u64, Uuid>,
+    capacity: usize,
+    /// Monotonic counter recording when each entry was last used.
+    clock: u64,
+    /// Whether an entry was evicted since the cache was last loaded.
+    evicted: bool,
+}
+
+impl EmbeddingCache {
+    fn new(capacity: usize) -> Self {
+        Self {
+            embeddings: Arc::new(Vec::new()),
+            slots: HashMap::new(),
+            recency: BTreeMap::new(),
+            capacity: capacity.max(1),
+            clock: 0,
+            evicted: false,
+        }
+    }
+
+    fn tick(&mut self) -> u64 {
+        self.clock += 1;
+        self.clock
+    }
+
+    fn len(&self) -> usize {
+        self.embeddings.len()
+    }
+
+    fn clear(&mut self) {
+        self.embeddings = Arc::new(Vec::new());
+        self.slots.clear();
+        self.recency.clear();
+        self.evicted = false;
+    }
+
+    /// Inserts or replaces a profile's embeddings as the most recently used
+    /// entry.
+    fn insert(&mut self, profile_id: Uuid, samples: Vec<FaceEmbedding>) {
+        let embeddings = Arc::make_mut(&mut self.embeddings);
+        match self.slots.get(&profile_id) {
+            Some(slot) => embeddings[slot.index].1 = samples,
+            None => {
+                embeddings.push((profile_id, samples));
+                let index = embeddings.len() - 1;
+                let used = self.tick();
+                self.slots.insert(profile_id, CacheSlot { index, used });
+                self.recency.insert(used, profile_id);
+            }
+        }
+        self.touch(profile_id);
+
+        if self.len() > self.capacity {
+            if let Some((_, oldest)) = self.recency.pop_first() {
+                self.remove(oldest);
+                self.evicted = true;
+            }
+        }
+    }
+
+    /// Marks an entry as just matched.
+    fn touch(&mut self, profile_id: Uuid) {
+        let now = self.tick();
+        if let Some(slot) = self.slots.get_mut(&profile_id) {
+            self.recency.remove(&slot.used);
+            slot.used = now;
+            self.recency.insert(now, profile_id);
+        }
+    }
+
+    fn remove(&mut self, profile_id: Uuid) {
+        let Some(slot) = self.slots.remove(&profile_id) else {
+            return;
+        };
+        self.recency.remove(&slot.used);
+        let embeddings = Arc::make_mut(&mut self.embeddings);
+        embeddings.swap_remove(slot.index);
+        // The last entry moved into the freed place
+        if let Some((moved, _)) = embeddings.get(slot.index) {
+            if let Some(moved) = self.slots.get_mut(moved) {
+                moved.index = slot.index;
+            }
+        }
+    }
+
+    /// Whether profiles were evicted, so the cache can miss matches.
+    fn has_evicted(&self) -> bool {
+        self.evicted
+    }
+
+    /// Returns the current embeddings without copying them.
+    fn snapshot(&self) -> Arc<Vec<CachedEmbedding>> {
+        self.embeddings.clone()
+    }
+}
+
 /// Service for matching face embeddings to profiles.
 pub struct FaceMatcher {
     profile_repo: Arc<dyn ProfileRepository>,
     /// Cached embeddings for fast matching.
-    embedding_cache: RwLock<Vec<(Uuid, FaceEmbedding)>>,
+    embedding_cache: RwLock<EmbeddingCache>,
     /// Match threshold (lower = stricter matching).
     threshold: RwLock<f32>,
+    cache_hits: AtomicU64,
+    cache_misses: AtomicU64,
+    /// When the database was last searched for an evicted profile.
+    last_repo_search: Mutex<Option<Instant>>,
 }
 
 impl FaceMatcher {
@@ -32,8 +165,21 @@ impl FaceMatcher {
     pub fn new(profile_repo: Arc<dyn ProfileRepository>, threshold: f32) -> Self {
         Self {
             profile_repo,
-            embedding_cache: RwLock::new(Vec::new()),
+            embedding_cache: RwLock::new(EmbeddingCache::new(DEFAULT_CACHE_CAPACITY)),
             threshold: RwLock::new(threshold),
+            cache_hits: AtomicU64::new(0),
+            cache_misses: AtomicU64::new(0),
+            last_repo_search: Mutex::new(None),
+        }
+    }
+
+    /// Sets how many profile embeddings are kept in memory.
+    /// Beyond this, the least recently matched profiles are evicted and
+    /// looked up in the database when the cache finds no match.
+    pub fn with_cache_capacity(self, capacity: usize) -> Self {
+        Self {
+            embedding_cache: RwLock::new(EmbeddingCache::new(capacity)),
+            ..self
         }
     }
 
@@ -43,8 +189,9 @@ impl FaceMatcher {
         let mut cache = self.embedding_cache.write().await;
         cache.clear();
 
-        for profile in profiles {
-            cache.push((profile.id(), profile.embedding().clone()));
+        // Placeholder embeddings would all match each other
+        for profile in profiles.iter().filter(|p| p.has_embedding()) {
+            cache.insert(profile.id(), profile.all_embeddings().cloned().collect());
         }
 
         tracing::info!("Loaded {} profile embeddings into cache", cache.len());
@@ -54,13 +201,20 @@ impl FaceMatcher {
     /// Adds a profile embedding to the cache.
     pub async fn add_to_cache(&self, profile_id: Uuid, embedding: FaceEmbedding) {
         let mut cache = self.embedding_cache.write().await;
-        cache.push((profile_id, embedding));
+        cache.insert(profile_id, vec![embedding]);
+    }
+
+    /// Adds or refreshes a profile in the cache with all its embedding
+    /// samples.
+    pub async fn cache_profile(&self, profile: &Profile) {
+        let mut cache = self.embedding_cache.write().await;
+        cache.insert(profile.id(), profile.all_embeddings().cloned().collect());
     }
 
     /// Removes a profile from the cache.
     pub async fn remove_from_cache(&self, profile_id: Uuid) {
         let mut cache = self.embedding_cache.write().await;
-        cache.retain(|(id, _)| *id != profile_id);
+        cache.remove(profile_id);
     }
 
     /// Updates the match threshold.
@@ -73,29 +227,43 @@ impl FaceMatcher {
         *self.threshold.read().await
     }
 
-    /// Finds the best matching profile for an embedding.
-    /// Returns None if no profile is within the threshold.
+    /// Finds the best matching profile for an embedding, by its nearest
+    /// sample. Returns None if no profile is within the threshold.
+    ///
+    /// Searches the cache first. Once profiles have been evicted, a miss
+    /// also searches the database, at most once per
+    /// [`REPO_SEARCH_INTERVAL`], since that search compares every profile.
     pub async fn find_match(&self, embedding: &FaceEmbedding) -> Option<MatchResult> {
-        let cache = self.embedding_cache.read().await;
         let threshold = *self.threshold.read().await;
 
-        let mut best_match: Option<(Uuid, f32)> = None;
-
-        for (profile_id, stored_embedding) in cache.iter() {
-            let distance = embedding.distance(stored_embedding);
+        let (snapshot, evicted) = {
+            let cache = self.embedding_cache.read().await;
+            (cache.snapshot(), cache.has_evicted())
+        };
+        let query = embedding.clone();
+        let cached = search_cache(snapshot.clone(), move |candidates, parallel| {
+            best_match(candidates, &query, threshold, parallel)
+        })
+        .await;
 
-            if distance < threshold {
-                match &best_match {
-                    None => best_match = Some((*profile_id, distance)),
-                    Some((_, best_distance)) if distance < *best_distance => {
-                        best_match = Some((*profile_id, distance));
-                    }
-                    _ => {}
+        let best = match cached {
+            Some((profile_id, distance)) => {
+                self.cache_hits.fetch_add(1, Ordering::Relaxed);
+                self.embedding_cache.write().await.touch(profile_id);
+                Some((profile_id, distance))
+            }
+            None => {
+                self.cache_misses.fetch_add(1, Ordering::Relaxed);
+                if evicted && self.claim_repo_search() {
+                    self.find_match_in_repo(embedding, threshold, &snapshot)
+                        .await
+                } else {
+                    None
                 }
             }
-        }
+        };
 
-        best_match.map(|(profile_id, distance)| {
+        best.map(|(profile_id, distance)| {
             let confidence = Self::distance_to_confidence(distance, threshold);
             MatchResult {
                 profile_id,
@@ -105,22 +273,53 @@ impl FaceMatcher {
         })
     }
 
+    /// Whether a miss may search the database now, recording the search.
+    fn claim_repo_search(&self) -> bool {
+        let mut last = self.last_repo_search.lock().unwrap();
+        if last.is_some_and(|at| at.elapsed() < REPO_SEARCH_INTERVAL) {
+            return false;
+        }
+        *last = Some(Instant::now());
+        true
+    }
+
+    /// Looks up an evicted profile in the database and caches it again.
+    ///
+    /// The cached profiles were just searched, so only the rest are loaded.
+    async fn find_match_in_repo(
+        &self,
+        embedding: &FaceEmbedding,
+        threshold: f32,
+        cached: &[CachedEmbedding],
+    ) -> Option<(Uuid, f32)> {
+        let excluded: Vec<Uuid> = cached.iter().map(|(profile_id, _)| *profile_id).collect();
+        let matches = match self
+            .profile_repo
+            .find_by_embedding(embedding, threshold, &excluded)
+            .await
+        {
+            Ok(matches) => matches,
+            Err(e) => {
+                tracing::warn!("Failed to search profiles by embedding: {}", e);
+                return None;
+            }
+        };
+
+        let (profile, distance) = matches.into_iter().next()?;
+        self.cache_profile(&profile).await;
+        Some((profile.id(), distance))
+    }
+
     /// Finds all profiles within the threshold, sorted by distance.
     pub async fn find_all_matches(&self, embedding: &FaceEmbedding) -> Vec<MatchResult> {
-        let cache = self.embedding_cache.read().await;
+        let snapshot = self.embedding_cache.read().await.snapshot();
         let threshold = *self.threshold.read().await;
 
-        let mut matches: Vec<_> = cache
-            .iter()
-            .map(|(profile_id, stored_embedding)| {
-                let distance = embedding.distance(stored_embedding);
-                (*profile_id, distance)
-            })
-            .filter(|(_, distance)| *distance < threshold)
-            .collect();
-
-        matches.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
-
+        let query = embedding.clone();
+        let matches = search_cache(snapshot, move |candidates, parallel| {
+            all_matches(candidates, &query, threshold, parallel)
+        })
+        .await;
         matches
             .into_iter()
             .map(|(profile_id, distance)| {
@@ -134,9 +333,31 @@ impl FaceMatcher {
             .collect()
     }
 
+    /// Finds the cached profile closest to `profile`, other than itself, by
+    /// the nearest pair of samples. Returns None for profiles without an
+    /// embedding or when no other profile is cached.
+    pub async fn nearest_other_profile(&self, profile: &Profile) -> Option<(Uuid, f32)> {
+        if !profile.has_embedding() {
+            return None;
+        }
+
+        let snapshot = self.embedding_cache.read().await.snapshot();
+        snapshot
+            .iter()
+            .filter(|(profile_id, _)| *profile_id != profile.id())
+            .map(|(profile_id, samples)| {
+                let distance = profile
+                    .all_embeddings()
+                    .map(|sample| nearest_distance(samples, sample))
+                    .fold(f32::INFINITY, f32::min);
+                (*profile_id, distance)
+            })
+            .min_by(closer)
+    }
+
     /// Converts a distance to a confidence score (0.0-1.0).
     /// Lower distance = higher confidence.
-    fn distance_to_confidence(distance: f32, threshold: f32) -> f32 {
+    pub fn distance_to_confidence(distance: f32, threshold: f32) -> f32 {
         (1.0 - (distance / threshold)).max(0.0).min(1.0)
     }
 
@@ -144,62 +365,144 @@ impl FaceMatcher {
     pub async fn cache_size(&self) -> usize {
         self.embedding_cache.read().await.len()
     }
+
+    /// Returns how many matches were found in the cache.
+    pub fn cache_hits(&self) -> u64 {
+        self.cache_hits.load(Ordering::Relaxed)
+    }
+
+    /// Returns how many lookups found no match in the cache.
+    pub fn cache_misses(&self) -> u64 {
+        self.cache_misses.load(Ordering::Relaxed)
+    }
+}
+
+/// Orders matches by distance, then by profile ID so ties resolve the same
+/// way whether matching ran serially or in parallel.
+fn closer(a: &(Uuid, f32), b: &(Uuid, f32)) -> std::cmp::Ordering {
+    a.1.partial_cmp(&b.1)
+        .unwrap_or(std::cmp::Ordering::Equal)
+        .then_with(|| a.0.cmp(&b.0))
+}
+
+/// Distance from `embedding` to the nearest of a profile's samples.
+fn nearest_distance(samples: &[FaceEmbedding], embedding: &FaceEmbedding) -> f32 {
+    samples
+        .iter()
+        .map(|sample| embedding.distance(sample))
+        .fold(f32::INFINITY, f32::min)
+}
+
+/// Runs `search` over the cached embeddings. Caches of at least
+/// [`PARALLEL_MATCH_MIN`] are searched in parallel on the blocking pool, so
+/// rayon does not tie up a runtime worker.
+async fn search_cache<T, F>(snapshot: Arc<Vec<CachedEmbedding>>, search: F) -> T
+where
+    T: Default + Send + 'static,
+    F: FnOnce(&[CachedEmbedding], bool) -> T + Send + 'static,
+{
+    if snapshot.len() < PARALLEL_MATCH_MIN {
+        return search(&snapshot, false);
+    }
+    tokio::task::spawn_blocking(move || search(&snapshot, true))
+        .await
+        .unwrap_or_else(|e| {
+            tracing::warn!("Embedding search failed: {}", e);
+            T::default()
+        })
+}
+
+/// Returns the closest embedding within the threshold.
+fn best_match(
+    candidates: &[CachedEmbedding],
+    embedding: &FaceEmbedding,
+    threshold: f32,
+    parallel: bool,
+) -> Option<(Uuid, f32)> {
+    let distance = |(profile_id, samples): &CachedEmbedding| {
+        (*profile_id, nearest_distance(samples, embedding))
+    };
+    if parallel {
+        candidates
+            .par_iter()
+            .map(distance)
+            .filter(|(_, d)| *d < threshold)
+            .min_by(closer)
+    } else {
+        candidates
+            .iter()
+            .map(distance)
+            .filter(|(_, d)| *d < threshold)
+            .min_by(closer)
+    }
+}
+
+/// Returns every embedding within the threshold, closest first.
+fn all_matches(
+    candidates: &[CachedEmbedding],
+    embedding: &FaceEmbedding,
+    threshold: f32,
+    parallel: bool,
+) -> Vec<(Uuid, f32)> {
+    let distance = |(profile_id, samples): &CachedEmbedding| {
+        (*profile_id, nearest_distance(samples, embedding))
+    };
+    let mut matches: Vec<_> = if parallel {
+        candidates
+            .par_iter()
+            .map(distance)
+            .filter(|(_, d)| *d < threshold)
+            .collect()
+    } else {
+        candidates
+            .iter()
+            .map(distance)
+            .filter(|(_, d)| *d < threshold)
+            .collect()
+    };
+    matches.sort_by(closer);
+    matches
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::domain::entities::Profile;
+    use crate::domain::repositories::fakes::InMemoryProfileRepo;
     use crate::domain::value_objects::EMBEDDING_DIMENSION;
-    use async_trait::async_trait;
     use std::sync::Arc;
 
-    struct MockProfileRepo;
-
-    #[async_trait]
-    impl ProfileRepository for MockProfileRepo {
-        async fn find_by_id(&self, _id: Uuid) -> RepoResult<Option<Profile>> {
-            Ok(None)
-        }
-        async fn find_all_active(&self) -> RepoResult<Vec<Profile>> {
-            Ok(vec![])
-        }
-        async fn find_by_embedding(
-            &self,
-            _: &FaceEmbedding,
-            _: f32,
-        ) -> RepoResult<Vec<(Profile, f32)>> {
-            Ok(vec![])
-        }
-        async fn save(&self, _: &Profile) -> RepoResult<()> {
-            Ok(())
-        }
-        async fn update(&self, _: &Profile) -> RepoResult<()> {
-            Ok(())
-        }
-        async fn delete(&self, _: Uuid) -> RepoResult<()> {
-            Ok(())
-        }
-        async fn count(&self) -> RepoResult<i64> {
-            Ok(0)
-        }
-    }
-
     fn create_embedding(value: f32) -> FaceEmbedding {
         FaceEmbedding::new(vec![value; EMBEDDING_DIMENSION])
     }
 
     #[tokio::test]
     async fn find_match_returns_none_when_empty_cache() {
-        let matcher = FaceMatcher::new(Arc::new(MockProfileRepo), 0.6);
+        let matcher = FaceMatcher::new(Arc::new(InMemoryProfileRepo::default()), 0.6);
         let embedding = create_embedding(0.5);
         let result = matcher.find_match(&embedding).await;
         assert!(result.is_none());
     }
 
+    #[tokio::test]
+    async fn profiles_without_embedding_never_match() {
+        let repo = InMemoryProfileRepo::with([
+            Profile::without_embedding(None),
+            Profile::without_embedding(None),
+            Profile::new(create_embedding(0.5), None),
+        ]);
+        let matcher = FaceMatcher::new(Arc::new(repo), 0.6);
+
+        matcher.load_cache().await.unwrap();
+        assert_eq!(matcher.cache_size().await, 1);
+
+        let placeholder = FaceEmbedding::zeros(EMBEDDING_DIMENSION);
+        assert!(matcher.find_match(&placeholder).await.is_none());
+    }
+
     #[tokio::test]
     async fn find_match_returns_best_match_within_threshold() {
-        let matcher = FaceMatcher::new(Arc::new(MockProfileRepo), 0.6);
+        let matcher = FaceMatcher::new(Arc::new(InMemoryProfileRepo::default()), 0.6);
 
         let profile_id = Uuid::new_v4();
         matcher
@@ -215,7 +518,7 @@ mod tests {
 
     #[tokio::test]
     async fn find_match_returns_none_when_outside_threshold() {
-        let matcher = FaceMatcher::new(Arc::new(MockProfileRepo), 0.1);
+        let matcher = FaceMatcher::new(Arc::new(InMemoryProfileRepo::default()), 0.1);
 
         matcher
             .add_to_cache(Uuid::new_v4(), create_embedding(0.0))
@@ -229,7 +532,7 @@ mod tests {
 
     #[tokio::test]
     async fn remove_from_cache_removes_profile() {
-        let matcher = FaceMatcher::new(Arc::new(MockProfileRepo), 0.6);
+        let matcher = FaceMatcher::new(Arc::new(InMemoryProfileRepo::default()), 0.6);
         let profile_id = Uuid::new_v4();
 
         matcher
@@ -240,4 +543,209 @@ mod tests {
         matcher.remove_from_cache(profile_id).await;
         assert_eq!(matcher.cache_size().await, 0);
     }
+
+    #[tokio::test]
+    async fn profile_matches_by_its_closest_sample() {
+        let matcher = FaceMatcher::new(Arc::new(InMemoryProfileRepo::default()), 0.6);
+        let mut profile = Profile::new(create_embedding(0.0), None);
+        assert!(profile.add_embedding_sample(create_embedding(0.5), 0.3));
+        let other = Profile::new(create_embedding(0.48), None);
+        matcher.cache_profile(&profile).await;
+        matcher.cache_profile(&other).await;
+
+        // Nearer the profile's second sample than the other profile
+        let result = matcher.find_match(&create_embedding(0.499)).await.unwrap();
+        assert_eq!(result.profile_id, profile.id());
+        assert!(result.distance < create_embedding(0.499).distance(other.embedding()));
+
+        // Nearer the other profile than any of the first profile's samples
+        let result = matcher.find_match(&create_embedding(0.47)).await.unwrap();
+        assert_eq!(result.profile_id, other.id());
+        assert_eq!(matcher.cache_size().await, 2);
+    }
+
+    #[tokio::test]
+    async fn nearest_other_profile_skips_the_profile_itself() {
+        let matcher = FaceMatcher::new(Arc::new(InMemoryProfileRepo::default()), 0.6);
+        let mut profile = Profile::new(create_embedding(0.0), None);
+        assert!(profile.add_embedding_sample(create_embedding(0.5), 0.3));
+        let near = Profile::new(create_embedding(0.45), None);
+        let far = Profile::new(create_embedding(0.9), None);
+        for p in [&profile, &near, &far] {
+            matcher.cache_profile(p).await;
+        }
+
+        let (nearest, distance) = matcher.nearest_other_profile(&profile).await.unwrap();
+        assert_eq!(nearest, near.id());
+        assert_eq!(distance, create_embedding(0.5).distance(near.embedding()));
+
+        let placeholder = Profile::without_embedding(None);
+        assert!(matcher.nearest_other_profile(&placeholder).await.is_none());
+    }
+
+    #[test]
+    fn cache_evicts_least_recently_used_entry() {
+        let (a, b, c) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let mut cache = EmbeddingCache::new(2);
+        cache.insert(a, vec![create_embedding(0.0)]);
+        cache.insert(b, vec![create_embedding(0.5)]);
+        cache.touch(a);
+        cache.insert(c, vec![create_embedding(1.0)]);
+
+        let mut ids: Vec<Uuid> = cache.snapshot().iter().map(|(id, _)| *id).collect();
+        ids.sort();
+        let mut expected = vec![a, c];
+        expected.sort();
+        assert_eq!(ids, expected);
+    }
+
+    #[tokio::test]
+    async fn cache_respects_capacity() {
+        let matcher =
+            FaceMatcher::new(Arc::new(InMemoryProfileRepo::default()), 0.6).with_cache_capacity(3);
+
+        for i in 0..10 {
+            matcher
+                .add_to_cache(Uuid::new_v4(), create_embedding(i as f32))
+                .await;
+        }
+
+        assert_eq!(matcher.cache_size().await, 3);
+    }
+
+    #[tokio::test]
+    async fn matching_keeps_profile_cached() {
+        let matcher =
+            FaceMatcher::new(Arc::new(InMemoryProfileRepo::default()), 0.6).with_cache_capacity(2);
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+        matcher.add_to_cache(a, create_embedding(0.0)).await;
+        matcher.add_to_cache(b, create_embedding(0.5)).await;
+
+        // Matching `a` makes `b` the least recently used entry
+        assert!(matcher.find_match(&create_embedding(0.0)).await.is_some());
+        matcher
+            .add_to_cache(Uuid::new_v4(), create_embedding(1.0))
+            .await;
+
+        assert!(matcher.find_match(&create_embedding(0.0)).await.is_some());
+        assert!(matcher.find_match(&create_embedding(0.5)).await.is_none());
+        assert_eq!(matcher.cache_hits(), 2);
+        assert_eq!(matcher.cache_misses(), 1);
+    }
+
+    #[tokio::test]
+    async fn evicted_profile_is_found_in_repository() {
+        let evicted = Profile::new(create_embedding(0.5), None);
+        let evicted_id = evicted.id();
+        let cached = Profile::new(create_embedding(0.0), None);
+        let cached_id = cached.id();
+        let repo = Arc::new(InMemoryProfileRepo::with([evicted.clone(), cached.clone()]));
+        let matcher = FaceMatcher::new(repo.clone(), 0.6).with_cache_capacity(1);
+        matcher
+            .add_to_cache(evicted_id, evicted.embedding().clone())
+            .await;
+        matcher
+            .add_to_cache(cached_id, cached.embedding().clone())
+            .await;
+
+        let result = matcher.find_match(&create_embedding(0.5)).await.unwrap();
+
+        assert_eq!(result.profile_id, evicted_id);
+        assert_eq!(matcher.cache_misses(), 1);
+        // Only profiles missing from the cache are loaded
+        assert_eq!(
+            *repo.find_by_embedding_excluded.lock().unwrap(),
+            vec![cached_id]
+        );
+        // The profile is cached again, so the next match is a hit
+        assert!(matcher.find_match(&create_embedding(0.5)).await.is_some());
+        assert_eq!(matcher.cache_hits(), 1);
+    }
+
+    #[tokio::test]
+    async fn repository_is_searched_only_after_an_eviction() {
+        let repo = Arc::new(InMemoryProfileRepo::default());
+        let matcher = FaceMatcher::new(repo.clone(), 0.1).with_cache_capacity(2);
+        let unknown = create_embedding(1.0);
+
+        // Exactly at capacity, nothing has been evicted
+        matcher
+            .add_to_cache(Uuid::new_v4(), create_embedding(0.0))
+            .await;
+        matcher
+            .add_to_cache(Uuid::new_v4(), create_embedding(0.5))
+            .await;
+        assert!(matcher.find_match(&unknown).await.is_none());
+        assert_eq!(repo.find_by_embedding_calls.load(Ordering::SeqCst), 0);
+
+        // After one, repeated misses search it once per interval
+        matcher
+            .add_to_cache(Uuid::new_v4(), create_embedding(0.7))
+            .await;
+        for _ in 0..5 {
+            assert!(matcher.find_match(&unknown).await.is_none());
+        }
+        assert_eq!(repo.find_by_embedding_calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn cache_keeps_entries_findable_after_evictions() {
+        let ids: Vec<Uuid> = (0..5).map(|_| Uuid::new_v4()).collect();
+        let mut cache = EmbeddingCache::new(3);
+        for (i, id) in ids.iter().enumerate() {
+            cache.insert(*id, vec![create_embedding(i as f32)]);
+        }
+        cache.remove(ids[3]);
+
+        // What's left is the newest entry, at the index its slot records
+        assert_eq!(cache.len(), 2);
+        for id in [ids[2], ids[4]] {
+            let slot = &cache.slots[&id];
+            assert_eq!(cache.snapshot()[slot.index].0, id);
+        }
+        assert_eq!(cache.recency.len(), 2);
+        assert!(cache.has_evicted());
+    }
+
+    #[tokio::test]
+    async fn large_caches_are_searched_off_the_runtime() {
+        let runtime = std::thread::current().id();
+        let where_searched = |size: usize| {
+            let snapshot = Arc::new(vec![(Uuid::new_v4(), vec![create_embedding(0.1)]); size]);
+            search_cache(snapshot, |_, parallel| {
+                (Some(std::thread::current().id()), parallel)
+            })
+        };
+
+        assert_eq!(where_searched(1).await, (Some(runtime), false));
+        let (thread, parallel) = where_searched(PARALLEL_MATCH_MIN).await;
+        assert!(parallel);
+        assert_ne!(thread, Some(runtime));
+    }
+
+    #[test]
+    fn parallel_and_serial_matching_agree() {
+        // Embeddings on a grid, with duplicates so ties must break the same way
+        let candidates: Vec<CachedEmbedding> = (0..3_000)
+            .map(|i| {
+                let values = (0..EMBEDDING_DIMENSION)
+                    .map(|d| ((i % 300) as f32 * 0.01 + d as f32 * 0.001).sin())
+                    .collect();
+                (Uuid::new_v4(), vec![FaceEmbedding::new(values)])
+            })
+            .collect();
+        let query = candidates[42].1[0].clone();
+
+        for threshold in [0.5, 2.0, 8.0] {
+            assert_eq!(
+                best_match(&candidates, &query, threshold, true),
+                best_match(&candidates, &query, threshold, false)
+            );
+            assert_eq!(
+                all_matches(&candidates, &query, threshold, true),
+                all_matches(&candidates, &query, threshold, false)
+            );
+        }
+        assert!(!all_matches(&candidates, &query, 2.0, false).is_empty());
+    }
 }
@@ -0,0 +1,97 @@
+//! Face Quality
+//!
+//! Scores face crops so profiles can keep their best thumbnail.
+//! Sharpness uses the variance of the Laplacian.
+//! Reference: https://pyimagesearch.com/2015/09/07/blur-detection-with-opencv/
+
+use image::{DynamicImage, GrayImage};
+
+/// Laplacian variance at which a crop counts as half sharp.
+const SHARPNESS_SCALE: f32 = 100.0;
+
+/// Face area (in pixels) at which a crop gets the full size score.
+const REFERENCE_AREA: f32 = 160.0 * 160.0;
+
+const SHARPNESS_WEIGHT: f32 = 0.5;
+const AREA_WEIGHT: f32 = 0.3;
+const FRONTAL_WEIGHT: f32 = 0.2;
+
+/// Scores a face crop between 0.0 (unusable) and 1.0 (sharp, large, frontal).
+///
+/// Combines sharpness, crop area, and frontal-ness. Frontal-ness is estimated
+/// from the aspect ratio: side-angle faces give narrow boxes.
+pub fn face_quality(face: &DynamicImage) -> f32 {
+    let (width, height) = (face.width() as f32, face.height() as f32);
+    if width == 0.0 || height == 0.0 {
+        return 0.0;
+    }
+
+    let variance = laplacian_variance(&face.to_luma8());
+    let sharpness = variance / (variance + SHARPNESS_SCALE);
+    let area = (width * height / REFERENCE_AREA).min(1.0);
+    let frontal = (width / height).min(height / width);
+
+    SHARPNESS_WEIGHT * sharpness + AREA_WEIGHT * area + FRONTAL_WEIGHT * frontal
+}
+
+/// Variance of the 4-neighbour Laplacian. Blurry images have low variance.
+fn laplacian_variance(gray: &GrayImage) -> f32 {
+    let (width, height) = gray.dimensions();
+    if width < 3 || height < 3 {
+        return 0.0;
+    }
+
+    let pixel = |x: u32, y: u32| gray.get_pixel(x, y)[0] as f32;
+    let mut sum = 0.0;
+    let mut sum_sq = 0.0;
+    for y in 1..height - 1 {
+        for x in 1..width - 1 {
+            let laplacian = pixel(x - 1, y) + pixel(x + 1, y) + pixel(x, y - 1) + pixel(x, y + 1)
+                - 4.0 * pixel(x, y);
+            sum += laplacian;
+            sum_sq += laplacian * laplacian;
+        }
+    }
+
+    let count = ((width - 2) * (height - 2)) as f32;
+    let mean = sum / count;
+    sum_sq / count - mean * mean
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageBuffer, Luma};
+
+    fn checkerboard(width: u32, height: u32) -> DynamicImage {
+        let img = ImageBuffer::from_fn(width, height, |x, y| {
+            let dark = (x / 2 + y / 2).is_multiple_of(2);
+            Luma([if dark { 30u8 } else { 220u8 }])
+        });
+        DynamicImage::ImageLuma8(img)
+    }
+
+    fn gradient(width: u32, height: u32) -> DynamicImage {
+        let img = ImageBuffer::from_fn(width, height, |x, _| Luma([(x % 256) as u8]));
+        DynamicImage::ImageLuma8(img)
+    }
+
+    #[test]
+    fn sharp_crop_scores_higher_than_blurry_crop() {
+        let sharp = checkerboard(128, 128);
+        let blurry = gradient(128, 128);
+        assert!(face_quality(&sharp) > face_quality(&blurry));
+    }
+
+    #[test]
+    fn frontal_crop_scores_higher_than_side_crop() {
+        let frontal = checkerboard(120, 120);
+        let side = checkerboard(60, 240);
+        assert!(face_quality(&frontal) > face_quality(&side));
+    }
+
+    #[test]
+    fn empty_crop_scores_zero() {
+        assert_eq!(face_quality(&DynamicImage::new_luma8(0, 0)), 0.0);
+    }
+}
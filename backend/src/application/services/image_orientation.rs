@@ -0,0 +1,168 @@
+//! Image Orientation
+//!
+//! Applies the EXIF orientation of JPEGs, so photos taken on phones are
+//! upright before faces are detected or cropped from them.
+//! Reference: https://www.exif.org/Exif2-2.PDF (tag 0x0112)
+
+use image::{DynamicImage, ImageFormat, ImageResult};
+
+const ORIENTATION_TAG: u16 = 0x0112;
+const EXIF_HEADER: &[u8] = b"Exif\0\0";
+
+/// Decodes a JPEG and rotates or flips it as its EXIF orientation says.
+pub fn decode_jpeg_upright(data: &[u8]) -> ImageResult<DynamicImage> {
+    let img = image::load_from_memory_with_format(data, ImageFormat::Jpeg)?;
+    Ok(match jpeg_orientation(data) {
+        Some(orientation) => apply_orientation(img, orientation),
+        None => img,
+    })
+}
+
+/// Returns the EXIF orientation (1-8) of a JPEG, if it has one.
+pub fn jpeg_orientation(data: &[u8]) -> Option<u16> {
+    if !data.starts_with(&[0xFF, 0xD8]) {
+        return None;
+    }
+
+    let mut pos = 2;
+    while pos + 4 <= data.len() {
+        if data[pos] != 0xFF {
+            return None;
+        }
+        let marker = data[pos + 1];
+        // Padding before a marker
+        if marker == 0xFF {
+            pos += 1;
+            continue;
+        }
+        // Image data starts; EXIF comes before it
+        if marker == 0xDA || marker == 0xD9 {
+            return None;
+        }
+
+        let length = u16::from_be_bytes([data[pos + 2], data[pos + 3]]) as usize;
+        let segment = data.get(pos + 4..pos + 2 + length)?;
+        if marker == 0xE1 {
+            if let Some(tiff) = segment.strip_prefix(EXIF_HEADER) {
+                return tiff_orientation(tiff);
+            }
+        }
+        pos += 2 + length;
+    }
+    None
+}
+
+/// Reads the orientation tag from the first IFD of a TIFF header.
+fn tiff_orientation(tiff: &[u8]) -> Option<u16> {
+    let big_endian = match tiff.get(..2)? {
+        b"MM" => true,
+        b"II" => false,
+        _ => return None,
+    };
+    let u16_at = |at: usize| {
+        let bytes = [*tiff.get(at)?, *tiff.get(at + 1)?];
+        Some(if big_endian {
+            u16::from_be_bytes(bytes)
+        } else {
+            u16::from_le_bytes(bytes)
+        })
+    };
+    let u32_at = |at: usize| {
+        let bytes: [u8; 4] = tiff.get(at..at + 4)?.try_into().ok()?;
+        Some(if big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        })
+    };
+
+    let ifd = u32_at(4)? as usize;
+    let entries = u16_at(ifd)? as usize;
+    (0..entries)
+        .map(|i| ifd + 2 + i * 12)
+        .find(|&entry| u16_at(entry) == Some(ORIENTATION_TAG))
+        .and_then(|entry| u16_at(entry + 8))
+        .filter(|orientation| (1..=8).contains(orientation))
+}
+
+/// Turns an image stored with the given EXIF orientation upright.
+pub fn apply_orientation(img: DynamicImage, orientation: u16) -> DynamicImage {
+    match orientation {
+        2 => img.fliph(),
+        3 => img.rotate180(),
+        4 => img.flipv(),
+        5 => img.rotate90().fliph(),
+        6 => img.rotate90(),
+        7 => img.rotate270().fliph(),
+        8 => img.rotate270(),
+        _ => img,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageBuffer, Rgb};
+
+    /// A 32x16 JPEG, red on the left half and blue on the right.
+    fn red_blue_jpeg() -> Vec<u8> {
+        let img = ImageBuffer::from_fn(32, 16, |x, _| {
+            if x < 16 {
+                Rgb([255u8, 0, 0])
+            } else {
+                Rgb([0u8, 0, 255])
+            }
+        });
+        let mut jpeg = Vec::new();
+        DynamicImage::ImageRgb8(img)
+            .write_to(&mut std::io::Cursor::new(&mut jpeg), ImageFormat::Jpeg)
+            .unwrap();
+        jpeg
+    }
+
+    /// Inserts a big-endian EXIF segment holding only an orientation tag.
+    fn with_orientation(jpeg: &[u8], orientation: u16) -> Vec<u8> {
+        let mut tiff = b"MM\0\x2a\0\0\0\x08\0\x01".to_vec();
+        tiff.extend_from_slice(&ORIENTATION_TAG.to_be_bytes());
+        tiff.extend_from_slice(&[0, 3, 0, 0, 0, 1]);
+        tiff.extend_from_slice(&orientation.to_be_bytes());
+        tiff.extend_from_slice(&[0; 6]);
+
+        let length = (2 + EXIF_HEADER.len() + tiff.len()) as u16;
+        let mut out = jpeg[..2].to_vec();
+        out.extend_from_slice(&[0xFF, 0xE1]);
+        out.extend_from_slice(&length.to_be_bytes());
+        out.extend_from_slice(EXIF_HEADER);
+        out.extend_from_slice(&tiff);
+        out.extend_from_slice(&jpeg[2..]);
+        out
+    }
+
+    fn is_red(img: &DynamicImage, x: u32, y: u32) -> bool {
+        let [r, _, b] = img.to_rgb8().get_pixel(x, y).0;
+        r > 200 && b < 60
+    }
+
+    #[test]
+    fn rotated_jpeg_is_decoded_upright() {
+        let jpeg = with_orientation(&red_blue_jpeg(), 6);
+        assert_eq!(jpeg_orientation(&jpeg), Some(6));
+
+        let img = decode_jpeg_upright(&jpeg).unwrap();
+
+        // Rotated 90° clockwise: the red left half is now on top
+        assert_eq!((img.width(), img.height()), (16, 32));
+        assert!(is_red(&img, 8, 4));
+        assert!(!is_red(&img, 8, 28));
+    }
+
+    #[test]
+    fn jpeg_without_exif_is_left_as_is() {
+        let jpeg = red_blue_jpeg();
+        assert_eq!(jpeg_orientation(&jpeg), None);
+
+        let img = decode_jpeg_upright(&jpeg).unwrap();
+        assert_eq!((img.width(), img.height()), (32, 16));
+        assert!(is_red(&img, 4, 8));
+    }
+}
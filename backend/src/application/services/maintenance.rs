@@ -0,0 +1,75 @@
+//! Maintenance
+//!
+//! Periodic upkeep that can't wait for frames to arrive: stopping recordings
+//! whose cameras went quiet, keeping storage under its limit and retiring
+//! stale unknown profiles.
+
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+use crate::application::services::{ProfileRetention, RecordingService, StorageManager};
+use crate::domain::repositories::RepoResult;
+
+/// Seconds between maintenance runs unless configured.
+pub const DEFAULT_MAINTENANCE_INTERVAL_SECS: u64 = 5;
+
+/// Runs recording timeouts and storage cleanup on a schedule.
+pub struct Maintenance {
+    recording_service: Arc<RecordingService>,
+    storage_manager: Arc<StorageManager>,
+    profile_retention: Option<ProfileRetention>,
+}
+
+impl Maintenance {
+    /// Creates maintenance over the given services.
+    pub fn new(
+        recording_service: Arc<RecordingService>,
+        storage_manager: Arc<StorageManager>,
+    ) -> Self {
+        Self {
+            recording_service,
+            storage_manager,
+            profile_retention: None,
+        }
+    }
+
+    /// Also deactivates stale unknown profiles, at most once per
+    /// [`RETENTION_CHECK_INTERVAL`](crate::application::services::RETENTION_CHECK_INTERVAL).
+    pub fn with_profile_retention(mut self, retention: ProfileRetention) -> Self {
+        self.profile_retention = Some(retention);
+        self
+    }
+
+    /// Stops timed-out recordings, cleans up storage if it is over its
+    /// limit and, when due, deactivates stale unknown profiles. Returns the
+    /// number of recordings stopped.
+    pub async fn run_once(&self) -> RepoResult<usize> {
+        let stopped = self.recording_service.check_timeouts().await?;
+        if stopped > 0 {
+            info!("Stopped {} timed-out recording(s)", stopped);
+        }
+
+        self.storage_manager.check_and_cleanup().await?;
+
+        if let Some(retention) = &self.profile_retention {
+            retention.run_if_due().await?;
+        }
+
+        Ok(stopped)
+    }
+
+    /// Runs [`Maintenance::run_once`] every `every` until aborted.
+    pub fn spawn(self, every: Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(every);
+            loop {
+                interval.tick().await;
+                if let Err(e) = self.run_once().await {
+                    warn!("Maintenance failed: {}", e);
+                }
+            }
+        })
+    }
+}
@@ -5,11 +5,25 @@
 mod detection_service;
 mod event_bus;
 mod face_matcher;
+mod face_quality;
+mod image_orientation;
+mod maintenance;
+mod profile_classifier;
+mod profile_retention;
+mod recent_events;
 mod recording_service;
 mod storage_manager;
+mod ttl_cache;
 
 pub use detection_service::*;
 pub use event_bus::*;
 pub use face_matcher::*;
+pub use face_quality::*;
+pub use image_orientation::*;
+pub use maintenance::*;
+pub use profile_classifier::*;
+pub use profile_retention::*;
+pub use recent_events::*;
 pub use recording_service::*;
 pub use storage_manager::*;
+pub use ttl_cache::*;
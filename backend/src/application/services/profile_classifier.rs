@@ -0,0 +1,70 @@
+//! Profile Classifier
+//!
+//! Applies the classification rules from settings to profiles as they are
+//! sighted or edited.
+
+use chrono::{DateTime, Utc};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tracing::info;
+
+use crate::domain::entities::{classify, ClassificationRule, Profile, ProfileClassification};
+use crate::domain::repositories::{RepoResult, SightingRepository};
+
+/// Most recent sightings consulted for time-of-day rules.
+const MAX_SIGHTINGS_CONSIDERED: i64 = 1000;
+
+/// Classifies profiles by the configured rules.
+pub struct ProfileClassifier {
+    sighting_repo: Arc<dyn SightingRepository>,
+    rules: RwLock<Vec<ClassificationRule>>,
+}
+
+impl ProfileClassifier {
+    /// Creates a classifier with the given rules.
+    pub fn new(sighting_repo: Arc<dyn SightingRepository>, rules: Vec<ClassificationRule>) -> Self {
+        Self {
+            sighting_repo,
+            rules: RwLock::new(rules),
+        }
+    }
+
+    /// Replaces the rules.
+    pub async fn set_rules(&self, rules: Vec<ClassificationRule>) {
+        *self.rules.write().await = rules;
+    }
+
+    /// Sets the classification of the first rule that applies to `profile`.
+    /// Returns the new classification if it changed; the caller saves it.
+    pub async fn apply(&self, profile: &mut Profile) -> RepoResult<Option<ProfileClassification>> {
+        let rules = self.rules.read().await;
+        if rules.is_empty() {
+            return Ok(None);
+        }
+
+        let sighted_at: Vec<DateTime<Utc>> =
+            if rules.iter().any(ClassificationRule::needs_sighting_times) {
+                self.sighting_repo
+                    .find_by_profile(profile.id(), MAX_SIGHTINGS_CONSIDERED)
+                    .await?
+                    .iter()
+                    .map(|s| s.detected_at())
+                    .collect()
+            } else {
+                Vec::new()
+            };
+
+        match classify(&rules, profile, &sighted_at) {
+            Some(classification) if classification != profile.classification() => {
+                info!(
+                    "Classification rule set profile {} to {:?}",
+                    profile.id(),
+                    classification
+                );
+                profile.set_classification(classification);
+                Ok(Some(classification))
+            }
+            _ => Ok(None),
+        }
+    }
+}
@@ -0,0 +1,160 @@
+//! Profile Retention
+//!
+//! Deactivates `Unknown` profiles that were seen a few times long ago and
+//! never since, so one-off passers-by don't pile up in the database and the
+//! matcher cache.
+
+use chrono::{Duration, Utc};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tracing::info;
+
+use crate::application::services::FaceMatcher;
+use crate::domain::repositories::{ProfileRepository, RepoResult};
+
+/// How often stale profiles are looked for.
+pub const RETENTION_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(3600);
+
+/// Deactivates stale `Unknown` profiles. Other classifications are never
+/// touched.
+pub struct ProfileRetention {
+    profile_repo: Arc<dyn ProfileRepository>,
+    face_matcher: Arc<FaceMatcher>,
+    /// Profiles seen more often than this are kept.
+    max_sightings: i64,
+    /// Profiles seen within this long are kept.
+    max_age: Duration,
+    last_run: Mutex<Option<Instant>>,
+}
+
+impl ProfileRetention {
+    /// Creates retention for unknown profiles seen at most `max_sightings`
+    /// times and not within `max_age`.
+    pub fn new(
+        profile_repo: Arc<dyn ProfileRepository>,
+        face_matcher: Arc<FaceMatcher>,
+        max_sightings: i64,
+        max_age: Duration,
+    ) -> Self {
+        Self {
+            profile_repo,
+            face_matcher,
+            max_sightings,
+            max_age,
+            last_run: Mutex::new(None),
+        }
+    }
+
+    /// Deactivates stale unknown profiles and evicts them from the matcher
+    /// cache. Returns the number deactivated.
+    pub async fn deactivate_stale(&self) -> RepoResult<usize> {
+        let seen_before = Utc::now() - self.max_age;
+        let ids = self
+            .profile_repo
+            .deactivate_stale_unknown(self.max_sightings, seen_before)
+            .await?;
+
+        for id in &ids {
+            self.face_matcher.remove_from_cache(*id).await;
+        }
+        if !ids.is_empty() {
+            info!("Deactivated {} stale unknown profile(s)", ids.len());
+        }
+        Ok(ids.len())
+    }
+
+    /// Runs [`ProfileRetention::deactivate_stale`] if it hasn't succeeded
+    /// within [`RETENTION_CHECK_INTERVAL`]. A failed run is retried on the
+    /// next call.
+    pub async fn run_if_due(&self) -> RepoResult<usize> {
+        let due = self
+            .last_run
+            .lock()
+            .unwrap()
+            .is_none_or(|at| at.elapsed() >= RETENTION_CHECK_INTERVAL);
+        if !due {
+            return Ok(0);
+        }
+        let deactivated = self.deactivate_stale().await?;
+        *self.last_run.lock().unwrap() = Some(Instant::now());
+        Ok(deactivated)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::entities::{Profile, ProfileClassification};
+    use crate::domain::repositories::fakes::InMemoryProfileRepo;
+    use crate::domain::value_objects::{embedding_dimension, FaceEmbedding};
+    use uuid::Uuid;
+
+    fn profile_seen(
+        days_ago: i64,
+        sightings: i64,
+        classification: ProfileClassification,
+    ) -> Profile {
+        let seen = Utc::now() - Duration::days(days_ago);
+        Profile::from_db(
+            Uuid::new_v4(),
+            None,
+            classification,
+            FaceEmbedding::new(vec![0.1; embedding_dimension()]),
+            true,
+            None,
+            None,
+            Vec::new(),
+            None,
+            seen,
+            seen,
+            sightings,
+            true,
+            seen,
+            seen,
+        )
+    }
+
+    #[tokio::test]
+    async fn only_stale_unknown_profiles_are_deactivated() {
+        let repo = Arc::new(InMemoryProfileRepo::default());
+        let matcher = Arc::new(FaceMatcher::new(repo.clone(), 0.6));
+
+        let stale = profile_seen(60, 1, ProfileClassification::Unknown);
+        let fresh = profile_seen(2, 1, ProfileClassification::Unknown);
+        let frequent = profile_seen(60, 5, ProfileClassification::Unknown);
+        let known = profile_seen(60, 1, ProfileClassification::Known);
+        let flagged = profile_seen(60, 1, ProfileClassification::Flagged);
+        for profile in [&stale, &fresh, &frequent, &known, &flagged] {
+            repo.save(profile).await.unwrap();
+        }
+        matcher.load_cache().await.unwrap();
+
+        let retention = ProfileRetention::new(repo.clone(), matcher.clone(), 1, Duration::days(30));
+        assert_eq!(retention.deactivate_stale().await.unwrap(), 1);
+
+        let active: Vec<Uuid> = repo
+            .find_all_active()
+            .await
+            .unwrap()
+            .iter()
+            .map(|p| p.id())
+            .collect();
+        assert!(!active.contains(&stale.id()));
+        assert_eq!(active.len(), 4);
+        assert_eq!(matcher.cache_size().await, 4);
+    }
+
+    #[tokio::test]
+    async fn retention_runs_at_most_once_per_interval() {
+        let repo = Arc::new(InMemoryProfileRepo::default());
+        let matcher = Arc::new(FaceMatcher::new(repo.clone(), 0.6));
+        let retention = ProfileRetention::new(repo.clone(), matcher, 1, Duration::days(30));
+
+        assert_eq!(retention.run_if_due().await.unwrap(), 0);
+        repo.save(&profile_seen(60, 1, ProfileClassification::Unknown))
+            .await
+            .unwrap();
+        assert_eq!(retention.run_if_due().await.unwrap(), 0);
+        assert_eq!(retention.deactivate_stale().await.unwrap(), 1);
+    }
+}
@@ -0,0 +1,163 @@
+//! Recent Events
+//!
+//! Keeps the latest domain events in memory, numbered in publish order, so
+//! clients that poll instead of holding a WebSocket can page through them.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use crate::domain::events::DomainEvent;
+
+/// Number of events kept by default.
+pub const DEFAULT_RECENT_EVENTS_CAPACITY: usize = 1000;
+
+/// An event with the id it was assigned when recorded.
+#[derive(Debug, Clone)]
+pub struct RecordedEvent {
+    /// Increases by one per event, starting at 1.
+    pub id: u64,
+    pub event: Arc<DomainEvent>,
+}
+
+/// Events after a cursor, and the cursor for the next poll.
+#[derive(Debug, Clone)]
+pub struct RecentEventsPage {
+    pub events: Vec<RecordedEvent>,
+    /// Id to pass as `since_id` on the next poll.
+    pub next_since_id: u64,
+    /// Set when events after the cursor were evicted before this poll.
+    pub missed_events: bool,
+}
+
+/// Bounded, numbered buffer of the most recent domain events.
+#[derive(Debug)]
+pub struct RecentEvents {
+    capacity: usize,
+    inner: Mutex<Buffer>,
+}
+
+#[derive(Debug, Default)]
+struct Buffer {
+    events: VecDeque<RecordedEvent>,
+    last_id: u64,
+}
+
+impl Default for RecentEvents {
+    fn default() -> Self {
+        Self::new(DEFAULT_RECENT_EVENTS_CAPACITY)
+    }
+}
+
+impl RecentEvents {
+    /// Creates a buffer keeping at most `capacity` events.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            inner: Mutex::new(Buffer::default()),
+        }
+    }
+
+    /// Records an event, evicting the oldest when full. Returns its id.
+    pub fn push(&self, event: Arc<DomainEvent>) -> u64 {
+        let mut buffer = self.inner.lock().unwrap();
+        buffer.last_id += 1;
+        let id = buffer.last_id;
+        if buffer.events.len() == self.capacity {
+            buffer.events.pop_front();
+        }
+        buffer.events.push_back(RecordedEvent { id, event });
+        id
+    }
+
+    /// Returns up to `limit` events with ids greater than `since_id`, oldest
+    /// first.
+    ///
+    /// A cursor ahead of the newest id, e.g. from before a restart, is treated
+    /// as `0` so the poller starts over rather than waiting forever.
+    pub fn since(&self, since_id: u64, limit: usize) -> RecentEventsPage {
+        let buffer = self.inner.lock().unwrap();
+        let since_id = if since_id > buffer.last_id {
+            0
+        } else {
+            since_id
+        };
+
+        let events: Vec<RecordedEvent> = buffer
+            .events
+            .iter()
+            .filter(|e| e.id > since_id)
+            .take(limit)
+            .cloned()
+            .collect();
+        let oldest_id = buffer.events.front().map_or(buffer.last_id + 1, |e| e.id);
+
+        RecentEventsPage {
+            next_since_id: events.last().map_or(since_id, |e| e.id),
+            missed_events: since_id + 1 < oldest_id && buffer.last_id > since_id,
+            events,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::events::SettingsChangedEvent;
+    use chrono::Utc;
+
+    fn event(category: &str) -> Arc<DomainEvent> {
+        Arc::new(DomainEvent::SettingsChanged(SettingsChangedEvent {
+            category: category.to_string(),
+            timestamp: Utc::now(),
+        }))
+    }
+
+    fn ids(page: &RecentEventsPage) -> Vec<u64> {
+        page.events.iter().map(|e| e.id).collect()
+    }
+
+    #[test]
+    fn sequential_polls_do_not_overlap() {
+        let recent = RecentEvents::new(100);
+        for i in 0..5 {
+            recent.push(event(&i.to_string()));
+        }
+
+        let first = recent.since(0, 3);
+        assert_eq!(ids(&first), vec![1, 2, 3]);
+
+        recent.push(event("late"));
+        let second = recent.since(first.next_since_id, 10);
+        assert_eq!(ids(&second), vec![4, 5, 6]);
+
+        // Nothing new keeps the cursor where it is
+        let third = recent.since(second.next_since_id, 10);
+        assert!(third.events.is_empty());
+        assert_eq!(third.next_since_id, 6);
+        assert!(!first.missed_events && !second.missed_events && !third.missed_events);
+    }
+
+    #[test]
+    fn evicted_events_are_reported_as_missed() {
+        let recent = RecentEvents::new(3);
+        for i in 0..5 {
+            recent.push(event(&i.to_string()));
+        }
+
+        let page = recent.since(1, 10);
+        assert_eq!(ids(&page), vec![3, 4, 5]);
+        assert!(page.missed_events);
+
+        assert!(!recent.since(2, 10).missed_events);
+    }
+
+    #[test]
+    fn cursor_from_before_restart_starts_over() {
+        let recent = RecentEvents::new(10);
+        recent.push(event("a"));
+
+        let page = recent.since(500, 10);
+        assert_eq!(ids(&page), vec![1]);
+        assert_eq!(page.next_since_id, 1);
+    }
+}
@@ -3,14 +3,17 @@
 //! Manages video recording with motion/detection triggering.
 
 use chrono::Utc;
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::io::SeekFrom;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
 use tokio::sync::RwLock;
-use tracing::info;
+use tracing::{info, warn};
 use uuid::Uuid;
 
 use crate::application::services::EventBus;
-use crate::domain::entities::Recording;
+use crate::domain::entities::{Recording, RecordingStatus};
 use crate::domain::events::{DomainEvent, RecordingEndedEvent, RecordingStartedEvent};
 use crate::domain::repositories::{RecordingRepository, RepoResult};
 
@@ -25,8 +28,24 @@ pub struct RecordingConfig {
     pub post_trigger_buffer_secs: i32,
     /// Maximum recording segment duration (seconds).
     pub max_segment_duration_secs: i32,
+    /// Recordings shorter than this many seconds are discarded when they
+    /// stop, unless a face was detected again after the one that started
+    /// them.
+    pub min_recording_duration_secs: i32,
     /// Base directory for recordings.
     pub recordings_dir: PathBuf,
+    /// Per-camera override of `detection_triggered`.
+    pub camera_overrides: HashMap<Uuid, bool>,
+}
+
+impl RecordingConfig {
+    /// Whether a detection on this camera starts a recording.
+    pub fn records_on_detection(&self, camera_id: Uuid) -> bool {
+        self.camera_overrides
+            .get(&camera_id)
+            .copied()
+            .unwrap_or(self.detection_triggered)
+    }
 }
 
 impl Default for RecordingConfig {
@@ -41,7 +60,9 @@ impl Default for RecordingConfig {
             pre_trigger_buffer_secs: 5,
             post_trigger_buffer_secs: 10,
             max_segment_duration_secs: 300,
+            min_recording_duration_secs: 2,
             recordings_dir,
+            camera_overrides: HashMap::new(),
         }
     }
 }
@@ -55,6 +76,25 @@ struct RecordingSession {
     bytes_written: i64,
 }
 
+impl RecordingSession {
+    /// Whether the recording has run its course at `now`. Detection-triggered
+    /// recordings end once no face was seen for the post-trigger buffer,
+    /// counting from the start if none was seen since; others end after the
+    /// maximum segment duration.
+    fn is_timed_out(&self, config: &RecordingConfig, now: chrono::DateTime<Utc>) -> bool {
+        let camera_id = self.recording.camera_id();
+        if config.records_on_detection(camera_id) {
+            let last_detection = self
+                .last_detection_at
+                .unwrap_or_else(|| self.recording.started_at());
+            (now - last_detection).num_seconds() > config.post_trigger_buffer_secs as i64
+        } else {
+            (now - self.recording.started_at()).num_seconds()
+                > config.max_segment_duration_secs as i64
+        }
+    }
+}
+
 /// Service for managing video recordings.
 pub struct RecordingService {
     recording_repo: Arc<dyn RecordingRepository>,
@@ -88,7 +128,7 @@ impl RecordingService {
         self.config.read().await.clone()
     }
 
-    /// Starts a new recording for a camera.
+    /// Starts a new recording for a camera, triggered by a detection.
     pub async fn start_recording(&self, camera_id: Uuid) -> RepoResult<Uuid> {
         let config = self.config.read().await;
         let timestamp = Utc::now().format("%Y%m%d_%H%M%S");
@@ -97,7 +137,9 @@ impl RecordingService {
 
         std::fs::create_dir_all(&config.recordings_dir).ok();
 
-        let recording = Recording::new(camera_id, file_path.to_string_lossy().to_string());
+        let mut recording = Recording::new(camera_id, file_path.to_string_lossy().to_string());
+        // The clip holds the detection that started it
+        recording.mark_has_detections();
         let recording_id = recording.id();
 
         self.recording_repo.save(&recording).await?;
@@ -129,8 +171,12 @@ impl RecordingService {
         Ok(recording_id)
     }
 
-    /// Stops a recording for a camera.
+    /// Stops a recording for a camera. Recordings shorter than the minimum
+    /// duration with no detection after the one that started them are
+    /// deleted, file and all, and `None` is returned for them without a
+    /// `RecordingEnded` event.
     pub async fn stop_recording(&self, camera_id: Uuid) -> RepoResult<Option<Recording>> {
+        let min_duration_secs = self.config.read().await.min_recording_duration_secs;
         let mut sessions = self.active_sessions.write().await;
 
         let session = match sessions.remove(&camera_id) {
@@ -142,6 +188,10 @@ impl RecordingService {
         let duration_ms = (Utc::now() - recording.started_at()).num_milliseconds();
 
         recording.complete(session.bytes_written, duration_ms, session.frame_count);
+        if duration_ms < min_duration_secs as i64 * 1000 && session.last_detection_at.is_none() {
+            self.discard_recording(&recording).await?;
+            return Ok(None);
+        }
         self.recording_repo.update(&recording).await?;
 
         self.event_bus
@@ -163,6 +213,23 @@ impl RecordingService {
         Ok(Some(recording))
     }
 
+    /// Deletes a recording's file and row.
+    async fn discard_recording(&self, recording: &Recording) -> RepoResult<()> {
+        if let Err(e) = tokio::fs::remove_file(recording.file_path()).await {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                warn!("Failed to remove {}: {}", recording.file_path(), e);
+            }
+        }
+        self.recording_repo.delete(recording.id()).await?;
+
+        info!(
+            "Discarded {} ms recording {} without further detections",
+            recording.duration_ms(),
+            recording.id()
+        );
+        Ok(())
+    }
+
     /// Records a detection event (triggers recording if configured).
     pub async fn on_detection(&self, camera_id: Uuid) -> RepoResult<()> {
         let config = self.config.read().await.clone();
@@ -174,7 +241,7 @@ impl RecordingService {
             let mut recording = session.recording.clone();
             recording.mark_has_detections();
             session.recording = recording;
-        } else if config.detection_triggered {
+        } else if config.records_on_detection(camera_id) {
             drop(sessions);
             self.start_recording(camera_id).await?;
         }
@@ -197,19 +264,9 @@ impl RecordingService {
         let config = self.config.read().await.clone();
         let sessions = self.active_sessions.read().await;
 
-        let should_stop = if let Some(session) = sessions.get(&camera_id) {
-            if !config.detection_triggered {
-                let duration = (Utc::now() - session.recording.started_at()).num_seconds();
-                duration > config.max_segment_duration_secs as i64
-            } else if let Some(last_detection) = session.last_detection_at {
-                let since_detection = (Utc::now() - last_detection).num_seconds();
-                since_detection > config.post_trigger_buffer_secs as i64
-            } else {
-                false
-            }
-        } else {
-            false
-        };
+        let should_stop = sessions
+            .get(&camera_id)
+            .is_some_and(|session| session.is_timed_out(&config, Utc::now()));
 
         drop(sessions);
 
@@ -221,6 +278,19 @@ impl RecordingService {
         Ok(false)
     }
 
+    /// Stops every recording that timed out. Returns how many were stopped.
+    pub async fn check_timeouts(&self) -> RepoResult<usize> {
+        let cameras: Vec<Uuid> = self.active_sessions.read().await.keys().copied().collect();
+
+        let mut stopped = 0;
+        for camera_id in cameras {
+            if self.check_timeout(camera_id).await? {
+                stopped += 1;
+            }
+        }
+        Ok(stopped)
+    }
+
     /// Returns active recording for a camera if any.
     pub async fn active_recording(&self, camera_id: Uuid) -> Option<Recording> {
         let sessions = self.active_sessions.read().await;
@@ -237,11 +307,321 @@ impl RecordingService {
     pub async fn is_recording(&self, camera_id: Uuid) -> bool {
         self.active_sessions.read().await.contains_key(&camera_id)
     }
+
+    /// Reconciles recordings left in progress by a previous run.
+    /// Returns the number of recordings recovered.
+    pub async fn recover_interrupted(&self) -> RepoResult<usize> {
+        let dangling = self
+            .recording_repo
+            .find_in_status(RecordingStatus::Recording)
+            .await?;
+        let count = dangling.len();
+
+        for mut recording in dangling {
+            let path = Path::new(recording.file_path());
+            let file_size = tokio::fs::metadata(path)
+                .await
+                .map(|m| m.len() as i64)
+                .unwrap_or(0);
+            let complete = file_size > 0 && mp4_looks_complete(path).await;
+
+            recording.recover(file_size, complete);
+            self.recording_repo.update(&recording).await?;
+
+            warn!(
+                "Recovered dangling recording {} as {:?} ({} bytes)",
+                recording.id(),
+                recording.status(),
+                file_size
+            );
+        }
+
+        Ok(count)
+    }
+}
+
+/// Checks that an MP4 file has a well-formed top-level box layout
+/// including a `moov` box, which is only written when a recording finishes.
+/// Only the box headers are read, seeking past each box's payload.
+/// Reference: ISO/IEC 14496-12 (ISO base media file format)
+async fn mp4_looks_complete(path: &Path) -> bool {
+    top_level_boxes_include_moov(path).await.unwrap_or(false)
+}
+
+async fn top_level_boxes_include_moov(path: &Path) -> std::io::Result<bool> {
+    let mut file = tokio::fs::File::open(path).await?;
+    let len = file.metadata().await?.len();
+
+    let mut offset = 0u64;
+    let mut has_moov = false;
+    let mut header = [0u8; 16];
+
+    while offset + 8 <= len {
+        file.seek(SeekFrom::Start(offset)).await?;
+        file.read_exact(&mut header[..8]).await?;
+        let size = u32::from_be_bytes([header[0], header[1], header[2], header[3]]);
+        let is_moov = &header[4..8] == b"moov";
+
+        // Size 0 means "extends to end of file", 1 means a 64-bit size follows
+        let size = match size {
+            0 => len - offset,
+            1 if offset + 16 <= len => {
+                file.read_exact(&mut header[8..16]).await?;
+                let mut large = [0u8; 8];
+                large.copy_from_slice(&header[8..16]);
+                match u64::from_be_bytes(large) {
+                    s if s >= 16 => s,
+                    _ => return Ok(false),
+                }
+            }
+            s if s >= 8 => u64::from(s),
+            _ => return Ok(false),
+        };
+
+        has_moov |= is_moov;
+
+        match offset.checked_add(size) {
+            Some(end) if end <= len => offset = end,
+            _ => return Ok(false),
+        }
+    }
+
+    Ok(has_moov && offset == len)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::domain::repositories::fakes::InMemoryRecordingRepo;
+
+    fn mp4_box(box_type: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+        let mut data = ((payload.len() + 8) as u32).to_be_bytes().to_vec();
+        data.extend_from_slice(box_type);
+        data.extend_from_slice(payload);
+        data
+    }
+
+    #[tokio::test]
+    async fn box_walk_follows_large_sizes_and_rejects_overruns() {
+        let dir = tempfile::tempdir().unwrap();
+        let check = |name: &str, data: Vec<u8>| {
+            let path = dir.path().join(name);
+            std::fs::write(&path, data).unwrap();
+            async move { mp4_looks_complete(&path).await }
+        };
+
+        // An mdat with a 64-bit size, then moov
+        let mut large = 1u32.to_be_bytes().to_vec();
+        large.extend_from_slice(b"mdat");
+        large.extend_from_slice(&(16u64 + 32).to_be_bytes());
+        large.extend_from_slice(&[0u8; 32]);
+        large.extend(mp4_box(b"moov", &[0u8; 8]));
+        assert!(check("large.mp4", large).await);
+
+        // A box claiming more bytes than the file holds
+        let mut overrun = mp4_box(b"moov", &[0u8; 8]);
+        overrun.extend_from_slice(&1000u32.to_be_bytes());
+        overrun.extend_from_slice(b"mdat");
+        assert!(!check("overrun.mp4", overrun).await);
+
+        // Trailing bytes too short for a box header
+        let mut trailing = mp4_box(b"moov", &[0u8; 8]);
+        trailing.extend_from_slice(&[0u8; 3]);
+        assert!(!check("trailing.mp4", trailing).await);
+    }
+
+    #[tokio::test]
+    async fn recover_interrupted_reconciles_dangling_recordings() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = Arc::new(InMemoryRecordingRepo::default());
+        let camera_id = Uuid::new_v4();
+
+        // Truncated file: mdat without a trailing moov box
+        let truncated_path = dir.path().join("truncated.mp4");
+        std::fs::write(&truncated_path, mp4_box(b"mdat", &[0u8; 64])).unwrap();
+        let truncated = Recording::new(camera_id, truncated_path.to_string_lossy().to_string());
+
+        // Whole file: ftyp, mdat and moov
+        let whole_path = dir.path().join("whole.mp4");
+        let mut whole_data = mp4_box(b"ftyp", b"isom");
+        whole_data.extend(mp4_box(b"mdat", &[0u8; 64]));
+        whole_data.extend(mp4_box(b"moov", &[0u8; 16]));
+        std::fs::write(&whole_path, &whole_data).unwrap();
+        let whole = Recording::new(camera_id, whole_path.to_string_lossy().to_string());
+
+        // Missing file
+        let missing = Recording::new(camera_id, "/nonexistent/missing.mp4".to_string());
+
+        for recording in [&truncated, &whole, &missing] {
+            repo.save(recording).await.unwrap();
+        }
+
+        let service =
+            RecordingService::new(repo.clone(), Arc::new(EventBus::new()), Default::default());
+        let recovered = service.recover_interrupted().await.unwrap();
+        assert_eq!(recovered, 3);
+
+        let truncated = repo.find_by_id(truncated.id()).await.unwrap().unwrap();
+        assert_eq!(truncated.status(), RecordingStatus::Interrupted);
+        assert_eq!(truncated.file_size_bytes(), 72);
+        assert!(truncated.ended_at().is_some());
+
+        let whole = repo.find_by_id(whole.id()).await.unwrap().unwrap();
+        assert_eq!(whole.status(), RecordingStatus::Completed);
+        assert_eq!(whole.file_size_bytes(), whole_data.len() as i64);
+
+        let missing = repo.find_by_id(missing.id()).await.unwrap().unwrap();
+        assert_eq!(missing.status(), RecordingStatus::Interrupted);
+        assert_eq!(missing.file_size_bytes(), 0);
+
+        assert!(repo
+            .find_in_status(RecordingStatus::Recording)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn camera_override_disables_detection_recording() {
+        let dir = tempfile::tempdir().unwrap();
+        let doorbell = Uuid::new_v4();
+        let hallway = Uuid::new_v4();
+        let config = RecordingConfig {
+            recordings_dir: dir.path().to_path_buf(),
+            camera_overrides: HashMap::from([(hallway, false)]),
+            ..Default::default()
+        };
+        let service = RecordingService::new(
+            Arc::new(InMemoryRecordingRepo::default()),
+            Arc::new(EventBus::new()),
+            config,
+        );
+
+        service.on_detection(doorbell).await.unwrap();
+        service.on_detection(hallway).await.unwrap();
+
+        assert!(service.is_recording(doorbell).await);
+        assert!(!service.is_recording(hallway).await);
+    }
+
+    #[tokio::test]
+    async fn stale_recording_is_stopped_by_timeout_check() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = Arc::new(InMemoryRecordingRepo::default());
+        let (stale, busy) = (Uuid::new_v4(), Uuid::new_v4());
+        let service = RecordingService::new(
+            repo.clone(),
+            Arc::new(EventBus::new()),
+            RecordingConfig {
+                recordings_dir: dir.path().to_path_buf(),
+                post_trigger_buffer_secs: 10,
+                // Both recordings are only moments old
+                min_recording_duration_secs: 0,
+                ..Default::default()
+            },
+        );
+        service.on_detection(stale).await.unwrap();
+        service.on_detection(busy).await.unwrap();
+
+        // The stale camera's last face was seen a minute ago
+        {
+            let mut sessions = service.active_sessions.write().await;
+            let session = sessions.get_mut(&stale).unwrap();
+            session.last_detection_at = Some(Utc::now() - chrono::Duration::seconds(60));
+        }
+        service.on_detection(busy).await.unwrap();
+
+        assert_eq!(service.check_timeouts().await.unwrap(), 1);
+        assert!(!service.is_recording(stale).await);
+        assert!(service.is_recording(busy).await);
+        let stopped = repo.find_all(10).await.unwrap();
+        assert!(stopped
+            .iter()
+            .any(|r| r.camera_id() == stale && r.status() == RecordingStatus::Completed));
+    }
+
+    #[tokio::test]
+    async fn short_recordings_without_further_detections_are_discarded() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = Arc::new(InMemoryRecordingRepo::default());
+        let event_bus = Arc::new(EventBus::new());
+        let mut events = event_bus.subscribe();
+        let (single, repeated) = (Uuid::new_v4(), Uuid::new_v4());
+        let config = RecordingConfig {
+            recordings_dir: dir.path().to_path_buf(),
+            min_recording_duration_secs: 60,
+            ..Default::default()
+        };
+        let service = RecordingService::new(repo.clone(), event_bus, config.clone());
+
+        // Both clips stop well short of the minimum; only one saw a face
+        // again after the detection that started it
+        service.on_detection(single).await.unwrap();
+        service.on_detection(repeated).await.unwrap();
+        service.on_detection(repeated).await.unwrap();
+        let single_file = service
+            .active_recording(single)
+            .await
+            .unwrap()
+            .file_path()
+            .to_string();
+        std::fs::write(&single_file, b"clip").unwrap();
+
+        assert!(service.stop_recording(single).await.unwrap().is_none());
+        let kept = service.stop_recording(repeated).await.unwrap().unwrap();
+
+        let recordings = repo.find_all(10).await.unwrap();
+        assert_eq!(recordings.len(), 1);
+        assert_eq!(recordings[0].id(), kept.id());
+        assert_eq!(recordings[0].status(), RecordingStatus::Completed);
+        assert!(recordings[0].has_detections());
+        assert!(!Path::new(&single_file).exists());
+        // Only the kept recording is announced as ended
+        let ended: Vec<Uuid> = std::iter::from_fn(|| events.try_recv())
+            .filter_map(|event| match &*event {
+                DomainEvent::RecordingEnded(e) => Some(e.recording_id),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(ended, vec![kept.id()]);
+
+        // Without a minimum, a single detection is kept
+        service
+            .update_config(RecordingConfig {
+                min_recording_duration_secs: 0,
+                ..config
+            })
+            .await;
+        service.on_detection(single).await.unwrap();
+        assert!(service.stop_recording(single).await.unwrap().is_some());
+    }
+
+    #[test]
+    fn recording_without_later_detections_times_out_from_its_start() {
+        let config = RecordingConfig::default();
+        let session = RecordingSession {
+            recording: Recording::new(Uuid::new_v4(), "a.mp4".to_string()),
+            last_detection_at: None,
+            frame_count: 0,
+            bytes_written: 0,
+        };
+        let started = session.recording.started_at();
+
+        assert!(!session.is_timed_out(&config, started + chrono::Duration::seconds(5)));
+        assert!(session.is_timed_out(&config, started + chrono::Duration::seconds(11)));
+    }
+
+    #[test]
+    fn camera_override_takes_precedence_over_global_default() {
+        let enabled = Uuid::new_v4();
+        let config = RecordingConfig {
+            detection_triggered: false,
+            camera_overrides: HashMap::from([(enabled, true)]),
+            ..Default::default()
+        };
+        assert!(config.records_on_detection(enabled));
+        assert!(!config.records_on_detection(Uuid::new_v4()));
+    }
 
     #[test]
     fn default_config_uses_documents_dir() {
@@ -2,14 +2,60 @@
 //!
 //! Manages disk storage for recordings with automatic cleanup.
 
-use std::path::PathBuf;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::time::Duration;
+use tokio::sync::{Mutex, RwLock};
+use tokio::task::JoinHandle;
 use tracing::{info, warn};
+use uuid::Uuid;
 
-use crate::domain::entities::Recording;
+use crate::domain::entities::{Recording, RecordingSettings, RecordingStatus};
 use crate::domain::repositories::{RecordingRepository, RepoResult};
 
+/// Subdirectory of the base directory holding face thumbnails and snapshots.
+pub const SNAPSHOTS_DIR: &str = "snapshots";
+
+/// Subdirectory of the snapshots directory caching resized snapshots, one
+/// directory per snapshot file name.
+pub const THUMBS_DIR: &str = "thumbs";
+
+/// Subdirectory of the base directory holding recordings.
+pub const RECORDINGS_DIR: &str = "recordings";
+
+/// Seconds a deleted recording can be restored before its file is purged.
+pub const DEFAULT_DELETION_GRACE_SECS: i64 = 24 * 60 * 60;
+
+/// Seconds a finished recording's file must stay missing before the
+/// recording is marked for deletion.
+pub const DEFAULT_MISSING_FILE_GRACE_SECS: i64 = 60 * 60;
+
+/// How often recordings past their deletion grace period are purged.
+pub const PURGE_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// Removes a snapshot stored as `file` and its cached thumbnails from
+/// `snapshots_dir`. Files that are already gone are ignored.
+pub async fn remove_snapshot_files(snapshots_dir: &Path, file: &str) {
+    // Only the file name is trusted, so stored paths can't escape the directory
+    let Some(name) = Path::new(file).file_name() else {
+        return;
+    };
+    let path = snapshots_dir.join(name);
+    if let Err(e) = tokio::fs::remove_file(&path).await {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            warn!("Failed to remove {}: {}", path.display(), e);
+        }
+    }
+    let thumbs = snapshots_dir.join(THUMBS_DIR).join(name);
+    if let Err(e) = tokio::fs::remove_dir_all(&thumbs).await {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            warn!("Failed to remove {}: {}", thumbs.display(), e);
+        }
+    }
+}
+
 /// Configuration for storage management.
 #[derive(Debug, Clone)]
 pub struct StorageConfig {
@@ -21,6 +67,17 @@ pub struct StorageConfig {
     pub cleanup_target_percent: f64,
     /// Base directory for all storage.
     pub base_dir: PathBuf,
+    /// Seconds a deleted recording stays restorable before it is purged.
+    pub deletion_grace_secs: i64,
+    /// Seconds a finished recording's file may be missing, as while its
+    /// disk is briefly unmounted, before the recording is marked for
+    /// deletion.
+    pub missing_file_grace_secs: i64,
+    /// Recordings younger than this many days are never deleted by cleanup.
+    pub min_retention_days: i64,
+    /// Recordings with detections younger than this many days are never
+    /// deleted by cleanup.
+    pub detection_retention_days: i64,
 }
 
 impl Default for StorageConfig {
@@ -34,10 +91,37 @@ impl Default for StorageConfig {
             auto_cleanup: true,
             cleanup_target_percent: 0.8,
             base_dir,
+            deletion_grace_secs: DEFAULT_DELETION_GRACE_SECS,
+            missing_file_grace_secs: DEFAULT_MISSING_FILE_GRACE_SECS,
+            min_retention_days: 1,
+            detection_retention_days: 7,
         }
     }
 }
 
+impl StorageConfig {
+    /// Returns this config with the values tunable from persisted settings
+    /// replaced by `settings`.
+    pub fn with_settings(self, settings: &RecordingSettings) -> Self {
+        Self {
+            max_storage_bytes: settings.max_storage_bytes,
+            auto_cleanup: settings.auto_cleanup_enabled,
+            cleanup_target_percent: settings.cleanup_target_percent,
+            min_retention_days: settings.min_retention_days as i64,
+            detection_retention_days: settings.detection_retention_days as i64,
+            ..self
+        }
+    }
+
+    /// Returns whether cleanup must keep `recording`, however full storage is.
+    pub fn retains(&self, recording: &Recording, now: DateTime<Utc>) -> bool {
+        let age = now - recording.started_at();
+        age < chrono::Duration::days(self.min_retention_days)
+            || (recording.has_detections()
+                && age < chrono::Duration::days(self.detection_retention_days))
+    }
+}
+
 /// Storage usage statistics.
 #[derive(Debug, Clone, Default)]
 pub struct StorageStats {
@@ -53,6 +137,8 @@ pub struct StorageStats {
 pub struct StorageManager {
     recording_repo: Arc<dyn RecordingRepository>,
     config: RwLock<StorageConfig>,
+    /// When each finished recording's file was first found missing.
+    missing_since: Mutex<HashMap<Uuid, DateTime<Utc>>>,
 }
 
 impl StorageManager {
@@ -61,6 +147,7 @@ impl StorageManager {
         Self {
             recording_repo,
             config: RwLock::new(config),
+            missing_since: Mutex::new(HashMap::new()),
         }
     }
 
@@ -69,6 +156,11 @@ impl StorageManager {
         *self.config.write().await = config;
     }
 
+    /// Gets the current config.
+    pub async fn config(&self) -> StorageConfig {
+        self.config.read().await.clone()
+    }
+
     /// Gets current storage statistics.
     pub async fn stats(&self) -> RepoResult<StorageStats> {
         let config = self.config.read().await;
@@ -108,28 +200,37 @@ impl StorageManager {
         let target_bytes = (config.max_storage_bytes as f64 * config.cleanup_target_percent) as i64;
         let bytes_to_free = stats.total_bytes - target_bytes;
 
-        self.cleanup_recordings(bytes_to_free).await?;
+        self.cleanup_recordings(bytes_to_free, &config).await?;
 
         Ok(true)
     }
 
     /// Deletes oldest recordings to free specified bytes.
-    async fn cleanup_recordings(&self, bytes_to_free: i64) -> RepoResult<()> {
+    async fn cleanup_recordings(
+        &self,
+        bytes_to_free: i64,
+        config: &StorageConfig,
+    ) -> RepoResult<()> {
+        let now = Utc::now();
         let mut freed = 0i64;
+        let mut retained = 0i64;
         let mut batch_size = 10;
 
         while freed < bytes_to_free {
-            let oldest = self.recording_repo.find_oldest(batch_size).await?;
+            // Retained recordings stay at the front of the oldest-first list,
+            // so each batch looks past the ones already kept
+            let limit = retained + batch_size;
+            let oldest = self.recording_repo.find_oldest(limit).await?;
+            let exhausted = (oldest.len() as i64) < limit;
 
-            if oldest.is_empty() {
-                warn!("No more recordings to delete, freed {} bytes", freed);
-                break;
-            }
-
-            for recording in oldest {
+            for recording in oldest.into_iter().skip(retained as usize) {
                 if freed >= bytes_to_free {
                     break;
                 }
+                if config.retains(&recording, now) {
+                    retained += 1;
+                    continue;
+                }
 
                 freed += recording.file_size_bytes();
                 self.delete_recording_files(&recording).await;
@@ -142,6 +243,11 @@ impl StorageManager {
                 );
             }
 
+            if exhausted && freed < bytes_to_free {
+                warn!("No more recordings can be deleted, freed {} bytes", freed);
+                break;
+            }
+
             batch_size = 50;
         }
 
@@ -150,6 +256,112 @@ impl StorageManager {
         Ok(())
     }
 
+    /// Removes recordings marked for deletion longer ago than the grace
+    /// period, files first. Returns how many were purged.
+    pub async fn purge_deleted(&self) -> RepoResult<usize> {
+        let grace = chrono::Duration::seconds(self.config.read().await.deletion_grace_secs);
+        let now = Utc::now();
+        let due: Vec<Recording> = self
+            .recording_repo
+            .find_in_status(RecordingStatus::Deleting)
+            .await?
+            .into_iter()
+            .filter(|r| r.is_purge_due(grace, now))
+            .collect();
+
+        for recording in &due {
+            self.delete_recording_files(recording).await;
+            self.recording_repo.delete(recording.id()).await?;
+            info!("Purged deleted recording {}", recording.id());
+        }
+
+        Ok(due.len())
+    }
+
+    /// Marks finished recordings whose file has been missing for the
+    /// configured grace period for deletion, so the purge removes their
+    /// rows. Nothing is marked while the recordings directory itself is
+    /// missing, as when its disk is unmounted. Returns how many were marked.
+    pub async fn mark_missing_files(&self) -> RepoResult<usize> {
+        let (recordings_dir, grace) = {
+            let config = self.config.read().await;
+            (
+                config.base_dir.join(RECORDINGS_DIR),
+                chrono::Duration::seconds(config.missing_file_grace_secs),
+            )
+        };
+        let mut missing_since = self.missing_since.lock().await;
+        if !tokio::fs::try_exists(&recordings_dir)
+            .await
+            .unwrap_or(false)
+        {
+            warn!(
+                "Recordings directory {} is missing; not checking recording files",
+                recordings_dir.display()
+            );
+            missing_since.clear();
+            return Ok(0);
+        }
+
+        let mut finished = self
+            .recording_repo
+            .find_in_status(RecordingStatus::Completed)
+            .await?;
+        finished.extend(
+            self.recording_repo
+                .find_in_status(RecordingStatus::Interrupted)
+                .await?,
+        );
+
+        let mut missing = Vec::new();
+        for recording in finished {
+            if !tokio::fs::try_exists(recording.file_path())
+                .await
+                .unwrap_or(true)
+            {
+                missing.push(recording);
+            }
+        }
+        // Forget files that have come back
+        missing_since.retain(|id, _| missing.iter().any(|r| r.id() == *id));
+
+        let now = Utc::now();
+        let mut marked = 0;
+        for mut recording in missing {
+            let since = *missing_since.entry(recording.id()).or_insert(now);
+            if now - since < grace {
+                continue;
+            }
+            recording.mark_for_deletion();
+            self.recording_repo.update(&recording).await?;
+            missing_since.remove(&recording.id());
+            info!(
+                "Marked recording {} for deletion; its file is missing",
+                recording.id()
+            );
+            marked += 1;
+        }
+        Ok(marked)
+    }
+
+    /// Runs [`StorageManager::mark_missing_files`] and
+    /// [`StorageManager::purge_deleted`] every `every` until aborted.
+    pub fn spawn_purge_task(self: &Arc<Self>, every: Duration) -> JoinHandle<()> {
+        let manager = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(every);
+            loop {
+                interval.tick().await;
+                if let Err(e) = manager.mark_missing_files().await {
+                    warn!("Failed to check recording files: {}", e);
+                }
+                if let Err(e) = manager.purge_deleted().await {
+                    warn!("Failed to purge deleted recordings: {}", e);
+                }
+            }
+        })
+    }
+
     /// Deletes the physical files for a recording.
     async fn delete_recording_files(&self, recording: &Recording) {
         let path = PathBuf::from(recording.file_path());
@@ -162,7 +374,7 @@ impl StorageManager {
 
     /// Calculates total size of snapshots directory.
     async fn calculate_snapshots_size(&self, base_dir: &PathBuf) -> i64 {
-        let snapshots_dir = base_dir.join("snapshots");
+        let snapshots_dir = base_dir.join(SNAPSHOTS_DIR);
 
         if !snapshots_dir.exists() {
             return 0;
@@ -181,22 +393,27 @@ impl StorageManager {
         total
     }
 
+    /// Returns the base directory, which is served under `/files`.
+    pub async fn base_dir(&self) -> PathBuf {
+        self.config.read().await.base_dir.clone()
+    }
+
     /// Returns the recordings directory path.
     pub async fn recordings_dir(&self) -> PathBuf {
-        self.config.read().await.base_dir.join("recordings")
+        self.config.read().await.base_dir.join(RECORDINGS_DIR)
     }
 
     /// Returns the snapshots directory path.
     pub async fn snapshots_dir(&self) -> PathBuf {
-        self.config.read().await.base_dir.join("snapshots")
+        self.config.read().await.base_dir.join(SNAPSHOTS_DIR)
     }
 
     /// Ensures all required directories exist.
     pub async fn ensure_directories(&self) -> std::io::Result<()> {
         let config = self.config.read().await;
 
-        tokio::fs::create_dir_all(config.base_dir.join("recordings")).await?;
-        tokio::fs::create_dir_all(config.base_dir.join("snapshots")).await?;
+        tokio::fs::create_dir_all(config.base_dir.join(RECORDINGS_DIR)).await?;
+        tokio::fs::create_dir_all(config.base_dir.join(SNAPSHOTS_DIR)).await?;
         tokio::fs::create_dir_all(config.base_dir.join("logs")).await?;
 
         Ok(())
@@ -206,6 +423,129 @@ impl StorageManager {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::domain::repositories::fakes::InMemoryRecordingRepo;
+    use crate::domain::repositories::RecordingFilter;
+    use uuid::Uuid;
+
+    /// A completed recording whose file exists in `dir`.
+    fn recording_on_disk(dir: &std::path::Path, name: &str) -> Recording {
+        let path = dir.join(name);
+        std::fs::write(&path, b"mp4").unwrap();
+        let mut recording = Recording::new(Uuid::new_v4(), path.to_string_lossy().to_string());
+        recording.complete(3, 1000, 30);
+        recording
+    }
+
+    fn manager(repo: Arc<InMemoryRecordingRepo>, deletion_grace_secs: i64) -> StorageManager {
+        StorageManager::new(
+            repo,
+            StorageConfig {
+                deletion_grace_secs,
+                ..Default::default()
+            },
+        )
+    }
+
+    #[tokio::test]
+    async fn soft_deleted_recording_is_kept_until_grace_passes() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = Arc::new(InMemoryRecordingRepo::default());
+        let mut recording = recording_on_disk(dir.path(), "a.mp4");
+        recording.mark_for_deletion();
+        repo.save(&recording).await.unwrap();
+
+        let purged = manager(repo.clone(), 3600).purge_deleted().await.unwrap();
+
+        assert_eq!(purged, 0);
+        assert!(std::path::Path::new(recording.file_path()).exists());
+        // Hidden from listings while pending deletion
+        assert!(repo
+            .query(&RecordingFilter::new(50))
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn restored_recording_is_not_purged() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = Arc::new(InMemoryRecordingRepo::default());
+        let mut recording = recording_on_disk(dir.path(), "a.mp4");
+        recording.mark_for_deletion();
+        repo.save(&recording).await.unwrap();
+
+        recording.restore();
+        repo.update(&recording).await.unwrap();
+        let purged = manager(repo.clone(), 0).purge_deleted().await.unwrap();
+
+        assert_eq!(purged, 0);
+        assert!(std::path::Path::new(recording.file_path()).exists());
+        let restored = repo.find_by_id(recording.id()).await.unwrap().unwrap();
+        assert_eq!(restored.status(), RecordingStatus::Completed);
+    }
+
+    #[tokio::test]
+    async fn recording_is_purged_after_grace_period() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = Arc::new(InMemoryRecordingRepo::default());
+        let mut deleted = recording_on_disk(dir.path(), "a.mp4");
+        deleted.mark_for_deletion();
+        let kept = recording_on_disk(dir.path(), "b.mp4");
+        repo.save(&deleted).await.unwrap();
+        repo.save(&kept).await.unwrap();
+
+        let purged = manager(repo.clone(), 0).purge_deleted().await.unwrap();
+
+        assert_eq!(purged, 1);
+        assert!(!std::path::Path::new(deleted.file_path()).exists());
+        assert!(repo.find_by_id(deleted.id()).await.unwrap().is_none());
+        assert!(std::path::Path::new(kept.file_path()).exists());
+    }
+
+    #[tokio::test]
+    async fn recordings_are_marked_only_after_their_file_stays_missing() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = Arc::new(InMemoryRecordingRepo::default());
+        let recordings_dir = dir.path().join(RECORDINGS_DIR);
+        std::fs::create_dir_all(&recordings_dir).unwrap();
+        let gone = recording_on_disk(&recordings_dir, "a.mp4");
+        let kept = recording_on_disk(&recordings_dir, "b.mp4");
+        repo.save(&gone).await.unwrap();
+        repo.save(&kept).await.unwrap();
+        std::fs::remove_file(gone.file_path()).unwrap();
+        let config = StorageConfig {
+            base_dir: dir.path().to_path_buf(),
+            missing_file_grace_secs: 3600,
+            ..Default::default()
+        };
+
+        let deleting = || async {
+            let deleting = repo.find_in_status(RecordingStatus::Deleting).await;
+            deleting.unwrap().iter().map(|r| r.id()).collect::<Vec<_>>()
+        };
+
+        // Within the grace period, nothing is marked
+        let manager = StorageManager::new(repo.clone(), config.clone());
+        assert_eq!(manager.mark_missing_files().await.unwrap(), 0);
+        assert!(deleting().await.is_empty());
+
+        // Nor while the whole directory is gone, as when unmounted
+        let manager = StorageManager::new(
+            repo.clone(),
+            StorageConfig {
+                missing_file_grace_secs: 0,
+                ..config.clone()
+            },
+        );
+        std::fs::rename(&recordings_dir, dir.path().join("unmounted")).unwrap();
+        assert_eq!(manager.mark_missing_files().await.unwrap(), 0);
+        assert!(deleting().await.is_empty());
+
+        // Once it's back, only the recording without a file is marked
+        std::fs::rename(dir.path().join("unmounted"), &recordings_dir).unwrap();
+        assert_eq!(manager.mark_missing_files().await.unwrap(), 1);
+        assert_eq!(deleting().await, vec![gone.id()]);
+    }
 
     #[test]
     fn default_config_is_100gb() {
@@ -238,4 +578,83 @@ mod tests {
 
         assert!((stats.usage_percent - 50.0).abs() < f64::EPSILON);
     }
+
+    /// A completed recording started `age_days` ago, with a file in `dir`.
+    fn completed_on_disk(
+        dir: &std::path::Path,
+        name: &str,
+        age_days: i64,
+        size: i64,
+        has_detections: bool,
+    ) -> Recording {
+        let path = dir.join(name);
+        std::fs::write(&path, b"mp4").unwrap();
+        let started_at = Utc::now() - chrono::Duration::days(age_days);
+        Recording::from_db(
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            path.to_string_lossy().to_string(),
+            size,
+            60_000,
+            900,
+            RecordingStatus::Completed,
+            has_detections,
+            started_at,
+            Some(started_at + chrono::Duration::minutes(1)),
+            started_at,
+            None,
+            None,
+        )
+    }
+
+    #[tokio::test]
+    async fn cleanup_keeps_recent_recordings_with_detections() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = Arc::new(InMemoryRecordingRepo::default());
+        let oldest = completed_on_disk(dir.path(), "a.mp4", 40, 100, false);
+        let flagged = completed_on_disk(dir.path(), "b.mp4", 5, 600, true);
+        let quiet = completed_on_disk(dir.path(), "c.mp4", 3, 200, false);
+        for recording in [&oldest, &flagged, &quiet] {
+            repo.save(recording).await.unwrap();
+        }
+
+        // 900 bytes against a budget of 800, cleaning down to 640
+        let manager = StorageManager::new(
+            repo.clone(),
+            StorageConfig {
+                base_dir: dir.path().to_path_buf(),
+                max_storage_bytes: 800,
+                ..Default::default()
+            },
+        );
+
+        assert!(manager.check_and_cleanup().await.unwrap());
+
+        // The detection-bearing recording is skipped, though deleting it
+        // would have freed the most space
+        assert!(repo.find_by_id(flagged.id()).await.unwrap().is_some());
+        assert!(std::path::Path::new(flagged.file_path()).exists());
+        assert!(repo.find_by_id(oldest.id()).await.unwrap().is_none());
+        assert!(repo.find_by_id(quiet.id()).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn cleanup_stops_when_only_retained_recordings_remain() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = Arc::new(InMemoryRecordingRepo::default());
+        let recent = completed_on_disk(dir.path(), "a.mp4", 0, 2000, false);
+        repo.save(&recent).await.unwrap();
+
+        let manager = StorageManager::new(
+            repo.clone(),
+            StorageConfig {
+                base_dir: dir.path().to_path_buf(),
+                max_storage_bytes: 1000,
+                ..Default::default()
+            },
+        );
+
+        assert!(manager.check_and_cleanup().await.unwrap());
+        assert!(repo.find_by_id(recent.id()).await.unwrap().is_some());
+    }
 }
@@ -0,0 +1,135 @@
+//! TTL Cache
+//!
+//! Holds one expensive-to-compute value for a short time, so handlers can
+//! serve it repeatedly instead of recomputing it on every request.
+
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// A single cached value that expires after a fixed time to live.
+#[derive(Debug)]
+pub struct TtlCache<T> {
+    ttl: Duration,
+    inner: Mutex<Slot<T>>,
+}
+
+#[derive(Debug)]
+struct Slot<T> {
+    value: Option<(Instant, T)>,
+    /// Bumped on every invalidation, so a value computed before one is
+    /// never stored after it.
+    generation: u64,
+}
+
+impl<T: Clone> TtlCache<T> {
+    /// Creates an empty cache. A zero `ttl` disables caching.
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            inner: Mutex::new(Slot {
+                value: None,
+                generation: 0,
+            }),
+        }
+    }
+
+    /// Returns the cached value if it hasn't expired.
+    pub fn get(&self) -> Option<T> {
+        let slot = self.inner.lock().unwrap();
+        slot.value
+            .as_ref()
+            .filter(|(stored_at, _)| stored_at.elapsed() < self.ttl)
+            .map(|(_, value)| value.clone())
+    }
+
+    /// Returns the cached value, or computes and caches it with `compute`
+    /// when missing or expired. Errors aren't cached.
+    pub async fn get_or_try_insert_with<F, Fut, E>(&self, compute: F) -> Result<T, E>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+    {
+        if let Some(value) = self.get() {
+            return Ok(value);
+        }
+
+        let generation = self.inner.lock().unwrap().generation;
+        let value = compute().await?;
+
+        let mut slot = self.inner.lock().unwrap();
+        if slot.generation == generation {
+            slot.value = Some((Instant::now(), value.clone()));
+        }
+        Ok(value)
+    }
+
+    /// Drops the cached value.
+    pub fn invalidate(&self) {
+        let mut slot = self.inner.lock().unwrap();
+        slot.value = None;
+        slot.generation += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Reads the cache, computing the value as the number of computes so far.
+    async fn count_computes(cache: &TtlCache<usize>, computes: &AtomicUsize) -> usize {
+        cache
+            .get_or_try_insert_with(|| async {
+                Ok::<_, ()>(computes.fetch_add(1, Ordering::SeqCst) + 1)
+            })
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn repeated_calls_within_ttl_reuse_the_value() {
+        let cache = TtlCache::new(Duration::from_secs(60));
+        let computes = AtomicUsize::new(0);
+
+        assert_eq!(count_computes(&cache, &computes).await, 1);
+        assert_eq!(count_computes(&cache, &computes).await, 1);
+        assert_eq!(computes.load(Ordering::SeqCst), 1);
+
+        cache.invalidate();
+        assert_eq!(cache.get(), None);
+        assert_eq!(count_computes(&cache, &computes).await, 2);
+    }
+
+    #[tokio::test]
+    async fn value_computed_across_an_invalidation_is_not_cached() {
+        let cache = TtlCache::new(Duration::from_secs(60));
+
+        let value = cache
+            .get_or_try_insert_with(|| async {
+                cache.invalidate();
+                Ok::<_, ()>(1)
+            })
+            .await;
+
+        assert_eq!(value, Ok(1));
+        assert_eq!(cache.get(), None);
+    }
+
+    #[tokio::test]
+    async fn errors_and_zero_ttl_are_not_cached() {
+        let cache = TtlCache::new(Duration::from_secs(60));
+        let failed = cache
+            .get_or_try_insert_with(|| async { Err::<usize, _>("db down") })
+            .await;
+        assert_eq!(failed, Err("db down"));
+        assert_eq!(cache.get(), None);
+
+        let uncached = TtlCache::new(Duration::ZERO);
+        let computes = AtomicUsize::new(0);
+        count_computes(&uncached, &computes).await;
+        count_computes(&uncached, &computes).await;
+        assert_eq!(computes.load(Ordering::SeqCst), 2);
+    }
+}
@@ -2,7 +2,9 @@
 //!
 //! Handles camera configuration and management.
 
+use std::future::Future;
 use std::sync::Arc;
+use tracing::{info, warn};
 use uuid::Uuid;
 
 use crate::domain::entities::{Camera, CameraStatus, CameraType};
@@ -55,8 +57,20 @@ impl ManageCamerasUseCase {
         self.camera_repo.find_enabled().await
     }
 
-    /// Creates a new camera.
-    pub async fn create_camera(&self, request: CreateCameraRequest) -> RepoResult<Camera> {
+    /// Creates a new camera, unless one already uses the device ID.
+    /// Returns the camera and whether it was created; an existing camera is
+    /// returned as is. Blank device IDs are never matched.
+    pub async fn create_camera(&self, request: CreateCameraRequest) -> RepoResult<(Camera, bool)> {
+        if !request.device_id.trim().is_empty() {
+            if let Some(existing) = self
+                .camera_repo
+                .find_by_device_id(&request.device_id)
+                .await?
+            {
+                return Ok((existing, false));
+            }
+        }
+
         let mut camera = Camera::new(
             request.name,
             request.camera_type,
@@ -70,7 +84,7 @@ impl ManageCamerasUseCase {
 
         self.camera_repo.save(&camera).await?;
 
-        Ok(camera)
+        Ok((camera, true))
     }
 
     /// Updates a camera.
@@ -157,6 +171,69 @@ impl ManageCamerasUseCase {
         Ok(true)
     }
 
+    /// Brings cameras in line with their enabled flag after a restart.
+    ///
+    /// Each enabled camera is started with `start`, which resolves once the
+    /// capture is running, and marked `Active`, or `Error` if it fails to
+    /// start; disabled cameras are marked `Inactive`. Cameras start
+    /// concurrently, so unreachable ones don't hold up the rest. The
+    /// built-in camera is started separately and browser cameras start with
+    /// their first pushed frame, so neither is started here. RTSP cameras
+    /// have no capture source yet and are marked `Inactive` rather than
+    /// failed. Returns the number of cameras started.
+    pub async fn reconcile_on_startup<F, Fut>(&self, start: F) -> RepoResult<usize>
+    where
+        F: Fn(Camera) -> Fut,
+        Fut: Future<Output = anyhow::Result<()>>,
+    {
+        let mut resuming = Vec::new();
+        for mut camera in self.camera_repo.find_all().await? {
+            let status = match camera.camera_type() {
+                _ if !camera.is_enabled() => CameraStatus::Inactive,
+                CameraType::Builtin | CameraType::Browser => continue,
+                CameraType::Rtsp => {
+                    info!(
+                        "Not resuming camera {}: RTSP capture is not supported",
+                        camera.name()
+                    );
+                    CameraStatus::Inactive
+                }
+                _ => {
+                    let attempt = start(camera.clone());
+                    resuming.push(async move { (camera, attempt.await) });
+                    continue;
+                }
+            };
+            self.store_status(&mut camera, status).await?;
+        }
+
+        let mut started = 0;
+        for (mut camera, result) in futures::future::join_all(resuming).await {
+            let status = match result {
+                Ok(()) => {
+                    info!("Resumed camera {} ({})", camera.name(), camera.id());
+                    started += 1;
+                    CameraStatus::Active
+                }
+                Err(e) => {
+                    warn!("Failed to resume camera {}: {}", camera.name(), e);
+                    CameraStatus::Error
+                }
+            };
+            self.store_status(&mut camera, status).await?;
+        }
+        Ok(started)
+    }
+
+    /// Saves `status` for the camera if it changed.
+    async fn store_status(&self, camera: &mut Camera, status: CameraStatus) -> RepoResult<()> {
+        if camera.status() != status {
+            camera.set_status(status);
+            self.camera_repo.update(camera).await?;
+        }
+        Ok(())
+    }
+
     /// Creates or ensures the built-in camera exists.
     pub async fn ensure_builtin_camera(&self) -> RepoResult<Camera> {
         let cameras = self.camera_repo.find_all().await?;
@@ -175,3 +252,135 @@ impl ManageCamerasUseCase {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::repositories::fakes::InMemoryCameraRepo;
+    use std::sync::Mutex;
+
+    fn request(name: &str, device_id: &str) -> CreateCameraRequest {
+        CreateCameraRequest {
+            name: name.to_string(),
+            camera_type: CameraType::Usb,
+            device_id: device_id.to_string(),
+            rtsp_url: None,
+            location: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn creating_the_same_device_twice_keeps_one_camera() {
+        let repo = Arc::new(InMemoryCameraRepo::default());
+        let use_case = ManageCamerasUseCase::new(repo.clone());
+
+        let (first, created) = use_case
+            .create_camera(request("Desk", "/dev/video0"))
+            .await
+            .unwrap();
+        assert!(created);
+
+        let (second, created) = use_case
+            .create_camera(request("Desk again", "/dev/video0"))
+            .await
+            .unwrap();
+        assert!(!created);
+        assert_eq!(second.id(), first.id());
+        assert_eq!(second.name(), "Desk");
+        assert_eq!(repo.find_all().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn startup_resumes_enabled_cameras_and_parks_disabled_ones() {
+        let repo = Arc::new(InMemoryCameraRepo::default());
+        let use_case = ManageCamerasUseCase::new(repo.clone());
+
+        let usb = Camera::new("Desk".to_string(), CameraType::Usb, "1".to_string(), None);
+        let unplugged = Camera::new("Hall".to_string(), CameraType::Usb, "3".to_string(), None);
+        let rtsp = Camera::new(
+            "Gate".to_string(),
+            CameraType::Rtsp,
+            "rtsp://192.168.1.100:554/stream".to_string(),
+            None,
+        );
+        let browser = Camera::new("Tab".to_string(), CameraType::Browser, String::new(), None);
+        let mut disabled = Camera::new("Attic".to_string(), CameraType::Usb, "2".to_string(), None);
+        disabled.set_enabled(false);
+        disabled.set_status(CameraStatus::Active);
+        for camera in [&usb, &unplugged, &rtsp, &browser, &disabled] {
+            repo.save(camera).await.unwrap();
+        }
+
+        // The first USB camera starts, the second fails to; the RTSP one
+        // isn't tried
+        let attempts = Mutex::new(Vec::new());
+        let started = use_case
+            .reconcile_on_startup(|camera| {
+                attempts.lock().unwrap().push(camera.id());
+                let ok = camera.id() == usb.id();
+                async move {
+                    if ok {
+                        Ok(())
+                    } else {
+                        Err(anyhow::anyhow!("no device"))
+                    }
+                }
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(started, 1);
+        assert_eq!(*attempts.lock().unwrap(), vec![usb.id(), unplugged.id()]);
+        let status = |camera: &Camera| repo.get(camera.id()).unwrap().status();
+        assert_eq!(status(&usb), CameraStatus::Active);
+        assert_eq!(status(&unplugged), CameraStatus::Error);
+        assert_eq!(status(&rtsp), CameraStatus::Inactive);
+        assert_eq!(status(&browser), browser.status());
+        assert_eq!(status(&disabled), CameraStatus::Inactive);
+    }
+
+    #[tokio::test]
+    async fn startup_starts_cameras_concurrently() {
+        let repo = Arc::new(InMemoryCameraRepo::default());
+        let use_case = ManageCamerasUseCase::new(repo.clone());
+        for device_id in ["1", "2"] {
+            let camera = Camera::new(
+                device_id.to_string(),
+                CameraType::Usb,
+                device_id.to_string(),
+                None,
+            );
+            repo.save(&camera).await.unwrap();
+        }
+
+        // Neither start completes until both are under way
+        let barrier = Arc::new(tokio::sync::Barrier::new(2));
+        let started = tokio::time::timeout(
+            std::time::Duration::from_secs(1),
+            use_case.reconcile_on_startup(|_| {
+                let barrier = barrier.clone();
+                async move {
+                    barrier.wait().await;
+                    Ok(())
+                }
+            }),
+        )
+        .await
+        .expect("cameras were started one at a time")
+        .unwrap();
+
+        assert_eq!(started, 2);
+    }
+
+    #[tokio::test]
+    async fn blank_device_ids_are_not_deduplicated() {
+        let repo = Arc::new(InMemoryCameraRepo::default());
+        let use_case = ManageCamerasUseCase::new(repo.clone());
+
+        for name in ["Porch", "Garage"] {
+            let (_, created) = use_case.create_camera(request(name, "")).await.unwrap();
+            assert!(created);
+        }
+        assert_eq!(repo.find_all().await.unwrap().len(), 2);
+    }
+}
@@ -2,13 +2,22 @@
 //!
 //! Handles CRUD operations for profiles.
 
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
 use std::sync::Arc;
 use uuid::Uuid;
 
-use crate::application::services::FaceMatcher;
+use crate::application::services::{
+    remove_snapshot_files, EventBus, FaceMatcher, ProfileClassifier,
+};
 use crate::domain::entities::{Profile, ProfileClassification};
-use crate::domain::repositories::{ProfileRepository, RepoResult, SightingRepository};
-use crate::domain::value_objects::ProfileTag;
+use crate::domain::events::{DomainEvent, ProfileClassificationChangedEvent, ProfileUpdatedEvent};
+use crate::domain::repositories::{
+    ProfileRepository, RepoResult, RepositoryError, SightingRepository,
+};
+use crate::domain::value_objects::{FaceEmbedding, ProfileTag};
 
 /// Request to update a profile.
 #[derive(Debug, Clone)]
@@ -20,11 +29,94 @@ pub struct UpdateProfileRequest {
     pub tags_to_remove: Vec<String>,
 }
 
+/// Portable representation of a profile for moving it between instances.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProfileExport {
+    pub id: Uuid,
+    pub name: Option<String>,
+    pub classification: ProfileClassification,
+    /// Base64-encoded embedding bytes (see `FaceEmbedding::to_bytes`).
+    pub embedding: String,
+    pub tags: Vec<String>,
+    pub notes: Option<String>,
+    pub first_seen_at: DateTime<Utc>,
+    pub last_seen_at: DateTime<Utc>,
+    pub sighting_count: i64,
+}
+
+impl From<&Profile> for ProfileExport {
+    fn from(p: &Profile) -> Self {
+        Self {
+            id: p.id(),
+            name: p.name().map(String::from),
+            classification: p.classification(),
+            embedding: BASE64.encode(p.embedding().to_bytes()),
+            tags: p.tags().iter().map(|t| t.value().to_string()).collect(),
+            notes: p.notes().map(String::from),
+            first_seen_at: p.first_seen_at(),
+            last_seen_at: p.last_seen_at(),
+            sighting_count: p.sighting_count(),
+        }
+    }
+}
+
+impl ProfileExport {
+    /// Converts the exported record back into a profile.
+    /// Thumbnails are not portable, so the profile has none.
+    fn into_profile(self, preserve_id: bool) -> RepoResult<Profile> {
+        let bytes = BASE64
+            .decode(&self.embedding)
+            .map_err(|e| RepositoryError::Serialization(e.to_string()))?;
+        let embedding = FaceEmbedding::from_bytes(&bytes).ok_or_else(|| {
+            RepositoryError::Serialization(format!("Invalid embedding for profile {}", self.id))
+        })?;
+
+        let id = if preserve_id { self.id } else { Uuid::new_v4() };
+        let now = Utc::now();
+        let has_embedding = !embedding.is_zero();
+
+        Ok(Profile::from_db(
+            id,
+            self.name,
+            self.classification,
+            embedding,
+            has_embedding,
+            None,
+            None,
+            self.tags.into_iter().map(ProfileTag::new).collect(),
+            self.notes,
+            self.first_seen_at,
+            self.last_seen_at,
+            self.sighting_count,
+            true,
+            now,
+            now,
+        ))
+    }
+}
+
+/// Outcome of a profile import.
+#[derive(Debug, Clone, Default)]
+pub struct ImportSummary {
+    pub created: usize,
+    pub updated: usize,
+}
+
+/// Per-profile outcome of a bulk classification.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BulkClassifyResult {
+    pub id: Uuid,
+    /// False when no profile with this ID exists.
+    pub updated: bool,
+}
+
 /// Use case for managing profiles.
 pub struct ManageProfilesUseCase {
     profile_repo: Arc<dyn ProfileRepository>,
     sighting_repo: Arc<dyn SightingRepository>,
     face_matcher: Arc<FaceMatcher>,
+    classifier: Option<Arc<ProfileClassifier>>,
+    event_bus: Option<Arc<EventBus>>,
 }
 
 impl ManageProfilesUseCase {
@@ -38,17 +130,35 @@ impl ManageProfilesUseCase {
             profile_repo,
             sighting_repo,
             face_matcher,
+            classifier: None,
+            event_bus: None,
         }
     }
 
+    /// Applies classification rules to profiles when they are edited,
+    /// unless the edit sets a classification itself.
+    pub fn with_classifier(mut self, classifier: Arc<ProfileClassifier>) -> Self {
+        self.classifier = Some(classifier);
+        self
+    }
+
+    /// Publishes profile edits, so live clients see them.
+    pub fn with_event_bus(mut self, event_bus: Arc<EventBus>) -> Self {
+        self.event_bus = Some(event_bus);
+        self
+    }
+
     /// Gets a profile by ID with sighting count.
     pub async fn get_profile(&self, id: Uuid) -> RepoResult<Option<Profile>> {
         self.profile_repo.find_by_id(id).await
     }
 
-    /// Lists all active profiles.
-    pub async fn list_profiles(&self) -> RepoResult<Vec<Profile>> {
-        self.profile_repo.find_all_active().await
+    /// Lists a page of active profiles, most recently seen first, with the
+    /// number of active profiles across all pages.
+    pub async fn list_profiles(&self, limit: i64, offset: i64) -> RepoResult<(Vec<Profile>, i64)> {
+        let profiles = self.profile_repo.find_active_page(limit, offset).await?;
+        let total = self.profile_repo.count().await?;
+        Ok((profiles, total))
     }
 
     /// Updates a profile with the given changes.
@@ -63,11 +173,13 @@ impl ManageProfilesUseCase {
         };
 
         let mut profile = profile;
+        let previous = profile.classification();
 
         if let Some(name) = request.name {
             profile.set_name(if name.is_empty() { None } else { Some(name) });
         }
 
+        let classified_by_hand = request.classification.is_some();
         if let Some(classification) = request.classification {
             profile.set_classification(classification);
         }
@@ -84,11 +196,91 @@ impl ManageProfilesUseCase {
             profile.remove_tag(&ProfileTag::new(tag_name));
         }
 
+        if let Some(classifier) = self.classifier.as_ref().filter(|_| !classified_by_hand) {
+            classifier.apply(&mut profile).await?;
+        }
+
         self.profile_repo.update(&profile).await?;
+        self.publish_update(&profile, previous);
 
         Ok(Some(profile))
     }
 
+    /// Publishes that `profile` was edited, and that its classification
+    /// changed if it is no longer `previous`.
+    fn publish_update(&self, profile: &Profile, previous: ProfileClassification) {
+        let Some(event_bus) = &self.event_bus else {
+            return;
+        };
+        let timestamp = Utc::now();
+
+        if profile.classification() != previous {
+            event_bus.publish(DomainEvent::ProfileClassificationChanged(
+                ProfileClassificationChangedEvent {
+                    profile_id: profile.id(),
+                    previous,
+                    classification: profile.classification(),
+                    timestamp,
+                },
+            ));
+        }
+        event_bus.publish(DomainEvent::ProfileUpdated(ProfileUpdatedEvent {
+            profile_id: profile.id(),
+            name: profile.name().map(String::from),
+            classification: profile.classification(),
+            tags: profile
+                .tags()
+                .iter()
+                .map(|t| t.value().to_string())
+                .collect(),
+            sighting_count: profile.sighting_count(),
+            timestamp,
+        }));
+    }
+
+    /// Sets the classification of several profiles in one transaction, and
+    /// publishes the same events as [`Self::update_profile`] for each
+    /// updated profile.
+    ///
+    /// The matcher cache only holds embeddings, so it needs no refresh.
+    pub async fn bulk_classify(
+        &self,
+        ids: &[Uuid],
+        classification: ProfileClassification,
+    ) -> RepoResult<Vec<BulkClassifyResult>> {
+        let mut unique = Vec::with_capacity(ids.len());
+        for id in ids {
+            if !unique.contains(id) {
+                unique.push(*id);
+            }
+        }
+
+        let profiles = match self.event_bus {
+            Some(_) => self.profile_repo.find_by_ids(&unique).await?,
+            None => Vec::new(),
+        };
+        let updated = self
+            .profile_repo
+            .update_classification(&unique, classification)
+            .await?;
+
+        for mut profile in profiles {
+            if updated.contains(&profile.id()) {
+                let previous = profile.classification();
+                profile.set_classification(classification);
+                self.publish_update(&profile, previous);
+            }
+        }
+
+        Ok(unique
+            .into_iter()
+            .map(|id| BulkClassifyResult {
+                id,
+                updated: updated.contains(&id),
+            })
+            .collect())
+    }
+
     /// Deactivates (soft deletes) a profile.
     pub async fn deactivate_profile(&self, id: Uuid) -> RepoResult<bool> {
         let profile = match self.profile_repo.find_by_id(id).await? {
@@ -105,6 +297,30 @@ impl ManageProfilesUseCase {
         Ok(true)
     }
 
+    /// Permanently deletes a profile, its sightings, and their image files.
+    ///
+    /// Stored thumbnail and snapshot names are resolved inside `snapshots_dir`.
+    pub async fn purge_profile(&self, id: Uuid, snapshots_dir: &Path) -> RepoResult<bool> {
+        let profile = match self.profile_repo.find_by_id(id).await? {
+            Some(p) => p,
+            None => return Ok(false),
+        };
+
+        let sightings = self.sighting_repo.delete_by_profile(id).await?;
+        self.profile_repo.hard_delete(id).await?;
+        self.face_matcher.remove_from_cache(id).await;
+
+        let files = profile
+            .thumbnail_path()
+            .into_iter()
+            .chain(sightings.iter().map(|s| s.snapshot_path()));
+        for file in files {
+            remove_snapshot_files(snapshots_dir, file).await;
+        }
+
+        Ok(true)
+    }
+
     /// Reactivates a deactivated profile.
     pub async fn reactivate_profile(&self, id: Uuid) -> RepoResult<bool> {
         let profile = match self.profile_repo.find_by_id(id).await? {
@@ -116,9 +332,25 @@ impl ManageProfilesUseCase {
         profile.reactivate();
 
         self.profile_repo.update(&profile).await?;
-        self.face_matcher
-            .add_to_cache(id, profile.embedding().clone())
-            .await;
+        if profile.has_embedding() {
+            self.face_matcher.cache_profile(&profile).await;
+        }
+
+        Ok(true)
+    }
+
+    /// Gives a profile a real face embedding, e.g. one created while no
+    /// embedding could be extracted, so it can be matched from now on.
+    pub async fn attach_embedding(&self, id: Uuid, embedding: FaceEmbedding) -> RepoResult<bool> {
+        let Some(mut profile) = self.profile_repo.find_by_id(id).await? else {
+            return Ok(false);
+        };
+
+        profile.update_embedding(embedding);
+        self.profile_repo.update(&profile).await?;
+        if profile.is_active() {
+            self.face_matcher.cache_profile(&profile).await;
+        }
 
         Ok(true)
     }
@@ -142,19 +374,69 @@ impl ManageProfilesUseCase {
         }
     }
 
-    /// Gets profile statistics.
-    pub async fn get_stats(&self) -> RepoResult<ProfileStats> {
+    /// Exports all active profiles in a portable format.
+    pub async fn export_profiles(&self) -> RepoResult<Vec<ProfileExport>> {
         let profiles = self.profile_repo.find_all_active().await?;
+        Ok(profiles.iter().map(ProfileExport::from).collect())
+    }
+
+    /// Imports exported profiles, updating existing ones when IDs are preserved.
+    /// The face matcher cache is reloaded afterwards.
+    pub async fn import_profiles(
+        &self,
+        exports: Vec<ProfileExport>,
+        preserve_ids: bool,
+    ) -> RepoResult<ImportSummary> {
+        // Decode everything up front so a bad record doesn't leave a partial import
+        let profiles = exports
+            .into_iter()
+            .map(|e| e.into_profile(preserve_ids))
+            .collect::<RepoResult<Vec<_>>>()?;
+
+        let mut created = Vec::new();
+        let mut updated = Vec::new();
+
+        for mut profile in profiles {
+            let stored = if preserve_ids {
+                self.profile_repo.find_by_id(profile.id()).await?
+            } else {
+                None
+            };
+
+            match stored {
+                Some(stored) => {
+                    // Thumbnails are not exported, so keep the stored one
+                    if let Some(path) = stored.thumbnail_path() {
+                        profile.set_thumbnail(path.to_string(), stored.thumbnail_quality());
+                    }
+                    updated.push(profile);
+                }
+                None => created.push(profile),
+            }
+        }
+
+        // Samples of a replaced face would match the wrong person, so the
+        // import replaces them along with the rows
+        self.profile_repo.import(&created, &updated).await?;
+        self.face_matcher.load_cache().await?;
+
+        Ok(ImportSummary {
+            created: created.len(),
+            updated: updated.len(),
+        })
+    }
 
+    /// Gets profile statistics.
+    pub async fn get_stats(&self) -> RepoResult<ProfileStats> {
         let mut stats = ProfileStats::default();
-        stats.total = profiles.len() as i64;
-
-        for profile in profiles {
-            match profile.classification() {
-                ProfileClassification::Trusted => stats.trusted += 1,
-                ProfileClassification::Known => stats.known += 1,
-                ProfileClassification::Unknown => stats.unknown += 1,
-                ProfileClassification::Flagged => stats.flagged += 1,
+
+        for (classification, count) in self.profile_repo.count_by_classification().await? {
+            stats.total += count;
+            match classification {
+                ProfileClassification::Trusted => stats.trusted += count,
+                ProfileClassification::Known => stats.known += count,
+                ProfileClassification::Unknown => stats.unknown += count,
+                ProfileClassification::Flagged => stats.flagged += count,
             }
         }
 
@@ -178,6 +460,385 @@ pub struct ProfileStats {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::application::services::THUMBS_DIR;
+    use crate::domain::entities::Sighting;
+    use crate::domain::repositories::fakes::{InMemoryProfileRepo, InMemorySightingRepo};
+    use crate::domain::value_objects::EMBEDDING_DIMENSION;
+
+    fn create_use_case(
+        repo: Arc<InMemoryProfileRepo>,
+    ) -> (ManageProfilesUseCase, Arc<FaceMatcher>) {
+        create_use_case_with_sightings(repo, Arc::new(InMemorySightingRepo::default()))
+    }
+
+    fn create_use_case_with_sightings(
+        repo: Arc<InMemoryProfileRepo>,
+        sightings: Arc<InMemorySightingRepo>,
+    ) -> (ManageProfilesUseCase, Arc<FaceMatcher>) {
+        let matcher = Arc::new(FaceMatcher::new(repo.clone(), 0.6));
+        let use_case = ManageProfilesUseCase::new(repo, sightings, matcher.clone());
+        (use_case, matcher)
+    }
+
+    #[tokio::test]
+    async fn tagging_a_profile_family_makes_it_trusted() {
+        use crate::domain::entities::ClassificationRule;
+
+        let repo = Arc::new(InMemoryProfileRepo::default());
+        let sightings = Arc::new(InMemorySightingRepo::default());
+        let classifier = Arc::new(ProfileClassifier::new(
+            sightings.clone(),
+            vec![ClassificationRule {
+                classification: ProfileClassification::Trusted,
+                from: ProfileClassification::Unknown,
+                tag: Some("family".to_string()),
+                min_sightings: None,
+                hours: None,
+            }],
+        ));
+        let (use_case, _) = create_use_case_with_sightings(repo.clone(), sightings);
+        let use_case = use_case.with_classifier(classifier);
+
+        let profile = Profile::new(FaceEmbedding::new(vec![0.25; EMBEDDING_DIMENSION]), None);
+        repo.save(&profile).await.unwrap();
+        let request = |tags_to_add: Vec<String>| UpdateProfileRequest {
+            name: None,
+            classification: None,
+            notes: None,
+            tags_to_add,
+            tags_to_remove: vec![],
+        };
+
+        let updated = use_case
+            .update_profile(profile.id(), request(vec!["neighbour".to_string()]))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(updated.classification(), ProfileClassification::Unknown);
+
+        let updated = use_case
+            .update_profile(profile.id(), request(vec!["family".to_string()]))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(updated.classification(), ProfileClassification::Trusted);
+        let stored = repo.find_by_id(profile.id()).await.unwrap().unwrap();
+        assert_eq!(stored.classification(), ProfileClassification::Trusted);
+    }
+
+    #[tokio::test]
+    async fn reclassifying_a_profile_publishes_the_change() {
+        let repo = Arc::new(InMemoryProfileRepo::default());
+        let event_bus = Arc::new(EventBus::new());
+        let mut events = event_bus.subscribe();
+        let (use_case, _) = create_use_case(repo.clone());
+        let use_case = use_case.with_event_bus(event_bus);
+
+        let profile = Profile::new(FaceEmbedding::new(vec![0.25; EMBEDDING_DIMENSION]), None);
+        repo.save(&profile).await.unwrap();
+        let request = |classification| UpdateProfileRequest {
+            name: None,
+            classification,
+            notes: None,
+            tags_to_add: vec![],
+            tags_to_remove: vec![],
+        };
+
+        use_case
+            .update_profile(profile.id(), request(Some(ProfileClassification::Flagged)))
+            .await
+            .unwrap();
+
+        match events.try_recv().as_deref() {
+            Some(DomainEvent::ProfileClassificationChanged(e)) => {
+                assert_eq!(e.profile_id, profile.id());
+                assert_eq!(e.previous, ProfileClassification::Unknown);
+                assert_eq!(e.classification, ProfileClassification::Flagged);
+            }
+            other => panic!("expected a classification change, got {:?}", other),
+        }
+        assert!(matches!(
+            events.try_recv().as_deref(),
+            Some(DomainEvent::ProfileUpdated(e)) if e.classification == ProfileClassification::Flagged
+        ));
+
+        // Edits that keep the classification only publish the update
+        use_case
+            .update_profile(profile.id(), request(Some(ProfileClassification::Flagged)))
+            .await
+            .unwrap();
+        assert!(matches!(
+            events.try_recv().as_deref(),
+            Some(DomainEvent::ProfileUpdated(_))
+        ));
+        assert!(events.try_recv().is_none());
+    }
+
+    #[tokio::test]
+    async fn export_import_round_trip_preserves_profiles() {
+        let repo = Arc::new(InMemoryProfileRepo::default());
+        let (use_case, matcher) = create_use_case(repo.clone());
+
+        let mut profile = Profile::new(FaceEmbedding::new(vec![0.25; EMBEDDING_DIMENSION]), None);
+        profile.set_name(Some("Alice".to_string()));
+        profile.set_classification(ProfileClassification::Trusted);
+        profile.set_notes(Some("Neighbour".to_string()));
+        profile.add_tag(ProfileTag::new("family".to_string()));
+        repo.save(&profile).await.unwrap();
+
+        let exported = use_case.export_profiles().await.unwrap();
+        let json = serde_json::to_string(&exported).unwrap();
+
+        repo.hard_delete(profile.id()).await.unwrap();
+
+        let bundle: Vec<ProfileExport> = serde_json::from_str(&json).unwrap();
+        let summary = use_case.import_profiles(bundle, true).await.unwrap();
+        assert_eq!(summary.created, 1);
+        assert_eq!(summary.updated, 0);
+
+        let imported = repo.find_by_id(profile.id()).await.unwrap().unwrap();
+        assert_eq!(imported.name(), Some("Alice"));
+        assert_eq!(imported.classification(), ProfileClassification::Trusted);
+        assert_eq!(imported.notes(), Some("Neighbour"));
+        assert_eq!(imported.tags(), profile.tags());
+        assert_eq!(
+            imported.embedding().to_bytes(),
+            profile.embedding().to_bytes()
+        );
+        assert_eq!(matcher.cache_size().await, 1);
+    }
+
+    #[tokio::test]
+    async fn import_over_existing_profile_keeps_its_thumbnail() {
+        let repo = Arc::new(InMemoryProfileRepo::default());
+        let (use_case, _) = create_use_case(repo.clone());
+
+        let mut profile = Profile::new(FaceEmbedding::new(vec![0.25; EMBEDDING_DIMENSION]), None);
+        profile.set_thumbnail("/snapshots/alice.jpg".to_string(), Some(0.8));
+        profile.add_embedding_sample(FaceEmbedding::new(vec![0.75; EMBEDDING_DIMENSION]), 0.0);
+        repo.save(&profile).await.unwrap();
+
+        let mut exported = use_case.export_profiles().await.unwrap();
+        exported[0].name = Some("Alice".to_string());
+        let summary = use_case.import_profiles(exported, true).await.unwrap();
+        assert_eq!(summary.created, 0);
+        assert_eq!(summary.updated, 1);
+
+        let imported = repo.find_by_id(profile.id()).await.unwrap().unwrap();
+        assert_eq!(imported.name(), Some("Alice"));
+        assert_eq!(imported.thumbnail_path(), Some("/snapshots/alice.jpg"));
+        assert_eq!(imported.thumbnail_quality(), Some(0.8));
+        // Samples of the stored face are dropped with it
+        assert!(imported.embeddings().is_empty());
+    }
+
+    #[tokio::test]
+    async fn import_with_regenerated_ids_creates_new_profiles() {
+        let repo = Arc::new(InMemoryProfileRepo::default());
+        let (use_case, _) = create_use_case(repo.clone());
+
+        let profile = Profile::new(FaceEmbedding::new(vec![0.5; EMBEDDING_DIMENSION]), None);
+        repo.save(&profile).await.unwrap();
+
+        let exported = use_case.export_profiles().await.unwrap();
+        let summary = use_case.import_profiles(exported, false).await.unwrap();
+
+        assert_eq!(summary.created, 1);
+        assert_eq!(repo.count().await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn import_rejects_invalid_embedding() {
+        let repo = Arc::new(InMemoryProfileRepo::default());
+        let (use_case, _) = create_use_case(repo.clone());
+
+        let mut export = ProfileExport::from(&Profile::new(
+            FaceEmbedding::new(vec![0.5; EMBEDDING_DIMENSION]),
+            None,
+        ));
+        export.embedding = BASE64.encode([1u8, 2, 3]);
+
+        let result = use_case.import_profiles(vec![export], true).await;
+        assert!(matches!(result, Err(RepositoryError::Serialization(_))));
+        assert_eq!(repo.count().await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn attached_embedding_makes_profile_matchable() {
+        let repo = Arc::new(InMemoryProfileRepo::default());
+        let (use_case, matcher) = create_use_case(repo.clone());
+        let profile = Profile::without_embedding(None);
+        repo.save(&profile).await.unwrap();
+
+        let embedding = FaceEmbedding::new(vec![0.5; EMBEDDING_DIMENSION]);
+        assert!(use_case
+            .attach_embedding(profile.id(), embedding.clone())
+            .await
+            .unwrap());
+
+        let stored = repo.find_by_id(profile.id()).await.unwrap().unwrap();
+        assert!(stored.has_embedding());
+        let found = matcher.find_match(&embedding).await.unwrap();
+        assert_eq!(found.profile_id, profile.id());
+    }
+
+    #[tokio::test]
+    async fn purge_removes_profile_sightings_and_files() {
+        let repo = Arc::new(InMemoryProfileRepo::default());
+        let sightings = Arc::new(InMemorySightingRepo::default());
+        let (use_case, matcher) = create_use_case_with_sightings(repo.clone(), sightings.clone());
+        let dir = tempfile::tempdir().unwrap();
+
+        std::fs::write(dir.path().join("thumb_a.jpg"), b"thumb").unwrap();
+        std::fs::write(dir.path().join("snap_a.jpg"), b"snap").unwrap();
+        std::fs::write(dir.path().join("snap_other.jpg"), b"snap").unwrap();
+        let cached_thumbs = dir.path().join(THUMBS_DIR).join("snap_a.jpg");
+        std::fs::create_dir_all(&cached_thumbs).unwrap();
+        std::fs::write(cached_thumbs.join("128.jpg"), b"thumb").unwrap();
+
+        let embedding = FaceEmbedding::new(vec![0.5; EMBEDDING_DIMENSION]);
+        let profile = Profile::new(embedding.clone(), Some("thumb_a.jpg".to_string()));
+        let other = Profile::new(embedding.clone(), None);
+        repo.save(&profile).await.unwrap();
+        repo.save(&other).await.unwrap();
+        matcher.add_to_cache(profile.id(), embedding).await;
+
+        let sighting = |profile_id, snapshot: &str| {
+            Sighting::new(
+                profile_id,
+                Uuid::new_v4(),
+                snapshot.to_string(),
+                crate::domain::value_objects::BoundingBox::new(0, 0, 10, 10),
+                0.9,
+                None,
+            )
+        };
+        sightings
+            .save(&sighting(profile.id(), "snap_a.jpg"))
+            .await
+            .unwrap();
+        sightings
+            .save(&sighting(other.id(), "snap_other.jpg"))
+            .await
+            .unwrap();
+
+        assert!(use_case
+            .purge_profile(profile.id(), dir.path())
+            .await
+            .unwrap());
+
+        assert!(repo.find_by_id(profile.id()).await.unwrap().is_none());
+        assert_eq!(sightings.count_by_profile(profile.id()).await.unwrap(), 0);
+        assert!(!dir.path().join("thumb_a.jpg").exists());
+        assert!(!dir.path().join("snap_a.jpg").exists());
+        assert!(!cached_thumbs.exists());
+        assert_eq!(matcher.cache_size().await, 0);
+
+        // Other profiles are untouched
+        assert!(repo.find_by_id(other.id()).await.unwrap().is_some());
+        assert_eq!(sightings.count_by_profile(other.id()).await.unwrap(), 1);
+        assert!(dir.path().join("snap_other.jpg").exists());
+    }
+
+    #[tokio::test]
+    async fn bulk_classify_reports_missing_ids() {
+        let repo = Arc::new(InMemoryProfileRepo::default());
+        let (use_case, _) = create_use_case(repo.clone());
+
+        let first = Profile::new(FaceEmbedding::new(vec![0.1; EMBEDDING_DIMENSION]), None);
+        let second = Profile::new(FaceEmbedding::new(vec![0.2; EMBEDDING_DIMENSION]), None);
+        repo.save(&first).await.unwrap();
+        repo.save(&second).await.unwrap();
+        let missing = Uuid::new_v4();
+
+        let results = use_case
+            .bulk_classify(
+                &[first.id(), missing, second.id(), first.id()],
+                ProfileClassification::Trusted,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            results,
+            vec![
+                BulkClassifyResult {
+                    id: first.id(),
+                    updated: true
+                },
+                BulkClassifyResult {
+                    id: missing,
+                    updated: false
+                },
+                BulkClassifyResult {
+                    id: second.id(),
+                    updated: true
+                },
+            ]
+        );
+        for id in [first.id(), second.id()] {
+            let profile = repo.find_by_id(id).await.unwrap().unwrap();
+            assert_eq!(profile.classification(), ProfileClassification::Trusted);
+        }
+    }
+
+    #[tokio::test]
+    async fn bulk_classify_publishes_each_change() {
+        let repo = Arc::new(InMemoryProfileRepo::default());
+        let event_bus = Arc::new(EventBus::new());
+        let mut events = event_bus.subscribe();
+        let (use_case, _) = create_use_case(repo.clone());
+        let use_case = use_case.with_event_bus(event_bus);
+
+        let unknown = Profile::new(FaceEmbedding::new(vec![0.1; EMBEDDING_DIMENSION]), None);
+        let mut flagged = Profile::new(FaceEmbedding::new(vec![0.2; EMBEDDING_DIMENSION]), None);
+        flagged.set_classification(ProfileClassification::Flagged);
+        repo.save(&unknown).await.unwrap();
+        repo.save(&flagged).await.unwrap();
+
+        use_case
+            .bulk_classify(
+                &[unknown.id(), Uuid::new_v4(), flagged.id()],
+                ProfileClassification::Flagged,
+            )
+            .await
+            .unwrap();
+
+        let mut changed = Vec::new();
+        let mut updated = Vec::new();
+        while let Some(event) = events.try_recv() {
+            match event.as_ref() {
+                DomainEvent::ProfileClassificationChanged(e) => {
+                    assert_eq!(e.previous, ProfileClassification::Unknown);
+                    assert_eq!(e.classification, ProfileClassification::Flagged);
+                    changed.push(e.profile_id);
+                }
+                DomainEvent::ProfileUpdated(e) => {
+                    assert_eq!(e.classification, ProfileClassification::Flagged);
+                    updated.push(e.profile_id);
+                }
+                other => panic!("unexpected event {:?}", other),
+            }
+        }
+        // Profiles already flagged only publish the update
+        assert_eq!(changed, vec![unknown.id()]);
+        updated.sort();
+        let mut expected = vec![unknown.id(), flagged.id()];
+        expected.sort();
+        assert_eq!(updated, expected);
+    }
+
+    #[tokio::test]
+    async fn purge_unknown_profile_returns_false() {
+        let repo = Arc::new(InMemoryProfileRepo::default());
+        let (use_case, _) = create_use_case(repo);
+        let dir = tempfile::tempdir().unwrap();
+
+        assert!(!use_case
+            .purge_profile(Uuid::new_v4(), dir.path())
+            .await
+            .unwrap());
+    }
 
     #[test]
     fn profile_stats_default_is_zero() {
@@ -5,7 +5,9 @@
 use std::sync::Arc;
 use uuid::Uuid;
 
-use crate::application::services::{DetectionService, RecordingService, StorageManager};
+use crate::application::services::{
+    DetectionResult, DetectionService, RecordingService, StorageManager,
+};
 use crate::domain::entities::FrameDetections;
 use crate::domain::repositories::RepoResult;
 
@@ -33,30 +35,50 @@ impl ProcessFrameUseCase {
     /// Processes a frame with detections.
     ///
     /// This orchestrates:
-    /// 1. Face matching and profile creation
-    /// 2. Recording management
+    /// 1. Recording management
+    /// 2. Face matching and profile creation
     /// 3. Storage cleanup if needed
+    ///
+    /// In detection-only mode faces are matched and published but nothing is
+    /// persisted, so recording and cleanup are skipped too.
     pub async fn execute(&self, frame: &mut FrameDetections) -> RepoResult<ProcessFrameResult> {
         let snapshot_dir = self.storage_manager.snapshots_dir().await;
         let snapshot_dir_str = snapshot_dir.to_string_lossy().to_string();
 
-        let created_profiles = self
-            .detection_service
-            .process_frame(frame, &snapshot_dir_str)
-            .await?;
+        if self.detection_service.config().await.detection_only {
+            let outcome = self
+                .detection_service
+                .process_frame(frame, &snapshot_dir_str, None)
+                .await?;
+            return Ok(ProcessFrameResult {
+                matched_profiles: outcome.matched_profiles,
+                detections: outcome.detections,
+                face_count: frame.face_count(),
+                ..Default::default()
+            });
+        }
 
         let camera_id = frame.camera_id();
 
+        // Start or extend the recording first so this frame's sightings link to it
         if frame.has_faces() {
             self.recording_service.on_detection(camera_id).await?;
         }
+        let recording = self.recording_service.active_recording(camera_id).await;
+
+        let outcome = self
+            .detection_service
+            .process_frame(frame, &snapshot_dir_str, recording.as_ref())
+            .await?;
 
         let recording_stopped = self.recording_service.check_timeout(camera_id).await?;
 
         let cleanup_performed = self.storage_manager.check_and_cleanup().await?;
 
         Ok(ProcessFrameResult {
-            created_profiles,
+            created_profiles: outcome.created_profiles,
+            matched_profiles: outcome.matched_profiles,
+            detections: outcome.detections,
             face_count: frame.face_count(),
             recording_stopped,
             cleanup_performed,
@@ -69,6 +91,10 @@ impl ProcessFrameUseCase {
 pub struct ProcessFrameResult {
     /// IDs of newly created profiles.
     pub created_profiles: Vec<Uuid>,
+    /// IDs of existing profiles that were recognised.
+    pub matched_profiles: Vec<Uuid>,
+    /// The profile each resolved detection went to.
+    pub detections: Vec<DetectionResult>,
     /// Number of faces detected.
     pub face_count: usize,
     /// Whether a recording was stopped due to timeout.
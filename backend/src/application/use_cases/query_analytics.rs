@@ -6,7 +6,7 @@ use chrono::{DateTime, Utc};
 use std::sync::Arc;
 use uuid::Uuid;
 
-use crate::domain::entities::Sighting;
+use crate::domain::entities::{Profile, Sighting};
 use crate::domain::repositories::{
     ProfileRepository, RecordingRepository, RepoResult, SightingRepository,
 };
@@ -65,6 +65,19 @@ pub struct TimelineEntry {
     pub event_type: String,
 }
 
+/// Consecutive sightings of one profile with no long gaps between them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SightingSession {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub count: usize,
+    /// Cameras that saw the profile during the session, in order of first sighting.
+    pub camera_ids: Vec<Uuid>,
+}
+
+/// Most recent sightings considered when grouping a profile into sessions.
+const SESSION_SIGHTING_LIMIT: i64 = 1000;
+
 /// Dashboard summary statistics.
 #[derive(Debug, Clone, Default)]
 pub struct DashboardStats {
@@ -156,19 +169,29 @@ impl QueryAnalyticsUseCase {
         self.sighting_repo.find_by_profile(profile_id, limit).await
     }
 
+    /// Groups a profile's recent sightings into sessions, newest first.
+    ///
+    /// A sighting joins the previous session when it follows it by less than
+    /// `gap_secs` seconds.
+    pub async fn get_sighting_sessions(
+        &self,
+        profile_id: Uuid,
+        gap_secs: i64,
+    ) -> RepoResult<Vec<SightingSession>> {
+        let sightings = self
+            .sighting_repo
+            .find_by_profile(profile_id, SESSION_SIGHTING_LIMIT)
+            .await?;
+
+        let mut sessions = group_sessions(&sightings, chrono::Duration::seconds(gap_secs));
+        sessions.reverse();
+        Ok(sessions)
+    }
+
     /// Gets profiles sorted by sighting count.
     pub async fn get_most_seen_profiles(&self, limit: usize) -> RepoResult<Vec<(Uuid, i64)>> {
         let profiles = self.profile_repo.find_all_active().await?;
-
-        let mut profile_counts: Vec<_> = profiles
-            .into_iter()
-            .map(|p| (p.id(), p.sighting_count()))
-            .collect();
-
-        profile_counts.sort_by(|a, b| b.1.cmp(&a.1));
-        profile_counts.truncate(limit);
-
-        Ok(profile_counts)
+        Ok(rank_by_sightings(&profiles, limit))
     }
 
     /// Gets sighting frequency by hour of day.
@@ -184,9 +207,115 @@ impl QueryAnalyticsUseCase {
     }
 }
 
+/// Returns the `limit` profiles with the most sightings, most seen first.
+fn rank_by_sightings(profiles: &[Profile], limit: usize) -> Vec<(Uuid, i64)> {
+    let mut profile_counts: Vec<_> = profiles
+        .iter()
+        .map(|p| (p.id(), p.sighting_count()))
+        .collect();
+
+    profile_counts.sort_by(|a, b| b.1.cmp(&a.1));
+    profile_counts.truncate(limit);
+
+    profile_counts
+}
+
+/// Groups sightings into chronological sessions split at gaps of `gap` or more.
+fn group_sessions(sightings: &[Sighting], gap: chrono::Duration) -> Vec<SightingSession> {
+    let mut ordered: Vec<&Sighting> = sightings.iter().collect();
+    ordered.sort_by_key(|s| s.detected_at());
+
+    let mut sessions: Vec<SightingSession> = Vec::new();
+    for sighting in ordered {
+        let detected_at = sighting.detected_at();
+        match sessions.last_mut() {
+            Some(session) if detected_at - session.end < gap => {
+                session.end = detected_at;
+                session.count += 1;
+                if !session.camera_ids.contains(&sighting.camera_id()) {
+                    session.camera_ids.push(sighting.camera_id());
+                }
+            }
+            _ => sessions.push(SightingSession {
+                start: detected_at,
+                end: detected_at,
+                count: 1,
+                camera_ids: vec![sighting.camera_id()],
+            }),
+        }
+    }
+
+    sessions
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::domain::value_objects::BoundingBox;
+    use chrono::TimeZone;
+
+    fn sighting_at(camera_id: Uuid, minute: u32) -> Sighting {
+        Sighting::from_db(
+            Uuid::new_v4(),
+            Uuid::nil(),
+            camera_id,
+            "snap.jpg".to_string(),
+            BoundingBox::new(0, 0, 10, 10),
+            0.9,
+            None,
+            None,
+            None,
+            None,
+            Utc.with_ymd_and_hms(2024, 12, 24, 10, minute, 0).unwrap(),
+        )
+    }
+
+    #[test]
+    fn ranking_is_descending_and_limited() {
+        use crate::domain::value_objects::{FaceEmbedding, EMBEDDING_DIMENSION};
+
+        let profiles: Vec<Profile> = [3, 9, 1, 5]
+            .iter()
+            .map(|&count| {
+                let mut profile =
+                    Profile::new(FaceEmbedding::new(vec![0.1; EMBEDDING_DIMENSION]), None);
+                for _ in 1..count {
+                    profile.record_sighting();
+                }
+                profile
+            })
+            .collect();
+
+        let ranked = rank_by_sightings(&profiles, 3);
+
+        let counts: Vec<i64> = ranked.iter().map(|(_, count)| *count).collect();
+        assert_eq!(counts, vec![9, 5, 3]);
+        assert_eq!(ranked[0].0, profiles[1].id());
+    }
+
+    #[test]
+    fn sightings_split_into_sessions_at_large_gaps() {
+        let front = Uuid::new_v4();
+        let back = Uuid::new_v4();
+        // Newest first, as the repository returns them
+        let sightings = vec![
+            sighting_at(front, 45),
+            sighting_at(front, 42),
+            sighting_at(back, 4),
+            sighting_at(front, 2),
+            sighting_at(front, 0),
+        ];
+
+        let sessions = group_sessions(&sightings, chrono::Duration::minutes(5));
+
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(sessions[0].count, 3);
+        assert_eq!(sessions[0].camera_ids, vec![front, back]);
+        assert_eq!(sessions[0].start.format("%M").to_string(), "00");
+        assert_eq!(sessions[0].end.format("%M").to_string(), "04");
+        assert_eq!(sessions[1].count, 2);
+        assert_eq!(sessions[1].camera_ids, vec![front]);
+    }
 
     #[test]
     fn time_range_last_hours_creates_correct_range() {
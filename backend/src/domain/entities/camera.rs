@@ -231,6 +231,14 @@ impl Camera {
     }
 }
 
+#[cfg(test)]
+impl Camera {
+    /// Sets when the camera last delivered a frame, as the repository does.
+    pub(crate) fn set_last_frame_at(&mut self, at: DateTime<Utc>) {
+        self.last_frame_at = Some(at);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
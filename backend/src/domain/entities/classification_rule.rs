@@ -0,0 +1,238 @@
+//! Classification Rule
+//!
+//! Declarative rules that classify profiles automatically, e.g. flagging
+//! strangers seen repeatedly at night or trusting everyone tagged `family`.
+
+use chrono::{DateTime, Local, Timelike, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::domain::entities::{Profile, ProfileClassification, SettingsValidationError};
+use crate::domain::value_objects::ProfileTag;
+
+/// Hours of the day in the server's local time, from `start` up to but not
+/// including `end`. Wraps past midnight when `end` is before `start`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HourRange {
+    pub start: u32,
+    pub end: u32,
+}
+
+impl HourRange {
+    /// Returns whether `hour` (0-23) falls in the range.
+    pub fn contains(&self, hour: u32) -> bool {
+        if self.start <= self.end {
+            (self.start..self.end).contains(&hour)
+        } else {
+            hour >= self.start || hour < self.end
+        }
+    }
+
+    /// Returns whether `at` falls in the range, in local time.
+    pub fn contains_time(&self, at: DateTime<Utc>) -> bool {
+        self.contains(at.with_timezone(&Local).hour())
+    }
+}
+
+/// Sets `classification` on profiles that meet every condition given.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClassificationRule {
+    /// Classification given to matching profiles.
+    pub classification: ProfileClassification,
+    /// Only profiles with this classification are changed, so rules don't
+    /// override classifications set by hand.
+    #[serde(default)]
+    pub from: ProfileClassification,
+    /// The profile must carry this tag.
+    #[serde(default)]
+    pub tag: Option<String>,
+    /// The profile must have been seen at least this many times, counting
+    /// only sightings within `hours` when set.
+    #[serde(default)]
+    pub min_sightings: Option<i64>,
+    /// Only sightings within these hours count towards `min_sightings`.
+    #[serde(default)]
+    pub hours: Option<HourRange>,
+}
+
+impl ClassificationRule {
+    /// Returns whether the rule applies to `profile`, given when it was
+    /// sighted. Sighting times are only consulted when `hours` is set.
+    pub fn matches(&self, profile: &Profile, sighted_at: &[DateTime<Utc>]) -> bool {
+        if profile.classification() != self.from {
+            return false;
+        }
+
+        if let Some(tag) = &self.tag {
+            if !profile.tags().contains(&ProfileTag::new(tag.clone())) {
+                return false;
+            }
+        }
+
+        if self.min_sightings.is_some() || self.hours.is_some() {
+            let sightings = match &self.hours {
+                Some(hours) => sighted_at
+                    .iter()
+                    .filter(|at| hours.contains_time(**at))
+                    .count() as i64,
+                None => profile.sighting_count(),
+            };
+            if sightings < self.min_sightings.unwrap_or(1) {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    /// Returns whether evaluating the rule needs the profile's sighting times.
+    pub fn needs_sighting_times(&self) -> bool {
+        self.hours.is_some()
+    }
+
+    /// Checks that the rule has a condition and its values are in range.
+    pub fn validate(&self) -> Result<(), SettingsValidationError> {
+        if self.tag.is_none() && self.min_sightings.is_none() && self.hours.is_none() {
+            return Err(SettingsValidationError::new(
+                "classification.rules",
+                "each rule needs a tag, min_sightings or hours",
+            ));
+        }
+        if self.classification == self.from {
+            return Err(SettingsValidationError::new(
+                "classification.rules",
+                "classification must differ from `from`",
+            ));
+        }
+        if self.min_sightings.is_some_and(|n| n < 1) {
+            return Err(SettingsValidationError::new(
+                "classification.rules",
+                "min_sightings must be at least 1",
+            ));
+        }
+        if self.hours.is_some_and(|h| h.start > 23 || h.end > 23) {
+            return Err(SettingsValidationError::new(
+                "classification.rules",
+                "hours must be between 0 and 23",
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Returns the classification given by the first rule that applies to
+/// `profile`, if any.
+pub fn classify(
+    rules: &[ClassificationRule],
+    profile: &Profile,
+    sighted_at: &[DateTime<Utc>],
+) -> Option<ProfileClassification> {
+    rules
+        .iter()
+        .find(|rule| rule.matches(profile, sighted_at))
+        .map(|rule| rule.classification)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::value_objects::{embedding_dimension, FaceEmbedding};
+    use chrono::TimeZone;
+
+    fn profile() -> Profile {
+        Profile::new(FaceEmbedding::new(vec![0.1; embedding_dimension()]), None)
+    }
+
+    fn at_local_hour(day: u32, hour: u32) -> DateTime<Utc> {
+        Local
+            .with_ymd_and_hms(2026, 3, day, hour, 30, 0)
+            .single()
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    fn night_rule() -> ClassificationRule {
+        ClassificationRule {
+            classification: ProfileClassification::Flagged,
+            from: ProfileClassification::Unknown,
+            tag: None,
+            min_sightings: Some(3),
+            hours: Some(HourRange { start: 22, end: 6 }),
+        }
+    }
+
+    #[test]
+    fn hour_range_wraps_past_midnight() {
+        let night = HourRange { start: 22, end: 6 };
+        assert!(night.contains(23) && night.contains(0) && night.contains(5));
+        assert!(!night.contains(6) && !night.contains(12) && !night.contains(21));
+
+        let office = HourRange { start: 9, end: 17 };
+        assert!(office.contains(9) && !office.contains(17));
+    }
+
+    #[test]
+    fn flags_after_enough_night_sightings() {
+        let rule = night_rule();
+        let profile = profile();
+
+        // Daytime sightings don't count
+        let mut sighted_at = vec![
+            at_local_hour(1, 23),
+            at_local_hour(2, 2),
+            at_local_hour(2, 14),
+            at_local_hour(3, 10),
+        ];
+        assert!(!rule.matches(&profile, &sighted_at));
+
+        sighted_at.push(at_local_hour(4, 4));
+        assert_eq!(
+            classify(&[rule], &profile, &sighted_at),
+            Some(ProfileClassification::Flagged)
+        );
+    }
+
+    #[test]
+    fn family_tag_makes_profile_trusted() {
+        let rule = ClassificationRule {
+            classification: ProfileClassification::Trusted,
+            from: ProfileClassification::Unknown,
+            tag: Some("family".to_string()),
+            min_sightings: None,
+            hours: None,
+        };
+        let mut profile = profile();
+        assert!(!rule.matches(&profile, &[]));
+
+        profile.add_tag(ProfileTag::new("Family".to_string()));
+        assert_eq!(
+            classify(&[rule], &profile, &[]),
+            Some(ProfileClassification::Trusted)
+        );
+    }
+
+    #[test]
+    fn rules_leave_classified_profiles_alone() {
+        let mut profile = profile();
+        profile.set_classification(ProfileClassification::Known);
+        let sighted_at = vec![at_local_hour(1, 23); 5];
+
+        assert_eq!(classify(&[night_rule()], &profile, &sighted_at), None);
+    }
+
+    #[test]
+    fn validate_rejects_rules_without_conditions() {
+        let rule = ClassificationRule {
+            min_sightings: None,
+            hours: None,
+            ..night_rule()
+        };
+        assert!(rule.validate().is_err());
+        assert!(night_rule().validate().is_ok());
+
+        let out_of_range = ClassificationRule {
+            hours: Some(HourRange { start: 22, end: 24 }),
+            ..night_rule()
+        };
+        assert!(out_of_range.validate().is_err());
+    }
+}
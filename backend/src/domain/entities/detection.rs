@@ -15,6 +15,7 @@ pub struct Detection {
     embedding: Option<FaceEmbedding>,
     matched_profile_id: Option<Uuid>,
     match_distance: Option<f32>,
+    match_confidence: Option<f32>,
 }
 
 impl Detection {
@@ -26,6 +27,7 @@ impl Detection {
             embedding: None,
             matched_profile_id: None,
             match_distance: None,
+            match_confidence: None,
         }
     }
 
@@ -41,6 +43,11 @@ impl Detection {
         self.embedding.as_ref()
     }
 
+    /// Returns whether an embedding was extracted for this detection.
+    pub fn has_embedding(&self) -> bool {
+        self.embedding.is_some()
+    }
+
     pub fn matched_profile_id(&self) -> Option<Uuid> {
         self.matched_profile_id
     }
@@ -49,15 +56,20 @@ impl Detection {
         self.match_distance
     }
 
+    pub fn match_confidence(&self) -> Option<f32> {
+        self.match_confidence
+    }
+
     /// Sets the face embedding for this detection.
     pub fn set_embedding(&mut self, embedding: FaceEmbedding) {
         self.embedding = Some(embedding);
     }
 
     /// Records a profile match.
-    pub fn set_match(&mut self, profile_id: Uuid, distance: f32) {
+    pub fn set_match(&mut self, profile_id: Uuid, distance: f32, confidence: f32) {
         self.matched_profile_id = Some(profile_id);
         self.match_distance = Some(distance);
+        self.match_confidence = Some(confidence);
     }
 
     /// Returns whether this detection matches a known profile.
@@ -74,6 +86,7 @@ pub struct FrameDetections {
     timestamp_ms: i64,
     detections: Vec<Detection>,
     frame_data: Option<Vec<u8>>,
+    frame_size: Option<(u32, u32)>,
 }
 
 impl FrameDetections {
@@ -85,6 +98,7 @@ impl FrameDetections {
             timestamp_ms,
             detections: Vec::new(),
             frame_data: None,
+            frame_size: None,
         }
     }
 
@@ -112,14 +126,20 @@ impl FrameDetections {
         self.frame_data.as_deref()
     }
 
+    /// Returns the frame's `(width, height)` in pixels, if frame data was set.
+    pub fn frame_size(&self) -> Option<(u32, u32)> {
+        self.frame_size
+    }
+
     /// Adds a detection to this frame.
     pub fn add_detection(&mut self, detection: Detection) {
         self.detections.push(detection);
     }
 
-    /// Sets the frame image data (JPEG encoded).
-    pub fn set_frame_data(&mut self, data: Vec<u8>) {
+    /// Sets the frame image data and its size in pixels.
+    pub fn set_frame_data(&mut self, data: Vec<u8>, width: u32, height: u32) {
         self.frame_data = Some(data);
+        self.frame_size = Some((width, height));
     }
 
     /// Returns the number of faces detected.
@@ -146,6 +166,7 @@ impl FrameDetections {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::domain::value_objects::embedding_dimension;
 
     #[test]
     fn new_detection_has_no_match() {
@@ -153,14 +174,32 @@ mod tests {
         let detection = Detection::new(bbox, 0.9);
         assert!(!detection.is_matched());
         assert!(detection.matched_profile_id().is_none());
+        assert!(detection.match_distance().is_none());
+        assert!(!detection.has_embedding());
     }
 
     #[test]
     fn set_match_marks_detection_as_matched() {
         let bbox = BoundingBox::new(10, 20, 100, 100);
         let mut detection = Detection::new(bbox, 0.9);
-        detection.set_match(Uuid::new_v4(), 0.3);
+        let profile_id = Uuid::new_v4();
+        detection.set_match(profile_id, 0.3, 0.5);
         assert!(detection.is_matched());
+        assert_eq!(detection.matched_profile_id(), Some(profile_id));
+        assert_eq!(detection.match_distance(), Some(0.3));
+        assert_eq!(detection.match_confidence(), Some(0.5));
+    }
+
+    #[test]
+    fn set_embedding_attaches_embedding() {
+        let bbox = BoundingBox::new(10, 20, 100, 100);
+        let mut detection = Detection::new(bbox, 0.9);
+        let embedding = FaceEmbedding::new(vec![0.5; embedding_dimension()]);
+        detection.set_embedding(embedding.clone());
+        assert!(detection.has_embedding());
+        assert_eq!(detection.embedding(), Some(&embedding));
+        // An embedding alone doesn't make a match
+        assert!(!detection.is_matched());
     }
 
     #[test]
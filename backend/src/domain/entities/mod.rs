@@ -3,6 +3,7 @@
 //! Core business objects with identity and lifecycle.
 
 mod camera;
+mod classification_rule;
 mod detection;
 mod profile;
 mod recording;
@@ -10,6 +11,7 @@ mod settings;
 mod sighting;
 
 pub use camera::*;
+pub use classification_rule::*;
 pub use detection::*;
 pub use profile::*;
 pub use recording::*;
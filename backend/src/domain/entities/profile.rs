@@ -6,7 +6,7 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use crate::domain::value_objects::{FaceEmbedding, ProfileTag};
+use crate::domain::value_objects::{embedding_dimension, FaceEmbedding, ProfileTag};
 
 /// Classification level for a profile.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
@@ -29,6 +29,9 @@ impl Default for ProfileClassification {
     }
 }
 
+/// Most embedding samples a profile keeps besides its primary embedding.
+pub const MAX_EMBEDDING_SAMPLES: usize = 4;
+
 /// A profile represents a unique individual identified by the system.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Profile {
@@ -36,7 +39,14 @@ pub struct Profile {
     name: Option<String>,
     classification: ProfileClassification,
     embedding: FaceEmbedding,
+    /// False while `embedding` is a placeholder, so the profile can't be matched.
+    has_embedding: bool,
+    /// Further samples of the face, e.g. in other lighting or poses, oldest
+    /// first. At most [`MAX_EMBEDDING_SAMPLES`].
+    #[serde(default)]
+    embeddings: Vec<FaceEmbedding>,
     thumbnail_path: Option<String>,
+    thumbnail_quality: Option<f32>,
     tags: Vec<ProfileTag>,
     notes: Option<String>,
     first_seen_at: DateTime<Utc>,
@@ -56,7 +66,10 @@ impl Profile {
             name: None,
             classification: ProfileClassification::default(),
             embedding,
+            has_embedding: true,
+            embeddings: Vec::new(),
             thumbnail_path,
+            thumbnail_quality: None,
             tags: Vec::new(),
             notes: None,
             first_seen_at: now,
@@ -68,6 +81,16 @@ impl Profile {
         }
     }
 
+    /// Creates a profile for a face no embedding could be extracted from.
+    /// It holds a zero placeholder embedding and is never matched until
+    /// [`Profile::update_embedding`] gives it a real one.
+    pub fn without_embedding(thumbnail_path: Option<String>) -> Self {
+        Self {
+            has_embedding: false,
+            ..Self::new(FaceEmbedding::zeros(embedding_dimension()), thumbnail_path)
+        }
+    }
+
     /// Reconstructs a profile from database fields.
     #[allow(clippy::too_many_arguments)]
     pub fn from_db(
@@ -75,7 +98,9 @@ impl Profile {
         name: Option<String>,
         classification: ProfileClassification,
         embedding: FaceEmbedding,
+        has_embedding: bool,
         thumbnail_path: Option<String>,
+        thumbnail_quality: Option<f32>,
         tags: Vec<ProfileTag>,
         notes: Option<String>,
         first_seen_at: DateTime<Utc>,
@@ -90,7 +115,10 @@ impl Profile {
             name,
             classification,
             embedding,
+            has_embedding,
+            embeddings: Vec::new(),
             thumbnail_path,
+            thumbnail_quality,
             tags,
             notes,
             first_seen_at,
@@ -102,6 +130,15 @@ impl Profile {
         }
     }
 
+    /// Sets the stored embedding samples, keeping the newest if there are
+    /// too many.
+    pub fn with_embedding_samples(mut self, mut samples: Vec<FaceEmbedding>) -> Self {
+        let excess = samples.len().saturating_sub(MAX_EMBEDDING_SAMPLES);
+        samples.drain(..excess);
+        self.embeddings = samples;
+        self
+    }
+
     pub fn id(&self) -> Uuid {
         self.id
     }
@@ -160,10 +197,37 @@ impl Profile {
         &self.embedding
     }
 
+    /// Returns false if the profile only holds a placeholder embedding.
+    pub fn has_embedding(&self) -> bool {
+        self.has_embedding
+    }
+
+    /// Embedding samples kept besides the primary embedding, oldest first.
+    pub fn embeddings(&self) -> &[FaceEmbedding] {
+        &self.embeddings
+    }
+
+    /// The primary embedding followed by the other samples.
+    pub fn all_embeddings(&self) -> impl Iterator<Item = &FaceEmbedding> {
+        std::iter::once(&self.embedding).chain(&self.embeddings)
+    }
+
+    /// Distance from `embedding` to the nearest of the profile's samples.
+    pub fn distance_to(&self, embedding: &FaceEmbedding) -> f32 {
+        self.all_embeddings()
+            .map(|sample| embedding.distance(sample))
+            .fold(f32::INFINITY, f32::min)
+    }
+
     pub fn thumbnail_path(&self) -> Option<&str> {
         self.thumbnail_path.as_deref()
     }
 
+    /// Quality score of the current thumbnail, if it was scored.
+    pub fn thumbnail_quality(&self) -> Option<f32> {
+        self.thumbnail_quality
+    }
+
     pub fn tags(&self) -> &[ProfileTag] {
         &self.tags
     }
@@ -235,6 +299,15 @@ impl Profile {
         self.updated_at = Utc::now();
     }
 
+    /// Returns true for an active `Unknown` profile seen at most
+    /// `max_sightings` times and not since `seen_before`.
+    pub fn is_stale_unknown(&self, max_sightings: i64, seen_before: DateTime<Utc>) -> bool {
+        self.is_active
+            && self.classification == ProfileClassification::Unknown
+            && self.sighting_count <= max_sightings
+            && self.last_seen_at < seen_before
+    }
+
     /// Deactivates the profile (soft delete).
     pub fn deactivate(&mut self) {
         self.is_active = false;
@@ -250,12 +323,30 @@ impl Profile {
     /// Updates the face embedding with a better quality sample.
     pub fn update_embedding(&mut self, embedding: FaceEmbedding) {
         self.embedding = embedding;
+        self.has_embedding = true;
         self.updated_at = Utc::now();
     }
 
-    /// Updates the thumbnail image.
-    pub fn set_thumbnail(&mut self, path: String) {
+    /// Keeps `embedding` as another sample of the face if it is at least
+    /// `min_distance` from every sample held, dropping the oldest sample once
+    /// [`MAX_EMBEDDING_SAMPLES`] are kept. Returns whether it was added.
+    pub fn add_embedding_sample(&mut self, embedding: FaceEmbedding, min_distance: f32) -> bool {
+        if !self.has_embedding || self.distance_to(&embedding) < min_distance {
+            return false;
+        }
+
+        if self.embeddings.len() >= MAX_EMBEDDING_SAMPLES {
+            self.embeddings.remove(0);
+        }
+        self.embeddings.push(embedding);
+        self.updated_at = Utc::now();
+        true
+    }
+
+    /// Updates the thumbnail image and the quality score it was chosen with.
+    pub fn set_thumbnail(&mut self, path: String, quality: Option<f32>) {
         self.thumbnail_path = Some(path);
+        self.thumbnail_quality = quality;
         self.updated_at = Utc::now();
     }
 }
@@ -315,4 +406,43 @@ mod tests {
         profile.deactivate();
         assert!(!profile.is_active());
     }
+
+    #[test]
+    fn profile_without_embedding_is_upgraded_by_a_real_one() {
+        let mut profile = Profile::without_embedding(None);
+        assert!(!profile.has_embedding());
+        assert!(profile.embedding().is_zero());
+
+        profile.update_embedding(create_test_embedding());
+        assert!(profile.has_embedding());
+    }
+
+    #[test]
+    fn distinct_samples_are_kept_up_to_the_cap() {
+        let mut profile = Profile::new(FaceEmbedding::new(vec![0.0; 128]), None);
+
+        // Too close to the primary embedding to be worth keeping
+        assert!(!profile.add_embedding_sample(FaceEmbedding::new(vec![0.01; 128]), 0.5));
+
+        for i in 1..=MAX_EMBEDDING_SAMPLES + 2 {
+            let sample = FaceEmbedding::new(vec![i as f32; 128]);
+            assert!(profile.add_embedding_sample(sample, 0.5));
+        }
+
+        // The oldest samples make way; the primary embedding stays
+        assert_eq!(profile.embeddings().len(), MAX_EMBEDDING_SAMPLES);
+        assert_eq!(profile.embeddings()[0], FaceEmbedding::new(vec![3.0; 128]));
+        assert_eq!(profile.embedding(), &FaceEmbedding::new(vec![0.0; 128]));
+        assert_eq!(
+            profile.distance_to(&FaceEmbedding::new(vec![6.0; 128])),
+            0.0
+        );
+    }
+
+    #[test]
+    fn placeholder_profiles_take_no_samples() {
+        let mut profile = Profile::without_embedding(None);
+        assert!(!profile.add_embedding_sample(create_test_embedding(), 0.0));
+        assert!(profile.embeddings().is_empty());
+    }
 }
@@ -35,6 +35,10 @@ pub struct Recording {
     started_at: DateTime<Utc>,
     ended_at: Option<DateTime<Utc>>,
     created_at: DateTime<Utc>,
+    /// When the recording was marked for deletion.
+    deleted_at: Option<DateTime<Utc>>,
+    /// Status to return to if the deletion is cancelled.
+    status_before_deletion: Option<RecordingStatus>,
 }
 
 impl Recording {
@@ -53,6 +57,8 @@ impl Recording {
             started_at: now,
             ended_at: None,
             created_at: now,
+            deleted_at: None,
+            status_before_deletion: None,
         }
     }
 
@@ -70,6 +76,8 @@ impl Recording {
         started_at: DateTime<Utc>,
         ended_at: Option<DateTime<Utc>>,
         created_at: DateTime<Utc>,
+        deleted_at: Option<DateTime<Utc>>,
+        status_before_deletion: Option<RecordingStatus>,
     ) -> Self {
         Self {
             id,
@@ -83,6 +91,8 @@ impl Recording {
             started_at,
             ended_at,
             created_at,
+            deleted_at,
+            status_before_deletion,
         }
     }
 
@@ -130,6 +140,14 @@ impl Recording {
         self.created_at
     }
 
+    pub fn deleted_at(&self) -> Option<DateTime<Utc>> {
+        self.deleted_at
+    }
+
+    pub fn status_before_deletion(&self) -> Option<RecordingStatus> {
+        self.status_before_deletion
+    }
+
     /// Updates recording stats during recording.
     pub fn update_stats(&mut self, file_size_bytes: i64, duration_ms: i64, frame_count: i64) {
         self.file_size_bytes = file_size_bytes;
@@ -157,9 +175,48 @@ impl Recording {
         self.ended_at = Some(Utc::now());
     }
 
-    /// Marks the recording for deletion.
+    /// Reconciles a recording left in progress by an unclean shutdown.
+    /// A playable file is kept as completed; anything else is interrupted.
+    pub fn recover(&mut self, file_size_bytes: i64, file_is_complete: bool) {
+        self.file_size_bytes = file_size_bytes;
+        self.status = if file_is_complete {
+            RecordingStatus::Completed
+        } else {
+            RecordingStatus::Interrupted
+        };
+        self.ended_at = Some(Utc::now());
+    }
+
+    /// Marks the recording for deletion. Its file is kept until it is
+    /// purged, so the deletion can be cancelled with [`Recording::restore`].
     pub fn mark_for_deletion(&mut self) {
+        if self.status == RecordingStatus::Deleting {
+            return;
+        }
+        self.status_before_deletion = Some(self.status);
         self.status = RecordingStatus::Deleting;
+        self.deleted_at = Some(Utc::now());
+    }
+
+    /// Cancels a pending deletion, returning the recording to its previous
+    /// status. Returns false if it was not marked for deletion.
+    pub fn restore(&mut self) -> bool {
+        if self.status != RecordingStatus::Deleting {
+            return false;
+        }
+        self.status = self
+            .status_before_deletion
+            .take()
+            .unwrap_or(RecordingStatus::Completed);
+        self.deleted_at = None;
+        true
+    }
+
+    /// Whether the recording was marked for deletion at least `grace` ago.
+    /// Recordings marked before deletion times were kept are always due.
+    pub fn is_purge_due(&self, grace: chrono::Duration, now: DateTime<Utc>) -> bool {
+        self.status == RecordingStatus::Deleting
+            && self.deleted_at.is_none_or(|at| now - at >= grace)
     }
 
     /// Returns true if the recording is still in progress.
@@ -192,6 +249,44 @@ mod tests {
         assert_eq!(recording.frame_count(), 150);
     }
 
+    #[test]
+    fn recover_marks_incomplete_file_interrupted() {
+        let mut recording = Recording::new(Uuid::new_v4(), "/path/to/file.mp4".to_string());
+        recording.recover(2048, false);
+
+        assert_eq!(recording.status(), RecordingStatus::Interrupted);
+        assert_eq!(recording.file_size_bytes(), 2048);
+        assert!(recording.ended_at().is_some());
+    }
+
+    #[test]
+    fn restore_cancels_deletion() {
+        let mut recording = Recording::new(Uuid::new_v4(), "/path/to/file.mp4".to_string());
+        recording.interrupt();
+        assert!(!recording.restore());
+
+        recording.mark_for_deletion();
+        assert_eq!(recording.status(), RecordingStatus::Deleting);
+        assert!(recording.deleted_at().is_some());
+
+        assert!(recording.restore());
+        assert_eq!(recording.status(), RecordingStatus::Interrupted);
+        assert!(recording.deleted_at().is_none());
+    }
+
+    #[test]
+    fn purge_is_due_after_grace_period() {
+        let mut recording = Recording::new(Uuid::new_v4(), "/path/to/file.mp4".to_string());
+        let grace = chrono::Duration::hours(1);
+        assert!(!recording.is_purge_due(grace, Utc::now()));
+
+        recording.complete(1000, 5000, 150);
+        recording.mark_for_deletion();
+
+        assert!(!recording.is_purge_due(grace, Utc::now()));
+        assert!(recording.is_purge_due(grace, Utc::now() + chrono::Duration::hours(2)));
+    }
+
     #[test]
     fn mark_has_detections_sets_flag() {
         let mut recording = Recording::new(Uuid::new_v4(), "/path/to/file.mp4".to_string());
@@ -2,9 +2,66 @@
 //!
 //! Application configuration stored in database.
 
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use uuid::Uuid;
 
+use crate::domain::entities::ClassificationRule;
+
+/// Smallest storage budget accepted for recordings (1 GB).
+pub const MIN_STORAGE_BYTES: i64 = 1024 * 1024 * 1024;
+
+/// A settings value outside its accepted range.
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+#[error("{field} {reason}")]
+pub struct SettingsValidationError {
+    /// Dotted path of the offending field, e.g. `detection.min_confidence`.
+    pub field: &'static str,
+    /// Human-readable description of the constraint.
+    pub reason: String,
+}
+
+impl SettingsValidationError {
+    pub(crate) fn new(field: &'static str, reason: impl Into<String>) -> Self {
+        Self {
+            field,
+            reason: reason.into(),
+        }
+    }
+}
+
+fn check_unit_range(field: &'static str, value: f32) -> Result<(), SettingsValidationError> {
+    if (0.0..=1.0).contains(&value) {
+        Ok(())
+    } else {
+        Err(SettingsValidationError::new(
+            field,
+            "must be between 0.0 and 1.0",
+        ))
+    }
+}
+
+fn check_non_negative(field: &'static str, value: i32) -> Result<(), SettingsValidationError> {
+    if value >= 0 {
+        Ok(())
+    } else {
+        Err(SettingsValidationError::new(field, "must not be negative"))
+    }
+}
+
+/// What the sighting cooldown is tracked per.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SightingCooldownScope {
+    /// A profile is recorded once per cooldown, whichever camera sees it.
+    #[default]
+    PerProfile,
+    /// A profile is recorded once per cooldown on each camera, so movement
+    /// between cameras is kept.
+    PerProfilePerCamera,
+}
+
 /// Detection settings.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DetectionSettings {
@@ -14,10 +71,47 @@ pub struct DetectionSettings {
     pub match_threshold: f32,
     /// Minimum time between sightings of same profile (seconds).
     pub sighting_cooldown_secs: i32,
+    /// Whether the cooldown is shared across cameras or kept per camera.
+    #[serde(default)]
+    pub sighting_cooldown_scope: SightingCooldownScope,
+    /// Faces with a smaller bounding box area (pixels) are ignored; 0 keeps all.
+    #[serde(default)]
+    pub min_face_area_px: u32,
     /// Enable motion detection trigger.
     pub motion_detection_enabled: bool,
     /// Motion sensitivity threshold (0.0-1.0).
     pub motion_sensitivity: f32,
+    /// Run face detection on every Nth captured frame (1 = every frame).
+    #[serde(default = "default_detection_sample_every")]
+    pub detection_sample_every: u64,
+    /// Detect and match faces without creating profiles, sightings or
+    /// recordings, for tuning thresholds.
+    #[serde(default)]
+    pub detection_only: bool,
+}
+
+fn default_detection_sample_every() -> u64 {
+    3
+}
+
+impl DetectionSettings {
+    /// Checks that every value is within its accepted range.
+    pub fn validate(&self) -> Result<(), SettingsValidationError> {
+        check_unit_range("detection.min_confidence", self.min_confidence)?;
+        check_unit_range("detection.match_threshold", self.match_threshold)?;
+        check_unit_range("detection.motion_sensitivity", self.motion_sensitivity)?;
+        check_non_negative(
+            "detection.sighting_cooldown_secs",
+            self.sighting_cooldown_secs,
+        )?;
+        if self.detection_sample_every < 1 {
+            return Err(SettingsValidationError::new(
+                "detection.detection_sample_every",
+                "must be at least 1",
+            ));
+        }
+        Ok(())
+    }
 }
 
 impl Default for DetectionSettings {
@@ -26,8 +120,12 @@ impl Default for DetectionSettings {
             min_confidence: 0.7,
             match_threshold: 0.6,
             sighting_cooldown_secs: 30,
+            sighting_cooldown_scope: SightingCooldownScope::default(),
+            min_face_area_px: 0,
             motion_detection_enabled: true,
             motion_sensitivity: 0.3,
+            detection_sample_every: default_detection_sample_every(),
+            detection_only: false,
         }
     }
 }
@@ -43,10 +141,85 @@ pub struct RecordingSettings {
     pub post_trigger_buffer_secs: i32,
     /// Maximum recording segment duration (seconds).
     pub max_segment_duration_secs: i32,
+    /// Recordings shorter than this many seconds are discarded unless a face
+    /// was detected again after the one that started them.
+    #[serde(default = "default_min_recording_duration_secs")]
+    pub min_recording_duration_secs: i32,
     /// Maximum total storage in bytes.
     pub max_storage_bytes: i64,
     /// Enable automatic cleanup when storage is full.
     pub auto_cleanup_enabled: bool,
+    /// Fraction of `max_storage_bytes` cleanup frees space down to.
+    #[serde(default = "default_cleanup_target_percent")]
+    pub cleanup_target_percent: f64,
+    /// Recordings younger than this many days are never deleted by cleanup.
+    #[serde(default = "default_min_retention_days")]
+    pub min_retention_days: i32,
+    /// Recordings with detections younger than this many days are never
+    /// deleted by cleanup.
+    #[serde(default = "default_detection_retention_days")]
+    pub detection_retention_days: i32,
+    /// Per-camera override of `detection_triggered`.
+    #[serde(default)]
+    pub camera_overrides: HashMap<Uuid, bool>,
+}
+
+fn default_min_recording_duration_secs() -> i32 {
+    2
+}
+
+fn default_cleanup_target_percent() -> f64 {
+    0.8
+}
+
+fn default_min_retention_days() -> i32 {
+    1
+}
+
+fn default_detection_retention_days() -> i32 {
+    7
+}
+
+impl RecordingSettings {
+    /// Checks that every value is within its accepted range.
+    pub fn validate(&self) -> Result<(), SettingsValidationError> {
+        check_non_negative(
+            "recording.pre_trigger_buffer_secs",
+            self.pre_trigger_buffer_secs,
+        )?;
+        check_non_negative(
+            "recording.post_trigger_buffer_secs",
+            self.post_trigger_buffer_secs,
+        )?;
+        if self.max_segment_duration_secs < 1 {
+            return Err(SettingsValidationError::new(
+                "recording.max_segment_duration_secs",
+                "must be at least 1",
+            ));
+        }
+        check_non_negative(
+            "recording.min_recording_duration_secs",
+            self.min_recording_duration_secs,
+        )?;
+        if self.max_storage_bytes < MIN_STORAGE_BYTES {
+            return Err(SettingsValidationError::new(
+                "recording.max_storage_bytes",
+                "must be at least 1 GB",
+            ));
+        }
+        if !(self.cleanup_target_percent > 0.0 && self.cleanup_target_percent <= 1.0) {
+            return Err(SettingsValidationError::new(
+                "recording.cleanup_target_percent",
+                "must be greater than 0.0 and at most 1.0",
+            ));
+        }
+        check_non_negative("recording.min_retention_days", self.min_retention_days)?;
+        check_non_negative(
+            "recording.detection_retention_days",
+            self.detection_retention_days,
+        )?;
+        Ok(())
+    }
 }
 
 impl Default for RecordingSettings {
@@ -56,8 +229,13 @@ impl Default for RecordingSettings {
             pre_trigger_buffer_secs: 5,
             post_trigger_buffer_secs: 10,
             max_segment_duration_secs: 300,
+            min_recording_duration_secs: default_min_recording_duration_secs(),
             max_storage_bytes: 100 * 1024 * 1024 * 1024, // 100GB
             auto_cleanup_enabled: true,
+            cleanup_target_percent: default_cleanup_target_percent(),
+            min_retention_days: default_min_retention_days(),
+            detection_retention_days: default_detection_retention_days(),
+            camera_overrides: HashMap::new(),
         }
     }
 }
@@ -110,6 +288,21 @@ impl Default for DisplaySettings {
     }
 }
 
+/// Rules that classify profiles automatically.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ClassificationSettings {
+    /// Checked in order; the first rule that applies sets the classification.
+    #[serde(default)]
+    pub rules: Vec<ClassificationRule>,
+}
+
+impl ClassificationSettings {
+    /// Checks every rule.
+    pub fn validate(&self) -> Result<(), SettingsValidationError> {
+        self.rules.iter().try_for_each(ClassificationRule::validate)
+    }
+}
+
 /// Instance settings for multi-device sync.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct InstanceSettings {
@@ -141,6 +334,8 @@ pub struct Settings {
     pub notification: NotificationSettings,
     pub display: DisplaySettings,
     pub instance: InstanceSettings,
+    #[serde(default)]
+    pub classification: ClassificationSettings,
 }
 
 impl Settings {
@@ -148,6 +343,23 @@ impl Settings {
     pub fn new() -> Self {
         Self::default()
     }
+
+    /// Validates the detection, recording and classification sections.
+    pub fn validate(&self) -> Result<(), SettingsValidationError> {
+        self.detection.validate()?;
+        self.recording.validate()?;
+        self.classification.validate()
+    }
+}
+
+/// Settings as persisted, with the version used to detect concurrent updates.
+#[derive(Debug, Clone)]
+pub struct StoredSettings {
+    pub settings: Settings,
+    /// Incremented on every save.
+    pub version: i64,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
 }
 
 #[cfg(test)]
@@ -161,6 +373,151 @@ mod tests {
         assert!(settings.min_confidence <= 1.0);
     }
 
+    #[test]
+    fn detection_sample_every_defaults_when_missing() {
+        let json = r#"{
+            "min_confidence": 0.7,
+            "match_threshold": 0.6,
+            "sighting_cooldown_secs": 30,
+            "motion_detection_enabled": true,
+            "motion_sensitivity": 0.3
+        }"#;
+        let settings: DetectionSettings = serde_json::from_str(json).unwrap();
+        assert_eq!(settings.detection_sample_every, 3);
+    }
+
+    #[test]
+    fn default_settings_are_valid() {
+        assert!(Settings::new().validate().is_ok());
+    }
+
+    fn detection_error(settings: DetectionSettings) -> &'static str {
+        settings.validate().unwrap_err().field
+    }
+
+    fn recording_error(settings: RecordingSettings) -> &'static str {
+        settings.validate().unwrap_err().field
+    }
+
+    #[test]
+    fn rejects_min_confidence_out_of_range() {
+        let settings = DetectionSettings {
+            min_confidence: 5.0,
+            ..Default::default()
+        };
+        assert_eq!(detection_error(settings), "detection.min_confidence");
+    }
+
+    #[test]
+    fn rejects_negative_match_threshold() {
+        let settings = DetectionSettings {
+            match_threshold: -0.1,
+            ..Default::default()
+        };
+        assert_eq!(detection_error(settings), "detection.match_threshold");
+    }
+
+    #[test]
+    fn rejects_nan_motion_sensitivity() {
+        let settings = DetectionSettings {
+            motion_sensitivity: f32::NAN,
+            ..Default::default()
+        };
+        assert_eq!(detection_error(settings), "detection.motion_sensitivity");
+    }
+
+    #[test]
+    fn rejects_negative_sighting_cooldown() {
+        let settings = DetectionSettings {
+            sighting_cooldown_secs: -1,
+            ..Default::default()
+        };
+        assert_eq!(
+            detection_error(settings),
+            "detection.sighting_cooldown_secs"
+        );
+    }
+
+    #[test]
+    fn rejects_zero_sample_rate() {
+        let settings = DetectionSettings {
+            detection_sample_every: 0,
+            ..Default::default()
+        };
+        assert_eq!(
+            detection_error(settings),
+            "detection.detection_sample_every"
+        );
+    }
+
+    #[test]
+    fn rejects_negative_trigger_buffers() {
+        let pre = RecordingSettings {
+            pre_trigger_buffer_secs: -5,
+            ..Default::default()
+        };
+        assert_eq!(recording_error(pre), "recording.pre_trigger_buffer_secs");
+
+        let post = RecordingSettings {
+            post_trigger_buffer_secs: -5,
+            ..Default::default()
+        };
+        assert_eq!(recording_error(post), "recording.post_trigger_buffer_secs");
+    }
+
+    #[test]
+    fn rejects_zero_segment_duration() {
+        let settings = RecordingSettings {
+            max_segment_duration_secs: 0,
+            ..Default::default()
+        };
+        assert_eq!(
+            recording_error(settings),
+            "recording.max_segment_duration_secs"
+        );
+    }
+
+    #[test]
+    fn rejects_storage_below_minimum() {
+        let settings = RecordingSettings {
+            max_storage_bytes: 0,
+            ..Default::default()
+        };
+        assert_eq!(recording_error(settings), "recording.max_storage_bytes");
+    }
+
+    #[test]
+    fn rejects_out_of_range_cleanup_settings() {
+        for target in [0.0, 1.5] {
+            let settings = RecordingSettings {
+                cleanup_target_percent: target,
+                ..Default::default()
+            };
+            assert_eq!(
+                recording_error(settings),
+                "recording.cleanup_target_percent"
+            );
+        }
+
+        let settings = RecordingSettings {
+            min_recording_duration_secs: -1,
+            ..Default::default()
+        };
+        assert_eq!(
+            recording_error(settings),
+            "recording.min_recording_duration_secs"
+        );
+
+        let settings = RecordingSettings {
+            detection_retention_days: -1,
+            ..Default::default()
+        };
+        assert_eq!(
+            recording_error(settings),
+            "recording.detection_retention_days"
+        );
+    }
+
     #[test]
     fn default_storage_is_100gb() {
         let settings = RecordingSettings::default();
@@ -17,6 +17,7 @@ pub struct Sighting {
     snapshot_path: String,
     bounding_box: BoundingBox,
     confidence: f32,
+    match_confidence: Option<f32>,
     location: Option<GeoLocation>,
     recording_id: Option<Uuid>,
     recording_timestamp_ms: Option<i64>,
@@ -40,6 +41,7 @@ impl Sighting {
             snapshot_path,
             bounding_box,
             confidence,
+            match_confidence: None,
             location,
             recording_id: None,
             recording_timestamp_ms: None,
@@ -56,6 +58,7 @@ impl Sighting {
         snapshot_path: String,
         bounding_box: BoundingBox,
         confidence: f32,
+        match_confidence: Option<f32>,
         location: Option<GeoLocation>,
         recording_id: Option<Uuid>,
         recording_timestamp_ms: Option<i64>,
@@ -68,6 +71,7 @@ impl Sighting {
             snapshot_path,
             bounding_box,
             confidence,
+            match_confidence,
             location,
             recording_id,
             recording_timestamp_ms,
@@ -95,10 +99,17 @@ impl Sighting {
         &self.bounding_box
     }
 
+    /// Detector score for the face.
     pub fn confidence(&self) -> f32 {
         self.confidence
     }
 
+    /// How confidently the face was matched to the profile, if it was
+    /// recognised rather than seen for the first time.
+    pub fn match_confidence(&self) -> Option<f32> {
+        self.match_confidence
+    }
+
     pub fn location(&self) -> Option<&GeoLocation> {
         self.location.as_ref()
     }
@@ -115,6 +126,11 @@ impl Sighting {
         self.detected_at
     }
 
+    /// Records how confidently the face was matched to the profile.
+    pub fn set_match_confidence(&mut self, confidence: f32) {
+        self.match_confidence = Some(confidence);
+    }
+
     /// Links this sighting to a recording.
     pub fn link_to_recording(&mut self, recording_id: Uuid, timestamp_ms: i64) {
         self.recording_id = Some(recording_id);
@@ -148,6 +164,24 @@ mod tests {
         assert_ne!(s1.id(), s2.id());
     }
 
+    #[test]
+    fn match_confidence_is_only_set_for_recognised_faces() {
+        let bbox = BoundingBox::new(10, 20, 100, 100);
+        let mut sighting = Sighting::new(
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            "path".into(),
+            bbox,
+            0.9,
+            None,
+        );
+        assert_eq!(sighting.match_confidence(), None);
+
+        sighting.set_match_confidence(0.8);
+        assert_eq!(sighting.match_confidence(), Some(0.8));
+        assert_eq!(sighting.confidence(), 0.9);
+    }
+
     #[test]
     fn link_to_recording_sets_fields() {
         let bbox = BoundingBox::new(10, 20, 100, 100);
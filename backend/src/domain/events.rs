@@ -19,6 +19,10 @@ pub enum DomainEvent {
     FaceDetected(FaceDetectedEvent),
     /// A known profile was spotted.
     ProfileSighted(ProfileSightedEvent),
+    /// A profile was edited.
+    ProfileUpdated(ProfileUpdatedEvent),
+    /// A profile's classification changed.
+    ProfileClassificationChanged(ProfileClassificationChangedEvent),
     /// A recording started.
     RecordingStarted(RecordingStartedEvent),
     /// A recording ended.
@@ -45,6 +49,9 @@ pub struct FaceDetectedEvent {
     pub camera_id: Uuid,
     pub frame_number: u64,
     pub bounding_box: BoundingBox,
+    /// Size of the frame the bounding box is in, in pixels. 0 when unknown.
+    pub frame_width: u32,
+    pub frame_height: u32,
     pub confidence: f32,
     pub profile_id: Option<Uuid>,
     pub profile_name: Option<String>,
@@ -65,6 +72,26 @@ pub struct ProfileSightedEvent {
     pub timestamp: DateTime<Utc>,
 }
 
+/// Event emitted when a profile is edited, with its state afterwards.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProfileUpdatedEvent {
+    pub profile_id: Uuid,
+    pub name: Option<String>,
+    pub classification: ProfileClassification,
+    pub tags: Vec<String>,
+    pub sighting_count: i64,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Event emitted when a profile's classification changes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProfileClassificationChangedEvent {
+    pub profile_id: Uuid,
+    pub previous: ProfileClassification,
+    pub classification: ProfileClassification,
+    pub timestamp: DateTime<Utc>,
+}
+
 /// Event emitted when recording starts.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RecordingStartedEvent {
@@ -90,6 +117,9 @@ pub struct CameraStatusChangedEvent {
     pub camera_id: Uuid,
     pub camera_name: String,
     pub status: String,
+    /// What the user can do about a failed camera, e.g. grant permission.
+    #[serde(default)]
+    pub message: Option<String>,
     pub timestamp: DateTime<Utc>,
 }
 
@@ -107,6 +137,8 @@ impl DomainEvent {
             DomainEvent::ProfileCreated(e) => e.timestamp,
             DomainEvent::FaceDetected(e) => e.timestamp,
             DomainEvent::ProfileSighted(e) => e.timestamp,
+            DomainEvent::ProfileUpdated(e) => e.timestamp,
+            DomainEvent::ProfileClassificationChanged(e) => e.timestamp,
             DomainEvent::RecordingStarted(e) => e.timestamp,
             DomainEvent::RecordingEnded(e) => e.timestamp,
             DomainEvent::CameraStatusChanged(e) => e.timestamp,
@@ -120,6 +152,8 @@ impl DomainEvent {
             DomainEvent::ProfileCreated(_) => "profile_created",
             DomainEvent::FaceDetected(_) => "face_detected",
             DomainEvent::ProfileSighted(_) => "profile_sighted",
+            DomainEvent::ProfileUpdated(_) => "profile_updated",
+            DomainEvent::ProfileClassificationChanged(_) => "profile_classification_changed",
             DomainEvent::RecordingStarted(_) => "recording_started",
             DomainEvent::RecordingEnded(_) => "recording_ended",
             DomainEvent::CameraStatusChanged(_) => "camera_status_changed",
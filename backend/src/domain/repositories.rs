@@ -3,11 +3,18 @@
 //! Abstractions for data persistence (Dependency Inversion Principle).
 
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use uuid::Uuid;
 
-use crate::domain::entities::{Camera, Profile, Recording, Settings, Sighting};
+use crate::domain::entities::{
+    Camera, Profile, ProfileClassification, Recording, RecordingStatus, Settings, Sighting,
+    StoredSettings,
+};
 use crate::domain::value_objects::FaceEmbedding;
 
+#[cfg(test)]
+pub mod fakes;
+
 /// Result type for repository operations.
 pub type RepoResult<T> = Result<T, RepositoryError>;
 
@@ -25,6 +32,9 @@ pub enum RepositoryError {
 
     #[error("Constraint violation: {0}")]
     Constraint(String),
+
+    #[error("Conflict: {0}")]
+    Conflict(String),
 }
 
 /// Profile repository interface.
@@ -36,24 +46,65 @@ pub trait ProfileRepository: Send + Sync {
     /// Finds all active profiles.
     async fn find_all_active(&self) -> RepoResult<Vec<Profile>>;
 
-    /// Finds profiles matching an embedding within the threshold.
+    /// Finds a page of active profiles, most recently seen first.
+    async fn find_active_page(&self, limit: i64, offset: i64) -> RepoResult<Vec<Profile>>;
+
+    /// Finds profiles by a set of IDs in a single query.
+    async fn find_by_ids(&self, ids: &[Uuid]) -> RepoResult<Vec<Profile>>;
+
+    /// Finds profiles matching an embedding within the threshold, closest
+    /// first, skipping the `excluded` ones.
+    ///
+    /// Compares against every other active profile with an embedding, so
+    /// callers should exclude the profiles they have already searched.
     async fn find_by_embedding(
         &self,
         embedding: &FaceEmbedding,
         threshold: f32,
+        excluded: &[Uuid],
     ) -> RepoResult<Vec<(Profile, f32)>>;
 
-    /// Saves a new profile.
+    /// Saves a new profile with its embedding samples.
     async fn save(&self, profile: &Profile) -> RepoResult<()>;
 
-    /// Updates an existing profile.
+    /// Updates an existing profile. Its embedding samples are left as stored.
     async fn update(&self, profile: &Profile) -> RepoResult<()>;
 
+    /// Replaces the stored embedding samples of a profile.
+    async fn save_embedding_samples(&self, profile: &Profile) -> RepoResult<()>;
+
+    /// Saves `created` as new profiles and `updated` over the stored ones,
+    /// samples included, all at once: if any write fails, none are kept.
+    async fn import(&self, created: &[Profile], updated: &[Profile]) -> RepoResult<()>;
+
     /// Deletes a profile (soft delete).
     async fn delete(&self, id: Uuid) -> RepoResult<()>;
 
+    /// Permanently removes a profile row.
+    async fn hard_delete(&self, id: Uuid) -> RepoResult<()>;
+
+    /// Sets the classification of several profiles atomically.
+    /// Returns the IDs that were updated; unknown IDs are skipped.
+    async fn update_classification(
+        &self,
+        ids: &[Uuid],
+        classification: ProfileClassification,
+    ) -> RepoResult<Vec<Uuid>>;
+
+    /// Deactivates active `Unknown` profiles seen at most `max_sightings`
+    /// times and not since `seen_before`. Returns their IDs.
+    async fn deactivate_stale_unknown(
+        &self,
+        max_sightings: i64,
+        seen_before: DateTime<Utc>,
+    ) -> RepoResult<Vec<Uuid>>;
+
     /// Counts total profiles.
     async fn count(&self) -> RepoResult<i64>;
+
+    /// Counts active profiles per classification. Classifications without
+    /// profiles are left out.
+    async fn count_by_classification(&self) -> RepoResult<Vec<(ProfileClassification, i64)>>;
 }
 
 /// Sighting repository interface.
@@ -65,11 +116,70 @@ pub trait SightingRepository: Send + Sync {
     /// Finds sightings for a profile.
     async fn find_by_profile(&self, profile_id: Uuid, limit: i64) -> RepoResult<Vec<Sighting>>;
 
+    /// Finds a page of a camera's sightings, newest first.
+    async fn find_by_camera(
+        &self,
+        camera_id: Uuid,
+        limit: i64,
+        offset: i64,
+    ) -> RepoResult<Vec<Sighting>>;
+
+    /// Finds a page of a profile's sightings, oldest first.
+    async fn find_by_profile_page(
+        &self,
+        profile_id: Uuid,
+        limit: i64,
+        offset: i64,
+    ) -> RepoResult<Vec<Sighting>>;
+
+    /// Finds a page of a profile's sightings, newest first.
+    async fn find_recent_by_profile_page(
+        &self,
+        profile_id: Uuid,
+        limit: i64,
+        offset: i64,
+    ) -> RepoResult<Vec<Sighting>>;
+
     /// Finds sightings within a time range.
     async fn find_in_range(
         &self,
-        start: chrono::DateTime<chrono::Utc>,
-        end: chrono::DateTime<chrono::Utc>,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        limit: i64,
+    ) -> RepoResult<Vec<Sighting>>;
+
+    /// Finds sightings within a time range, optionally restricted to a camera
+    /// and/or profile. The limit applies to the filtered set.
+    async fn find_filtered(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        camera_id: Option<Uuid>,
+        profile_id: Option<Uuid>,
+        limit: i64,
+    ) -> RepoResult<Vec<Sighting>>;
+
+    /// Finds sightings within a time range, optionally for one camera,
+    /// ordered newest first by `(detected_at, id)`. With `before`, only
+    /// sightings strictly before that cursor are returned, so a caller can
+    /// page through rows sharing one timestamp.
+    async fn find_page_before(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        camera_id: Option<Uuid>,
+        before: Option<(DateTime<Utc>, Uuid)>,
+        limit: i64,
+    ) -> RepoResult<Vec<Sighting>>;
+
+    /// Finds sightings located within a latitude/longitude box, bounds
+    /// included, newest first. Sightings without a location are skipped.
+    async fn find_in_bbox(
+        &self,
+        min_lat: f64,
+        min_lon: f64,
+        max_lat: f64,
+        max_lon: f64,
         limit: i64,
     ) -> RepoResult<Vec<Sighting>>;
 
@@ -84,6 +194,23 @@ pub trait SightingRepository: Send + Sync {
 
     /// Counts sightings for a profile.
     async fn count_by_profile(&self, profile_id: Uuid) -> RepoResult<i64>;
+
+    /// Counts sightings from a camera.
+    async fn count_by_camera(&self, camera_id: Uuid) -> RepoResult<i64>;
+
+    /// Counts sightings per camera within a time range, with each camera's
+    /// most recent sighting. Cameras without sightings are omitted.
+    async fn count_by_camera_in_range(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> RepoResult<Vec<(Uuid, i64, DateTime<Utc>)>>;
+
+    /// Deletes a sighting, returning it if it existed.
+    async fn delete(&self, id: Uuid) -> RepoResult<Option<Sighting>>;
+
+    /// Deletes every sighting of a profile, returning the removed sightings.
+    async fn delete_by_profile(&self, profile_id: Uuid) -> RepoResult<Vec<Sighting>>;
 }
 
 /// Camera repository interface.
@@ -107,6 +234,10 @@ pub trait CameraRepository: Send + Sync {
     /// Updates an existing camera.
     async fn update(&self, camera: &Camera) -> RepoResult<()>;
 
+    /// Sets only the time a camera last delivered a frame, leaving changes
+    /// made to the rest of the camera meanwhile in place.
+    async fn update_last_frame(&self, id: Uuid, at: DateTime<Utc>) -> RepoResult<()>;
+
     /// Deletes a camera.
     async fn delete(&self, id: Uuid) -> RepoResult<()>;
 }
@@ -126,6 +257,16 @@ pub trait RecordingRepository: Send + Sync {
     /// Finds recordings with detections.
     async fn find_with_detections(&self, limit: i64) -> RepoResult<Vec<Recording>>;
 
+    /// Finds all recordings in the given status.
+    async fn find_in_status(&self, status: RecordingStatus) -> RepoResult<Vec<Recording>>;
+
+    /// Finds recordings matching every criterion of the filter, newest first.
+    /// Recordings marked for deletion are left out.
+    async fn query(&self, filter: &RecordingFilter) -> RepoResult<Vec<Recording>>;
+
+    /// Counts recordings matching the filter, ignoring its limit and offset.
+    async fn count_matching(&self, filter: &RecordingFilter) -> RepoResult<i64>;
+
     /// Saves a new recording.
     async fn save(&self, recording: &Recording) -> RepoResult<()>;
 
@@ -142,12 +283,170 @@ pub trait RecordingRepository: Send + Sync {
     async fn find_oldest(&self, limit: i64) -> RepoResult<Vec<Recording>>;
 }
 
+/// Criteria for [`RecordingRepository::query`]. Unset criteria match every
+/// recording.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordingFilter {
+    pub camera_id: Option<Uuid>,
+    pub has_detections: Option<bool>,
+    /// Earliest start time, inclusive.
+    pub start: Option<DateTime<Utc>>,
+    /// Latest start time, inclusive.
+    pub end: Option<DateTime<Utc>>,
+    pub limit: i64,
+    /// Matching recordings skipped before the first one returned.
+    pub offset: i64,
+}
+
+impl RecordingFilter {
+    /// Creates a filter matching the `limit` most recent recordings.
+    pub fn new(limit: i64) -> Self {
+        Self {
+            camera_id: None,
+            has_detections: None,
+            start: None,
+            end: None,
+            limit,
+            offset: 0,
+        }
+    }
+
+    /// Skips the first `offset` matching recordings.
+    pub fn offset(mut self, offset: i64) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Restricts to one camera.
+    pub fn camera(mut self, camera_id: Uuid) -> Self {
+        self.camera_id = Some(camera_id);
+        self
+    }
+
+    /// Restricts to recordings with, or without, detections.
+    pub fn has_detections(mut self, has_detections: bool) -> Self {
+        self.has_detections = Some(has_detections);
+        self
+    }
+
+    /// Restricts to recordings started on or after `start`.
+    pub fn started_after(mut self, start: DateTime<Utc>) -> Self {
+        self.start = Some(start);
+        self
+    }
+
+    /// Restricts to recordings started on or before `end`.
+    pub fn started_before(mut self, end: DateTime<Utc>) -> Self {
+        self.end = Some(end);
+        self
+    }
+
+    /// Whether a recording meets every criterion, ignoring the limit and
+    /// offset.
+    pub fn matches(&self, recording: &Recording) -> bool {
+        recording.status() != RecordingStatus::Deleting
+            && self.camera_id.is_none_or(|id| recording.camera_id() == id)
+            && self
+                .has_detections
+                .is_none_or(|flag| recording.has_detections() == flag)
+            && self
+                .start
+                .is_none_or(|start| recording.started_at() >= start)
+            && self.end.is_none_or(|end| recording.started_at() <= end)
+    }
+}
+
 /// Settings repository interface.
 #[async_trait]
 pub trait SettingsRepository: Send + Sync {
     /// Gets current settings.
     async fn get(&self) -> RepoResult<Settings>;
 
-    /// Saves settings.
-    async fn save(&self, settings: &Settings) -> RepoResult<()>;
+    /// Gets current settings with their version and timestamps.
+    async fn get_stored(&self) -> RepoResult<StoredSettings>;
+
+    /// Saves settings if the stored version still equals `expected_version`.
+    ///
+    /// Returns `RepositoryError::Conflict` when another update got there first.
+    async fn save(&self, settings: &Settings, expected_version: i64) -> RepoResult<StoredSettings>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn recording(camera_id: Uuid, has_detections: bool, started_at: DateTime<Utc>) -> Recording {
+        Recording::from_db(
+            Uuid::new_v4(),
+            camera_id,
+            "rec.mp4".to_string(),
+            0,
+            0,
+            0,
+            RecordingStatus::Completed,
+            has_detections,
+            started_at,
+            None,
+            started_at,
+            None,
+            None,
+        )
+    }
+
+    #[test]
+    fn recording_filter_combines_criteria() {
+        let camera = Uuid::new_v4();
+        let other_camera = Uuid::new_v4();
+        let now = Utc::now();
+        let hour_ago = now - Duration::hours(1);
+        let day_ago = now - Duration::days(1);
+
+        let recent_with_faces = recording(camera, true, now);
+        let old_with_faces = recording(camera, true, day_ago);
+        let recent_empty = recording(camera, false, now);
+        let other_camera_with_faces = recording(other_camera, true, now);
+        let all = [
+            &recent_with_faces,
+            &old_with_faces,
+            &recent_empty,
+            &other_camera_with_faces,
+        ];
+        let matching = |filter: RecordingFilter| -> Vec<Uuid> {
+            all.iter()
+                .filter(|r| filter.matches(r))
+                .map(|r| r.id())
+                .collect()
+        };
+
+        // No criteria match everything, including recordings without detections
+        assert_eq!(matching(RecordingFilter::new(50)).len(), 4);
+
+        assert_eq!(
+            matching(RecordingFilter::new(50).camera(camera)),
+            vec![
+                recent_with_faces.id(),
+                old_with_faces.id(),
+                recent_empty.id()
+            ]
+        );
+        assert_eq!(
+            matching(RecordingFilter::new(50).has_detections(false)),
+            vec![recent_empty.id()]
+        );
+        assert_eq!(
+            matching(RecordingFilter::new(50).started_before(hour_ago)),
+            vec![old_with_faces.id()]
+        );
+        assert_eq!(
+            matching(
+                RecordingFilter::new(50)
+                    .camera(camera)
+                    .has_detections(true)
+                    .started_after(hour_ago)
+                    .started_before(now)
+            ),
+            vec![recent_with_faces.id()]
+        );
+    }
 }
@@ -0,0 +1,580 @@
+//! In-Memory Repositories
+//!
+//! Test doubles for the repository traits, shared by every module's tests so
+//! a new trait method is implemented once.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use uuid::Uuid;
+
+use super::{
+    CameraRepository, ProfileRepository, RecordingFilter, RecordingRepository, RepoResult,
+    SettingsRepository, SightingRepository,
+};
+use crate::domain::entities::{
+    Camera, Profile, ProfileClassification, Recording, RecordingStatus, Settings, Sighting,
+    StoredSettings,
+};
+use crate::domain::repositories::RepositoryError;
+use crate::domain::value_objects::FaceEmbedding;
+
+/// Profiles kept in a map. Counts the lookups tests assert on.
+#[derive(Default)]
+pub struct InMemoryProfileRepo {
+    profiles: Mutex<HashMap<Uuid, Profile>>,
+    pub find_by_id_calls: AtomicUsize,
+    pub find_by_ids_calls: AtomicUsize,
+    pub find_by_embedding_calls: AtomicUsize,
+    /// The profiles excluded from the last embedding search.
+    pub find_by_embedding_excluded: Mutex<Vec<Uuid>>,
+    pub save_embedding_samples_calls: AtomicUsize,
+}
+
+impl InMemoryProfileRepo {
+    /// Creates a repository holding `profiles`.
+    pub fn with(profiles: impl IntoIterator<Item = Profile>) -> Self {
+        let repo = Self::default();
+        repo.profiles
+            .lock()
+            .unwrap()
+            .extend(profiles.into_iter().map(|p| (p.id(), p)));
+        repo
+    }
+
+    /// Returns the stored profile, active or not.
+    pub fn get(&self, id: Uuid) -> Option<Profile> {
+        self.profiles.lock().unwrap().get(&id).cloned()
+    }
+
+    /// Returns every stored profile, active or not.
+    pub fn all(&self) -> Vec<Profile> {
+        self.profiles.lock().unwrap().values().cloned().collect()
+    }
+}
+
+#[async_trait]
+impl ProfileRepository for InMemoryProfileRepo {
+    async fn find_by_id(&self, id: Uuid) -> RepoResult<Option<Profile>> {
+        self.find_by_id_calls.fetch_add(1, Ordering::SeqCst);
+        Ok(self.get(id))
+    }
+    async fn find_all_active(&self) -> RepoResult<Vec<Profile>> {
+        let profiles = self.profiles.lock().unwrap();
+        Ok(profiles
+            .values()
+            .filter(|p| p.is_active())
+            .cloned()
+            .collect())
+    }
+    async fn find_active_page(&self, limit: i64, offset: i64) -> RepoResult<Vec<Profile>> {
+        let mut profiles = self.find_all_active().await?;
+        profiles.sort_by_key(|p| (std::cmp::Reverse(p.last_seen_at()), p.id()));
+        Ok(profiles
+            .into_iter()
+            .skip(offset as usize)
+            .take(limit as usize)
+            .collect())
+    }
+    async fn find_by_ids(&self, ids: &[Uuid]) -> RepoResult<Vec<Profile>> {
+        self.find_by_ids_calls.fetch_add(1, Ordering::SeqCst);
+        let profiles = self.profiles.lock().unwrap();
+        Ok(ids
+            .iter()
+            .filter_map(|id| profiles.get(id).cloned())
+            .collect())
+    }
+    async fn find_by_embedding(
+        &self,
+        embedding: &FaceEmbedding,
+        threshold: f32,
+        excluded: &[Uuid],
+    ) -> RepoResult<Vec<(Profile, f32)>> {
+        self.find_by_embedding_calls.fetch_add(1, Ordering::SeqCst);
+        *self.find_by_embedding_excluded.lock().unwrap() = excluded.to_vec();
+        let mut matches: Vec<(Profile, f32)> = self
+            .find_all_active()
+            .await?
+            .into_iter()
+            .filter(|p| p.has_embedding() && !excluded.contains(&p.id()))
+            .map(|p| {
+                let distance = p.distance_to(embedding);
+                (p, distance)
+            })
+            .filter(|(_, distance)| *distance < threshold)
+            .collect();
+        matches.sort_by(|a, b| a.1.total_cmp(&b.1));
+        Ok(matches)
+    }
+    async fn save(&self, profile: &Profile) -> RepoResult<()> {
+        let mut profiles = self.profiles.lock().unwrap();
+        profiles.insert(profile.id(), profile.clone());
+        Ok(())
+    }
+    async fn update(&self, profile: &Profile) -> RepoResult<()> {
+        let mut profiles = self.profiles.lock().unwrap();
+        // Like the database, keep the stored samples
+        let samples = profiles
+            .get(&profile.id())
+            .map(|p| p.embeddings().to_vec())
+            .unwrap_or_default();
+        profiles.insert(
+            profile.id(),
+            profile.clone().with_embedding_samples(samples),
+        );
+        Ok(())
+    }
+    async fn save_embedding_samples(&self, profile: &Profile) -> RepoResult<()> {
+        self.save_embedding_samples_calls
+            .fetch_add(1, Ordering::SeqCst);
+        if let Some(stored) = self.profiles.lock().unwrap().get_mut(&profile.id()) {
+            *stored = stored
+                .clone()
+                .with_embedding_samples(profile.embeddings().to_vec());
+        }
+        Ok(())
+    }
+    async fn import(&self, created: &[Profile], updated: &[Profile]) -> RepoResult<()> {
+        let mut profiles = self.profiles.lock().unwrap();
+        if let Some(missing) = updated.iter().find(|p| !profiles.contains_key(&p.id())) {
+            return Err(RepositoryError::NotFound(format!(
+                "Profile {}",
+                missing.id()
+            )));
+        }
+        for profile in created.iter().chain(updated) {
+            profiles.insert(profile.id(), profile.clone());
+        }
+        Ok(())
+    }
+    async fn delete(&self, id: Uuid) -> RepoResult<()> {
+        if let Some(profile) = self.profiles.lock().unwrap().get_mut(&id) {
+            profile.deactivate();
+        }
+        Ok(())
+    }
+    async fn hard_delete(&self, id: Uuid) -> RepoResult<()> {
+        self.profiles.lock().unwrap().remove(&id);
+        Ok(())
+    }
+    async fn update_classification(
+        &self,
+        ids: &[Uuid],
+        classification: ProfileClassification,
+    ) -> RepoResult<Vec<Uuid>> {
+        let mut profiles = self.profiles.lock().unwrap();
+        let mut updated = Vec::new();
+        for id in ids {
+            if let Some(profile) = profiles.get_mut(id) {
+                profile.set_classification(classification);
+                updated.push(*id);
+            }
+        }
+        Ok(updated)
+    }
+    async fn deactivate_stale_unknown(
+        &self,
+        max_sightings: i64,
+        seen_before: DateTime<Utc>,
+    ) -> RepoResult<Vec<Uuid>> {
+        let mut profiles = self.profiles.lock().unwrap();
+        let mut deactivated = Vec::new();
+        for profile in profiles.values_mut() {
+            if profile.is_stale_unknown(max_sightings, seen_before) {
+                profile.deactivate();
+                deactivated.push(profile.id());
+            }
+        }
+        Ok(deactivated)
+    }
+    async fn count(&self) -> RepoResult<i64> {
+        Ok(self.find_all_active().await?.len() as i64)
+    }
+    async fn count_by_classification(&self) -> RepoResult<Vec<(ProfileClassification, i64)>> {
+        let mut counts: Vec<(ProfileClassification, i64)> = Vec::new();
+        for profile in self.find_all_active().await? {
+            match counts
+                .iter_mut()
+                .find(|(c, _)| *c == profile.classification())
+            {
+                Some((_, n)) => *n += 1,
+                None => counts.push((profile.classification(), 1)),
+            }
+        }
+        Ok(counts)
+    }
+}
+
+/// Sightings kept in insertion order.
+#[derive(Default)]
+pub struct InMemorySightingRepo {
+    sightings: Mutex<Vec<Sighting>>,
+}
+
+impl InMemorySightingRepo {
+    /// Returns every stored sighting in insertion order.
+    pub fn all(&self) -> Vec<Sighting> {
+        self.sightings.lock().unwrap().clone()
+    }
+
+    /// Sightings passing `keep`, newest first, at most `limit` of them.
+    fn newest(&self, limit: i64, keep: impl Fn(&Sighting) -> bool) -> Vec<Sighting> {
+        let mut sightings: Vec<Sighting> = self
+            .sightings
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|s| keep(s))
+            .cloned()
+            .collect();
+        sightings.sort_by_key(|s| std::cmp::Reverse(s.detected_at()));
+        sightings.truncate(limit.max(0) as usize);
+        sightings
+    }
+}
+
+#[async_trait]
+impl SightingRepository for InMemorySightingRepo {
+    async fn find_by_id(&self, id: Uuid) -> RepoResult<Option<Sighting>> {
+        let sightings = self.sightings.lock().unwrap();
+        Ok(sightings.iter().find(|s| s.id() == id).cloned())
+    }
+    async fn find_by_profile(&self, profile_id: Uuid, limit: i64) -> RepoResult<Vec<Sighting>> {
+        Ok(self.newest(limit, |s| s.profile_id() == profile_id))
+    }
+    async fn find_by_camera(
+        &self,
+        camera_id: Uuid,
+        limit: i64,
+        offset: i64,
+    ) -> RepoResult<Vec<Sighting>> {
+        Ok(self
+            .newest(i64::MAX, |s| s.camera_id() == camera_id)
+            .into_iter()
+            .skip(offset.max(0) as usize)
+            .take(limit.max(0) as usize)
+            .collect())
+    }
+    async fn find_by_profile_page(
+        &self,
+        profile_id: Uuid,
+        limit: i64,
+        offset: i64,
+    ) -> RepoResult<Vec<Sighting>> {
+        let mut sightings = self.newest(i64::MAX, |s| s.profile_id() == profile_id);
+        sightings.sort_by_key(|s| (s.detected_at(), s.id()));
+        Ok(sightings
+            .into_iter()
+            .skip(offset.max(0) as usize)
+            .take(limit.max(0) as usize)
+            .collect())
+    }
+    async fn find_recent_by_profile_page(
+        &self,
+        profile_id: Uuid,
+        limit: i64,
+        offset: i64,
+    ) -> RepoResult<Vec<Sighting>> {
+        Ok(self
+            .newest(i64::MAX, |s| s.profile_id() == profile_id)
+            .into_iter()
+            .skip(offset.max(0) as usize)
+            .take(limit.max(0) as usize)
+            .collect())
+    }
+    async fn find_in_range(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        limit: i64,
+    ) -> RepoResult<Vec<Sighting>> {
+        Ok(self.newest(limit, |s| (start..=end).contains(&s.detected_at())))
+    }
+    async fn find_filtered(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        camera_id: Option<Uuid>,
+        profile_id: Option<Uuid>,
+        limit: i64,
+    ) -> RepoResult<Vec<Sighting>> {
+        Ok(self.newest(limit, |s| {
+            (start..=end).contains(&s.detected_at())
+                && camera_id.is_none_or(|id| s.camera_id() == id)
+                && profile_id.is_none_or(|id| s.profile_id() == id)
+        }))
+    }
+    async fn find_page_before(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        camera_id: Option<Uuid>,
+        before: Option<(DateTime<Utc>, Uuid)>,
+        limit: i64,
+    ) -> RepoResult<Vec<Sighting>> {
+        let mut sightings = self.newest(i64::MAX, |s| {
+            (start..=end).contains(&s.detected_at())
+                && camera_id.is_none_or(|id| s.camera_id() == id)
+                && before.is_none_or(|cursor| (s.detected_at(), s.id()) < cursor)
+        });
+        sightings.sort_by_key(|s| std::cmp::Reverse((s.detected_at(), s.id())));
+        sightings.truncate(limit.max(0) as usize);
+        Ok(sightings)
+    }
+    async fn find_in_bbox(
+        &self,
+        min_lat: f64,
+        min_lon: f64,
+        max_lat: f64,
+        max_lon: f64,
+        limit: i64,
+    ) -> RepoResult<Vec<Sighting>> {
+        Ok(self.newest(limit, |s| {
+            s.location().is_some_and(|l| {
+                (min_lat..=max_lat).contains(&l.latitude())
+                    && (min_lon..=max_lon).contains(&l.longitude())
+            })
+        }))
+    }
+    async fn save(&self, sighting: &Sighting) -> RepoResult<()> {
+        self.sightings.lock().unwrap().push(sighting.clone());
+        Ok(())
+    }
+    async fn get_location_heatmap(&self) -> RepoResult<Vec<(f64, f64, i64)>> {
+        Ok(vec![])
+    }
+    async fn count(&self) -> RepoResult<i64> {
+        Ok(self.sightings.lock().unwrap().len() as i64)
+    }
+    async fn count_by_profile(&self, profile_id: Uuid) -> RepoResult<i64> {
+        let sightings = self.sightings.lock().unwrap();
+        Ok(sightings
+            .iter()
+            .filter(|s| s.profile_id() == profile_id)
+            .count() as i64)
+    }
+    async fn count_by_camera(&self, camera_id: Uuid) -> RepoResult<i64> {
+        let sightings = self.sightings.lock().unwrap();
+        Ok(sightings
+            .iter()
+            .filter(|s| s.camera_id() == camera_id)
+            .count() as i64)
+    }
+    async fn count_by_camera_in_range(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> RepoResult<Vec<(Uuid, i64, DateTime<Utc>)>> {
+        let mut counts: HashMap<Uuid, (i64, DateTime<Utc>)> = HashMap::new();
+        for sighting in self.find_in_range(start, end, i64::MAX).await? {
+            let entry = counts
+                .entry(sighting.camera_id())
+                .or_insert((0, sighting.detected_at()));
+            entry.0 += 1;
+            entry.1 = entry.1.max(sighting.detected_at());
+        }
+        let mut counts: Vec<_> = counts
+            .into_iter()
+            .map(|(camera_id, (count, last))| (camera_id, count, last))
+            .collect();
+        counts.sort_by_key(|(_, count, _)| std::cmp::Reverse(*count));
+        Ok(counts)
+    }
+    async fn delete(&self, id: Uuid) -> RepoResult<Option<Sighting>> {
+        let mut sightings = self.sightings.lock().unwrap();
+        let index = sightings.iter().position(|s| s.id() == id);
+        Ok(index.map(|i| sightings.remove(i)))
+    }
+    async fn delete_by_profile(&self, profile_id: Uuid) -> RepoResult<Vec<Sighting>> {
+        let mut sightings = self.sightings.lock().unwrap();
+        let (removed, kept) = sightings
+            .drain(..)
+            .partition(|s| s.profile_id() == profile_id);
+        *sightings = kept;
+        Ok(removed)
+    }
+}
+
+/// Cameras kept in insertion order.
+#[derive(Default)]
+pub struct InMemoryCameraRepo {
+    cameras: Mutex<Vec<Camera>>,
+}
+
+impl InMemoryCameraRepo {
+    /// Returns the stored camera.
+    pub fn get(&self, id: Uuid) -> Option<Camera> {
+        let cameras = self.cameras.lock().unwrap();
+        cameras.iter().find(|c| c.id() == id).cloned()
+    }
+}
+
+#[async_trait]
+impl CameraRepository for InMemoryCameraRepo {
+    async fn find_by_id(&self, id: Uuid) -> RepoResult<Option<Camera>> {
+        let cameras = self.cameras.lock().unwrap();
+        Ok(cameras.iter().find(|c| c.id() == id).cloned())
+    }
+    async fn find_by_device_id(&self, device_id: &str) -> RepoResult<Option<Camera>> {
+        let cameras = self.cameras.lock().unwrap();
+        Ok(cameras.iter().find(|c| c.device_id() == device_id).cloned())
+    }
+    async fn find_all(&self) -> RepoResult<Vec<Camera>> {
+        Ok(self.cameras.lock().unwrap().clone())
+    }
+    async fn find_enabled(&self) -> RepoResult<Vec<Camera>> {
+        let cameras = self.cameras.lock().unwrap();
+        Ok(cameras.iter().filter(|c| c.is_enabled()).cloned().collect())
+    }
+    async fn save(&self, camera: &Camera) -> RepoResult<()> {
+        self.cameras.lock().unwrap().push(camera.clone());
+        Ok(())
+    }
+    async fn update(&self, camera: &Camera) -> RepoResult<()> {
+        let mut cameras = self.cameras.lock().unwrap();
+        if let Some(existing) = cameras.iter_mut().find(|c| c.id() == camera.id()) {
+            *existing = camera.clone();
+        }
+        Ok(())
+    }
+    async fn update_last_frame(&self, id: Uuid, at: DateTime<Utc>) -> RepoResult<()> {
+        let mut cameras = self.cameras.lock().unwrap();
+        if let Some(camera) = cameras.iter_mut().find(|c| c.id() == id) {
+            camera.set_last_frame_at(at);
+        }
+        Ok(())
+    }
+    async fn delete(&self, id: Uuid) -> RepoResult<()> {
+        self.cameras.lock().unwrap().retain(|c| c.id() != id);
+        Ok(())
+    }
+}
+
+/// Recordings kept in insertion order.
+#[derive(Default)]
+pub struct InMemoryRecordingRepo {
+    recordings: Mutex<Vec<Recording>>,
+}
+
+#[async_trait]
+impl RecordingRepository for InMemoryRecordingRepo {
+    async fn find_by_id(&self, id: Uuid) -> RepoResult<Option<Recording>> {
+        let recordings = self.recordings.lock().unwrap();
+        Ok(recordings.iter().find(|r| r.id() == id).cloned())
+    }
+    async fn find_all(&self, _: i64) -> RepoResult<Vec<Recording>> {
+        Ok(self.recordings.lock().unwrap().clone())
+    }
+    async fn find_by_camera(&self, camera_id: Uuid, limit: i64) -> RepoResult<Vec<Recording>> {
+        self.query(&RecordingFilter::new(limit).camera(camera_id))
+            .await
+    }
+    async fn find_with_detections(&self, limit: i64) -> RepoResult<Vec<Recording>> {
+        self.query(&RecordingFilter::new(limit).has_detections(true))
+            .await
+    }
+    async fn find_in_status(&self, status: RecordingStatus) -> RepoResult<Vec<Recording>> {
+        let recordings = self.recordings.lock().unwrap();
+        Ok(recordings
+            .iter()
+            .filter(|r| r.status() == status)
+            .cloned()
+            .collect())
+    }
+    async fn query(&self, filter: &RecordingFilter) -> RepoResult<Vec<Recording>> {
+        let mut recordings: Vec<Recording> = self
+            .recordings
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|r| filter.matches(r))
+            .cloned()
+            .collect();
+        recordings.sort_by_key(|r| std::cmp::Reverse(r.started_at()));
+        Ok(recordings
+            .into_iter()
+            .skip(filter.offset.max(0) as usize)
+            .take(filter.limit.max(0) as usize)
+            .collect())
+    }
+    async fn count_matching(&self, filter: &RecordingFilter) -> RepoResult<i64> {
+        let recordings = self.recordings.lock().unwrap();
+        Ok(recordings.iter().filter(|r| filter.matches(r)).count() as i64)
+    }
+    async fn save(&self, recording: &Recording) -> RepoResult<()> {
+        self.recordings.lock().unwrap().push(recording.clone());
+        Ok(())
+    }
+    async fn update(&self, recording: &Recording) -> RepoResult<()> {
+        let mut recordings = self.recordings.lock().unwrap();
+        if let Some(existing) = recordings.iter_mut().find(|r| r.id() == recording.id()) {
+            *existing = recording.clone();
+        }
+        Ok(())
+    }
+    async fn delete(&self, id: Uuid) -> RepoResult<()> {
+        self.recordings.lock().unwrap().retain(|r| r.id() != id);
+        Ok(())
+    }
+    async fn total_storage_bytes(&self) -> RepoResult<i64> {
+        let recordings = self.recordings.lock().unwrap();
+        Ok(recordings.iter().map(Recording::file_size_bytes).sum())
+    }
+    async fn find_oldest(&self, limit: i64) -> RepoResult<Vec<Recording>> {
+        let mut completed: Vec<Recording> = self.find_in_status(RecordingStatus::Completed).await?;
+        completed.sort_by_key(Recording::started_at);
+        completed.truncate(limit as usize);
+        Ok(completed)
+    }
+}
+
+/// Settings with the version bookkeeping of the real table.
+pub struct InMemorySettingsRepo {
+    stored: Mutex<StoredSettings>,
+}
+
+impl InMemorySettingsRepo {
+    /// Creates a repository holding `settings` at version 1.
+    pub fn new(settings: Settings) -> Self {
+        let now = Utc::now();
+        Self {
+            stored: Mutex::new(StoredSettings {
+                settings,
+                version: 1,
+                created_at: now,
+                updated_at: now,
+            }),
+        }
+    }
+}
+
+impl Default for InMemorySettingsRepo {
+    fn default() -> Self {
+        Self::new(Settings::default())
+    }
+}
+
+#[async_trait]
+impl SettingsRepository for InMemorySettingsRepo {
+    async fn get(&self) -> RepoResult<Settings> {
+        Ok(self.stored.lock().unwrap().settings.clone())
+    }
+    async fn get_stored(&self) -> RepoResult<StoredSettings> {
+        Ok(self.stored.lock().unwrap().clone())
+    }
+    async fn save(&self, settings: &Settings, expected_version: i64) -> RepoResult<StoredSettings> {
+        let mut stored = self.stored.lock().unwrap();
+        if stored.version != expected_version {
+            return Err(RepositoryError::Conflict(format!(
+                "settings are at version {}",
+                stored.version
+            )));
+        }
+        stored.settings = settings.clone();
+        stored.version += 1;
+        stored.updated_at = Utc::now();
+        Ok(stored.clone())
+    }
+}
@@ -116,6 +116,43 @@ impl BoundingBox {
         }
     }
 
+    /// Returns `(x, y, width, height)` as fractions of the frame size.
+    /// The box is clipped to the frame, so every value lies in `[0, 1]`.
+    pub fn normalized(&self, frame_width: u32, frame_height: u32) -> (f32, f32, f32, f32) {
+        if frame_width == 0 || frame_height == 0 {
+            return (0.0, 0.0, 0.0, 0.0);
+        }
+        let (fw, fh) = (frame_width as f32, frame_height as f32);
+        let x1 = (self.x as f32 / fw).clamp(0.0, 1.0);
+        let y1 = (self.y as f32 / fh).clamp(0.0, 1.0);
+        let x2 = (self.right() as f32 / fw).clamp(0.0, 1.0);
+        let y2 = (self.bottom() as f32 / fh).clamp(0.0, 1.0);
+        (x1, y1, (x2 - x1).max(0.0), (y2 - y1).max(0.0))
+    }
+
+    /// Creates a pixel bounding box from fractions of the frame size.
+    /// Values are clamped so the box stays inside the frame.
+    pub fn from_normalized(
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+        frame_width: u32,
+        frame_height: u32,
+    ) -> Self {
+        let (fw, fh) = (frame_width as f32, frame_height as f32);
+        let x1 = x.clamp(0.0, 1.0);
+        let y1 = y.clamp(0.0, 1.0);
+        let x2 = (x + width).clamp(x1, 1.0);
+        let y2 = (y + height).clamp(y1, 1.0);
+        Self::from_corners(
+            (x1 * fw).round() as i32,
+            (y1 * fh).round() as i32,
+            (x2 * fw).round() as i32,
+            (y2 * fh).round() as i32,
+        )
+    }
+
     /// Converts to a JSON-compatible array format [x, y, width, height].
     pub fn to_array(&self) -> [i32; 4] {
         [self.x, self.y, self.width, self.height]
@@ -171,6 +208,39 @@ mod tests {
         assert!((b1.iou(&b2) - 0.0).abs() < f32::EPSILON);
     }
 
+    #[test]
+    fn normalized_roundtrip_preserves_values() {
+        let original = BoundingBox::new(320, 180, 64, 96);
+        let (x, y, w, h) = original.normalized(1280, 720);
+        assert_eq!((x, y), (0.25, 0.25));
+        assert!((w - 0.05).abs() < 1e-6);
+        assert!((h - 96.0 / 720.0).abs() < 1e-6);
+        assert_eq!(
+            BoundingBox::from_normalized(x, y, w, h, 1280, 720),
+            original
+        );
+    }
+
+    #[test]
+    fn normalized_clamps_at_frame_edges() {
+        let bbox = BoundingBox::new(-20, 600, 100, 200);
+        let (x, y, w, h) = bbox.normalized(640, 720);
+        assert_eq!((x, y), (0.0, 600.0 / 720.0));
+        assert_eq!(w, 80.0 / 640.0);
+        assert!((y + h - 1.0).abs() < f32::EPSILON);
+
+        assert_eq!(
+            BoundingBox::new(0, 0, 10, 10).normalized(0, 0),
+            (0.0, 0.0, 0.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn from_normalized_clamps_to_frame() {
+        let bbox = BoundingBox::from_normalized(-0.1, 0.9, 0.5, 0.5, 100, 100);
+        assert_eq!(bbox, BoundingBox::new(0, 90, 40, 10));
+    }
+
     #[test]
     fn array_roundtrip_preserves_values() {
         let original = BoundingBox::new(10, 20, 30, 40);
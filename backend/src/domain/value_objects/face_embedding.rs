@@ -1,13 +1,27 @@
 //! Face Embedding Value Object
 //!
-//! Represents a 128-dimensional face embedding vector.
+//! Represents a face embedding vector (128-d FaceNet by default, 512-d ArcFace).
 //! Reference: https://arxiv.org/abs/1503.03832 (FaceNet)
 
 use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicUsize, Ordering};
 
-/// Dimension of face embedding vector (FaceNet standard).
+/// Default dimension of face embedding vectors (FaceNet standard).
 pub const EMBEDDING_DIMENSION: usize = 128;
 
+/// Dimension produced by the configured embedding model.
+static CONFIGURED_DIMENSION: AtomicUsize = AtomicUsize::new(EMBEDDING_DIMENSION);
+
+/// Sets the dimension new embeddings must have. Called once at startup.
+pub fn set_embedding_dimension(dimension: usize) {
+    CONFIGURED_DIMENSION.store(dimension, Ordering::Relaxed);
+}
+
+/// Returns the dimension new embeddings must have.
+pub fn embedding_dimension() -> usize {
+    CONFIGURED_DIMENSION.load(Ordering::Relaxed)
+}
+
 /// A face embedding vector for facial recognition.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FaceEmbedding {
@@ -18,16 +32,17 @@ impl FaceEmbedding {
     /// Creates a new face embedding from a vector.
     ///
     /// # Panics
-    /// Panics if the vector length does not match EMBEDDING_DIMENSION.
+    /// Panics if the vector length does not match the configured dimension.
     pub fn new(values: Vec<f32>) -> Self {
-        assert_eq!(
-            values.len(),
-            EMBEDDING_DIMENSION,
-            "Embedding must have {} dimensions, got {}",
-            EMBEDDING_DIMENSION,
-            values.len()
-        );
-        Self { values }
+        let expected = embedding_dimension();
+        let len = values.len();
+        Self::with_dimension(values, expected)
+            .unwrap_or_else(|| panic!("Embedding must have {} dimensions, got {}", expected, len))
+    }
+
+    /// Creates an embedding, returning `None` unless it has `dimension` values.
+    pub fn with_dimension(values: Vec<f32>, dimension: usize) -> Option<Self> {
+        (values.len() == dimension).then_some(Self { values })
     }
 
     /// Creates a zero-filled embedding of the given dimension.
@@ -38,9 +53,18 @@ impl FaceEmbedding {
         }
     }
 
+    /// Returns true if every value is zero, as in a placeholder embedding.
+    pub fn is_zero(&self) -> bool {
+        self.values.iter().all(|v| *v == 0.0)
+    }
+
     /// Creates an embedding from a byte slice (for database storage).
+    ///
+    /// The dimension is taken from the blob length rather than the configured
+    /// dimension, so profiles enrolled with a previous model still load while
+    /// switching models. Embeddings of different dimensions never match.
     pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
-        if bytes.len() != EMBEDDING_DIMENSION * 4 {
+        if bytes.is_empty() || !bytes.len().is_multiple_of(4) {
             return None;
         }
 
@@ -62,11 +86,21 @@ impl FaceEmbedding {
         &self.values
     }
 
+    /// Returns the number of values in the embedding.
+    pub fn dimension(&self) -> usize {
+        self.values.len()
+    }
+
     /// Calculates the Euclidean distance to another embedding.
-    /// Lower values indicate more similar faces.
+    /// Lower values indicate more similar faces. Embeddings of different
+    /// dimensions are infinitely far apart.
     ///
     /// Reference: https://en.wikipedia.org/wiki/Euclidean_distance
     pub fn distance(&self, other: &FaceEmbedding) -> f32 {
+        if self.dimension() != other.dimension() {
+            return f32::INFINITY;
+        }
+
         self.values
             .iter()
             .zip(other.values.iter())
@@ -80,6 +114,10 @@ impl FaceEmbedding {
     ///
     /// Reference: https://en.wikipedia.org/wiki/Cosine_similarity
     pub fn cosine_similarity(&self, other: &FaceEmbedding) -> f32 {
+        if self.dimension() != other.dimension() {
+            return 0.0;
+        }
+
         let dot_product: f32 = self
             .values
             .iter()
@@ -97,9 +135,15 @@ impl FaceEmbedding {
         dot_product / (magnitude_self * magnitude_other)
     }
 
+    /// Returns the Euclidean length of the embedding; zero for a
+    /// placeholder.
+    pub fn l2_norm(&self) -> f32 {
+        self.values.iter().map(|x| x.powi(2)).sum::<f32>().sqrt()
+    }
+
     /// Normalizes the embedding to unit length (L2 normalization).
     pub fn normalize(&mut self) {
-        let magnitude: f32 = self.values.iter().map(|x| x.powi(2)).sum::<f32>().sqrt();
+        let magnitude = self.l2_norm();
         if magnitude > 0.0 {
             for v in &mut self.values {
                 *v /= magnitude;
@@ -117,10 +161,12 @@ impl FaceEmbedding {
 
 impl PartialEq for FaceEmbedding {
     fn eq(&self, other: &Self) -> bool {
-        self.values
-            .iter()
-            .zip(other.values.iter())
-            .all(|(a, b)| (a - b).abs() < f32::EPSILON)
+        self.dimension() == other.dimension()
+            && self
+                .values
+                .iter()
+                .zip(other.values.iter())
+                .all(|(a, b)| (a - b).abs() < f32::EPSILON)
     }
 }
 
@@ -181,4 +227,38 @@ mod tests {
     fn rejects_wrong_dimension() {
         FaceEmbedding::new(vec![0.0; 64]);
     }
+
+    #[test]
+    fn bytes_roundtrip_preserves_512_dimensions() {
+        let original = FaceEmbedding::with_dimension(vec![0.25; 512], 512).unwrap();
+        let restored = FaceEmbedding::from_bytes(&original.to_bytes()).unwrap();
+        assert_eq!(restored.dimension(), 512);
+        assert_eq!(original, restored);
+    }
+
+    #[test]
+    fn bytes_roundtrip_preserves_128_dimensions() {
+        let original = FaceEmbedding::with_dimension(vec![0.25; 128], 128).unwrap();
+        let restored = FaceEmbedding::from_bytes(&original.to_bytes()).unwrap();
+        assert_eq!(restored.dimension(), 128);
+    }
+
+    #[test]
+    fn with_dimension_rejects_mismatch() {
+        assert!(FaceEmbedding::with_dimension(vec![0.0; 128], 512).is_none());
+    }
+
+    #[test]
+    fn from_bytes_rejects_partial_values() {
+        assert!(FaceEmbedding::from_bytes(&[0u8; 6]).is_none());
+        assert!(FaceEmbedding::from_bytes(&[]).is_none());
+    }
+
+    #[test]
+    fn embeddings_of_different_dimensions_never_match() {
+        let small = FaceEmbedding::with_dimension(vec![0.0; 128], 128).unwrap();
+        let large = FaceEmbedding::with_dimension(vec![0.0; 512], 512).unwrap();
+        assert_eq!(small.distance(&large), f32::INFINITY);
+        assert_ne!(small, large);
+    }
 }
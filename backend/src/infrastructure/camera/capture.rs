@@ -1,13 +1,19 @@
 //! Camera Capture
 //!
-//! Video capture from various camera sources using nokhwa (AVFoundation on macOS).
+//! Video capture from various camera sources using nokhwa (AVFoundation on macOS),
+//! or from a directory of images replayed as frames.
 //! Reference: https://docs.rs/nokhwa/latest/nokhwa/
 
-use nokhwa::pixel_format::RgbFormat;
-use nokhwa::utils::{CameraIndex, RequestedFormat, RequestedFormatType};
-use nokhwa::Camera;
+use nokhwa::pixel_format::{LumaFormat, RgbFormat};
+use nokhwa::utils::{
+    CameraFormat, CameraIndex, FrameFormat, RequestedFormat, RequestedFormatType, Resolution,
+};
+use nokhwa::{Buffer, Camera, NokhwaError};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use tokio::sync::{broadcast, RwLock};
+use std::time::Duration;
+use tokio::sync::{broadcast, watch, RwLock};
 use tracing::{debug, error, info, warn};
 use uuid::Uuid;
 
@@ -19,9 +25,399 @@ pub struct CapturedFrame {
     pub timestamp_ms: i64,
     pub width: u32,
     pub height: u32,
+    pub pixel_format: PixelFormat,
     pub data: Vec<u8>,
 }
 
+/// Layout of the bytes in a captured frame.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PixelFormat {
+    /// Packed 8-bit RGB, 3 bytes per pixel.
+    #[default]
+    Rgb,
+    /// Packed YUYV 4:2:2 (YUY2), 2 bytes per pixel.
+    Yuyv,
+    /// Full Y plane followed by interleaved half-resolution UV.
+    Nv12,
+    /// 8-bit luma only.
+    Gray,
+    /// Not reported by the camera; consumers must guess from the buffer size.
+    Unknown,
+}
+
+impl PixelFormat {
+    /// Parses a format name: `rgb`, `yuyv`, `nv12` or `gray`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "rgb" => Some(Self::Rgb),
+            "yuyv" | "yuy2" => Some(Self::Yuyv),
+            "nv12" => Some(Self::Nv12),
+            "gray" | "grey" | "luma" => Some(Self::Gray),
+            _ => None,
+        }
+    }
+
+    /// Camera frame format delivering this layout without conversion, if
+    /// there is one.
+    fn frame_format(&self) -> Option<FrameFormat> {
+        match self {
+            Self::Yuyv => Some(FrameFormat::YUYV),
+            Self::Nv12 => Some(FrameFormat::NV12),
+            Self::Gray => Some(FrameFormat::GRAY),
+            Self::Rgb | Self::Unknown => None,
+        }
+    }
+}
+
+impl std::str::FromStr for PixelFormat {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Self::from_name(name).ok_or_else(|| format!("Unknown pixel format {:?}", name))
+    }
+}
+
+impl From<FrameFormat> for PixelFormat {
+    fn from(format: FrameFormat) -> Self {
+        match format {
+            FrameFormat::RAWRGB => Self::Rgb,
+            FrameFormat::YUYV => Self::Yuyv,
+            FrameFormat::NV12 => Self::Nv12,
+            FrameFormat::GRAY => Self::Gray,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+/// A frame read from a [`FrameSource`].
+#[derive(Debug, Clone)]
+pub struct SourceFrame {
+    pub width: u32,
+    pub height: u32,
+    pub pixel_format: PixelFormat,
+    pub data: Vec<u8>,
+}
+
+/// Where a capture reads its frames from.
+///
+/// Methods block, so the capture loop calls them on the blocking pool.
+pub trait FrameSource: Send {
+    /// Opens the source and returns its resolution.
+    fn open(&mut self) -> anyhow::Result<(u32, u32)>;
+
+    /// Reads the next frame. `None` means the source is exhausted and the
+    /// capture stops.
+    fn next_frame(&mut self) -> anyhow::Result<Option<SourceFrame>>;
+}
+
+/// A camera device failure the user can fix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum DeviceAccessError {
+    #[error(
+        "Camera access was denied. Allow Safelynx under System Settings > Privacy & Security > \
+         Camera, then start the camera again."
+    )]
+    PermissionDenied,
+    #[error("No camera device was found. Check that the camera is connected.")]
+    NoDevice,
+}
+
+/// Error text that means camera access was refused.
+const PERMISSION_DENIED_HINTS: &[&str] = &[
+    "permission",
+    "not authorized",
+    "unauthorized",
+    "denied",
+    "restricted",
+];
+
+/// Error text that means the device isn't there.
+const NO_DEVICE_HINTS: &[&str] = &[
+    "not found",
+    "no device",
+    "no such device",
+    "no camera",
+    "out of range",
+    "does not exist",
+];
+
+impl DeviceAccessError {
+    /// Classifies a nokhwa error, returning `None` for failures that are
+    /// neither a permission denial nor a missing device.
+    pub fn classify(error: &NokhwaError) -> Option<Self> {
+        let message = error.to_string().to_lowercase();
+        let mentions = |hints: &[&str]| hints.iter().any(|hint| message.contains(hint));
+
+        if mentions(PERMISSION_DENIED_HINTS) {
+            return Some(Self::PermissionDenied);
+        }
+        match error {
+            NokhwaError::OpenDeviceError(..)
+            | NokhwaError::GeneralError(_)
+            | NokhwaError::StructureError { .. }
+                if mentions(NO_DEVICE_HINTS) =>
+            {
+                Some(Self::NoDevice)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Wraps a nokhwa error from opening a device, tagged with its
+/// [`DeviceAccessError`] if it has one.
+fn open_error(error: NokhwaError) -> anyhow::Error {
+    match DeviceAccessError::classify(&error) {
+        Some(kind) => anyhow::Error::new(error).context(kind),
+        None => error.into(),
+    }
+}
+
+/// How long to wait for the user to answer the camera permission prompt.
+const PERMISSION_PROMPT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Checks camera permission, asking for it if it hasn't been decided, so a
+/// denial is reported as such rather than as a failure to open the device.
+fn check_camera_permission() -> Result<(), DeviceAccessError> {
+    if nokhwa::nokhwa_check() {
+        return Ok(());
+    }
+
+    info!("Requesting camera access - macOS should prompt for it now...");
+    let (granted_tx, granted_rx) = std::sync::mpsc::channel();
+    nokhwa::nokhwa_initialize(move |granted| {
+        let _ = granted_tx.send(granted);
+    });
+    match granted_rx.recv_timeout(PERMISSION_PROMPT_TIMEOUT) {
+        Ok(true) => Ok(()),
+        _ => Err(DeviceAccessError::PermissionDenied),
+    }
+}
+
+/// Builds the nokhwa format request for frames in `pixel_format`.
+///
+/// RGB and gray frames are decoded from whatever the camera delivers; YUYV
+/// and NV12 are only accepted from cameras that deliver them natively.
+fn requested_format(
+    format_type: RequestedFormatType,
+    pixel_format: PixelFormat,
+) -> RequestedFormat<'static> {
+    match pixel_format {
+        PixelFormat::Yuyv => RequestedFormat::with_formats(format_type, &[FrameFormat::YUYV]),
+        PixelFormat::Nv12 => RequestedFormat::with_formats(format_type, &[FrameFormat::NV12]),
+        PixelFormat::Gray => RequestedFormat::new::<LumaFormat>(format_type),
+        PixelFormat::Rgb | PixelFormat::Unknown => RequestedFormat::new::<RgbFormat>(format_type),
+    }
+}
+
+/// Frames from a local camera device, read through nokhwa.
+pub struct NokhwaSource {
+    device_index: u32,
+    permission_preflight: bool,
+    format_type: RequestedFormatType,
+    pixel_format: PixelFormat,
+    camera: Option<Camera>,
+    frames_read: u64,
+}
+
+impl NokhwaSource {
+    /// Creates a source for the camera at the given enumeration index.
+    pub fn new(device_index: u32) -> Self {
+        Self {
+            device_index,
+            permission_preflight: true,
+            format_type: RequestedFormatType::AbsoluteHighestResolution,
+            pixel_format: PixelFormat::Rgb,
+            camera: None,
+            frames_read: 0,
+        }
+    }
+
+    /// Sets the camera format to ask for and the layout frames are
+    /// delivered in.
+    pub fn with_format(
+        mut self,
+        format_type: RequestedFormatType,
+        pixel_format: PixelFormat,
+    ) -> Self {
+        self.format_type = format_type;
+        self.pixel_format = pixel_format;
+        self
+    }
+
+    /// Sets whether camera permission is checked before opening the device.
+    pub fn with_permission_preflight(mut self, enabled: bool) -> Self {
+        self.permission_preflight = enabled;
+        self
+    }
+}
+
+impl FrameSource for NokhwaSource {
+    fn open(&mut self) -> anyhow::Result<(u32, u32)> {
+        if self.permission_preflight {
+            check_camera_permission()?;
+        }
+
+        let index = CameraIndex::Index(self.device_index);
+        let requested = requested_format(self.format_type, self.pixel_format);
+
+        info!("Opening camera at index {}...", self.device_index);
+        let mut cam = Camera::new(index, requested).map_err(open_error)?;
+
+        // Get actual resolution
+        let resolution = cam.resolution();
+        info!(
+            "Camera resolution: {}x{}",
+            resolution.width(),
+            resolution.height()
+        );
+
+        // Open the camera stream - this triggers macOS permission dialog
+        info!("Opening camera stream - macOS should prompt for camera access now...");
+        cam.open_stream().map_err(open_error)?;
+
+        info!("Camera stream opened successfully!");
+        self.camera = Some(cam);
+        Ok((resolution.width(), resolution.height()))
+    }
+
+    fn next_frame(&mut self) -> anyhow::Result<Option<SourceFrame>> {
+        let camera = self
+            .camera
+            .as_mut()
+            .ok_or_else(|| anyhow::anyhow!("Camera stream is not open"))?;
+        let buffer = camera.frame()?;
+        self.frames_read += 1;
+
+        match self.pixel_format {
+            // Native YUV frames are passed through for the detector to read
+            // the luma from directly
+            PixelFormat::Yuyv | PixelFormat::Nv12
+                if self.pixel_format.frame_format() == Some(buffer.source_frame_format()) =>
+            {
+                return Ok(Some(raw_frame(&buffer)));
+            }
+            PixelFormat::Gray => {
+                return Ok(Some(match buffer.decode_image::<LumaFormat>() {
+                    Ok(decoded) => SourceFrame {
+                        width: decoded.width(),
+                        height: decoded.height(),
+                        pixel_format: PixelFormat::Gray,
+                        data: decoded.into_raw(),
+                    },
+                    Err(e) => {
+                        warn!("Failed to decode frame to gray: {}, using raw buffer", e);
+                        raw_frame(&buffer)
+                    }
+                }));
+            }
+            _ => {}
+        }
+
+        // nokhwa returns data in various formats - decode to RGB
+        let frame = match buffer.decode_image::<RgbFormat>() {
+            Ok(decoded) => {
+                if self.frames_read == 1 {
+                    info!(
+                        "First frame decoded: {}x{}, RGB buffer {} bytes (expected {})",
+                        decoded.width(),
+                        decoded.height(),
+                        decoded.as_raw().len(),
+                        decoded.width() as usize * decoded.height() as usize * 3
+                    );
+                }
+                SourceFrame {
+                    width: decoded.width(),
+                    height: decoded.height(),
+                    pixel_format: PixelFormat::Rgb,
+                    data: decoded.into_raw(),
+                }
+            }
+            Err(e) => {
+                warn!("Failed to decode frame to RGB: {}, using raw buffer", e);
+                raw_frame(&buffer)
+            }
+        };
+
+        Ok(Some(frame))
+    }
+}
+
+/// Copies a camera buffer as is, in the format the camera delivered it.
+fn raw_frame(buffer: &Buffer) -> SourceFrame {
+    SourceFrame {
+        width: buffer.resolution().width(),
+        height: buffer.resolution().height(),
+        pixel_format: buffer.source_frame_format().into(),
+        data: buffer.buffer().to_vec(),
+    }
+}
+
+/// Replays the images in a directory as RGB frames, in file name order, so
+/// the pipeline can be driven without camera hardware.
+#[derive(Debug)]
+pub struct FakeSource {
+    frames: std::vec::IntoIter<PathBuf>,
+    resolution: (u32, u32),
+}
+
+impl FakeSource {
+    /// Creates a source replaying the images in `dir`. Files that are not
+    /// images are skipped.
+    pub fn from_dir(dir: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let dir = dir.as_ref();
+        let mut frames: Vec<PathBuf> = std::fs::read_dir(dir)?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.is_file() && image::ImageFormat::from_path(path).is_ok())
+            .collect();
+        frames.sort();
+
+        let Some(first) = frames.first() else {
+            anyhow::bail!("No images to replay in {}", dir.display());
+        };
+        let resolution = image::image_dimensions(first)?;
+
+        Ok(Self {
+            frames: frames.into_iter(),
+            resolution,
+        })
+    }
+}
+
+impl FrameSource for FakeSource {
+    fn open(&mut self) -> anyhow::Result<(u32, u32)> {
+        Ok(self.resolution)
+    }
+
+    fn next_frame(&mut self) -> anyhow::Result<Option<SourceFrame>> {
+        let Some(path) = self.frames.next() else {
+            return Ok(None);
+        };
+        let image = image::open(&path)?.to_rgb8();
+
+        Ok(Some(SourceFrame {
+            width: image.width(),
+            height: image.height(),
+            pixel_format: PixelFormat::Rgb,
+            data: image.into_raw(),
+        }))
+    }
+}
+
+/// Which [`FrameSource`] a capture reads from.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum CaptureSource {
+    /// The local camera at the configured device index.
+    #[default]
+    Device,
+    /// Images in a directory, replayed once in file name order.
+    Replay(PathBuf),
+}
+
+/// Frames buffered for each subscriber unless configured.
+pub const DEFAULT_FRAME_BUFFER: usize = 32;
+
 /// Camera capture configuration.
 #[derive(Debug, Clone)]
 pub struct CaptureConfig {
@@ -29,6 +425,19 @@ pub struct CaptureConfig {
     pub width: u32,
     pub height: u32,
     pub fps: u32,
+    pub source: CaptureSource,
+    /// Frames a subscriber can fall behind before it skips the oldest ones.
+    pub frame_buffer: usize,
+    /// Check camera permission before opening a device, so a denial is
+    /// reported as [`CaptureState::PermissionDenied`].
+    pub permission_preflight: bool,
+    /// Camera format to ask the device for. `None` asks for the format
+    /// closest to `width`, `height` and `fps`.
+    pub format_type: Option<RequestedFormatType>,
+    /// Layout frames are delivered in. YUYV and NV12 skip the RGB
+    /// conversion but leave frames without face embeddings or thumbnails,
+    /// which need RGB.
+    pub pixel_format: PixelFormat,
 }
 
 impl Default for CaptureConfig {
@@ -38,10 +447,44 @@ impl Default for CaptureConfig {
             width: 1280,
             height: 720,
             fps: 30,
+            source: CaptureSource::Device,
+            frame_buffer: DEFAULT_FRAME_BUFFER,
+            permission_preflight: true,
+            format_type: None,
+            pixel_format: PixelFormat::Rgb,
         }
     }
 }
 
+impl CaptureConfig {
+    /// The camera format to ask the device for.
+    pub fn requested_format_type(&self) -> RequestedFormatType {
+        self.format_type.unwrap_or_else(|| {
+            let frame_format = self
+                .pixel_format
+                .frame_format()
+                .unwrap_or(FrameFormat::MJPEG);
+            RequestedFormatType::Closest(CameraFormat::new(
+                Resolution::new(self.width, self.height),
+                frame_format,
+                self.fps,
+            ))
+        })
+    }
+
+    /// Creates the frame source this configuration selects.
+    pub fn frame_source(&self) -> anyhow::Result<Box<dyn FrameSource>> {
+        Ok(match &self.source {
+            CaptureSource::Device => Box::new(
+                NokhwaSource::new(self.device_index)
+                    .with_permission_preflight(self.permission_preflight)
+                    .with_format(self.requested_format_type(), self.pixel_format),
+            ),
+            CaptureSource::Replay(dir) => Box::new(FakeSource::from_dir(dir)?),
+        })
+    }
+}
+
 /// Camera capture state.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CaptureState {
@@ -49,27 +492,89 @@ pub enum CaptureState {
     Starting,
     Running,
     Error,
+    /// The camera couldn't be opened because access to it was denied.
+    PermissionDenied,
+    /// The camera device couldn't be found.
+    NoDevice,
 }
 
-/// Camera capture manager using nokhwa for real hardware access.
+impl CaptureState {
+    /// Status string reported to clients; a running capture is `streaming`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Stopped => "stopped",
+            Self::Starting => "starting",
+            Self::Running => "streaming",
+            Self::Error => "error",
+            Self::PermissionDenied => "permission_denied",
+            Self::NoDevice => "no_device",
+        }
+    }
+
+    /// Returns true if the capture failed.
+    pub fn is_failed(&self) -> bool {
+        matches!(self, Self::Error | Self::PermissionDenied | Self::NoDevice)
+    }
+
+    /// What the user can do to fix a failed capture, if anything.
+    pub fn hint(&self) -> Option<String> {
+        match self {
+            Self::PermissionDenied => Some(DeviceAccessError::PermissionDenied.to_string()),
+            Self::NoDevice => Some(DeviceAccessError::NoDevice.to_string()),
+            _ => None,
+        }
+    }
+
+    /// The state a capture that failed to open with `error` ends in.
+    fn failed_with(error: &anyhow::Error) -> Self {
+        match error.downcast_ref::<DeviceAccessError>() {
+            Some(DeviceAccessError::PermissionDenied) => Self::PermissionDenied,
+            Some(DeviceAccessError::NoDevice) => Self::NoDevice,
+            None => Self::Error,
+        }
+    }
+}
+
+/// Moves a capture to `new`, notifying watchers only if the state changed.
+fn set_state(state: &watch::Sender<CaptureState>, new: CaptureState) {
+    state.send_if_modified(|current| {
+        let changed = *current != new;
+        *current = new;
+        changed
+    });
+}
+
+/// A capture's configuration next to what it is actually doing.
+#[derive(Debug, Clone)]
+pub struct CaptureStatus {
+    pub state: CaptureState,
+    pub config: CaptureConfig,
+    /// Frame size the source opened at, or of the latest pushed frame;
+    /// `None` until the capture has opened.
+    pub resolution: Option<(u32, u32)>,
+}
+
+/// Camera capture manager, reading frames from the configured [`FrameSource`].
 pub struct CameraCapture {
     camera_id: Uuid,
     config: CaptureConfig,
-    state: Arc<RwLock<CaptureState>>,
+    state: Arc<watch::Sender<CaptureState>>,
     frame_sender: broadcast::Sender<CapturedFrame>,
     frame_count: Arc<RwLock<u64>>,
+    resolution: Arc<std::sync::Mutex<Option<(u32, u32)>>>,
 }
 
 impl CameraCapture {
     /// Creates a new camera capture.
     pub fn new(camera_id: Uuid, config: CaptureConfig) -> Self {
-        let (frame_sender, _) = broadcast::channel(32);
+        let (frame_sender, _) = broadcast::channel(config.frame_buffer.max(1));
         Self {
             camera_id,
             config,
-            state: Arc::new(RwLock::new(CaptureState::Stopped)),
+            state: Arc::new(watch::channel(CaptureState::Stopped).0),
             frame_sender,
             frame_count: Arc::new(RwLock::new(0)),
+            resolution: Arc::new(std::sync::Mutex::new(None)),
         }
     }
 
@@ -78,19 +583,37 @@ impl CameraCapture {
         self.frame_sender.subscribe()
     }
 
+    /// Returns the number of receivers currently subscribed to frames.
+    pub fn subscriber_count(&self) -> usize {
+        self.frame_sender.receiver_count()
+    }
+
     /// Returns the current capture state.
     pub async fn state(&self) -> CaptureState {
-        *self.state.read().await
+        *self.state.borrow()
+    }
+
+    /// Returns the capture's configuration, state and actual resolution.
+    pub fn status(&self) -> CaptureStatus {
+        CaptureStatus {
+            state: *self.state.borrow(),
+            config: self.config.clone(),
+            resolution: *self.resolution.lock().unwrap(),
+        }
+    }
+
+    /// Watches the capture state. The receiver sees each change, and closes
+    /// once the capture and its capture loop are gone.
+    pub fn watch_state(&self) -> watch::Receiver<CaptureState> {
+        self.state.subscribe()
     }
 
     /// Starts the camera capture.
     pub async fn start(&self) -> anyhow::Result<()> {
-        let mut state = self.state.write().await;
-        if *state == CaptureState::Running {
+        if *self.state.borrow() == CaptureState::Running {
             return Ok(());
         }
-        *state = CaptureState::Starting;
-        drop(state);
+        set_state(&self.state, CaptureState::Starting);
 
         info!(
             "Starting camera capture for {} with device index {}",
@@ -103,15 +626,30 @@ impl CameraCapture {
         let state = self.state.clone();
         let frame_sender = self.frame_sender.clone();
         let frame_count = self.frame_count.clone();
+        let resolution = self.resolution.clone();
 
         tokio::spawn(async move {
-            match Self::capture_loop(camera_id, config, state.clone(), frame_sender, frame_count)
-                .await
+            match Self::capture_loop(
+                camera_id,
+                config,
+                state.clone(),
+                frame_sender,
+                frame_count,
+                resolution,
+            )
+            .await
             {
                 Ok(_) => info!("Camera capture stopped for {}", camera_id),
                 Err(e) => {
-                    error!("Camera capture error for {}: {}", camera_id, e);
-                    *state.write().await = CaptureState::Error;
+                    error!("Camera capture error for {}: {:#}", camera_id, e);
+                    // Keep a more specific failure set by the capture loop
+                    state.send_if_modified(|current| {
+                        let failed = !current.is_failed();
+                        if failed {
+                            *current = CaptureState::Error;
+                        }
+                        failed
+                    });
                 }
             }
         });
@@ -122,65 +660,56 @@ impl CameraCapture {
     /// Stops the camera capture.
     pub async fn stop(&self) {
         info!("Stopping camera capture for {}", self.camera_id);
-        *self.state.write().await = CaptureState::Stopped;
+        set_state(&self.state, CaptureState::Stopped);
     }
 
     async fn capture_loop(
         camera_id: Uuid,
         config: CaptureConfig,
-        state: Arc<RwLock<CaptureState>>,
+        state: Arc<watch::Sender<CaptureState>>,
         frame_sender: broadcast::Sender<CapturedFrame>,
         frame_count: Arc<RwLock<u64>>,
+        resolution: Arc<std::sync::Mutex<Option<(u32, u32)>>>,
     ) -> anyhow::Result<()> {
-        info!(
-            "Initializing camera {} with nokhwa (AVFoundation)",
-            camera_id
-        );
+        let mut source = config.frame_source()?;
 
-        // Camera initialization must happen in a blocking context
-        let device_index = config.device_index;
-        let init_result = tokio::task::spawn_blocking(move || {
-            let index = CameraIndex::Index(device_index);
-            let requested =
-                RequestedFormat::new::<RgbFormat>(RequestedFormatType::AbsoluteHighestResolution);
-
-            info!("Opening camera at index {}...", device_index);
-            let mut cam = Camera::new(index, requested)?;
-
-            // Get actual resolution
-            let resolution = cam.resolution();
-            info!(
-                "Camera resolution: {}x{}",
-                resolution.width(),
-                resolution.height()
-            );
-
-            // Open the camera stream - this triggers macOS permission dialog
-            info!("Opening camera stream - macOS should prompt for camera access now...");
-            cam.open_stream()?;
-
-            info!("Camera stream opened successfully!");
-            Ok::<_, nokhwa::NokhwaError>((cam, resolution.width(), resolution.height()))
+        // Opening a camera blocks, so it happens in a blocking context
+        let (source, init_result) = tokio::task::spawn_blocking(move || {
+            let result = source.open();
+            (source, result)
         })
         .await?;
 
-        let (camera, actual_width, actual_height) = match init_result {
+        let (actual_width, actual_height) = match init_result {
             Ok(result) => result,
             Err(e) => {
-                error!("Failed to initialize camera: {}", e);
-                *state.write().await = CaptureState::Error;
-                return Err(anyhow::anyhow!("Camera initialization failed: {}", e));
+                error!("Failed to initialize camera: {:#}", e);
+                set_state(&state, CaptureState::failed_with(&e));
+                return Err(e.context("Camera initialization failed"));
             }
         };
 
-        *state.write().await = CaptureState::Running;
+        // A stop while the device was opening wins; dropping the source
+        // closes it again
+        let started = state.send_if_modified(|current| {
+            let starting = *current == CaptureState::Starting;
+            if starting {
+                *current = CaptureState::Running;
+            }
+            starting
+        });
+        if !started {
+            info!("Capture for {} stopped while opening", camera_id);
+            return Ok(());
+        }
+        *resolution.lock().unwrap() = Some((actual_width, actual_height));
         info!(
             "Camera capture running - resolution: {}x{}",
             actual_width, actual_height
         );
 
-        // Wrap camera in Arc<Mutex> for safe access across blocking tasks
-        let camera = Arc::new(std::sync::Mutex::new(camera));
+        // Wrap source in Arc<Mutex> for safe access across blocking tasks
+        let source = Arc::new(std::sync::Mutex::new(source));
 
         let frame_interval = std::time::Duration::from_millis(1000 / config.fps as u64);
         let mut interval = tokio::time::interval(frame_interval);
@@ -188,61 +717,42 @@ impl CameraCapture {
         loop {
             interval.tick().await;
 
-            if *state.read().await != CaptureState::Running {
+            if *state.borrow() != CaptureState::Running {
                 info!("Capture state changed, stopping loop");
                 break;
             }
 
             // Capture frame in blocking context
-            let camera_clone = camera.clone();
+            let source_clone = source.clone();
             let frame_result = tokio::task::spawn_blocking(move || {
-                let mut cam = camera_clone.lock().unwrap();
-                cam.frame()
+                let mut source = source_clone.lock().unwrap();
+                source.next_frame()
             })
             .await;
 
             match frame_result {
-                Ok(Ok(buffer)) => {
+                Ok(Ok(Some(captured))) => {
                     let mut count = frame_count.write().await;
                     *count += 1;
                     let frame_num = *count;
                     drop(count);
 
-                    // nokhwa returns data in various formats - decode to RGB
-                    let rgb_data = match buffer.decode_image::<RgbFormat>() {
-                        Ok(decoded) => {
-                            if frame_num == 1 {
-                                info!(
-                                    "First frame decoded: {}x{}, RGB buffer {} bytes (expected {})",
-                                    decoded.width(),
-                                    decoded.height(),
-                                    decoded.as_raw().len(),
-                                    decoded.width() as usize * decoded.height() as usize * 3
-                                );
-                            }
-                            decoded.into_raw()
-                        }
-                        Err(e) => {
-                            warn!("Failed to decode frame to RGB: {}, using raw buffer", e);
-                            buffer.buffer().to_vec()
-                        }
-                    };
-
                     let frame = CapturedFrame {
                         camera_id,
                         frame_number: frame_num,
                         timestamp_ms: chrono::Utc::now().timestamp_millis(),
-                        width: actual_width,
-                        height: actual_height,
-                        data: rgb_data,
+                        width: captured.width,
+                        height: captured.height,
+                        pixel_format: captured.pixel_format,
+                        data: captured.data,
                     };
 
                     if frame_num % 30 == 0 {
                         debug!(
                             "Captured frame {} ({}x{}, {} bytes)",
                             frame_num,
-                            actual_width,
-                            actual_height,
+                            frame.width,
+                            frame.height,
                             frame.data.len()
                         );
                     }
@@ -251,6 +761,10 @@ impl CameraCapture {
                         // No subscribers - that's OK, just means no one is processing frames yet
                     }
                 }
+                Ok(Ok(None)) => {
+                    info!("Frame source for {} is exhausted", camera_id);
+                    break;
+                }
                 Ok(Err(e)) => {
                     warn!("Frame capture error: {}", e);
                 }
@@ -262,12 +776,38 @@ impl CameraCapture {
 
         // Close camera
         info!("Closing camera...");
-        drop(camera);
-        *state.write().await = CaptureState::Stopped;
+        drop(source);
+        set_state(&state, CaptureState::Stopped);
 
         Ok(())
     }
 
+    /// Publishes an RGB frame from an external source, such as a browser,
+    /// to subscribers as if it had been captured from a device.
+    pub async fn push_frame(&self, width: u32, height: u32, data: Vec<u8>) {
+        *self.resolution.lock().unwrap() = Some((width, height));
+        set_state(&self.state, CaptureState::Running);
+
+        let frame_number = {
+            let mut count = self.frame_count.write().await;
+            *count += 1;
+            *count
+        };
+
+        let frame = CapturedFrame {
+            camera_id: self.camera_id,
+            frame_number,
+            timestamp_ms: chrono::Utc::now().timestamp_millis(),
+            width,
+            height,
+            pixel_format: PixelFormat::Rgb,
+            data,
+        };
+
+        // No subscribers just means nothing is processing frames yet
+        let _ = self.frame_sender.send(frame);
+    }
+
     /// Returns the current frame count.
     pub async fn frame_count(&self) -> u64 {
         *self.frame_count.read().await
@@ -328,9 +868,241 @@ mod tests {
         assert_eq!(config.fps, 30);
     }
 
+    #[test]
+    fn pixel_format_follows_source_frame_format() {
+        assert_eq!(PixelFormat::from(FrameFormat::YUYV), PixelFormat::Yuyv);
+        assert_eq!(PixelFormat::from(FrameFormat::NV12), PixelFormat::Nv12);
+        assert_eq!(PixelFormat::from(FrameFormat::RAWRGB), PixelFormat::Rgb);
+        assert_eq!(PixelFormat::from(FrameFormat::MJPEG), PixelFormat::Unknown);
+    }
+
+    #[test]
+    fn requested_format_follows_configured_resolution() {
+        let config = CaptureConfig {
+            width: 640,
+            height: 480,
+            fps: 15,
+            pixel_format: PixelFormat::Yuyv,
+            ..Default::default()
+        };
+        let expected = RequestedFormatType::Closest(CameraFormat::new(
+            Resolution::new(640, 480),
+            FrameFormat::YUYV,
+            15,
+        ));
+        assert_eq!(config.requested_format_type(), expected);
+        assert_eq!(
+            requested_format(config.requested_format_type(), config.pixel_format)
+                .requested_format_type(),
+            expected
+        );
+
+        let highest = CaptureConfig {
+            format_type: Some(RequestedFormatType::AbsoluteHighestResolution),
+            ..config
+        };
+        assert_eq!(
+            highest.requested_format_type(),
+            RequestedFormatType::AbsoluteHighestResolution
+        );
+        assert_eq!(PixelFormat::from_name("NV12"), Some(PixelFormat::Nv12));
+        assert_eq!(PixelFormat::from_name("mjpeg"), None);
+    }
+
+    #[test]
+    fn device_errors_are_classified_by_cause() {
+        let denied = NokhwaError::OpenDeviceError(
+            "0".to_string(),
+            "AVFoundation: camera access not authorized".to_string(),
+        );
+        assert_eq!(
+            DeviceAccessError::classify(&denied),
+            Some(DeviceAccessError::PermissionDenied)
+        );
+        let denied_stream = NokhwaError::OpenStreamError("Permission denied".to_string());
+        assert_eq!(
+            DeviceAccessError::classify(&denied_stream),
+            Some(DeviceAccessError::PermissionDenied)
+        );
+
+        let missing = NokhwaError::OpenDeviceError(
+            "3".to_string(),
+            "Index 3 out of range of 1 devices".to_string(),
+        );
+        assert_eq!(
+            DeviceAccessError::classify(&missing),
+            Some(DeviceAccessError::NoDevice)
+        );
+        let none_attached = NokhwaError::GeneralError("No device found".to_string());
+        assert_eq!(
+            DeviceAccessError::classify(&none_attached),
+            Some(DeviceAccessError::NoDevice)
+        );
+
+        let other = NokhwaError::ReadFrameError("timed out".to_string());
+        assert_eq!(DeviceAccessError::classify(&other), None);
+    }
+
+    #[test]
+    fn open_errors_set_a_specific_failure_state() {
+        let denied = open_error(NokhwaError::OpenStreamError("access denied".to_string()));
+        assert_eq!(
+            CaptureState::failed_with(&denied),
+            CaptureState::PermissionDenied
+        );
+        assert_eq!(CaptureState::PermissionDenied.as_str(), "permission_denied");
+        assert!(CaptureState::PermissionDenied
+            .hint()
+            .is_some_and(|hint| hint.contains("Privacy & Security")));
+
+        let other = open_error(NokhwaError::ReadFrameError("timed out".to_string()));
+        assert_eq!(CaptureState::failed_with(&other), CaptureState::Error);
+        assert_eq!(CaptureState::Error.hint(), None);
+        assert!(CaptureState::NoDevice.is_failed());
+    }
+
     #[tokio::test]
     async fn new_capture_is_stopped() {
         let capture = CameraCapture::new(Uuid::new_v4(), CaptureConfig::default());
         assert_eq!(capture.state().await, CaptureState::Stopped);
     }
+
+    #[tokio::test]
+    async fn subscribers_skip_frames_beyond_the_configured_buffer() {
+        let capture = CameraCapture::new(
+            Uuid::new_v4(),
+            CaptureConfig {
+                frame_buffer: 4,
+                ..Default::default()
+            },
+        );
+        let mut rx = capture.subscribe();
+
+        for _ in 0..10 {
+            capture.push_frame(2, 2, vec![0; 12]).await;
+        }
+
+        assert!(matches!(
+            rx.recv().await,
+            Err(broadcast::error::RecvError::Lagged(6))
+        ));
+        for expected in 7..=10 {
+            assert_eq!(rx.recv().await.unwrap().frame_number, expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn status_reports_running_config_and_actual_resolution() {
+        let config = CaptureConfig {
+            width: 640,
+            height: 480,
+            fps: 15,
+            ..Default::default()
+        };
+        let capture = CameraCapture::new(Uuid::new_v4(), config);
+
+        let idle = capture.status();
+        assert_eq!(idle.state, CaptureState::Stopped);
+        assert_eq!(idle.resolution, None);
+
+        capture.push_frame(320, 240, vec![0; 320 * 240 * 3]).await;
+
+        let running = capture.status();
+        assert_eq!(running.state, CaptureState::Running);
+        assert_eq!((running.config.width, running.config.height), (640, 480));
+        assert_eq!(running.config.fps, 15);
+        assert_eq!(running.resolution, Some((320, 240)));
+    }
+
+    #[tokio::test]
+    async fn pushed_frames_are_numbered_and_broadcast() {
+        let capture = CameraCapture::new(Uuid::new_v4(), CaptureConfig::default());
+        let mut rx = capture.subscribe();
+
+        capture.push_frame(2, 2, vec![0; 12]).await;
+        capture.push_frame(2, 2, vec![0; 12]).await;
+
+        assert_eq!(rx.recv().await.unwrap().frame_number, 1);
+        assert_eq!(rx.recv().await.unwrap().frame_number, 2);
+        assert_eq!(capture.state().await, CaptureState::Running);
+    }
+
+    /// Writes solid-colour frames named so they sort in the given order.
+    fn write_frames(dir: &Path, shades: &[u8]) {
+        for (i, shade) in shades.iter().enumerate() {
+            image::RgbImage::from_pixel(4, 3, image::Rgb([*shade; 3]))
+                .save(dir.join(format!("frame_{:02}.png", i)))
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn fake_source_replays_images_in_name_order() {
+        let dir = tempfile::tempdir().unwrap();
+        write_frames(dir.path(), &[10, 20]);
+        std::fs::write(dir.path().join("notes.txt"), "not a frame").unwrap();
+
+        let mut source = FakeSource::from_dir(dir.path()).unwrap();
+        assert_eq!(source.open().unwrap(), (4, 3));
+
+        let first = source.next_frame().unwrap().unwrap();
+        assert_eq!((first.width, first.height), (4, 3));
+        assert_eq!(first.pixel_format, PixelFormat::Rgb);
+        assert_eq!(first.data, vec![10; 4 * 3 * 3]);
+        assert_eq!(source.next_frame().unwrap().unwrap().data[0], 20);
+        assert!(source.next_frame().unwrap().is_none());
+
+        assert!(FakeSource::from_dir(tempfile::tempdir().unwrap().path()).is_err());
+    }
+
+    #[tokio::test]
+    async fn capture_stopped_while_opening_stays_stopped() {
+        let dir = tempfile::tempdir().unwrap();
+        write_frames(dir.path(), &[10, 20]);
+        let config = CaptureConfig {
+            source: CaptureSource::Replay(dir.path().to_path_buf()),
+            ..Default::default()
+        };
+        let capture = CameraCapture::new(Uuid::new_v4(), config);
+        let mut rx = capture.subscribe();
+
+        // The capture loop has not run yet, so the source is still opening
+        capture.start().await.unwrap();
+        capture.stop().await;
+
+        let frame = tokio::time::timeout(std::time::Duration::from_millis(300), rx.recv()).await;
+        assert!(frame.is_err(), "a stopped capture broadcast a frame");
+        assert_eq!(capture.state().await, CaptureState::Stopped);
+        assert_eq!(capture.frame_count().await, 0);
+    }
+
+    #[tokio::test]
+    async fn replay_capture_broadcasts_each_image_then_stops() {
+        let dir = tempfile::tempdir().unwrap();
+        write_frames(dir.path(), &[10, 20]);
+        let config = CaptureConfig {
+            source: CaptureSource::Replay(dir.path().to_path_buf()),
+            ..Default::default()
+        };
+        let capture = CameraCapture::new(Uuid::new_v4(), config);
+        let mut rx = capture.subscribe();
+        let mut states = capture.watch_state();
+
+        capture.start().await.unwrap();
+
+        let first = rx.recv().await.unwrap();
+        let second = rx.recv().await.unwrap();
+        assert_eq!((first.frame_number, first.data[0]), (1, 10));
+        assert_eq!((second.frame_number, second.data[0]), (2, 20));
+
+        tokio::time::timeout(
+            std::time::Duration::from_secs(1),
+            states.wait_for(|state| *state == CaptureState::Stopped),
+        )
+        .await
+        .expect("capture did not stop")
+        .unwrap();
+        assert_eq!(capture.frame_count().await, 2);
+        assert_eq!(capture.status().resolution, Some((4, 3)));
+    }
 }
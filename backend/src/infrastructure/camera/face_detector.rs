@@ -1,23 +1,28 @@
 //! Face Detector
 //!
 //! Face detection and embedding extraction.
-//! Uses rustface for detection in a dedicated thread (since Detector is not Send).
+//! Uses rustface for detection on dedicated threads (since Detector is not
+//! Send), each owning its own detector.
 //!
 //! References:
 //! - rustface: https://github.com/nickelc/rustface
 //! - FaceNet: https://arxiv.org/abs/1503.03832
 
-use crossbeam_channel::{bounded, Receiver, Sender};
+use async_trait::async_trait;
+use crossbeam_channel::{bounded, Receiver, Sender, TrySendError};
+use image::{imageops, GrayImage, RgbImage};
 use rustface::{FaceInfo, ImageData};
 use std::path::PathBuf;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::thread;
+use std::time::Instant;
 use tracing::{debug, error, info, warn};
 
 use crate::domain::entities::Detection;
-use crate::domain::value_objects::{BoundingBox, FaceEmbedding, EMBEDDING_DIMENSION};
-use crate::infrastructure::camera::CapturedFrame;
+use crate::domain::value_objects::{BoundingBox, FaceEmbedding};
+use crate::infrastructure::camera::{CapturedFrame, LatencyStats, LatencyWindow, PixelFormat};
+use crate::infrastructure::config::DetectorSection;
 
 /// Face detector configuration.
 #[derive(Debug, Clone)]
@@ -32,6 +37,14 @@ pub struct DetectorConfig {
     pub extract_embeddings: bool,
     /// Path to rustface model file.
     pub model_path: PathBuf,
+    /// Detection requests queued for the detector threads; requests beyond
+    /// it are dropped.
+    pub queue_capacity: usize,
+    /// Detector threads taking requests from the queue.
+    pub threads: usize,
+    /// Scale frames are shrunk to before detection, in (0, 1]; boxes are
+    /// mapped back to full-frame coordinates. 1.0 detects at full size.
+    pub detection_downscale: f32,
 }
 
 impl Default for DetectorConfig {
@@ -42,22 +55,50 @@ impl Default for DetectorConfig {
             scale_factor: 0.8,
             extract_embeddings: true,
             model_path: PathBuf::from("models/seeta_fd_frontal_v1.0.bin"),
+            queue_capacity: 32,
+            threads: 2,
+            detection_downscale: 1.0,
         }
     }
 }
 
-/// Detection request sent to the detector thread.
+impl From<&DetectorSection> for DetectorConfig {
+    fn from(section: &DetectorSection) -> Self {
+        Self {
+            min_face_size: section.min_face_size,
+            confidence_threshold: section.confidence_threshold,
+            extract_embeddings: section.extract_embeddings,
+            model_path: section.model_path.clone(),
+            queue_capacity: section.queue_capacity,
+            threads: section.threads,
+            detection_downscale: section.detection_downscale,
+            ..Default::default()
+        }
+    }
+}
+
+/// Detection request sent to the detector threads.
+/// Carries a batch of frames so several frames can be processed per `recv`.
 struct DetectionRequest {
-    frame: CapturedFrame,
-    response_tx: tokio::sync::oneshot::Sender<Vec<Detection>>,
+    frames: Vec<CapturedFrame>,
+    response_tx: tokio::sync::oneshot::Sender<Vec<Vec<Detection>>>,
 }
 
+/// Detects faces in one frame. Each detector thread creates its own.
+type FrameDetector = Box<dyn FnMut(&CapturedFrame) -> Vec<Detection>>;
+
+/// Computes the embedding of a cropped face.
+type FaceEmbedder = fn(&RgbImage) -> Option<FaceEmbedding>;
+
 /// Face detector using rustface.
-/// Runs detection in a dedicated thread since rustface Detector is not Send.
+/// Runs detection on a pool of threads since rustface Detector is not Send.
 pub struct FaceDetector {
     request_tx: Sender<DetectionRequest>,
     detection_count: Arc<AtomicU64>,
+    requests_dropped: AtomicU64,
+    latency: Arc<LatencyWindow>,
     config: DetectorConfig,
+    embed: FaceEmbedder,
 }
 
 // FaceDetector is now Send + Sync since it only holds channels and atomics
@@ -82,86 +123,165 @@ impl FaceDetector {
             ));
         }
 
-        // Create bounded channel for detection requests
-        let (request_tx, request_rx): (Sender<DetectionRequest>, Receiver<DetectionRequest>) =
-            bounded(32);
         let detection_count = Arc::new(AtomicU64::new(0));
-        let detection_count_clone = detection_count.clone();
-        let config_clone = config.clone();
+        let latency = Arc::new(LatencyWindow::default());
+
+        let make_detector = {
+            let config = config.clone();
+            let detection_count = detection_count.clone();
+            let latency = latency.clone();
+            move || Self::rustface_detector(&config, detection_count.clone(), latency.clone())
+        };
 
-        // Spawn detector thread
-        thread::Builder::new()
-            .name("face-detector".to_string())
-            .spawn(move || {
-                Self::detector_thread(request_rx, config_clone, detection_count_clone);
-            })?;
+        if config.extract_embeddings {
+            warn!(
+                "No face embedding model is available; \
+                faces are detected but not matched to profiles"
+            );
+        }
 
+        let detector = Self::with_workers(config, detection_count, latency, make_detector)?;
         info!("Face detector initialized successfully");
+        Ok(detector)
+    }
+
+    /// Starts `config.threads` detector threads, each detecting with the
+    /// detector `make_detector` creates on it.
+    fn with_workers(
+        config: DetectorConfig,
+        detection_count: Arc<AtomicU64>,
+        latency: Arc<LatencyWindow>,
+        make_detector: impl Fn() -> Option<FrameDetector> + Send + Sync + 'static,
+    ) -> anyhow::Result<Self> {
+        // Create bounded channel for detection requests, shared by all threads
+        let (request_tx, request_rx): (Sender<DetectionRequest>, Receiver<DetectionRequest>) =
+            bounded(config.queue_capacity.max(1));
+        let make_detector = Arc::new(make_detector);
+
+        for worker in 0..config.threads.max(1) {
+            let request_rx = request_rx.clone();
+            let make_detector = make_detector.clone();
+            thread::Builder::new()
+                .name(format!("face-detector-{}", worker))
+                .spawn(move || Self::detector_thread(request_rx, make_detector.as_ref()))?;
+        }
 
         Ok(Self {
             request_tx,
             detection_count,
+            requests_dropped: AtomicU64::new(0),
+            latency,
             config,
+            embed: |_| None,
         })
     }
 
-    /// The detector thread that processes frames.
+    /// A detector thread that processes frames until the detector is dropped.
     fn detector_thread(
         request_rx: Receiver<DetectionRequest>,
-        config: DetectorConfig,
-        detection_count: Arc<AtomicU64>,
+        make_detector: &dyn Fn() -> Option<FrameDetector>,
     ) {
-        info!("Face detector thread starting...");
+        debug!("Face detector thread starting...");
 
         // Create the detector in this thread
+        let Some(mut detect) = make_detector() else {
+            return;
+        };
+
+        while let Ok(request) = request_rx.recv() {
+            let results = request.frames.iter().map(&mut detect).collect();
+
+            // Send response
+            let _ = request.response_tx.send(results);
+        }
+
+        debug!("Face detector thread stopping");
+    }
+
+    /// Creates a rustface detector for the calling thread.
+    fn rustface_detector(
+        config: &DetectorConfig,
+        detection_count: Arc<AtomicU64>,
+        latency: Arc<LatencyWindow>,
+    ) -> Option<FrameDetector> {
         let model_path_str = config.model_path.to_string_lossy();
         let mut detector = match rustface::create_detector(&model_path_str) {
             Ok(d) => d,
             Err(e) => {
                 error!("Failed to create face detector: {}", e);
-                return;
+                return None;
             }
         };
 
-        detector.set_min_face_size(config.min_face_size);
+        // The minimum face size is in full-frame pixels, down to the
+        // smallest rustface supports
+        let downscale = effective_downscale(config.detection_downscale);
+        let min_face_size = (config.min_face_size as f32 * downscale).round() as u32;
+        detector.set_min_face_size(min_face_size.max(MIN_DETECTOR_FACE_SIZE));
         detector.set_score_thresh(config.confidence_threshold as f64);
         detector.set_pyramid_scale_factor(config.scale_factor);
         detector.set_slide_window_step(4, 4);
 
-        info!("Face detector thread ready, waiting for frames...");
-
-        while let Ok(request) = request_rx.recv() {
-            let frame = request.frame;
-
-            if frame.data.is_empty() {
-                let _ = request.response_tx.send(Vec::new());
-                continue;
-            }
+        let config = config.clone();
+        Some(Box::new(move |frame| {
+            Self::detect_frame(
+                detector.as_mut(),
+                frame,
+                &config,
+                &detection_count,
+                &latency,
+            )
+        }))
+    }
 
-            // Convert to grayscale
-            let gray_data = Self::rgb_to_grayscale(&frame.data, frame.width, frame.height);
+    /// Runs detection on a single frame inside the detector thread.
+    fn detect_frame(
+        detector: &mut dyn rustface::Detector,
+        frame: &CapturedFrame,
+        config: &DetectorConfig,
+        detection_count: &AtomicU64,
+        latency: &LatencyWindow,
+    ) -> Vec<Detection> {
+        if frame.data.is_empty() {
+            return Vec::new();
+        }
 
-            // Create image data for rustface
-            let image = ImageData::new(&gray_data, frame.width, frame.height);
+        // Convert to grayscale
+        let gray_data =
+            Self::to_grayscale(&frame.data, frame.width, frame.height, frame.pixel_format);
 
-            // Detect faces
-            let faces = detector.detect(&image);
-            let detections = Self::convert_faces_to_detections(faces, config.confidence_threshold);
+        let started = Instant::now();
+        let (gray_data, width, height, downscale) = downscale_gray(
+            gray_data,
+            frame.width,
+            frame.height,
+            config.detection_downscale,
+        );
 
-            if !detections.is_empty() {
-                detection_count.fetch_add(detections.len() as u64, Ordering::Relaxed);
-                debug!(
-                    "Detected {} face(s) in frame {}",
-                    detections.len(),
-                    frame.frame_number
-                );
+        // Create image data for rustface
+        let image = ImageData::new(&gray_data, width, height);
+
+        // Detect faces
+        let faces = detector.detect(&image);
+        latency.record(started.elapsed());
+        let mut detections = Self::convert_faces_to_detections(faces, config.confidence_threshold);
+        if downscale < 1.0 {
+            for detection in &mut detections {
+                let bbox = to_frame_coordinates(detection.bounding_box(), downscale);
+                *detection = Detection::new(bbox, detection.confidence());
             }
+        }
 
-            // Send response
-            let _ = request.response_tx.send(detections);
+        if !detections.is_empty() {
+            detection_count.fetch_add(detections.len() as u64, Ordering::Relaxed);
+            debug!(
+                "Detected {} face(s) in frame {}",
+                detections.len(),
+                frame.frame_number
+            );
         }
 
-        info!("Face detector thread stopping");
+        detections
     }
 
     /// Detects faces in a frame asynchronously.
@@ -170,32 +290,83 @@ impl FaceDetector {
             return Vec::new();
         }
 
+        self.detect_batch(std::slice::from_ref(frame))
+            .await
+            .pop()
+            .unwrap_or_default()
+    }
+
+    /// Detects faces in several frames with a single detector round-trip.
+    /// Returns one result per input frame, in the same order.
+    pub async fn detect_batch(&self, frames: &[CapturedFrame]) -> Vec<Vec<Detection>> {
+        if frames.is_empty() {
+            return Vec::new();
+        }
+
         let (response_tx, response_rx) = tokio::sync::oneshot::channel();
 
         let request = DetectionRequest {
-            frame: frame.clone(),
+            frames: frames.to_vec(),
             response_tx,
         };
 
-        // Send request to detector thread
-        if self.request_tx.send(request).is_err() {
-            warn!("Failed to send detection request - detector thread may have stopped");
-            return Vec::new();
+        // Never wait on a full queue; a busy detector skips frames instead
+        match self.request_tx.try_send(request) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => {
+                self.requests_dropped.fetch_add(1, Ordering::Relaxed);
+                debug!("Detector queue full; dropping {} frame(s)", frames.len());
+                return vec![Vec::new(); frames.len()];
+            }
+            Err(TrySendError::Disconnected(_)) => {
+                warn!("Failed to send detection request - detector threads may have stopped");
+                return vec![Vec::new(); frames.len()];
+            }
         }
 
         // Wait for response
         match response_rx.await {
-            Ok(detections) => detections,
+            Ok(results) => results,
             Err(_) => {
                 warn!("Detection response channel closed");
-                Vec::new()
+                vec![Vec::new(); frames.len()]
+            }
+        }
+    }
+
+    /// Converts a frame buffer to grayscale using the camera's reported format.
+    /// Falls back to guessing from the buffer size when the format is unknown
+    /// or the buffer is too short for it.
+    fn to_grayscale(data: &[u8], width: u32, height: u32, format: PixelFormat) -> Vec<u8> {
+        let pixel_count = (width * height) as usize;
+
+        match format {
+            PixelFormat::Rgb if data.len() >= pixel_count * 3 => rgb_luma(data, pixel_count),
+            // Y is at even indices in YUY2
+            PixelFormat::Yuyv if data.len() >= pixel_count * 2 => {
+                data.iter().step_by(2).take(pixel_count).copied().collect()
+            }
+            // The Y plane comes first in NV12
+            PixelFormat::Nv12 | PixelFormat::Gray if data.len() >= pixel_count => {
+                data[..pixel_count].to_vec()
+            }
+            PixelFormat::Unknown => Self::guess_grayscale(data, width, height),
+            _ => {
+                warn!(
+                    "{:?} frame too short: {} bytes for {}x{}",
+                    format,
+                    data.len(),
+                    width,
+                    height
+                );
+                Self::guess_grayscale(data, width, height)
             }
         }
     }
 
-    /// Convert image buffer to grayscale.
-    /// Handles both RGB and YUV (NV12/YUY2) formats.
-    fn rgb_to_grayscale(data: &[u8], width: u32, height: u32) -> Vec<u8> {
+    /// Converts a buffer of unknown format to grayscale, guessing the format
+    /// from its length. Handles both RGB and YUV (NV12/YUY2) formats.
+    fn guess_grayscale(data: &[u8], width: u32, height: u32) -> Vec<u8> {
         let pixel_count = (width * height) as usize;
         let expected_rgb_size = pixel_count * 3;
         let expected_yuv_size = pixel_count * 2; // YUY2 format
@@ -222,22 +393,7 @@ impl FaceDetector {
         } else if data.len() >= expected_rgb_size {
             // RGB format
             debug!("Converting RGB frame to grayscale ({} bytes)", data.len());
-            let mut gray = Vec::with_capacity(pixel_count);
-
-            for i in 0..pixel_count {
-                let idx = i * 3;
-                if idx + 2 < data.len() {
-                    let r = data[idx] as f32;
-                    let g = data[idx + 1] as f32;
-                    let b = data[idx + 2] as f32;
-                    // Standard grayscale conversion
-                    let gray_val = (0.299 * r + 0.587 * g + 0.114 * b) as u8;
-                    gray.push(gray_val);
-                } else {
-                    gray.push(128);
-                }
-            }
-            return gray;
+            return rgb_luma(data, pixel_count);
         }
 
         // Unknown format - try to extract Y channel assuming packed format
@@ -283,27 +439,186 @@ impl FaceDetector {
     }
 
     /// Extracts face embedding from a cropped face image.
-    pub async fn extract_embedding(&self, _face_data: &[u8]) -> Option<FaceEmbedding> {
+    pub async fn extract_embedding(&self, face: &RgbImage) -> Option<FaceEmbedding> {
         if !self.config.extract_embeddings {
             return None;
         }
 
         // Note: Actual implementation would use ONNX Runtime with a face embedding model
-        // This is a placeholder that generates a dummy embedding
+        // Until one is bundled no embedding is produced, so faces aren't
+        // matched to profiles on anything but a real descriptor
 
         // In production, you would:
         // 1. Preprocess the face image (align, resize to 160x160)
         // 2. Run through FaceNet/ArcFace model
         // 3. L2 normalize the output
 
-        let values = vec![0.0f32; EMBEDDING_DIMENSION];
-        Some(FaceEmbedding::new(values))
+        (self.embed)(face)
     }
 
     /// Returns total detection count.
     pub async fn detection_count(&self) -> u64 {
         self.detection_count.load(Ordering::Relaxed)
     }
+
+    /// Returns the number of detection requests dropped because the
+    /// detector queue was full.
+    pub fn requests_dropped(&self) -> u64 {
+        self.requests_dropped.load(Ordering::Relaxed)
+    }
+
+    /// Average and p95 time the detector spent on recent frames.
+    pub fn latency_stats(&self) -> LatencyStats {
+        self.latency.stats()
+    }
+}
+
+#[cfg(test)]
+impl FaceDetector {
+    /// A detector that reports `detections` in every frame without loading
+    /// the model, embedding faces with [`appearance_embedding`].
+    pub(crate) fn stub(detections: Vec<Detection>) -> Self {
+        let mut detector = Self::with_workers(
+            DetectorConfig::default(),
+            Arc::default(),
+            Arc::default(),
+            move || {
+                let detections = detections.clone();
+                Some(Box::new(move |_: &CapturedFrame| detections.clone()))
+            },
+        )
+        .expect("detector threads start");
+        detector.embed =
+            |face| appearance_embedding(face, crate::domain::value_objects::embedding_dimension());
+        detector
+    }
+}
+
+/// Smallest face size rustface accepts.
+const MIN_DETECTOR_FACE_SIZE: u32 = 20;
+
+/// `downscale` if it is in (0, 1), otherwise 1.0 (no scaling).
+fn effective_downscale(downscale: f32) -> f32 {
+    if downscale > 0.0 && downscale < 1.0 {
+        downscale
+    } else {
+        1.0
+    }
+}
+
+/// Shrinks a grayscale frame by `downscale`. Returns the pixels, their size
+/// and the scale actually applied, which is 1.0 when the frame was left as
+/// is.
+fn downscale_gray(
+    gray: Vec<u8>,
+    width: u32,
+    height: u32,
+    downscale: f32,
+) -> (Vec<u8>, u32, u32, f32) {
+    let downscale = effective_downscale(downscale);
+    // A buffer too short for the frame is detected as is
+    if downscale == 1.0 || gray.len() < width as usize * height as usize {
+        return (gray, width, height, 1.0);
+    }
+    let image = GrayImage::from_raw(width, height, gray).expect("buffer covers the frame");
+
+    let scaled_width = ((width as f32 * downscale).round() as u32).max(1);
+    let scaled_height = ((height as f32 * downscale).round() as u32).max(1);
+    let scaled = imageops::resize(
+        &image,
+        scaled_width,
+        scaled_height,
+        imageops::FilterType::Triangle,
+    );
+    (scaled.into_raw(), scaled_width, scaled_height, downscale)
+}
+
+/// Maps a box found in a frame shrunk by `downscale` back to the full frame.
+fn to_frame_coordinates(bbox: &BoundingBox, downscale: f32) -> BoundingBox {
+    let up = |value: i32| (value as f32 / downscale).round() as i32;
+    BoundingBox::new(
+        up(bbox.x()),
+        up(bbox.y()),
+        up(bbox.width()),
+        up(bbox.height()),
+    )
+}
+
+/// Computes embeddings for cropped faces.
+#[async_trait]
+pub trait EmbeddingExtractor: Send + Sync {
+    /// Returns the face's embedding, or `None` if extraction is disabled.
+    async fn extract_embedding(&self, face: &RgbImage) -> Option<FaceEmbedding>;
+}
+
+#[async_trait]
+impl EmbeddingExtractor for FaceDetector {
+    async fn extract_embedding(&self, face: &RgbImage) -> Option<FaceEmbedding> {
+        FaceDetector::extract_embedding(self, face).await
+    }
+}
+
+/// Fraction a face box is widened by before cropping it for embedding.
+const EMBEDDING_CROP_MARGIN: f32 = 0.2;
+
+/// Crops each detected face from the frame and attaches its embedding.
+/// Only RGB frames are cropped; detections in other frames are left as is.
+pub async fn attach_embeddings(
+    extractor: &dyn EmbeddingExtractor,
+    frame: &CapturedFrame,
+    detections: &mut [Detection],
+) {
+    if frame.pixel_format != PixelFormat::Rgb {
+        return;
+    }
+
+    for detection in detections {
+        let Some(face) = crop_face(
+            &frame.data,
+            frame.width,
+            frame.height,
+            detection.bounding_box(),
+            EMBEDDING_CROP_MARGIN,
+        ) else {
+            continue;
+        };
+        if let Some(embedding) = extractor.extract_embedding(&face).await {
+            detection.set_embedding(embedding);
+        }
+    }
+}
+
+/// Test stand-in for a face embedding model: the crop's luma on a coarse
+/// grid, mean-centred and L2-normalized. It only tells crops apart by
+/// lighting, so it must not identify people.
+///
+/// Returns `None` for an empty crop.
+#[cfg(test)]
+fn appearance_embedding(face: &RgbImage, dimension: usize) -> Option<FaceEmbedding> {
+    if face.width() == 0 || face.height() == 0 || dimension == 0 {
+        return None;
+    }
+
+    let gray: GrayImage = imageops::grayscale(face);
+    let cols = (dimension as f64).sqrt().ceil() as u32;
+    let rows = (dimension as u32).div_ceil(cols);
+    let grid = imageops::resize(&gray, cols, rows, imageops::FilterType::Triangle);
+
+    let mut values: Vec<f32> = grid.pixels().take(dimension).map(|p| p[0] as f32).collect();
+    let mean = values.iter().sum::<f32>() / values.len() as f32;
+    for v in &mut values {
+        *v -= mean;
+    }
+
+    FaceEmbedding::with_dimension(values, dimension).map(|e| e.normalized())
+}
+
+/// Standard grayscale conversion of packed RGB. `data` must hold `pixel_count` pixels.
+fn rgb_luma(data: &[u8], pixel_count: usize) -> Vec<u8> {
+    data.chunks_exact(3)
+        .take(pixel_count)
+        .map(|px| (0.299 * px[0] as f32 + 0.587 * px[1] as f32 + 0.114 * px[2] as f32) as u8)
+        .collect()
 }
 
 /// Aligns a face for better embedding extraction.
@@ -318,26 +633,35 @@ pub fn align_face(_image_data: &[u8], _width: u32, _height: u32, _bbox: &Boundin
     Vec::new()
 }
 
-/// Crops a face from an image.
-#[allow(dead_code)]
+/// Crops a face, widened by `margin` on each side, from a packed RGB frame.
+/// Returns `None` if the frame is truncated or the box misses it.
 pub fn crop_face(
-    _image_data: &[u8],
+    image_data: &[u8],
     width: u32,
     height: u32,
     bbox: &BoundingBox,
     margin: f32,
-) -> Vec<u8> {
+) -> Option<RgbImage> {
     // Expand bounding box by margin
     let expanded = bbox.scale(1.0 + margin);
 
     // Clamp to image bounds
-    let _x1 = expanded.x().max(0) as u32;
-    let _y1 = expanded.y().max(0) as u32;
-    let _x2 = (expanded.right() as u32).min(width);
-    let _y2 = (expanded.bottom() as u32).min(height);
+    let x1 = expanded.x().clamp(0, width as i32) as u32;
+    let y1 = expanded.y().clamp(0, height as i32) as u32;
+    let x2 = expanded.right().clamp(0, width as i32) as u32;
+    let y2 = expanded.bottom().clamp(0, height as i32) as u32;
+    if x2 <= x1 || y2 <= y1 {
+        return None;
+    }
 
-    // Note: Actual cropping would be done here
-    Vec::new()
+    let frame = image_data.get(..width as usize * height as usize * 3)?;
+    let row_bytes = (x2 - x1) as usize * 3;
+    let mut face = Vec::with_capacity(row_bytes * (y2 - y1) as usize);
+    for y in y1..y2 {
+        let start = (y as usize * width as usize + x1 as usize) * 3;
+        face.extend_from_slice(&frame[start..start + row_bytes]);
+    }
+    RgbImage::from_raw(x2 - x1, y2 - y1, face)
 }
 
 #[cfg(test)]
@@ -352,6 +676,194 @@ mod tests {
         assert!(config.scale_factor > 0.0 && config.scale_factor < 1.0);
     }
 
+    // A 4x2 frame whose luma is 10, 20, ..., 80
+    const WIDTH: u32 = 4;
+    const HEIGHT: u32 = 2;
+    const LUMA: [u8; 8] = [10, 20, 30, 40, 50, 60, 70, 80];
+
+    #[test]
+    fn yuyv_frame_yields_y_samples() {
+        let data: Vec<u8> = LUMA.iter().flat_map(|&y| [y, 128]).collect();
+        let gray = FaceDetector::to_grayscale(&data, WIDTH, HEIGHT, PixelFormat::Yuyv);
+        assert_eq!(gray, LUMA);
+    }
+
+    #[test]
+    fn nv12_frame_yields_y_plane() {
+        let mut data = LUMA.to_vec();
+        data.extend_from_slice(&[128; 4]);
+        let gray = FaceDetector::to_grayscale(&data, WIDTH, HEIGHT, PixelFormat::Nv12);
+        assert_eq!(gray, LUMA);
+    }
+
+    #[test]
+    fn rgb_frame_yields_luma() {
+        let data: Vec<u8> = LUMA.iter().flat_map(|&y| [y, y, y]).collect();
+        let gray = FaceDetector::to_grayscale(&data, WIDTH, HEIGHT, PixelFormat::Rgb);
+        // Weights sum to 1.0, so only float rounding can shift a value
+        for (actual, expected) in gray.iter().zip(LUMA) {
+            assert!(actual.abs_diff(expected) <= 1);
+        }
+    }
+
+    #[test]
+    fn declared_format_wins_over_length_guess() {
+        // Oversized YUYV buffer, e.g. with row padding, that the length
+        // heuristic would misread as RGB
+        let mut data: Vec<u8> = LUMA.iter().flat_map(|&y| [y, 128]).collect();
+        data.resize(LUMA.len() * 3, 0);
+        let gray = FaceDetector::to_grayscale(&data, WIDTH, HEIGHT, PixelFormat::Yuyv);
+        assert_eq!(gray, LUMA);
+    }
+
+    #[test]
+    fn config_is_built_from_detector_section() {
+        let section = DetectorSection {
+            model_path: PathBuf::from("/opt/safelynx/models/face.bin"),
+            min_face_size: 64,
+            confidence_threshold: 0.9,
+            extract_embeddings: false,
+            queue_capacity: 8,
+            threads: 4,
+            detection_downscale: 0.5,
+        };
+
+        let config = DetectorConfig::from(&section);
+
+        assert_eq!(config.model_path, section.model_path);
+        assert_eq!(config.min_face_size, 64);
+        assert_eq!(config.confidence_threshold, 0.9);
+        assert!(!config.extract_embeddings);
+        assert_eq!(config.queue_capacity, 8);
+        assert_eq!(config.threads, 4);
+        assert_eq!(config.detection_downscale, 0.5);
+        assert_eq!(config.scale_factor, DetectorConfig::default().scale_factor);
+    }
+
+    #[test]
+    fn boxes_found_at_half_scale_map_back_to_the_frame() {
+        let gray = vec![100; 640 * 480];
+        let (scaled, width, height, downscale) = downscale_gray(gray, 640, 480, 0.5);
+        assert_eq!((width, height), (320, 240));
+        assert_eq!(scaled.len(), 320 * 240);
+        assert_eq!(downscale, 0.5);
+
+        let found = BoundingBox::new(50, 30, 40, 45);
+        let bbox = to_frame_coordinates(&found, downscale);
+        assert_eq!(
+            (bbox.x(), bbox.y(), bbox.width(), bbox.height()),
+            (100, 60, 80, 90)
+        );
+    }
+
+    #[test]
+    fn out_of_range_downscale_detects_at_full_size() {
+        for downscale in [1.0, 0.0, -0.5, 2.0, f32::NAN] {
+            let (scaled, width, height, applied) = downscale_gray(vec![0; 8], 4, 2, downscale);
+            assert_eq!((scaled.len(), width, height, applied), (8, 4, 2, 1.0));
+        }
+    }
+
+    #[test]
+    fn appearance_embedding_separates_different_crops() {
+        let frame: Vec<u8> = (0..64u32 * 48)
+            .flat_map(|i| {
+                let v = if (i % 64) < 32 { 40 } else { 200 };
+                [v, v, v]
+            })
+            .collect();
+        let left_dark = crop_face(&frame, 64, 48, &BoundingBox::new(16, 8, 32, 32), 0.0).unwrap();
+        let flipped = imageops::flip_horizontal(&left_dark);
+
+        let a = appearance_embedding(&left_dark, 128).unwrap();
+        let b = appearance_embedding(&flipped, 128).unwrap();
+        assert_eq!(a.dimension(), 128);
+        assert!((a.distance(&FaceEmbedding::zeros(128)) - 1.0).abs() < 1e-5);
+        assert!(a.distance(&b) > 1.0);
+    }
+
+    #[tokio::test]
+    async fn no_embedding_is_extracted_without_a_model() {
+        let detector = FaceDetector::with_workers(
+            DetectorConfig::default(),
+            Arc::default(),
+            Arc::default(),
+            || None,
+        )
+        .unwrap();
+        let face = RgbImage::from_pixel(32, 32, image::Rgb([90, 120, 150]));
+
+        assert!(detector.extract_embedding(&face).await.is_none());
+    }
+
+    #[test]
+    fn crop_face_clips_to_frame_and_rejects_short_buffers() {
+        let frame = vec![0u8; 64 * 48 * 3];
+        let crop = crop_face(&frame, 64, 48, &BoundingBox::new(50, 40, 20, 20), 0.0).unwrap();
+        assert_eq!(crop.dimensions(), (14, 8));
+
+        assert!(crop_face(&frame[..100], 64, 48, &BoundingBox::new(0, 0, 8, 8), 0.0).is_none());
+        assert!(crop_face(&frame, 64, 48, &BoundingBox::new(80, 0, 8, 8), 0.0).is_none());
+    }
+
+    /// Starts a detector whose threads each take 100ms per frame, tracking
+    /// the most frames detected at once.
+    fn slow_detector(threads: usize) -> (FaceDetector, Arc<AtomicU64>) {
+        let in_flight = Arc::new(AtomicU64::new(0));
+        let most_in_flight = Arc::new(AtomicU64::new(0));
+        let config = DetectorConfig {
+            threads,
+            ..Default::default()
+        };
+
+        let peak = most_in_flight.clone();
+        let detector =
+            FaceDetector::with_workers(config, Arc::default(), Arc::default(), move || {
+                let (in_flight, peak) = (in_flight.clone(), peak.clone());
+                Some(Box::new(move |_: &CapturedFrame| {
+                    let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(now, Ordering::SeqCst);
+                    thread::sleep(std::time::Duration::from_millis(100));
+                    in_flight.fetch_sub(1, Ordering::SeqCst);
+                    Vec::new()
+                }))
+            })
+            .unwrap();
+        (detector, most_in_flight)
+    }
+
+    fn blank_frame() -> CapturedFrame {
+        CapturedFrame {
+            camera_id: uuid::Uuid::new_v4(),
+            frame_number: 0,
+            timestamp_ms: 0,
+            width: 64,
+            height: 48,
+            pixel_format: PixelFormat::Rgb,
+            data: vec![128; 64 * 48 * 3],
+        }
+    }
+
+    #[tokio::test]
+    async fn detector_threads_run_concurrent_requests_at_once() {
+        let (detector, most_in_flight) = slow_detector(2);
+        let frame = blank_frame();
+
+        tokio::join!(detector.detect(&frame), detector.detect(&frame));
+
+        assert_eq!(most_in_flight.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn single_detector_thread_serializes_requests() {
+        let (detector, most_in_flight) = slow_detector(1);
+        let frame = blank_frame();
+
+        tokio::join!(detector.detect(&frame), detector.detect(&frame));
+
+        assert_eq!(most_in_flight.load(Ordering::SeqCst), 1);
+    }
+
     #[tokio::test]
     async fn detector_tracks_detection_count() {
         // Skip test if model doesn't exist
@@ -362,4 +874,35 @@ mod tests {
         let detector = FaceDetector::new(config).unwrap();
         assert_eq!(detector.detection_count().await, 0);
     }
+
+    #[tokio::test]
+    async fn detect_batch_returns_results_per_frame_in_order() {
+        // Each frame's face sits at its frame number, so results can be told apart
+        let detector = FaceDetector::with_workers(
+            DetectorConfig::default(),
+            Arc::default(),
+            Arc::default(),
+            || {
+                Some(Box::new(|frame: &CapturedFrame| {
+                    let x = frame.frame_number as i32;
+                    vec![Detection::new(BoundingBox::new(x, 0, 20, 20), 0.9)]
+                }))
+            },
+        )
+        .unwrap();
+        let frames: Vec<CapturedFrame> = (0..8u64)
+            .map(|frame_number| CapturedFrame {
+                frame_number,
+                ..blank_frame()
+            })
+            .collect();
+
+        let batched = detector.detect_batch(&frames).await;
+
+        assert_eq!(batched.len(), frames.len());
+        for (i, detections) in batched.iter().enumerate() {
+            assert_eq!(detections.len(), 1);
+            assert_eq!(detections[0].bounding_box().x(), i as i32);
+        }
+    }
 }
@@ -0,0 +1,186 @@
+//! Camera Health
+//!
+//! Measures each camera's frame rate from capture timestamps, and how long
+//! face detection takes per frame.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+/// Number of recent frames the frame rate is measured over.
+const FPS_WINDOW: usize = 30;
+
+/// Number of recent detections the latency is measured over.
+const LATENCY_WINDOW: usize = 128;
+
+/// Measured frame rate and most recent frame of a running camera.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CameraHealth {
+    pub fps: f64,
+    /// Capture time of the newest frame, in Unix milliseconds.
+    pub last_frame_ms: i64,
+}
+
+/// Recent frame timestamps per camera, written by the pipeline.
+#[derive(Debug, Clone, Default)]
+pub struct FrameTimings {
+    recent: Arc<RwLock<HashMap<Uuid, Vec<i64>>>>,
+}
+
+impl FrameTimings {
+    /// Records a captured frame's timestamp.
+    pub async fn record(&self, camera_id: Uuid, timestamp_ms: i64) {
+        let mut recent = self.recent.write().await;
+        let timestamps = recent.entry(camera_id).or_default();
+        if timestamps.len() == FPS_WINDOW {
+            timestamps.remove(0);
+        }
+        timestamps.push(timestamp_ms);
+    }
+
+    /// Returns the camera's health, or `None` if it has produced no frames.
+    pub async fn health(&self, camera_id: Uuid) -> Option<CameraHealth> {
+        let recent = self.recent.read().await;
+        let timestamps = recent.get(&camera_id)?;
+        Some(CameraHealth {
+            fps: measured_fps(timestamps),
+            last_frame_ms: *timestamps.last()?,
+        })
+    }
+
+    /// Forgets a camera's timings, e.g. when it stops.
+    pub async fn remove(&self, camera_id: Uuid) {
+        self.recent.write().await.remove(&camera_id);
+    }
+}
+
+/// Average and 95th percentile of recent detection times.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct LatencyStats {
+    pub avg_ms: f64,
+    pub p95_ms: f64,
+    /// Number of detections the figures are measured over.
+    pub samples: usize,
+}
+
+/// Rolling window of detection times, written by the detector thread.
+///
+/// Samples go into a ring of atomics so recording never blocks detection.
+#[derive(Debug)]
+pub struct LatencyWindow {
+    /// Detection times in microseconds.
+    samples: [AtomicU64; LATENCY_WINDOW],
+    /// Total samples recorded; the next slot is this modulo the window.
+    recorded: AtomicU64,
+}
+
+impl Default for LatencyWindow {
+    fn default() -> Self {
+        Self {
+            samples: std::array::from_fn(|_| AtomicU64::new(0)),
+            recorded: AtomicU64::new(0),
+        }
+    }
+}
+
+impl LatencyWindow {
+    /// Records how long one detection took.
+    pub fn record(&self, elapsed: Duration) {
+        let slot = self.recorded.fetch_add(1, Ordering::Relaxed) as usize % LATENCY_WINDOW;
+        self.samples[slot].store(elapsed.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    /// Summarizes the recent detection times.
+    pub fn stats(&self) -> LatencyStats {
+        let count = (self.recorded.load(Ordering::Relaxed) as usize).min(LATENCY_WINDOW);
+        let mut micros: Vec<u64> = self.samples[..count]
+            .iter()
+            .map(|s| s.load(Ordering::Relaxed))
+            .collect();
+        if micros.is_empty() {
+            return LatencyStats::default();
+        }
+        micros.sort_unstable();
+
+        // Nearest-rank percentile
+        let p95_index = (micros.len() * 95).div_ceil(100) - 1;
+        LatencyStats {
+            avg_ms: micros.iter().sum::<u64>() as f64 / micros.len() as f64 / 1000.0,
+            p95_ms: micros[p95_index] as f64 / 1000.0,
+            samples: micros.len(),
+        }
+    }
+}
+
+/// Frames per second across ascending timestamps, in milliseconds.
+/// Returns 0.0 when there are too few frames to measure an interval.
+pub fn measured_fps(timestamps_ms: &[i64]) -> f64 {
+    match (timestamps_ms.first(), timestamps_ms.last()) {
+        (Some(first), Some(last)) if last > first => {
+            (timestamps_ms.len() - 1) as f64 * 1000.0 / (last - first) as f64
+        }
+        _ => 0.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fps_is_frames_per_elapsed_second() {
+        // 16 frames spaced 66ms apart, about 15 fps
+        let timestamps: Vec<i64> = (0..16).map(|i| 1_000 + i * 66).collect();
+        let fps = measured_fps(&timestamps);
+        assert!((fps - 15.15).abs() < 0.01, "fps = {}", fps);
+    }
+
+    #[test]
+    fn fps_is_zero_without_an_interval() {
+        assert_eq!(measured_fps(&[]), 0.0);
+        assert_eq!(measured_fps(&[5_000]), 0.0);
+        assert_eq!(measured_fps(&[5_000, 5_000]), 0.0);
+    }
+
+    #[test]
+    fn latency_stats_cover_recent_detections() {
+        let window = LatencyWindow::default();
+        assert_eq!(window.stats(), LatencyStats::default());
+
+        // Old slow detections are pushed out by a full window of 1..=100ms
+        for _ in 0..LATENCY_WINDOW {
+            window.record(Duration::from_secs(1));
+        }
+        for i in 0..LATENCY_WINDOW as u64 {
+            window.record(Duration::from_millis(i % 100 + 1));
+        }
+
+        let stats = window.stats();
+        assert_eq!(stats.samples, LATENCY_WINDOW);
+        assert!(stats.p95_ms <= 100.0);
+        assert!(stats.p95_ms >= 90.0);
+        let expected_avg = (0..LATENCY_WINDOW as u64).map(|i| i % 100 + 1).sum::<u64>() as f64
+            / LATENCY_WINDOW as f64;
+        assert!((stats.avg_ms - expected_avg).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn health_uses_recent_window() {
+        let timings = FrameTimings::default();
+        let camera_id = Uuid::new_v4();
+        assert!(timings.health(camera_id).await.is_none());
+
+        // A slow start followed by a full window at 10 fps
+        timings.record(camera_id, 0).await;
+        for i in 0..FPS_WINDOW as i64 {
+            timings.record(camera_id, 10_000 + i * 100).await;
+        }
+
+        let health = timings.health(camera_id).await.unwrap();
+        assert!((health.fps - 10.0).abs() < 1e-9);
+        assert_eq!(health.last_frame_ms, 10_000 + (FPS_WINDOW as i64 - 1) * 100);
+    }
+}
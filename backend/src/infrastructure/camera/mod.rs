@@ -4,8 +4,12 @@
 
 mod capture;
 mod face_detector;
+mod health;
+mod overlay;
 mod service;
 
 pub use capture::*;
 pub use face_detector::*;
+pub use health::*;
+pub use overlay::*;
 pub use service::*;
@@ -0,0 +1,207 @@
+//! Detection Overlay
+//!
+//! Draws the latest face detections onto live frames.
+
+use image::{Rgb, RgbImage};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+use crate::domain::entities::{Detection, DisplaySettings};
+use crate::domain::value_objects::BoundingBox;
+
+/// Detections older than this are not drawn, so boxes do not linger.
+const OVERLAY_TTL: Duration = Duration::from_secs(1);
+
+const BOX_COLOR: Rgb<u8> = Rgb([0, 255, 0]);
+const BOX_THICKNESS: u32 = 2;
+const CONFIDENCE_BAR_HEIGHT: u32 = 4;
+
+/// Which overlays to draw, taken from the display settings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OverlayOptions {
+    pub show_bounding_boxes: bool,
+    pub show_confidence: bool,
+}
+
+impl OverlayOptions {
+    /// Whether anything is drawn at all.
+    pub fn is_enabled(&self) -> bool {
+        self.show_bounding_boxes
+    }
+}
+
+impl From<&DisplaySettings> for OverlayOptions {
+    fn from(settings: &DisplaySettings) -> Self {
+        Self {
+            show_bounding_boxes: settings.show_bounding_boxes,
+            show_confidence: settings.show_confidence,
+        }
+    }
+}
+
+/// A face box to draw on the stream.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OverlayBox {
+    pub bounding_box: BoundingBox,
+    pub confidence: f32,
+}
+
+impl From<&Detection> for OverlayBox {
+    fn from(detection: &Detection) -> Self {
+        Self {
+            bounding_box: detection.bounding_box().clone(),
+            confidence: detection.confidence(),
+        }
+    }
+}
+
+/// When a camera's detections were produced, and the boxes themselves.
+type TimedBoxes = (Instant, Vec<OverlayBox>);
+
+/// Latest detections per camera, written by the pipeline and read by streams.
+#[derive(Debug, Clone, Default)]
+pub struct DetectionOverlayCache {
+    latest: Arc<RwLock<HashMap<Uuid, TimedBoxes>>>,
+}
+
+impl DetectionOverlayCache {
+    /// Replaces the camera's detections with those from its newest frame.
+    pub async fn update(&self, camera_id: Uuid, boxes: Vec<OverlayBox>) {
+        self.latest
+            .write()
+            .await
+            .insert(camera_id, (Instant::now(), boxes));
+    }
+
+    /// Returns the camera's recent detections, or none if they are stale.
+    pub async fn latest(&self, camera_id: Uuid) -> Vec<OverlayBox> {
+        match self.latest.read().await.get(&camera_id) {
+            Some((at, boxes)) if at.elapsed() < OVERLAY_TTL => boxes.clone(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Forgets a camera's detections, e.g. when it stops.
+    pub async fn remove(&self, camera_id: Uuid) {
+        self.latest.write().await.remove(&camera_id);
+    }
+}
+
+/// Draws detection boxes, and a confidence bar under each, onto the frame.
+///
+/// Boxes are clipped to the frame. Names are not drawn, as there is no font
+/// rendering in the pipeline.
+pub fn draw_overlays(frame: &mut RgbImage, boxes: &[OverlayBox], options: OverlayOptions) {
+    if !options.is_enabled() {
+        return;
+    }
+
+    for overlay in boxes {
+        let bbox = &overlay.bounding_box;
+        let x0 = bbox.x().max(0) as u32;
+        let y0 = bbox.y().max(0) as u32;
+        let x1 = (bbox.x().saturating_add(bbox.width())).max(0) as u32;
+        let y1 = (bbox.y().saturating_add(bbox.height())).max(0) as u32;
+
+        draw_rect_outline(frame, x0, y0, x1, y1);
+
+        if options.show_confidence {
+            let width = x1.saturating_sub(x0) as f32 * overlay.confidence.clamp(0.0, 1.0);
+            fill_rect(frame, x0, y1, x0 + width as u32, y1 + CONFIDENCE_BAR_HEIGHT);
+        }
+    }
+}
+
+fn draw_rect_outline(frame: &mut RgbImage, x0: u32, y0: u32, x1: u32, y1: u32) {
+    if x1 <= x0 || y1 <= y0 {
+        return;
+    }
+    let t = BOX_THICKNESS;
+    fill_rect(frame, x0, y0, x1, y0 + t);
+    fill_rect(frame, x0, y1.saturating_sub(t), x1, y1);
+    fill_rect(frame, x0, y0, x0 + t, y1);
+    fill_rect(frame, x1.saturating_sub(t), y0, x1, y1);
+}
+
+/// Fills the half-open rectangle `[x0, x1) x [y0, y1)`, clipped to the frame.
+fn fill_rect(frame: &mut RgbImage, x0: u32, y0: u32, x1: u32, y1: u32) {
+    let x1 = x1.min(frame.width());
+    let y1 = y1.min(frame.height());
+    for y in y0..y1 {
+        for x in x0..x1 {
+            frame.put_pixel(x, y, BOX_COLOR);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BACKGROUND: Rgb<u8> = Rgb([10, 10, 10]);
+
+    fn options() -> OverlayOptions {
+        OverlayOptions {
+            show_bounding_boxes: true,
+            show_confidence: false,
+        }
+    }
+
+    fn one_box() -> Vec<OverlayBox> {
+        vec![OverlayBox {
+            bounding_box: BoundingBox::new(10, 10, 20, 20),
+            confidence: 0.5,
+        }]
+    }
+
+    #[test]
+    fn draws_box_outline_on_frame() {
+        let mut frame = RgbImage::from_pixel(64, 48, BACKGROUND);
+        draw_overlays(&mut frame, &one_box(), options());
+
+        assert_eq!(*frame.get_pixel(10, 10), BOX_COLOR);
+        assert_eq!(*frame.get_pixel(29, 20), BOX_COLOR);
+        assert_eq!(*frame.get_pixel(20, 29), BOX_COLOR);
+        // Inside and outside the box are untouched
+        assert_eq!(*frame.get_pixel(20, 20), BACKGROUND);
+        assert_eq!(*frame.get_pixel(40, 40), BACKGROUND);
+    }
+
+    #[test]
+    fn confidence_bar_scales_with_confidence() {
+        let mut frame = RgbImage::from_pixel(64, 48, BACKGROUND);
+        let options = OverlayOptions {
+            show_confidence: true,
+            ..options()
+        };
+        draw_overlays(&mut frame, &one_box(), options);
+
+        assert_eq!(*frame.get_pixel(19, 31), BOX_COLOR);
+        assert_eq!(*frame.get_pixel(21, 31), BACKGROUND);
+    }
+
+    #[test]
+    fn draws_nothing_when_disabled() {
+        let mut frame = RgbImage::from_pixel(64, 48, BACKGROUND);
+        let options = OverlayOptions {
+            show_bounding_boxes: false,
+            show_confidence: true,
+        };
+        draw_overlays(&mut frame, &one_box(), options);
+        assert!(frame.pixels().all(|p| *p == BACKGROUND));
+    }
+
+    #[test]
+    fn clips_boxes_past_frame_edge() {
+        let mut frame = RgbImage::from_pixel(16, 16, BACKGROUND);
+        let boxes = vec![OverlayBox {
+            bounding_box: BoundingBox::new(-5, 8, 40, 40),
+            confidence: 1.0,
+        }];
+        draw_overlays(&mut frame, &boxes, options());
+        assert_eq!(*frame.get_pixel(0, 8), BOX_COLOR);
+    }
+}
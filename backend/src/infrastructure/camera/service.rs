@@ -2,17 +2,159 @@
 //!
 //! Manages camera capture and frame processing pipeline.
 
+use chrono::Utc;
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, watch, RwLock};
+use tokio::task::JoinHandle;
 use tracing::{debug, info, warn};
 use uuid::Uuid;
 
-use super::capture::{list_cameras, CameraCapture, CaptureConfig, CaptureState, CapturedFrame};
-use super::FaceDetector;
+use super::capture::{
+    list_cameras, CameraCapture, CameraInfo, CaptureConfig, CaptureState, CaptureStatus,
+    CapturedFrame, PixelFormat, DEFAULT_FRAME_BUFFER,
+};
+use super::{
+    attach_embeddings, CameraHealth, DetectionOverlayCache, EmbeddingExtractor, FaceDetector,
+    FrameTimings, OverlayBox,
+};
+use crate::application::services::EventBus;
 use crate::application::use_cases::ProcessFrameUseCase;
-use crate::domain::entities::{Camera, Detection, FrameDetections};
-use crate::domain::repositories::CameraRepository;
+use crate::domain::entities::{Camera, Detection, DetectionSettings, FrameDetections};
+use crate::domain::events::{CameraStatusChangedEvent, DomainEvent};
+use crate::domain::repositories::{CameraRepository, RepositoryError};
+
+/// Minimum time between writes of a camera's `last_frame_at`.
+const LAST_FRAME_PERSIST_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Default for how long a partial detection batch waits for more frames.
+pub const DEFAULT_DETECTION_BATCH_TIMEOUT_MS: u64 = 200;
+
+/// Frame processing pipeline configuration.
+#[derive(Debug, Clone)]
+pub struct PipelineConfig {
+    /// Run face detection on every Nth captured frame. Must be >= 1.
+    /// Can be changed while cameras run with
+    /// [`CameraService::set_detection_sample_every`].
+    pub detection_sample_every: u64,
+    /// Number of sampled frames buffered before submitting them to the
+    /// detector as one batch. `1` disables buffering.
+    pub detection_batch_size: usize,
+    /// Longest a partial batch waits for more frames before it is detected
+    /// anyway, so a camera that goes quiet doesn't hold frames back.
+    pub detection_batch_timeout: Duration,
+    /// Frames buffered for each consumer of a capture.
+    pub frame_buffer: usize,
+    /// Check camera permission before opening a local camera.
+    pub permission_preflight: bool,
+    /// Layout local cameras deliver frames in.
+    pub pixel_format: PixelFormat,
+}
+
+impl Default for PipelineConfig {
+    fn default() -> Self {
+        Self {
+            detection_sample_every: DetectionSettings::default().detection_sample_every,
+            detection_batch_size: 1,
+            detection_batch_timeout: Duration::from_millis(DEFAULT_DETECTION_BATCH_TIMEOUT_MS),
+            frame_buffer: DEFAULT_FRAME_BUFFER,
+            permission_preflight: true,
+            pixel_format: PixelFormat::Rgb,
+        }
+    }
+}
+
+impl PipelineConfig {
+    /// Creates a pipeline configuration from the persisted detection settings.
+    pub fn from_settings(settings: &DetectionSettings) -> anyhow::Result<Self> {
+        if settings.detection_sample_every < 1 {
+            anyhow::bail!("detection_sample_every must be at least 1");
+        }
+
+        Ok(Self {
+            detection_sample_every: settings.detection_sample_every,
+            ..Default::default()
+        })
+    }
+}
+
+/// Returns true if the given frame should be sent to the detector when
+/// detecting on every `sample_every`th frame.
+fn should_sample(frame_number: u64, sample_every: u64) -> bool {
+    frame_number.is_multiple_of(sample_every.max(1))
+}
+
+/// Parses a local camera device ID into an enumeration index.
+/// Returns None for non-numeric IDs such as RTSP URLs or browser sources.
+pub fn parse_device_index(device_id: &str) -> Option<u32> {
+    device_id.trim().parse().ok()
+}
+
+/// Resolves a camera's device index and checks it against the enumerated devices.
+fn resolve_device_index(camera: &Camera, available: &[CameraInfo]) -> anyhow::Result<u32> {
+    let index = parse_device_index(camera.device_id()).ok_or_else(|| {
+        anyhow::anyhow!(
+            "Camera {} has device ID {:?}, which is not a local device index",
+            camera.name(),
+            camera.device_id()
+        )
+    })?;
+
+    if !available.iter().any(|info| info.index == index) {
+        anyhow::bail!(
+            "Camera device index {} not found ({} device(s) available)",
+            index,
+            available.len()
+        );
+    }
+
+    Ok(index)
+}
+
+/// Waits for a starting capture to settle, succeeding once it runs and
+/// failing if it stops or fails first, or is still starting after `timeout`.
+async fn wait_until_running(
+    mut states: watch::Receiver<CaptureState>,
+    timeout: Duration,
+) -> anyhow::Result<()> {
+    let settled = tokio::time::timeout(
+        timeout,
+        states.wait_for(|state| *state != CaptureState::Starting),
+    )
+    .await
+    .map_err(|_| anyhow::anyhow!("capture did not start within {:?}", timeout))?
+    .map(|state| *state)
+    .map_err(|_| anyhow::anyhow!("capture was dropped while starting"))?;
+
+    match settled {
+        CaptureState::Running => Ok(()),
+        state => anyhow::bail!("capture {}", state.as_str()),
+    }
+}
+
+/// Publishes a `CameraStatusChanged` event for each state change of a
+/// capture, until the capture is dropped.
+fn spawn_status_events(
+    event_bus: Arc<EventBus>,
+    camera_id: Uuid,
+    camera_name: String,
+    mut states: watch::Receiver<CaptureState>,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        while states.changed().await.is_ok() {
+            let status = *states.borrow_and_update();
+            event_bus.publish(DomainEvent::CameraStatusChanged(CameraStatusChangedEvent {
+                camera_id,
+                camera_name: camera_name.clone(),
+                status: status.as_str().to_string(),
+                message: status.hint(),
+                timestamp: Utc::now(),
+            }));
+        }
+    })
+}
 
 /// Camera service that manages capture and processing.
 pub struct CameraService {
@@ -20,6 +162,14 @@ pub struct CameraService {
     face_detector: Arc<FaceDetector>,
     process_frame: Arc<ProcessFrameUseCase>,
     camera_repo: Arc<dyn CameraRepository>,
+    event_bus: Arc<EventBus>,
+    pipeline: PipelineConfig,
+    /// Frame sampling rate, shared with the running captures.
+    detection_sample_every: Arc<AtomicU64>,
+    overlays: DetectionOverlayCache,
+    timings: FrameTimings,
+    stream_frames_lagged: AtomicU64,
+    capture_frames_lagged: Arc<AtomicU64>,
 }
 
 impl CameraService {
@@ -28,17 +178,42 @@ impl CameraService {
         face_detector: Arc<FaceDetector>,
         process_frame: Arc<ProcessFrameUseCase>,
         camera_repo: Arc<dyn CameraRepository>,
+        event_bus: Arc<EventBus>,
     ) -> Self {
         Self {
             captures: Arc::new(RwLock::new(HashMap::new())),
             face_detector,
             process_frame,
             camera_repo,
+            event_bus,
+            pipeline: PipelineConfig::default(),
+            detection_sample_every: Arc::new(AtomicU64::new(
+                PipelineConfig::default().detection_sample_every,
+            )),
+            overlays: DetectionOverlayCache::default(),
+            timings: FrameTimings::default(),
+            stream_frames_lagged: AtomicU64::new(0),
+            capture_frames_lagged: Arc::new(AtomicU64::new(0)),
         }
     }
 
+    /// Sets the frame processing pipeline configuration.
+    /// Applies to cameras started after this call.
+    pub fn with_pipeline_config(mut self, pipeline: PipelineConfig) -> Self {
+        self.set_detection_sample_every(pipeline.detection_sample_every);
+        self.pipeline = pipeline;
+        self
+    }
+
+    /// Changes how often frames are sampled for detection, for running
+    /// cameras as well as those started later.
+    pub fn set_detection_sample_every(&self, sample_every: u64) {
+        self.detection_sample_every
+            .store(sample_every.max(1), Ordering::Relaxed);
+    }
+
     /// Lists available cameras on the system.
-    pub fn list_available_cameras(&self) -> Vec<super::capture::CameraInfo> {
+    pub fn list_available_cameras(&self) -> Vec<CameraInfo> {
         list_cameras()
     }
 
@@ -52,52 +227,104 @@ impl CameraService {
             camera_id
         );
 
+        let device_index = resolve_device_index(camera, &list_cameras())?;
+
         // Create capture config based on camera settings
         let config = CaptureConfig {
-            device_index: 0, // Default to first camera for built-in
+            device_index,
             width: 1280,
             height: 720,
             fps: 15, // Lower FPS for face detection processing
+            frame_buffer: self.pipeline.frame_buffer,
+            permission_preflight: self.pipeline.permission_preflight,
+            pixel_format: self.pipeline.pixel_format,
+            ..Default::default()
         };
 
-        let capture = Arc::new(CameraCapture::new(camera_id, config));
+        self.start_capture(camera, config).await
+    }
 
-        // Store capture reference
-        {
+    /// Starts capture for a camera from the source selected in `config`,
+    /// feeding its frames into the processing pipeline. Does nothing if the
+    /// camera is already starting or running.
+    pub async fn start_capture(
+        &self,
+        camera: &Camera,
+        config: CaptureConfig,
+    ) -> anyhow::Result<()> {
+        let camera_id = camera.id();
+
+        // Check and register under one write lock, so concurrent starts
+        // can't both spawn a capture loop
+        let capture = {
             let mut captures = self.captures.write().await;
+            if let Some(existing) = captures.get(&camera_id) {
+                match existing.state().await {
+                    CaptureState::Starting | CaptureState::Running => {
+                        info!("Camera {} is already running", camera_id);
+                        return Ok(());
+                    }
+                    // Replace a capture that stopped or failed
+                    CaptureState::Stopped
+                    | CaptureState::Error
+                    | CaptureState::PermissionDenied
+                    | CaptureState::NoDevice => existing.stop().await,
+                }
+            }
+            let capture = Arc::new(CameraCapture::new(camera_id, config));
             captures.insert(camera_id, capture.clone());
-        }
+            capture
+        };
+        spawn_status_events(
+            self.event_bus.clone(),
+            camera_id,
+            camera.name().to_string(),
+            capture.watch_state(),
+        );
 
         // Start the capture
-        capture.start().await?;
-
-        // Start frame processing in background
-        let face_detector = self.face_detector.clone();
-        let process_frame = self.process_frame.clone();
-        let mut frame_rx = capture.subscribe();
-
-        tokio::spawn(async move {
-            info!("Frame processing started for camera {}", camera_id);
-
-            while let Ok(frame) = frame_rx.recv().await {
-                // Process every 3rd frame to reduce CPU load
-                if frame.frame_number % 3 != 0 {
-                    continue;
-                }
-
-                if let Err(e) =
-                    Self::process_frame_internal(&face_detector, &process_frame, frame).await
-                {
-                    warn!("Frame processing error: {}", e);
-                }
+        if let Err(e) = capture.start().await {
+            let mut captures = self.captures.write().await;
+            if captures
+                .get(&camera_id)
+                .is_some_and(|current| Arc::ptr_eq(current, &capture))
+            {
+                captures.remove(&camera_id);
             }
+            return Err(e);
+        }
 
-            info!("Frame processing stopped for camera {}", camera_id);
-        });
+        // Start frame processing in background
+        self.spawn_frame_processing(camera_id, &capture);
 
         Ok(())
     }
 
+    /// Starts capture for a camera like [`Self::start_camera`], and waits up
+    /// to `timeout` for it to deliver frames. A capture that fails or times
+    /// out is stopped and removed.
+    pub async fn start_camera_and_wait(
+        &self,
+        camera: &Camera,
+        timeout: Duration,
+    ) -> anyhow::Result<()> {
+        self.start_camera(camera).await?;
+        let states = self
+            .captures
+            .read()
+            .await
+            .get(&camera.id())
+            .map(|capture| capture.watch_state())
+            .ok_or_else(|| anyhow::anyhow!("capture for camera {} was removed", camera.id()))?;
+        let result = wait_until_running(states, timeout).await;
+        if result.is_err() {
+            // Don't leave a capture that may still come up after the camera
+            // was reported as failed
+            self.stop_camera(camera.id()).await;
+        }
+        result
+    }
+
     /// Stops capture for a camera.
     pub async fn stop_camera(&self, camera_id: Uuid) {
         let mut captures = self.captures.write().await;
@@ -105,6 +332,25 @@ impl CameraService {
             capture.stop().await;
             info!("Stopped camera {}", camera_id);
         }
+        self.overlays.remove(camera_id).await;
+        self.timings.remove(camera_id).await;
+    }
+
+    /// Returns the measured frame rate and newest frame time of a running camera.
+    pub async fn camera_health(&self, camera_id: Uuid) -> Option<CameraHealth> {
+        self.timings.health(camera_id).await
+    }
+
+    /// Returns the configuration, state and actual resolution of a camera's
+    /// capture, or `None` if it isn't capturing.
+    pub async fn capture_status(&self, camera_id: Uuid) -> Option<CaptureStatus> {
+        let captures = self.captures.read().await;
+        captures.get(&camera_id).map(|capture| capture.status())
+    }
+
+    /// Returns the most recent detections for drawing on a camera's stream.
+    pub async fn latest_detections(&self, camera_id: Uuid) -> Vec<OverlayBox> {
+        self.overlays.latest(camera_id).await
     }
 
     /// Gets the capture state for a camera.
@@ -117,6 +363,46 @@ impl CameraService {
         }
     }
 
+    /// Returns the number of frames captured so far by each active capture.
+    pub async fn frame_counts(&self) -> Vec<(Uuid, u64)> {
+        let captures = self.captures.read().await;
+        let mut counts = Vec::with_capacity(captures.len());
+        for (id, capture) in captures.iter() {
+            counts.push((*id, capture.frame_count().await));
+        }
+        counts
+    }
+
+    /// Returns the number of captures currently running.
+    pub async fn active_capture_count(&self) -> usize {
+        let captures = self.captures.read().await;
+        let mut running = 0;
+        for capture in captures.values() {
+            if capture.state().await == CaptureState::Running {
+                running += 1;
+            }
+        }
+        running
+    }
+
+    /// Returns the number of frames live streams skipped because their client
+    /// fell behind the camera.
+    pub fn stream_frames_lagged(&self) -> u64 {
+        self.stream_frames_lagged.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of captured frames the detection pipeline skipped
+    /// because it fell behind the camera.
+    pub fn capture_frames_lagged(&self) -> u64 {
+        self.capture_frames_lagged.load(Ordering::Relaxed)
+    }
+
+    /// Counts frames a live stream skipped because its client fell behind.
+    pub fn record_stream_lag(&self, skipped: u64) {
+        self.stream_frames_lagged
+            .fetch_add(skipped, Ordering::Relaxed);
+    }
+
     /// Subscribe to frame updates for a specific camera.
     /// Returns a broadcast receiver for frames if the camera is active.
     pub async fn subscribe_frames(
@@ -127,84 +413,243 @@ impl CameraService {
         captures.get(&camera_id).map(|capture| capture.subscribe())
     }
 
+    /// Feeds an RGB frame pushed by a browser camera into the processing
+    /// pipeline. The camera's capture is registered on its first frame.
+    pub async fn push_frame(&self, camera_id: Uuid, width: u32, height: u32, data: Vec<u8>) {
+        let existing = self.captures.read().await.get(&camera_id).cloned();
+        let capture = match existing {
+            Some(capture) => capture,
+            None => self.register_pushed_capture(camera_id).await,
+        };
+
+        capture.push_frame(width, height, data).await;
+    }
+
+    /// Registers the capture that frames pushed for a camera are fed through.
+    async fn register_pushed_capture(&self, camera_id: Uuid) -> Arc<CameraCapture> {
+        let camera_name = match self.camera_repo.find_by_id(camera_id).await {
+            Ok(camera) => camera.map(|c| c.name().to_string()).unwrap_or_default(),
+            Err(e) => {
+                warn!("Failed to load camera {}: {}", camera_id, e);
+                String::new()
+            }
+        };
+
+        let mut captures = self.captures.write().await;
+        if let Some(capture) = captures.get(&camera_id) {
+            // Another request registered it while the name was loading
+            return capture.clone();
+        }
+
+        info!("Receiving pushed frames for camera {}", camera_id);
+        let capture = Arc::new(CameraCapture::new(
+            camera_id,
+            CaptureConfig {
+                frame_buffer: self.pipeline.frame_buffer,
+                ..Default::default()
+            },
+        ));
+        spawn_status_events(
+            self.event_bus.clone(),
+            camera_id,
+            camera_name,
+            capture.watch_state(),
+        );
+        captures.insert(camera_id, capture.clone());
+        self.spawn_frame_processing(camera_id, &capture);
+        capture
+    }
+
     /// Starts capture for the built-in camera automatically.
-    /// Reuses existing camera if one with device_id "0" already exists.
-    pub async fn start_builtin_camera(&self) -> anyhow::Result<Uuid> {
+    /// Reuses existing camera if one with device_id "0" already exists, and
+    /// returns `None` without starting it if that camera is disabled.
+    pub async fn start_builtin_camera(&self) -> anyhow::Result<Option<Uuid>> {
         info!("Starting built-in camera capture automatically");
 
         // Check if built-in camera already exists in database (device_id = "0")
-        let camera_id = if let Ok(Some(mut existing)) = self.camera_repo.find_by_device_id("0").await {
-            info!("Found existing built-in camera in database: {}", existing.id());
+        let camera = if let Ok(Some(mut existing)) = self.camera_repo.find_by_device_id("0").await {
+            info!(
+                "Found existing built-in camera in database: {}",
+                existing.id()
+            );
+            if !existing.is_enabled() {
+                info!("Built-in camera is disabled, not starting it");
+                return Ok(None);
+            }
             // Update status to Active
             existing.set_status(crate::domain::entities::CameraStatus::Active);
             if let Err(e) = self.camera_repo.update(&existing).await {
                 warn!("Failed to update camera status: {}", e);
             }
-            existing.id()
+            existing
         } else {
             // Create and save the built-in camera to the database
             let mut camera = Camera::builtin();
             camera.set_status(crate::domain::entities::CameraStatus::Active);
-            let id = camera.id();
             self.camera_repo.save(&camera).await?;
-            info!("Registered new built-in camera in database with ID: {}", id);
-            id
+            info!(
+                "Registered new built-in camera in database with ID: {}",
+                camera.id()
+            );
+            camera
         };
+        let camera_id = camera.id();
 
         let config = CaptureConfig {
             device_index: 0,
             width: 1280,
             height: 720,
             fps: 15,
+            frame_buffer: self.pipeline.frame_buffer,
+            permission_preflight: self.pipeline.permission_preflight,
+            pixel_format: self.pipeline.pixel_format,
+            ..Default::default()
         };
 
-        let capture = Arc::new(CameraCapture::new(camera_id, config));
+        self.start_capture(&camera, config).await?;
 
-        {
-            let mut captures = self.captures.write().await;
-            captures.insert(camera_id, capture.clone());
-        }
-
-        capture.start().await?;
+        Ok(Some(camera_id))
+    }
 
-        // Start frame processing
+    /// Spawns the background task that samples frames from a capture,
+    /// runs them through the detector in batches and processes the results.
+    fn spawn_frame_processing(&self, camera_id: Uuid, capture: &CameraCapture) {
         let face_detector = self.face_detector.clone();
         let process_frame_uc = self.process_frame.clone();
+        let overlays = self.overlays.clone();
+        let timings = self.timings.clone();
+        let camera_repo = self.camera_repo.clone();
+        let pipeline = self.pipeline.clone();
+        let sample_every = self.detection_sample_every.clone();
+        let batch_size = pipeline.detection_batch_size.max(1);
+        let frames_lagged = self.capture_frames_lagged.clone();
         let mut frame_rx = capture.subscribe();
 
         tokio::spawn(async move {
-            info!("Built-in camera frame processing started");
+            info!("Frame processing started for camera {}", camera_id);
+
+            let mut batch: Vec<CapturedFrame> = Vec::with_capacity(batch_size);
+            // When the partial batch must be detected without further frames
+            let mut batch_deadline: Option<tokio::time::Instant> = None;
+            let mut last_persisted: Option<Instant> = None;
+
+            loop {
+                let received = match batch_deadline {
+                    Some(deadline) => {
+                        match tokio::time::timeout_at(deadline, frame_rx.recv()).await {
+                            Ok(received) => received,
+                            Err(_) => {
+                                batch_deadline = None;
+                                Self::process_batch(
+                                    &face_detector,
+                                    &process_frame_uc,
+                                    &overlays,
+                                    camera_id,
+                                    &mut batch,
+                                )
+                                .await;
+                                continue;
+                            }
+                        }
+                    }
+                    None => frame_rx.recv().await,
+                };
+                let frame = match received {
+                    Ok(frame) => frame,
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        debug!("Camera {} pipeline skipped {} frames", camera_id, skipped);
+                        frames_lagged.fetch_add(skipped, Ordering::Relaxed);
+                        continue;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+
+                timings.record(camera_id, frame.timestamp_ms).await;
+                if last_persisted.is_none_or(|at| at.elapsed() >= LAST_FRAME_PERSIST_INTERVAL) {
+                    last_persisted = Some(Instant::now());
+                    Self::persist_last_frame(camera_repo.as_ref(), camera_id).await;
+                }
 
-            while let Ok(frame) = frame_rx.recv().await {
-                // Process every 5th frame to reduce CPU load
-                if frame.frame_number % 5 != 0 {
+                // Only sample a subset of frames to reduce CPU load
+                let every = sample_every.load(Ordering::Relaxed);
+                if !should_sample(frame.frame_number, every) || frame.data.is_empty() {
                     continue;
                 }
 
-                if let Err(e) =
-                    Self::process_frame_internal(&face_detector, &process_frame_uc, frame).await
-                {
-                    warn!("Frame processing error: {}", e);
+                batch.push(frame);
+                if batch.len() < batch_size {
+                    batch_deadline.get_or_insert_with(|| {
+                        tokio::time::Instant::now() + pipeline.detection_batch_timeout
+                    });
+                    continue;
                 }
+
+                batch_deadline = None;
+                Self::process_batch(
+                    &face_detector,
+                    &process_frame_uc,
+                    &overlays,
+                    camera_id,
+                    &mut batch,
+                )
+                .await;
             }
-        });
 
-        Ok(camera_id)
+            // Frames buffered when the capture stopped are still processed
+            Self::process_batch(
+                &face_detector,
+                &process_frame_uc,
+                &overlays,
+                camera_id,
+                &mut batch,
+            )
+            .await;
+            info!("Frame processing stopped for camera {}", camera_id);
+        });
     }
 
-    async fn process_frame_internal(
+    /// Runs the buffered frames through the detector as one batch and
+    /// processes each frame's detections, leaving `batch` empty.
+    async fn process_batch(
         face_detector: &FaceDetector,
         process_frame_uc: &ProcessFrameUseCase,
-        frame: CapturedFrame,
-    ) -> anyhow::Result<()> {
-        // Skip empty frames
-        if frame.data.is_empty() {
-            return Ok(());
+        overlays: &DetectionOverlayCache,
+        camera_id: Uuid,
+        batch: &mut Vec<CapturedFrame>,
+    ) {
+        if batch.is_empty() {
+            return;
+        }
+
+        let results = face_detector.detect_batch(batch).await;
+        for (frame, detections) in batch.drain(..).zip(results) {
+            overlays
+                .update(camera_id, detections.iter().map(OverlayBox::from).collect())
+                .await;
+            if let Err(e) =
+                Self::process_frame_internal(process_frame_uc, face_detector, frame, detections)
+                    .await
+            {
+                warn!("Frame processing error: {}", e);
+            }
         }
+    }
 
-        // Detect faces in the frame using the async detect method
-        let detections = face_detector.detect(&frame).await;
+    /// Bumps the camera's `last_frame_at` so silent cameras can be spotted.
+    async fn persist_last_frame(camera_repo: &dyn CameraRepository, camera_id: Uuid) {
+        match camera_repo.update_last_frame(camera_id, Utc::now()).await {
+            // Pushed frames may arrive for a camera that was never saved
+            Ok(()) | Err(RepositoryError::NotFound(_)) => {}
+            Err(e) => warn!("Failed to update last frame time for {}: {}", camera_id, e),
+        }
+    }
 
+    async fn process_frame_internal(
+        process_frame_uc: &ProcessFrameUseCase,
+        extractor: &dyn EmbeddingExtractor,
+        frame: CapturedFrame,
+        detections: Vec<Detection>,
+    ) -> anyhow::Result<()> {
         if detections.is_empty() {
             return Ok(());
         }
@@ -216,25 +661,7 @@ impl CameraService {
             frame.camera_id
         );
 
-        // Convert detections to domain Detection objects
-        let domain_detections: Vec<Detection> = detections
-            .iter()
-            .map(|d| Detection::new(d.bounding_box().clone(), d.confidence()))
-            .collect();
-
-        // Create FrameDetections for the use case
-        let mut frame_detections = FrameDetections::new(
-            frame.camera_id,
-            frame.frame_number,
-            frame.timestamp_ms,
-        );
-
-        for detection in domain_detections {
-            frame_detections.add_detection(detection);
-        }
-
-        // Store the frame data for thumbnail creation
-        frame_detections.set_frame_data(frame.data.clone());
+        let mut frame_detections = Self::build_frame_detections(extractor, frame, detections).await;
 
         // Process the frame through the use case (creates profiles, sightings, etc.)
         match process_frame_uc.execute(&mut frame_detections).await {
@@ -247,8 +674,9 @@ impl CameraService {
                     );
                 }
                 debug!(
-                    "Frame processed: {} faces, {} new profiles",
+                    "Frame processed: {} faces, {} recognised, {} new profiles",
                     result.face_count,
+                    result.matched_profiles.len(),
                     result.created_profiles.len()
                 );
             }
@@ -260,6 +688,26 @@ impl CameraService {
         Ok(())
     }
 
+    /// Builds the use case input from a frame and its detections, attaching
+    /// an embedding to each face.
+    async fn build_frame_detections(
+        extractor: &dyn EmbeddingExtractor,
+        frame: CapturedFrame,
+        mut detections: Vec<Detection>,
+    ) -> FrameDetections {
+        attach_embeddings(extractor, &frame, &mut detections).await;
+
+        let mut frame_detections =
+            FrameDetections::new(frame.camera_id, frame.frame_number, frame.timestamp_ms);
+        for detection in detections {
+            frame_detections.add_detection(detection);
+        }
+
+        // Store the frame data for thumbnail creation
+        frame_detections.set_frame_data(frame.data, frame.width, frame.height);
+        frame_detections
+    }
+
     /// Stops all cameras.
     pub async fn stop_all(&self) {
         let mut captures = self.captures.write().await;
@@ -269,3 +717,485 @@ impl CameraService {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::application::services::EventSubscriber;
+    use crate::domain::repositories::fakes::{InMemoryCameraRepo, InMemorySightingRepo};
+    use crate::domain::repositories::SightingRepository;
+    use crate::domain::value_objects::{embedding_dimension, BoundingBox, FaceEmbedding};
+    use crate::infrastructure::camera::CaptureSource;
+
+    fn available(indices: &[u32]) -> Vec<CameraInfo> {
+        indices
+            .iter()
+            .map(|&index| CameraInfo {
+                index,
+                name: format!("Camera {}", index),
+                description: String::new(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn parse_device_index_accepts_numeric_ids() {
+        assert_eq!(parse_device_index("0"), Some(0));
+        assert_eq!(parse_device_index("2"), Some(2));
+        assert_eq!(parse_device_index(" 1 "), Some(1));
+    }
+
+    #[test]
+    fn parse_device_index_rejects_non_numeric_ids() {
+        assert_eq!(parse_device_index("rtsp://192.168.1.100:554/stream"), None);
+        assert_eq!(parse_device_index("browser"), None);
+        assert_eq!(parse_device_index("-1"), None);
+        assert_eq!(parse_device_index(""), None);
+    }
+
+    #[test]
+    fn resolve_device_index_requires_enumerated_device() {
+        use crate::domain::entities::CameraType;
+
+        let usb = Camera::new("USB".to_string(), CameraType::Usb, "1".to_string(), None);
+        assert_eq!(resolve_device_index(&usb, &available(&[0, 1])).unwrap(), 1);
+        assert!(resolve_device_index(&usb, &available(&[0])).is_err());
+
+        let rtsp = Camera::new(
+            "IP".to_string(),
+            CameraType::Rtsp,
+            "rtsp://192.168.1.100:554/stream".to_string(),
+            None,
+        );
+        assert!(resolve_device_index(&rtsp, &available(&[0])).is_err());
+    }
+
+    #[test]
+    fn sampling_every_frame_processes_all_frames() {
+        assert!((0..10).all(|n| should_sample(n, 1)));
+    }
+
+    #[test]
+    fn sampling_processes_every_nth_frame() {
+        let sampled: Vec<u64> = (1..=10).filter(|n| should_sample(*n, 3)).collect();
+        assert_eq!(sampled, vec![3, 6, 9]);
+    }
+
+    #[test]
+    fn pipeline_config_rejects_zero_sample_rate() {
+        let settings = DetectionSettings {
+            detection_sample_every: 0,
+            ..Default::default()
+        };
+        assert!(PipelineConfig::from_settings(&settings).is_err());
+
+        let settings = DetectionSettings {
+            detection_sample_every: 5,
+            ..Default::default()
+        };
+        let pipeline = PipelineConfig::from_settings(&settings).unwrap();
+        assert_eq!(pipeline.detection_sample_every, 5);
+    }
+
+    struct StubExtractor(FaceEmbedding);
+
+    #[async_trait::async_trait]
+    impl EmbeddingExtractor for StubExtractor {
+        async fn extract_embedding(&self, _: &image::RgbImage) -> Option<FaceEmbedding> {
+            Some(self.0.clone())
+        }
+    }
+
+    #[tokio::test]
+    async fn detections_carry_embeddings_into_use_case() {
+        let embedding = FaceEmbedding::new(vec![0.5; embedding_dimension()]);
+        let frame = CapturedFrame {
+            camera_id: Uuid::new_v4(),
+            frame_number: 7,
+            timestamp_ms: 1_000,
+            width: 64,
+            height: 48,
+            pixel_format: PixelFormat::Rgb,
+            data: vec![90; 64 * 48 * 3],
+        };
+        let detections = vec![Detection::new(BoundingBox::new(10, 10, 20, 20), 0.9)];
+
+        let frame_detections = CameraService::build_frame_detections(
+            &StubExtractor(embedding.clone()),
+            frame,
+            detections,
+        )
+        .await;
+
+        assert_eq!(frame_detections.frame_number(), 7);
+        assert_eq!(frame_detections.detections().len(), 1);
+        assert_eq!(
+            frame_detections.detections()[0].embedding(),
+            Some(&embedding)
+        );
+        assert_eq!(frame_detections.frame_size(), Some((64, 48)));
+    }
+
+    /// Waits for the next camera status event and returns its status.
+    async fn next_status(events: &mut EventSubscriber, camera_id: Uuid) -> String {
+        let event = tokio::time::timeout(Duration::from_secs(1), events.recv())
+            .await
+            .expect("no camera status event")
+            .unwrap();
+        match &*event {
+            DomainEvent::CameraStatusChanged(e) => {
+                assert_eq!(e.camera_id, camera_id);
+                assert_eq!(e.camera_name, "Porch");
+                e.status.clone()
+            }
+            other => panic!("unexpected event {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn capture_state_changes_publish_camera_status() {
+        let event_bus = Arc::new(EventBus::new());
+        let mut events = event_bus.subscribe();
+        let camera_id = Uuid::new_v4();
+        let capture = CameraCapture::new(camera_id, CaptureConfig::default());
+        spawn_status_events(
+            event_bus.clone(),
+            camera_id,
+            "Porch".to_string(),
+            capture.watch_state(),
+        );
+
+        // Further frames keep it streaming without repeating the event
+        capture.push_frame(2, 2, vec![0; 12]).await;
+        capture.push_frame(2, 2, vec![0; 12]).await;
+        assert_eq!(next_status(&mut events, camera_id).await, "streaming");
+
+        capture.stop().await;
+        assert_eq!(next_status(&mut events, camera_id).await, "stopped");
+    }
+
+    #[tokio::test]
+    async fn waiting_for_a_capture_reports_whether_it_runs() {
+        let timeout = Duration::from_secs(1);
+
+        let capture = CameraCapture::new(Uuid::new_v4(), CaptureConfig::default());
+        let states = capture.watch_state();
+        capture.push_frame(2, 2, vec![0; 12]).await;
+        assert!(wait_until_running(states, timeout).await.is_ok());
+
+        let capture = CameraCapture::new(Uuid::new_v4(), CaptureConfig::default());
+        let states = capture.watch_state();
+        capture.stop().await;
+        assert!(wait_until_running(states, timeout).await.is_err());
+    }
+
+    async fn saved_camera<R: CameraRepository + ?Sized>(camera_repo: &R, name: &str) -> Camera {
+        let camera = Camera::new(
+            name.to_string(),
+            crate::domain::entities::CameraType::Usb,
+            format!("replay-{}", Uuid::new_v4()),
+            None,
+        );
+        camera_repo.save(&camera).await.unwrap();
+        camera
+    }
+
+    /// A camera service over in-memory repositories and a detector stub.
+    struct Pipeline {
+        service: CameraService,
+        camera_repo: Arc<InMemoryCameraRepo>,
+        sighting_repo: Arc<InMemorySightingRepo>,
+        events: EventSubscriber,
+        _data_dir: tempfile::TempDir,
+    }
+
+    /// Builds a [`Pipeline`] whose detector reports `detections` in every
+    /// frame, processing every frame.
+    fn pipeline(detections: Vec<Detection>) -> Pipeline {
+        use crate::application::services::{
+            DetectionConfig, DetectionService, FaceMatcher, RecordingConfig, RecordingService,
+            StorageConfig, StorageManager,
+        };
+        use crate::domain::repositories::fakes::{InMemoryProfileRepo, InMemoryRecordingRepo};
+
+        let profile_repo = Arc::new(InMemoryProfileRepo::default());
+        let sighting_repo = Arc::new(InMemorySightingRepo::default());
+        let camera_repo = Arc::new(InMemoryCameraRepo::default());
+        let recording_repo = Arc::new(InMemoryRecordingRepo::default());
+        let event_bus = Arc::new(EventBus::new());
+        let events = event_bus.subscribe();
+
+        let data_dir = tempfile::tempdir().unwrap();
+        let detection_service = Arc::new(DetectionService::new(
+            profile_repo.clone(),
+            sighting_repo.clone(),
+            Arc::new(FaceMatcher::new(profile_repo, 0.6)),
+            event_bus.clone(),
+            DetectionConfig::default(),
+        ));
+        let recording_service = Arc::new(RecordingService::new(
+            recording_repo.clone(),
+            event_bus.clone(),
+            RecordingConfig {
+                recordings_dir: data_dir.path().join("recordings"),
+                ..Default::default()
+            },
+        ));
+        let storage_manager = Arc::new(StorageManager::new(
+            recording_repo,
+            StorageConfig {
+                base_dir: data_dir.path().to_path_buf(),
+                ..Default::default()
+            },
+        ));
+        std::fs::create_dir_all(data_dir.path().join("recordings")).unwrap();
+        std::fs::create_dir_all(data_dir.path().join("snapshots")).unwrap();
+
+        let service = CameraService::new(
+            Arc::new(FaceDetector::stub(detections)),
+            Arc::new(ProcessFrameUseCase::new(
+                detection_service,
+                recording_service,
+                storage_manager,
+            )),
+            camera_repo.clone(),
+            event_bus,
+        )
+        .with_pipeline_config(PipelineConfig {
+            detection_sample_every: 1,
+            ..Default::default()
+        });
+
+        Pipeline {
+            service,
+            camera_repo,
+            sighting_repo,
+            events,
+            _data_dir: data_dir,
+        }
+    }
+
+    #[tokio::test]
+    async fn disabled_builtin_camera_is_not_started() {
+        let Pipeline {
+            service,
+            camera_repo,
+            ..
+        } = pipeline(Vec::new());
+        let mut builtin = Camera::builtin();
+        builtin.set_enabled(false);
+        camera_repo.save(&builtin).await.unwrap();
+
+        assert_eq!(service.start_builtin_camera().await.unwrap(), None);
+        assert!(service.capture_status(builtin.id()).await.is_none());
+    }
+
+    /// Writes `count` flat grey frames for a replay source.
+    fn replay_frames(count: usize) -> tempfile::TempDir {
+        let frames = tempfile::tempdir().unwrap();
+        for n in 0..count {
+            image::RgbImage::from_pixel(160, 120, image::Rgb([120, 120, 120]))
+                .save(frames.path().join(format!("frame_{:02}.png", n)))
+                .unwrap();
+        }
+        frames
+    }
+
+    #[tokio::test]
+    async fn replayed_face_creates_profile_and_sighting() {
+        let mut face = Detection::new(BoundingBox::new(40, 30, 60, 60), 0.9);
+        face.set_embedding(FaceEmbedding::new(vec![0.5; embedding_dimension()]).normalized());
+        let Pipeline {
+            service,
+            camera_repo,
+            sighting_repo,
+            mut events,
+            ..
+        } = pipeline(vec![face]);
+        let camera = saved_camera(camera_repo.as_ref(), "Replay").await;
+
+        let frames = replay_frames(2);
+        service
+            .start_capture(
+                &camera,
+                CaptureConfig {
+                    source: CaptureSource::Replay(frames.path().to_path_buf()),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+
+        // The first frame creates the profile, the second is a sighting of it
+        let (mut created, mut sighted) = (None, None);
+        while created.is_none() || sighted.is_none() {
+            let event = tokio::time::timeout(Duration::from_secs(10), events.recv())
+                .await
+                .expect("pipeline did not create a profile and sighting")
+                .unwrap();
+            match &*event {
+                DomainEvent::ProfileCreated(e) if e.camera_id == camera.id() => {
+                    created = Some(e.profile_id)
+                }
+                DomainEvent::ProfileSighted(e) if e.camera_id == camera.id() => {
+                    sighted = Some(e.profile_id)
+                }
+                _ => {}
+            }
+        }
+        assert_eq!(created, sighted);
+
+        let sightings = sighting_repo
+            .find_by_profile(created.unwrap(), 10)
+            .await
+            .unwrap();
+        assert_eq!(sightings.len(), 1);
+        assert_eq!(sightings[0].camera_id(), camera.id());
+    }
+
+    #[tokio::test]
+    async fn starting_a_camera_twice_runs_one_capture() {
+        let pipeline = pipeline(Vec::new());
+        let camera = saved_camera(pipeline.camera_repo.as_ref(), "Twice").await;
+
+        // Enough frames to keep the replay running during both starts
+        let frames = replay_frames(60);
+        let config = CaptureConfig {
+            source: CaptureSource::Replay(frames.path().to_path_buf()),
+            ..Default::default()
+        };
+
+        let service = &pipeline.service;
+        service
+            .start_capture(&camera, config.clone())
+            .await
+            .unwrap();
+        let first = service.captures.read().await[&camera.id()].clone();
+        service.start_capture(&camera, config).await.unwrap();
+
+        let captures = service.captures.read().await;
+        assert_eq!(captures.len(), 1);
+        assert!(Arc::ptr_eq(&captures[&camera.id()], &first));
+        // Only the first start's frame processing task is subscribed
+        assert_eq!(first.subscriber_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn frames_set_last_frame_time_and_keep_camera_edits() {
+        let pipeline = pipeline(Vec::new());
+        let mut camera = saved_camera(pipeline.camera_repo.as_ref(), "Porch").await;
+        // Renamed and disabled through the API while frames arrive
+        camera.set_name("Front door".to_string());
+        camera.set_enabled(false);
+        pipeline.camera_repo.update(&camera).await.unwrap();
+
+        pipeline
+            .service
+            .push_frame(camera.id(), 160, 120, grey_frame())
+            .await;
+
+        let stored = tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                let stored = pipeline.camera_repo.find_by_id(camera.id()).await.unwrap();
+                match stored {
+                    Some(stored) if stored.last_frame_at().is_some() => return stored,
+                    _ => tokio::time::sleep(Duration::from_millis(10)).await,
+                }
+            }
+        })
+        .await
+        .expect("last frame time was not recorded");
+        assert_eq!(stored.name(), "Front door");
+        assert!(!stored.is_enabled());
+    }
+
+    /// A pipeline that batches up to four frames, waiting at most
+    /// `timeout` for a batch to fill, over a detector reporting one face.
+    fn batched_pipeline(timeout: Duration) -> Pipeline {
+        let mut face = Detection::new(BoundingBox::new(40, 30, 60, 60), 0.9);
+        face.set_embedding(FaceEmbedding::new(vec![0.5; embedding_dimension()]).normalized());
+        let mut pipeline = pipeline(vec![face]);
+        pipeline.service = pipeline.service.with_pipeline_config(PipelineConfig {
+            detection_sample_every: 1,
+            detection_batch_size: 4,
+            detection_batch_timeout: timeout,
+            ..Default::default()
+        });
+        pipeline
+    }
+
+    /// Waits for the profile the pipeline creates for a face on `camera_id`.
+    async fn profile_created(events: &mut EventSubscriber, camera_id: Uuid, within: Duration) {
+        tokio::time::timeout(within, async {
+            loop {
+                if let DomainEvent::ProfileCreated(e) = &*events.recv().await.unwrap() {
+                    if e.camera_id == camera_id {
+                        return;
+                    }
+                }
+            }
+        })
+        .await
+        .expect("partial batch was not processed")
+    }
+
+    fn grey_frame() -> Vec<u8> {
+        vec![120; 160 * 120 * 3]
+    }
+
+    #[tokio::test]
+    async fn sample_rate_changes_apply_to_running_cameras() {
+        let mut face = Detection::new(BoundingBox::new(40, 30, 60, 60), 0.9);
+        face.set_embedding(FaceEmbedding::new(vec![0.5; embedding_dimension()]).normalized());
+        let mut pipeline = pipeline(vec![face]);
+        let camera = saved_camera(pipeline.camera_repo.as_ref(), "Busy").await;
+
+        // The first frame starts the capture but isn't sampled
+        pipeline.service.set_detection_sample_every(1000);
+        pipeline
+            .service
+            .push_frame(camera.id(), 160, 120, grey_frame())
+            .await;
+        let sampled = tokio::time::timeout(
+            Duration::from_millis(200),
+            profile_created(&mut pipeline.events, camera.id(), Duration::from_secs(1)),
+        )
+        .await;
+        assert!(sampled.is_err(), "frame 1 was sampled");
+
+        pipeline.service.set_detection_sample_every(1);
+        pipeline
+            .service
+            .push_frame(camera.id(), 160, 120, grey_frame())
+            .await;
+        profile_created(&mut pipeline.events, camera.id(), Duration::from_secs(5)).await;
+    }
+
+    #[tokio::test]
+    async fn partial_batch_is_processed_after_the_timeout() {
+        let mut pipeline = batched_pipeline(Duration::from_millis(50));
+        let camera = saved_camera(pipeline.camera_repo.as_ref(), "Quiet").await;
+
+        // One frame never fills the batch of four
+        pipeline
+            .service
+            .push_frame(camera.id(), 160, 120, grey_frame())
+            .await;
+
+        profile_created(&mut pipeline.events, camera.id(), Duration::from_secs(5)).await;
+    }
+
+    #[tokio::test]
+    async fn partial_batch_is_processed_when_the_camera_stops() {
+        let mut pipeline = batched_pipeline(Duration::from_secs(3600));
+        let camera = saved_camera(pipeline.camera_repo.as_ref(), "Stopping").await;
+
+        pipeline
+            .service
+            .push_frame(camera.id(), 160, 120, grey_frame())
+            .await;
+        pipeline.service.stop_camera(camera.id()).await;
+
+        profile_created(&mut pipeline.events, camera.id(), Duration::from_secs(5)).await;
+    }
+}
@@ -15,7 +15,9 @@ pub struct ProfileRow {
     pub name: Option<String>,
     pub classification: ProfileClassification,
     pub embedding: Vec<u8>,
+    pub has_embedding: bool,
     pub thumbnail_path: Option<String>,
+    pub thumbnail_quality: Option<f32>,
     pub tags: sqlx::types::Json<Vec<serde_json::Value>>,
     pub notes: Option<String>,
     pub first_seen_at: DateTime<Utc>,
@@ -60,6 +62,7 @@ pub struct SightingRow {
     pub bbox_width: i32,
     pub bbox_height: i32,
     pub confidence: f32,
+    pub match_confidence: Option<f32>,
     pub location_lat: Option<f64>,
     pub location_lon: Option<f64>,
     pub recording_id: Option<Uuid>,
@@ -81,6 +84,8 @@ pub struct RecordingRow {
     pub started_at: DateTime<Utc>,
     pub ended_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
+    pub deleted_at: Option<DateTime<Utc>>,
+    pub status_before_deletion: Option<RecordingStatus>,
 }
 
 /// Settings database row.
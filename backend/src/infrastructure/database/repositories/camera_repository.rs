@@ -1,6 +1,7 @@
 //! Camera Repository Implementation
 
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use sqlx::PgPool;
 use uuid::Uuid;
 
@@ -237,6 +238,20 @@ impl CameraRepository for PgCameraRepository {
         Ok(())
     }
 
+    async fn update_last_frame(&self, id: Uuid, at: DateTime<Utc>) -> RepoResult<()> {
+        let result = sqlx::query(r#"UPDATE cameras SET last_frame_at = $2 WHERE id = $1"#)
+            .bind(id)
+            .bind(at)
+            .execute(&self.pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(RepositoryError::NotFound(format!("Camera {}", id)));
+        }
+
+        Ok(())
+    }
+
     async fn delete(&self, id: Uuid) -> RepoResult<()> {
         let result = sqlx::query(r#"DELETE FROM cameras WHERE id = $1"#)
             .bind(id)
@@ -1,14 +1,19 @@
 //! Profile Repository Implementation
 
 use async_trait::async_trait;
-use sqlx::PgPool;
+use chrono::{DateTime, Utc};
+use sqlx::{PgExecutor, PgPool};
+use std::collections::HashMap;
 use uuid::Uuid;
 
-use crate::domain::entities::Profile;
+use crate::domain::entities::{Profile, ProfileClassification};
 use crate::domain::repositories::{ProfileRepository, RepoResult, RepositoryError};
 use crate::domain::value_objects::{FaceEmbedding, ProfileTag};
 use crate::infrastructure::database::models::ProfileRow;
 
+/// Profiles loaded per query when searching by embedding.
+const EMBEDDING_SEARCH_PAGE_SIZE: i64 = 500;
+
 /// PostgreSQL profile repository.
 pub struct PgProfileRepository {
     pool: PgPool,
@@ -35,7 +40,9 @@ impl PgProfileRepository {
             r.name,
             r.classification,
             embedding,
+            r.has_embedding,
             r.thumbnail_path,
+            r.thumbnail_quality,
             tags,
             r.notes,
             r.first_seen_at,
@@ -46,6 +53,41 @@ impl PgProfileRepository {
             r.updated_at,
         ))
     }
+
+    /// Fills in the embedding samples of `profiles`.
+    async fn with_samples(&self, profiles: Vec<Profile>) -> RepoResult<Vec<Profile>> {
+        if profiles.is_empty() {
+            return Ok(profiles);
+        }
+
+        let ids: Vec<Uuid> = profiles.iter().map(|p| p.id()).collect();
+        let rows: Vec<(Uuid, Vec<u8>)> = sqlx::query_as(
+            r#"
+            SELECT profile_id, embedding
+            FROM profile_embeddings
+            WHERE profile_id = ANY($1)
+            ORDER BY profile_id, position
+            "#,
+        )
+        .bind(&ids)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut samples: HashMap<Uuid, Vec<FaceEmbedding>> = HashMap::new();
+        for (profile_id, bytes) in rows {
+            if let Some(embedding) = FaceEmbedding::from_bytes(&bytes) {
+                samples.entry(profile_id).or_default().push(embedding);
+            }
+        }
+
+        Ok(profiles
+            .into_iter()
+            .map(|p| match samples.remove(&p.id()) {
+                Some(samples) => p.with_embedding_samples(samples),
+                None => p,
+            })
+            .collect())
+    }
 }
 
 #[async_trait]
@@ -54,8 +96,8 @@ impl ProfileRepository for PgProfileRepository {
         let row: Option<ProfileRow> = sqlx::query_as(
             r#"
             SELECT 
-                id, name, classification, embedding, thumbnail_path, 
-                tags, notes, first_seen_at, last_seen_at, sighting_count,
+                id, name, classification, embedding, has_embedding, thumbnail_path,
+                thumbnail_quality, tags, notes, first_seen_at, last_seen_at, sighting_count,
                 is_active, created_at, updated_at
             FROM profiles
             WHERE id = $1
@@ -65,18 +107,19 @@ impl ProfileRepository for PgProfileRepository {
         .fetch_optional(&self.pool)
         .await?;
 
-        match row {
-            Some(r) => Ok(self.row_to_profile(r)),
-            None => Ok(None),
-        }
+        let profiles = row
+            .and_then(|r| self.row_to_profile(r))
+            .into_iter()
+            .collect();
+        Ok(self.with_samples(profiles).await?.pop())
     }
 
     async fn find_all_active(&self) -> RepoResult<Vec<Profile>> {
         let rows: Vec<ProfileRow> = sqlx::query_as(
             r#"
             SELECT 
-                id, name, classification, embedding, thumbnail_path, 
-                tags, notes, first_seen_at, last_seen_at, sighting_count,
+                id, name, classification, embedding, has_embedding, thumbnail_path,
+                thumbnail_quality, tags, notes, first_seen_at, last_seen_at, sighting_count,
                 is_active, created_at, updated_at
             FROM profiles
             WHERE is_active = TRUE
@@ -91,99 +134,218 @@ impl ProfileRepository for PgProfileRepository {
             .filter_map(|r| self.row_to_profile(r))
             .collect();
 
-        Ok(profiles)
+        self.with_samples(profiles).await
     }
 
-    async fn find_by_embedding(
-        &self,
-        _embedding: &FaceEmbedding,
-        _threshold: f32,
-    ) -> RepoResult<Vec<(Profile, f32)>> {
-        // Note: In-memory matching is used via FaceMatcher for performance.
-        // This method exists for potential future DB-level vector search (pgvector).
-        Ok(vec![])
+    async fn find_active_page(&self, limit: i64, offset: i64) -> RepoResult<Vec<Profile>> {
+        let rows: Vec<ProfileRow> = sqlx::query_as(
+            r#"
+            SELECT 
+                id, name, classification, embedding, has_embedding, thumbnail_path,
+                thumbnail_quality, tags, notes, first_seen_at, last_seen_at, sighting_count,
+                is_active, created_at, updated_at
+            FROM profiles
+            WHERE is_active = TRUE
+            ORDER BY last_seen_at DESC, id
+            LIMIT $1 OFFSET $2
+            "#,
+        )
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let profiles: Vec<Profile> = rows
+            .into_iter()
+            .filter_map(|r| self.row_to_profile(r))
+            .collect();
+
+        self.with_samples(profiles).await
     }
 
-    async fn save(&self, profile: &Profile) -> RepoResult<()> {
-        let tags_json =
-            serde_json::to_value(profile.tags().iter().map(|t| t.value()).collect::<Vec<_>>())
-                .unwrap_or_default();
+    async fn find_by_ids(&self, ids: &[Uuid]) -> RepoResult<Vec<Profile>> {
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
 
-        sqlx::query(
+        let rows: Vec<ProfileRow> = sqlx::query_as(
             r#"
-            INSERT INTO profiles (
-                id, name, classification, embedding, thumbnail_path,
-                tags, notes, first_seen_at, last_seen_at, sighting_count,
+            SELECT 
+                id, name, classification, embedding, has_embedding, thumbnail_path,
+                thumbnail_quality, tags, notes, first_seen_at, last_seen_at, sighting_count,
                 is_active, created_at, updated_at
-            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+            FROM profiles
+            WHERE id = ANY($1)
             "#,
         )
-        .bind(profile.id())
-        .bind(profile.name())
-        .bind(profile.classification())
-        .bind(profile.embedding().to_bytes())
-        .bind(profile.thumbnail_path())
-        .bind(tags_json)
-        .bind(profile.notes())
-        .bind(profile.first_seen_at())
-        .bind(profile.last_seen_at())
-        .bind(profile.sighting_count())
-        .bind(profile.is_active())
-        .bind(profile.created_at())
-        .bind(profile.updated_at())
-        .execute(&self.pool)
+        .bind(ids)
+        .fetch_all(&self.pool)
         .await?;
 
-        Ok(())
+        let profiles: Vec<Profile> = rows
+            .into_iter()
+            .filter_map(|r| self.row_to_profile(r))
+            .collect();
+
+        self.with_samples(profiles).await
+    }
+
+    async fn find_by_embedding(
+        &self,
+        embedding: &FaceEmbedding,
+        threshold: f32,
+        excluded: &[Uuid],
+    ) -> RepoResult<Vec<(Profile, f32)>> {
+        // Note: FaceMatcher matches in memory and only falls back here when a
+        // profile may have been evicted from its cache, excluding the cached
+        // ones. Without pgvector the remaining profiles and their samples are
+        // still loaded and compared here, a page at a time to bound memory.
+        let mut matches: Vec<(Profile, f32)> = Vec::new();
+        let mut after = Uuid::nil();
+
+        loop {
+            let rows: Vec<ProfileRow> = sqlx::query_as(
+                r#"
+                SELECT 
+                    id, name, classification, embedding, has_embedding, thumbnail_path,
+                    thumbnail_quality, tags, notes, first_seen_at, last_seen_at, sighting_count,
+                    is_active, created_at, updated_at
+                FROM profiles
+                WHERE is_active = TRUE AND has_embedding = TRUE
+                    AND id > $1 AND NOT (id = ANY($2))
+                ORDER BY id
+                LIMIT $3
+                "#,
+            )
+            .bind(after)
+            .bind(excluded)
+            .bind(EMBEDDING_SEARCH_PAGE_SIZE)
+            .fetch_all(&self.pool)
+            .await?;
+
+            let Some(last) = rows.last() else {
+                break;
+            };
+            after = last.id;
+            let full_page = rows.len() as i64 == EMBEDDING_SEARCH_PAGE_SIZE;
+
+            let page: Vec<Profile> = rows
+                .into_iter()
+                .filter_map(|r| self.row_to_profile(r))
+                .collect();
+            matches.extend(
+                self.with_samples(page)
+                    .await?
+                    .into_iter()
+                    .map(|p| {
+                        let distance = p.distance_to(embedding);
+                        (p, distance)
+                    })
+                    .filter(|(_, distance)| *distance < threshold),
+            );
+
+            if !full_page {
+                break;
+            }
+        }
+
+        matches.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
+        Ok(matches)
+    }
+
+    async fn save(&self, profile: &Profile) -> RepoResult<()> {
+        insert_profile(&self.pool, profile).await?;
+        replace_samples(&self.pool, profile).await
     }
 
     async fn update(&self, profile: &Profile) -> RepoResult<()> {
-        let tags_json =
-            serde_json::to_value(profile.tags().iter().map(|t| t.value()).collect::<Vec<_>>())
-                .unwrap_or_default();
+        update_profile(&self.pool, profile).await
+    }
 
-        let result = sqlx::query(
+    async fn save_embedding_samples(&self, profile: &Profile) -> RepoResult<()> {
+        replace_samples(&self.pool, profile).await
+    }
+
+    async fn import(&self, created: &[Profile], updated: &[Profile]) -> RepoResult<()> {
+        let mut tx = self.pool.begin().await?;
+        for profile in created {
+            insert_profile(&mut *tx, profile).await?;
+            replace_samples(&mut *tx, profile).await?;
+        }
+        for profile in updated {
+            update_profile(&mut *tx, profile).await?;
+            replace_samples(&mut *tx, profile).await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn delete(&self, id: Uuid) -> RepoResult<()> {
+        let result = sqlx::query(r#"UPDATE profiles SET is_active = FALSE WHERE id = $1"#)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(RepositoryError::NotFound(format!("Profile {}", id)));
+        }
+
+        Ok(())
+    }
+
+    async fn update_classification(
+        &self,
+        ids: &[Uuid],
+        classification: ProfileClassification,
+    ) -> RepoResult<Vec<Uuid>> {
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let rows: Vec<(Uuid,)> = sqlx::query_as(
             r#"
             UPDATE profiles SET
-                name = $2,
-                classification = $3,
-                embedding = $4,
-                thumbnail_path = $5,
-                tags = $6,
-                notes = $7,
-                last_seen_at = $8,
-                sighting_count = $9,
-                is_active = $10,
-                updated_at = $11
-            WHERE id = $1
+                classification = $2,
+                updated_at = NOW()
+            WHERE id = ANY($1)
+            RETURNING id
             "#,
         )
-        .bind(profile.id())
-        .bind(profile.name())
-        .bind(profile.classification())
-        .bind(profile.embedding().to_bytes())
-        .bind(profile.thumbnail_path())
-        .bind(tags_json)
-        .bind(profile.notes())
-        .bind(profile.last_seen_at())
-        .bind(profile.sighting_count())
-        .bind(profile.is_active())
-        .bind(profile.updated_at())
-        .execute(&self.pool)
+        .bind(ids)
+        .bind(classification)
+        .fetch_all(&self.pool)
         .await?;
 
-        if result.rows_affected() == 0 {
-            return Err(RepositoryError::NotFound(format!(
-                "Profile {}",
-                profile.id()
-            )));
-        }
+        Ok(rows.into_iter().map(|(id,)| id).collect())
+    }
 
-        Ok(())
+    async fn deactivate_stale_unknown(
+        &self,
+        max_sightings: i64,
+        seen_before: DateTime<Utc>,
+    ) -> RepoResult<Vec<Uuid>> {
+        let rows: Vec<(Uuid,)> = sqlx::query_as(
+            r#"
+            UPDATE profiles SET
+                is_active = FALSE,
+                updated_at = NOW()
+            WHERE is_active = TRUE
+              AND classification = 'unknown'
+              AND sighting_count <= $1
+              AND last_seen_at < $2
+            RETURNING id
+            "#,
+        )
+        .bind(max_sightings)
+        .bind(seen_before)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|(id,)| id).collect())
     }
 
-    async fn delete(&self, id: Uuid) -> RepoResult<()> {
-        let result = sqlx::query(r#"UPDATE profiles SET is_active = FALSE WHERE id = $1"#)
+    async fn hard_delete(&self, id: Uuid) -> RepoResult<()> {
+        let result = sqlx::query(r#"DELETE FROM profiles WHERE id = $1"#)
             .bind(id)
             .execute(&self.pool)
             .await?;
@@ -203,4 +365,128 @@ impl ProfileRepository for PgProfileRepository {
 
         Ok(result.0)
     }
+
+    async fn count_by_classification(&self) -> RepoResult<Vec<(ProfileClassification, i64)>> {
+        let rows: Vec<(ProfileClassification, i64)> = sqlx::query_as(
+            r#"
+            SELECT classification, COUNT(*)
+            FROM profiles
+            WHERE is_active = TRUE
+            GROUP BY classification
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows)
+    }
+}
+
+/// Inserts a profile row.
+async fn insert_profile(conn: impl PgExecutor<'_>, profile: &Profile) -> RepoResult<()> {
+    let tags_json =
+        serde_json::to_value(profile.tags().iter().map(|t| t.value()).collect::<Vec<_>>())
+            .unwrap_or_default();
+
+    sqlx::query(
+        r#"
+        INSERT INTO profiles (
+            id, name, classification, embedding, thumbnail_path,
+            tags, notes, first_seen_at, last_seen_at, sighting_count,
+            is_active, created_at, updated_at, thumbnail_quality, has_embedding
+        ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15)
+        "#,
+    )
+    .bind(profile.id())
+    .bind(profile.name())
+    .bind(profile.classification())
+    .bind(profile.embedding().to_bytes())
+    .bind(profile.thumbnail_path())
+    .bind(tags_json)
+    .bind(profile.notes())
+    .bind(profile.first_seen_at())
+    .bind(profile.last_seen_at())
+    .bind(profile.sighting_count())
+    .bind(profile.is_active())
+    .bind(profile.created_at())
+    .bind(profile.updated_at())
+    .bind(profile.thumbnail_quality())
+    .bind(profile.has_embedding())
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Updates a profile row, failing if it does not exist.
+async fn update_profile(conn: impl PgExecutor<'_>, profile: &Profile) -> RepoResult<()> {
+    let tags_json =
+        serde_json::to_value(profile.tags().iter().map(|t| t.value()).collect::<Vec<_>>())
+            .unwrap_or_default();
+
+    let result = sqlx::query(
+        r#"
+        UPDATE profiles SET
+            name = $2,
+            classification = $3,
+            embedding = $4,
+            thumbnail_path = $5,
+            tags = $6,
+            notes = $7,
+            last_seen_at = $8,
+            sighting_count = $9,
+            is_active = $10,
+            updated_at = $11,
+            thumbnail_quality = $12,
+            has_embedding = $13
+        WHERE id = $1
+        "#,
+    )
+    .bind(profile.id())
+    .bind(profile.name())
+    .bind(profile.classification())
+    .bind(profile.embedding().to_bytes())
+    .bind(profile.thumbnail_path())
+    .bind(tags_json)
+    .bind(profile.notes())
+    .bind(profile.last_seen_at())
+    .bind(profile.sighting_count())
+    .bind(profile.is_active())
+    .bind(profile.updated_at())
+    .bind(profile.thumbnail_quality())
+    .bind(profile.has_embedding())
+    .execute(conn)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(RepositoryError::NotFound(format!(
+            "Profile {}",
+            profile.id()
+        )));
+    }
+
+    Ok(())
+}
+
+/// Replaces the embedding samples of a profile.
+async fn replace_samples(conn: impl PgExecutor<'_>, profile: &Profile) -> RepoResult<()> {
+    let samples: Vec<Vec<u8>> = profile.embeddings().iter().map(|e| e.to_bytes()).collect();
+
+    // One statement, so readers never see the samples half replaced
+    sqlx::query(
+        r#"
+        WITH cleared AS (
+            DELETE FROM profile_embeddings WHERE profile_id = $1
+        )
+        INSERT INTO profile_embeddings (profile_id, position, embedding)
+        SELECT $1, (s.ord - 1)::INT, s.embedding
+        FROM UNNEST($2::BYTEA[]) WITH ORDINALITY AS s(embedding, ord)
+        "#,
+    )
+    .bind(profile.id())
+    .bind(samples)
+    .execute(conn)
+    .await?;
+
+    Ok(())
 }
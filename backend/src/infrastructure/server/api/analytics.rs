@@ -1,19 +1,21 @@
 //! Analytics API Endpoints
 
-use axum::{
-    extract::{Query, State},
-    http::StatusCode,
-    Json,
-};
-use chrono::{DateTime, Utc};
+use axum::{extract::State, http::StatusCode, Json};
+use chrono::{DateTime, Datelike, Utc};
 use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use uuid::Uuid;
 
-use crate::domain::entities::ProfileClassification;
+use crate::domain::entities::{Profile, ProfileClassification, Sighting};
+use crate::domain::repositories::{ProfileRepository, RepoResult};
+use crate::infrastructure::server::api::cameras::camera_names;
+use crate::infrastructure::server::api::extract::Query;
+use crate::infrastructure::server::api::profiles::thumbnail_url;
+use crate::infrastructure::server::signed_url::FileUrls;
 use crate::infrastructure::server::AppState;
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 pub struct DashboardStats {
     pub total_profiles: i64,
     pub known_profiles: i64,
@@ -37,6 +39,7 @@ pub struct HeatmapData {
 
 #[derive(Debug, Serialize)]
 pub struct HeatmapPoint {
+    /// Face position as fractions of the camera's frame size.
     pub x: f32,
     pub y: f32,
     pub intensity: f32,
@@ -69,6 +72,8 @@ pub struct TimelineQuery {
     pub limit: Option<i32>,
     pub camera_id: Option<Uuid>,
     pub profile_id: Option<Uuid>,
+    pub min_confidence: Option<f32>,
+    pub classification: Option<ProfileClassification>,
 }
 
 #[derive(Debug, Serialize)]
@@ -111,9 +116,19 @@ pub struct ActivityChartQuery {
 }
 
 /// GET /api/v1/analytics/dashboard
+///
+/// Served from a short-lived cache; see `AppState::dashboard_cache`.
 pub async fn get_dashboard_stats(
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<DashboardStats>, StatusCode> {
+    state
+        .dashboard_cache
+        .get_or_try_insert_with(|| compute_dashboard_stats(&state))
+        .await
+        .map(Json)
+}
+
+async fn compute_dashboard_stats(state: &AppState) -> Result<DashboardStats, StatusCode> {
     let profiles = state
         .profile_repo
         .find_all_active()
@@ -166,12 +181,11 @@ pub async fn get_dashboard_stats(
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
-    let storage_path = &state.config.data_dir;
-    let storage_used = calculate_directory_size(storage_path).unwrap_or(0);
+    let storage_used = directory_size(state.config.data_dir.clone()).await;
     let storage_total = settings.recording.max_storage_bytes;
     let storage_percent = (storage_used as f32 / storage_total as f32) * 100.0;
 
-    Ok(Json(DashboardStats {
+    Ok(DashboardStats {
         total_profiles: profiles.len() as i64,
         known_profiles: known_count,
         unknown_profiles: unknown_count,
@@ -184,7 +198,7 @@ pub async fn get_dashboard_stats(
         storage_used_human: format_bytes(storage_used),
         storage_total_bytes: storage_total,
         storage_percent_used: storage_percent,
-    }))
+    })
 }
 
 /// GET /api/v1/analytics/heatmap
@@ -203,11 +217,24 @@ pub async fn get_heatmap_data(
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
+    // Sightings do not record the frame size, so use the camera's resolution
+    let resolutions: std::collections::HashMap<Uuid, (u32, u32)> = cameras
+        .iter()
+        .map(|cam| {
+            let (width, height) = cam.resolution();
+            (cam.id(), (width.max(0) as u32, height.max(0) as u32))
+        })
+        .collect();
+
     let mut points = Vec::new();
     for sighting in &sightings {
+        let Some(&(width, height)) = resolutions.get(&sighting.camera_id()) else {
+            continue;
+        };
+        let (x, y, _, _) = sighting.bounding_box().normalized(width, height);
         points.push(HeatmapPoint {
-            x: sighting.bounding_box().x() as f32,
-            y: sighting.bounding_box().y() as f32,
+            x,
+            y,
             intensity: sighting.confidence(),
             camera_id: sighting.camera_id(),
         });
@@ -241,44 +268,65 @@ pub async fn get_timeline(
     let end = query.end.unwrap_or_else(Utc::now);
     let limit = query.limit.unwrap_or(50);
 
+    // Camera/profile filters run in SQL so the limit applies to matching rows
     let sightings = state
         .sighting_repo
-        .find_in_range(start, end, limit as i64)
+        .find_filtered(start, end, query.camera_id, query.profile_id, limit as i64)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
-    let cameras = state
-        .camera_repo
-        .find_all()
+    let camera_map = camera_names(state.camera_repo.as_ref())
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
-    let camera_map: std::collections::HashMap<Uuid, String> = cameras
+    let entries = build_timeline(
+        state.profile_repo.as_ref(),
+        sightings,
+        &camera_map,
+        &query,
+        &state.file_urls,
+    )
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(entries))
+}
+
+/// Applies the timeline filters and resolves profiles with a single batch lookup.
+async fn build_timeline(
+    profile_repo: &dyn ProfileRepository,
+    sightings: Vec<Sighting>,
+    camera_map: &std::collections::HashMap<Uuid, String>,
+    query: &TimelineQuery,
+    urls: &FileUrls,
+) -> RepoResult<Vec<TimelineEntry>> {
+    let sightings: Vec<Sighting> = sightings
         .into_iter()
-        .map(|c| (c.id(), c.name().to_string()))
+        .filter(|s| query.min_confidence.is_none_or(|min| s.confidence() >= min))
+        .collect();
+
+    let mut profile_ids: Vec<Uuid> = sightings.iter().map(|s| s.profile_id()).collect();
+    profile_ids.sort();
+    profile_ids.dedup();
+
+    let profiles: std::collections::HashMap<Uuid, _> = profile_repo
+        .find_by_ids(&profile_ids)
+        .await?
+        .into_iter()
+        .map(|p| (p.id(), p))
         .collect();
 
     let mut entries: Vec<TimelineEntry> = Vec::new();
 
-    for sighting in sightings.into_iter().take(limit as usize) {
-        if let Some(camera_id) = query.camera_id {
-            if sighting.camera_id() != camera_id {
-                continue;
-            }
-        }
-        if let Some(profile_id) = query.profile_id {
-            if sighting.profile_id() != profile_id {
+    for sighting in sightings {
+        let profile = profiles.get(&sighting.profile_id());
+
+        if let Some(classification) = query.classification {
+            if profile.map(|p| p.classification()) != Some(classification) {
                 continue;
             }
         }
 
-        let profile = state
-            .profile_repo
-            .find_by_id(sighting.profile_id())
-            .await
-            .ok()
-            .flatten();
-
         entries.push(TimelineEntry {
             timestamp: sighting.detected_at(),
             event_type: "sighting".to_string(),
@@ -289,11 +337,159 @@ pub async fn get_timeline(
                 .get(&sighting.camera_id())
                 .cloned()
                 .unwrap_or_default(),
-            thumbnail_url: Some(sighting.snapshot_path().to_string()),
+            thumbnail_url: Some(thumbnail_url(sighting.snapshot_path(), urls)),
         });
     }
 
-    Ok(Json(entries))
+    Ok(entries)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TopProfilesQuery {
+    /// Number of profiles to return (default: 10, max: 100).
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TopProfile {
+    pub id: Uuid,
+    pub name: Option<String>,
+    pub classification: ProfileClassification,
+    pub thumbnail_url: Option<String>,
+    pub sighting_count: i64,
+}
+
+/// GET /api/v1/analytics/top-profiles
+pub async fn get_top_profiles(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<TopProfilesQuery>,
+) -> Result<Json<Vec<TopProfile>>, StatusCode> {
+    let limit = query.limit.unwrap_or(10).min(100);
+
+    let ranked = state
+        .query_analytics
+        .get_most_seen_profiles(limit)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let profiles = build_top_profiles(state.profile_repo.as_ref(), &ranked, &state.file_urls)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(profiles))
+}
+
+/// Resolves ranked profile IDs with a single batch lookup, keeping the ranking order.
+async fn build_top_profiles(
+    profile_repo: &dyn ProfileRepository,
+    ranked: &[(Uuid, i64)],
+    urls: &FileUrls,
+) -> RepoResult<Vec<TopProfile>> {
+    let ids: Vec<Uuid> = ranked.iter().map(|(id, _)| *id).collect();
+    let mut profiles: std::collections::HashMap<Uuid, _> = profile_repo
+        .find_by_ids(&ids)
+        .await?
+        .into_iter()
+        .map(|p| (p.id(), p))
+        .collect();
+
+    Ok(ranked
+        .iter()
+        .filter_map(|(id, count)| {
+            let profile = profiles.remove(id)?;
+            Some(TopProfile {
+                id: *id,
+                name: profile.name().map(String::from),
+                classification: profile.classification(),
+                thumbnail_url: profile
+                    .thumbnail_path()
+                    .map(|path| thumbnail_url(path, urls)),
+                sighting_count: *count,
+            })
+        })
+        .collect())
+}
+
+/// Default window for camera activity, in hours.
+const DEFAULT_ACTIVITY_WINDOW_HOURS: i64 = 24;
+
+/// Longest window for camera activity, in hours.
+const MAX_ACTIVITY_WINDOW_HOURS: i64 = 24 * 90;
+
+#[derive(Debug, Deserialize)]
+pub struct CameraActivityQuery {
+    pub hours: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CameraActivity {
+    pub camera_id: Uuid,
+    pub camera_name: String,
+    pub sighting_count: i64,
+    pub last_activity_at: Option<DateTime<Utc>>,
+}
+
+/// GET /api/v1/analytics/camera-activity
+pub async fn get_camera_activity(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<CameraActivityQuery>,
+) -> Result<Json<Vec<CameraActivity>>, StatusCode> {
+    let hours = query
+        .hours
+        .unwrap_or(DEFAULT_ACTIVITY_WINDOW_HOURS)
+        .clamp(1, MAX_ACTIVITY_WINDOW_HOURS);
+    let end = Utc::now();
+    let start = end - chrono::Duration::hours(hours);
+
+    let counts = state
+        .sighting_repo
+        .count_by_camera_in_range(start, end)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let camera_map = camera_names(state.camera_repo.as_ref())
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(build_camera_activity(counts, camera_map)))
+}
+
+/// Lists every camera with its sighting count, busiest first. Counts for
+/// cameras that no longer exist are dropped.
+fn build_camera_activity(
+    counts: Vec<(Uuid, i64, DateTime<Utc>)>,
+    mut camera_map: std::collections::HashMap<Uuid, String>,
+) -> Vec<CameraActivity> {
+    let mut activity: Vec<CameraActivity> = counts
+        .into_iter()
+        .filter_map(|(camera_id, count, last)| {
+            Some(CameraActivity {
+                camera_id,
+                camera_name: camera_map.remove(&camera_id)?,
+                sighting_count: count,
+                last_activity_at: Some(last),
+            })
+        })
+        .collect();
+
+    // Idle cameras are listed too, so the dashboard can show them as quiet
+    activity.extend(
+        camera_map
+            .into_iter()
+            .map(|(camera_id, camera_name)| CameraActivity {
+                camera_id,
+                camera_name,
+                sighting_count: 0,
+                last_activity_at: None,
+            }),
+    );
+
+    activity.sort_by(|a, b| {
+        b.sighting_count
+            .cmp(&a.sighting_count)
+            .then_with(|| a.camera_name.cmp(&b.camera_name))
+    });
+    activity
 }
 
 /// GET /api/v1/analytics/storage
@@ -306,12 +502,14 @@ pub async fn get_storage_stats(
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
-    let storage_path = &state.config.data_dir;
-    let recordings_path = storage_path.join("recordings");
-    let snapshots_path = storage_path.join("snapshots");
+    let recordings_path = state.storage_manager.recordings_dir().await;
+    let snapshots_path = state.storage_manager.snapshots_dir().await;
 
-    let recordings_bytes = calculate_directory_size(&recordings_path).unwrap_or(0);
-    let snapshots_bytes = calculate_directory_size(&snapshots_path).unwrap_or(0);
+    let (recordings_bytes, snapshots_bytes, snapshots_count) = tokio::join!(
+        directory_size(recordings_path),
+        directory_size(snapshots_path.clone()),
+        file_count(snapshots_path),
+    );
     let total_used = recordings_bytes + snapshots_bytes;
 
     let recordings = state
@@ -343,8 +541,6 @@ pub async fn get_storage_stats(
         });
     }
 
-    let snapshots_count = count_files_in_directory(&snapshots_path).unwrap_or(0);
-
     Ok(Json(StorageStats {
         total_bytes: settings.recording.max_storage_bytes,
         used_bytes: total_used,
@@ -365,32 +561,25 @@ pub async fn get_activity_chart(
     let period = query.period.as_deref().unwrap_or("week");
     let group_by = query.group_by.as_deref().unwrap_or("day");
 
-    let (start, labels) = match (period, group_by) {
+    let (start, labels, bucketing) = match (period, group_by) {
         ("day", "hour") => {
             let start = Utc::now() - chrono::Duration::hours(24);
             let labels: Vec<String> = (0..24).map(|h| format!("{:02}:00", h)).collect();
-            (start, labels)
-        }
-        ("week", "day") => {
-            let start = Utc::now() - chrono::Duration::days(7);
-            let labels: Vec<String> = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"]
-                .iter()
-                .map(|s| s.to_string())
-                .collect();
-            (start, labels)
+            (start, labels, Bucketing::Hour)
         }
         ("month", "day") => {
             let start = Utc::now() - chrono::Duration::days(30);
             let labels: Vec<String> = (1..=30).map(|d| format!("Day {}", d)).collect();
-            (start, labels)
+            (start, labels, Bucketing::Day)
         }
+        // ("week", "day") and anything unrecognised
         _ => {
             let start = Utc::now() - chrono::Duration::days(7);
             let labels: Vec<String> = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"]
                 .iter()
                 .map(|s| s.to_string())
                 .collect();
-            (start, labels)
+            (start, labels, Bucketing::Weekday)
         }
     };
 
@@ -406,60 +595,112 @@ pub async fn get_activity_chart(
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
-    let mut known_data = vec![0i64; labels.len()];
-    let mut unknown_data = vec![0i64; labels.len()];
-    let mut flagged_data = vec![0i64; labels.len()];
+    let datasets = build_activity_datasets(&sightings, &profiles, &start, bucketing, labels.len());
 
-    for sighting in &sightings {
-        let profile = profiles.iter().find(|p| p.id() == sighting.profile_id());
-        let bucket = calculate_bucket(&sighting.detected_at(), &start, group_by, labels.len());
+    Ok(Json(ActivityChart { labels, datasets }))
+}
+
+/// Counts sightings per bucket, with one dataset per profile classification.
+/// Sightings of profiles that are no longer active count as unknown.
+fn build_activity_datasets(
+    sightings: &[Sighting],
+    profiles: &[Profile],
+    start: &DateTime<Utc>,
+    bucketing: Bucketing,
+    num_buckets: usize,
+) -> Vec<ActivityDataset> {
+    let classifications: std::collections::HashMap<Uuid, ProfileClassification> = profiles
+        .iter()
+        .map(|p| (p.id(), p.classification()))
+        .collect();
+
+    let mut trusted_data = vec![0i64; num_buckets];
+    let mut known_data = vec![0i64; num_buckets];
+    let mut unknown_data = vec![0i64; num_buckets];
+    let mut flagged_data = vec![0i64; num_buckets];
+
+    for sighting in sightings {
+        let bucket = calculate_bucket(&sighting.detected_at(), start, bucketing, num_buckets);
 
-        if bucket < labels.len() {
-            match profile.map(|p| p.classification()) {
+        if bucket < num_buckets {
+            match classifications.get(&sighting.profile_id()) {
+                Some(ProfileClassification::Trusted) => trusted_data[bucket] += 1,
                 Some(ProfileClassification::Known) => known_data[bucket] += 1,
                 Some(ProfileClassification::Flagged) => flagged_data[bucket] += 1,
-                _ => unknown_data[bucket] += 1,
+                Some(ProfileClassification::Unknown) | None => unknown_data[bucket] += 1,
             }
         }
     }
 
-    Ok(Json(ActivityChart {
-        labels,
-        datasets: vec![
-            ActivityDataset {
-                label: "Known".to_string(),
-                data: known_data,
-                color: "#10b981".to_string(),
-            },
-            ActivityDataset {
-                label: "Unknown".to_string(),
-                data: unknown_data,
-                color: "#6b7280".to_string(),
-            },
-            ActivityDataset {
-                label: "Flagged".to_string(),
-                data: flagged_data,
-                color: "#ef4444".to_string(),
-            },
-        ],
-    }))
+    vec![
+        ActivityDataset {
+            label: "Trusted".to_string(),
+            data: trusted_data,
+            color: "#3b82f6".to_string(),
+        },
+        ActivityDataset {
+            label: "Known".to_string(),
+            data: known_data,
+            color: "#10b981".to_string(),
+        },
+        ActivityDataset {
+            label: "Unknown".to_string(),
+            data: unknown_data,
+            color: "#6b7280".to_string(),
+        },
+        ActivityDataset {
+            label: "Flagged".to_string(),
+            data: flagged_data,
+            color: "#ef4444".to_string(),
+        },
+    ]
+}
+
+/// How sightings are grouped into activity chart buckets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Bucketing {
+    /// Hours elapsed since the start of the period.
+    Hour,
+    /// Days elapsed since the start of the period.
+    Day,
+    /// Day of the week (UTC), Monday first, matching the "Mon".."Sun" labels.
+    Weekday,
 }
 
 fn calculate_bucket(
     timestamp: &DateTime<Utc>,
     start: &DateTime<Utc>,
-    group_by: &str,
+    bucketing: Bucketing,
     num_buckets: usize,
 ) -> usize {
     let duration = *timestamp - *start;
 
-    match group_by {
-        "hour" => duration.num_hours() as usize % num_buckets,
-        "day" => duration.num_days() as usize % num_buckets,
-        _ => 0,
+    match bucketing {
+        Bucketing::Hour => duration.num_hours() as usize % num_buckets,
+        Bucketing::Day => duration.num_days() as usize % num_buckets,
+        Bucketing::Weekday => timestamp.weekday().num_days_from_monday() as usize % num_buckets,
     }
 }
 
+/// Total size of the files under `path`, or 0 if it can't be read. The walk
+/// runs on the blocking pool so a large data directory doesn't stall the
+/// runtime.
+async fn directory_size(path: PathBuf) -> i64 {
+    walk_off_runtime(path, calculate_directory_size).await
+}
+
+/// Number of files under `path`, or 0 if it can't be read. Walked on the
+/// blocking pool like [`directory_size`].
+async fn file_count(path: PathBuf) -> i64 {
+    walk_off_runtime(path, count_files_in_directory).await
+}
+
+async fn walk_off_runtime(path: PathBuf, walk: fn(&Path) -> std::io::Result<i64>) -> i64 {
+    tokio::task::spawn_blocking(move || walk(&path).unwrap_or(0))
+        .await
+        .unwrap_or(0)
+}
+
 fn calculate_directory_size(path: &std::path::Path) -> std::io::Result<i64> {
     let mut total = 0i64;
 
@@ -469,9 +710,12 @@ fn calculate_directory_size(path: &std::path::Path) -> std::io::Result<i64> {
 
     for entry in std::fs::read_dir(path)? {
         let entry = entry?;
+        // Symlinks aren't followed, so a link back up the tree can't loop
         let metadata = entry.metadata()?;
 
-        if metadata.is_dir() {
+        if metadata.is_symlink() {
+            continue;
+        } else if metadata.is_dir() {
             total += calculate_directory_size(&entry.path())?;
         } else {
             total += metadata.len() as i64;
@@ -492,7 +736,9 @@ fn count_files_in_directory(path: &std::path::Path) -> std::io::Result<i64> {
         let entry = entry?;
         let metadata = entry.metadata()?;
 
-        if metadata.is_dir() {
+        if metadata.is_symlink() {
+            continue;
+        } else if metadata.is_dir() {
             count += count_files_in_directory(&entry.path())?;
         } else {
             count += 1;
@@ -517,3 +763,270 @@ fn format_bytes(bytes: i64) -> String {
         format!("{} B", bytes)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::repositories::fakes::InMemoryProfileRepo;
+    use crate::domain::value_objects::{BoundingBox, FaceEmbedding, EMBEDDING_DIMENSION};
+    use std::collections::HashMap;
+    use std::sync::atomic::Ordering;
+
+    fn profile_with(classification: ProfileClassification) -> Profile {
+        let mut profile = Profile::new(FaceEmbedding::new(vec![0.1; EMBEDDING_DIMENSION]), None);
+        profile.set_classification(classification);
+        profile
+    }
+
+    fn sighting(profile_id: Uuid, camera_id: Uuid, confidence: f32) -> Sighting {
+        Sighting::new(
+            profile_id,
+            camera_id,
+            "snap.jpg".to_string(),
+            BoundingBox::new(0, 0, 10, 10),
+            confidence,
+            None,
+        )
+    }
+
+    fn timeline_query() -> TimelineQuery {
+        TimelineQuery {
+            start: None,
+            end: None,
+            limit: None,
+            camera_id: None,
+            profile_id: None,
+            min_confidence: None,
+            classification: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn timeline_applies_confidence_and_classification_filters() {
+        let known = profile_with(ProfileClassification::Known);
+        let unknown = profile_with(ProfileClassification::Unknown);
+        let camera_id = Uuid::new_v4();
+
+        let repo = InMemoryProfileRepo::with([known.clone(), unknown.clone()]);
+
+        let sightings = vec![
+            sighting(known.id(), camera_id, 0.95),
+            sighting(known.id(), camera_id, 0.5),
+            sighting(unknown.id(), camera_id, 0.99),
+            sighting(known.id(), camera_id, 0.85),
+        ];
+
+        let query = TimelineQuery {
+            min_confidence: Some(0.8),
+            classification: Some(ProfileClassification::Known),
+            ..timeline_query()
+        };
+
+        let entries = build_timeline(
+            &repo,
+            sightings,
+            &HashMap::new(),
+            &query,
+            &FileUrls::default(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(entries.len(), 2);
+        assert!(entries.iter().all(|e| e.profile_id == Some(known.id())));
+    }
+
+    #[tokio::test]
+    async fn top_profiles_keep_ranking_order_with_one_lookup() {
+        let repo = InMemoryProfileRepo::default();
+        let mut ranked = Vec::new();
+        for count in [12, 7, 3] {
+            let profile = profile_with(ProfileClassification::Known);
+            ranked.push((profile.id(), count));
+            repo.save(&profile).await.unwrap();
+        }
+
+        let top = build_top_profiles(&repo, &ranked, &FileUrls::default())
+            .await
+            .unwrap();
+
+        let counts: Vec<i64> = top.iter().map(|p| p.sighting_count).collect();
+        assert_eq!(counts, vec![12, 7, 3]);
+        assert_eq!(top[0].id, ranked[0].0);
+        assert_eq!(repo.find_by_ids_calls.load(Ordering::SeqCst), 1);
+        assert_eq!(repo.find_by_id_calls.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn timeline_loads_profiles_in_one_batch() {
+        let camera_id = Uuid::new_v4();
+        let repo = InMemoryProfileRepo::default();
+        let mut sightings = Vec::new();
+
+        for _ in 0..10 {
+            let profile = profile_with(ProfileClassification::Unknown);
+            sightings.push(sighting(profile.id(), camera_id, 0.9));
+            sightings.push(sighting(profile.id(), camera_id, 0.9));
+            repo.save(&profile).await.unwrap();
+        }
+
+        let entries = build_timeline(
+            &repo,
+            sightings,
+            &HashMap::new(),
+            &timeline_query(),
+            &FileUrls::default(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(entries.len(), 20);
+        assert_eq!(repo.find_by_ids_calls.load(Ordering::SeqCst), 1);
+        assert_eq!(repo.find_by_id_calls.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn activity_chart_buckets_each_classification_separately() {
+        let camera_id = Uuid::new_v4();
+        let profiles: Vec<Profile> = [
+            ProfileClassification::Trusted,
+            ProfileClassification::Known,
+            ProfileClassification::Unknown,
+            ProfileClassification::Flagged,
+        ]
+        .into_iter()
+        .map(profile_with)
+        .collect();
+        let mut sightings: Vec<Sighting> = profiles
+            .iter()
+            .map(|p| sighting(p.id(), camera_id, 0.9))
+            .collect();
+        // A profile that is no longer active
+        sightings.push(sighting(Uuid::new_v4(), camera_id, 0.9));
+
+        let start = Utc::now() - chrono::Duration::hours(1);
+        let datasets = build_activity_datasets(&sightings, &profiles, &start, Bucketing::Day, 7);
+
+        let totals: Vec<(&str, i64)> = datasets
+            .iter()
+            .map(|d| (d.label.as_str(), d.data.iter().sum()))
+            .collect();
+        assert_eq!(
+            totals,
+            vec![("Trusted", 1), ("Known", 1), ("Unknown", 2), ("Flagged", 1)]
+        );
+    }
+
+    #[test]
+    fn week_view_buckets_sightings_by_weekday() {
+        // 2024-12-23 was a Monday
+        let monday = DateTime::parse_from_rfc3339("2024-12-23T10:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let labels = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+        // The bucket does not depend on when the week starts
+        for start_offset in 0..7 {
+            let start = monday - chrono::Duration::days(start_offset) - chrono::Duration::hours(3);
+            for (day, label) in labels.iter().enumerate() {
+                let timestamp = monday + chrono::Duration::days(day as i64);
+                let bucket = calculate_bucket(&timestamp, &start, Bucketing::Weekday, 7);
+                assert_eq!(labels[bucket], *label);
+            }
+        }
+
+        // Late Sunday lands in Sunday, not the next day's bucket
+        let sunday_night = DateTime::parse_from_rfc3339("2024-12-29T23:59:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        assert_eq!(
+            labels[calculate_bucket(&sunday_night, &monday, Bucketing::Weekday, 7)],
+            "Sun"
+        );
+    }
+
+    #[test]
+    fn camera_activity_lists_busiest_first_with_idle_cameras() {
+        let (busy, quiet, idle, deleted) = (
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+        );
+        let camera_map = HashMap::from([
+            (busy, "Front Door".to_string()),
+            (quiet, "Garage".to_string()),
+            (idle, "Backyard".to_string()),
+        ]);
+        let now = Utc::now();
+        let counts = vec![(quiet, 2, now), (busy, 9, now), (deleted, 4, now)];
+
+        let activity = build_camera_activity(counts, camera_map);
+
+        let rows: Vec<(&str, i64)> = activity
+            .iter()
+            .map(|a| (a.camera_name.as_str(), a.sighting_count))
+            .collect();
+        assert_eq!(
+            rows,
+            vec![("Front Door", 9), ("Garage", 2), ("Backyard", 0)]
+        );
+        assert_eq!(activity[0].last_activity_at, Some(now));
+        assert_eq!(activity[2].last_activity_at, None);
+    }
+
+    static WALK_THREAD: std::sync::Mutex<Option<std::thread::ThreadId>> =
+        std::sync::Mutex::new(None);
+
+    fn recording_walk(path: &Path) -> std::io::Result<i64> {
+        *WALK_THREAD.lock().unwrap() = Some(std::thread::current().id());
+        calculate_directory_size(path)
+    }
+
+    #[tokio::test]
+    async fn directory_walk_runs_on_a_blocking_thread() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("snapshots")).unwrap();
+        std::fs::write(dir.path().join("snapshots/a.jpg"), [0u8; 10]).unwrap();
+        std::fs::write(dir.path().join("b.mp4"), [0u8; 5]).unwrap();
+
+        let size = walk_off_runtime(dir.path().to_path_buf(), recording_walk).await;
+
+        assert_eq!(size, 15);
+        let walk_thread = WALK_THREAD.lock().unwrap().unwrap();
+        assert_ne!(walk_thread, std::thread::current().id());
+    }
+
+    fn slow_walk(_: &Path) -> std::io::Result<i64> {
+        std::thread::sleep(std::time::Duration::from_millis(200));
+        Ok(1)
+    }
+
+    #[tokio::test]
+    async fn runtime_keeps_serving_during_a_slow_walk() {
+        let walk = tokio::spawn(walk_off_runtime(PathBuf::from("."), slow_walk));
+
+        // On this single-threaded runtime, these only run if the walk isn't
+        // holding the executor
+        let mut ticks = 0;
+        while !walk.is_finished() {
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            ticks += 1;
+        }
+
+        assert_eq!(walk.await.unwrap(), 1);
+        assert!(ticks >= 5, "runtime stalled during walk ({ticks} ticks)");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn directory_walks_skip_symlink_loops() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("nested")).unwrap();
+        std::fs::write(dir.path().join("nested/a.jpg"), [0u8; 10]).unwrap();
+        std::os::unix::fs::symlink(dir.path(), dir.path().join("nested/loop")).unwrap();
+
+        assert_eq!(calculate_directory_size(dir.path()).unwrap(), 10);
+        assert_eq!(count_files_in_directory(dir.path()).unwrap(), 1);
+    }
+}
@@ -1,22 +1,34 @@
 //! Camera API Endpoints
 
 use axum::{
-    body::Body,
-    extract::{Path, State},
-    http::StatusCode,
+    body::{Body, Bytes},
+    extract::State,
+    http::{header, HeaderMap, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
 use uuid::Uuid;
 use futures_util::stream::StreamExt;
+use tokio::sync::broadcast;
+use tokio_stream::wrappers::errors::BroadcastStreamRecvError;
 use tokio_stream::wrappers::BroadcastStream;
 
+use crate::application::services::decode_jpeg_upright;
 use crate::application::use_cases::{CreateCameraRequest, UpdateCameraRequest};
 use crate::domain::entities::{Camera, CameraStatus, CameraType};
+use crate::domain::repositories::{CameraRepository, RepoResult};
 use crate::domain::value_objects::GeoLocation;
-use crate::infrastructure::camera::list_cameras as list_system_cameras;
+use crate::infrastructure::camera::{
+    draw_overlays, list_cameras as list_system_cameras, CameraService, CaptureState, CaptureStatus,
+    CapturedFrame, LatencyStats, OverlayOptions, PixelFormat,
+};
+use crate::infrastructure::server::api::extract::{Path, Query};
+use crate::infrastructure::server::api::pagination::{Page, PageParams};
+use crate::infrastructure::server::api::sightings::SightingResponse;
 use crate::infrastructure::server::AppState;
 
 #[derive(Debug, Serialize)]
@@ -108,6 +120,95 @@ pub struct ResolutionBody {
     pub height: i32,
 }
 
+#[derive(Debug, Serialize)]
+pub struct CameraHealthResponse {
+    pub camera_id: Uuid,
+    /// Frames per second measured over recent frames; 0 when not capturing.
+    pub fps: f64,
+    pub last_frame_at: Option<String>,
+    pub seconds_since_last_frame: Option<f64>,
+    /// Recent face detection times, shared by all cameras on the detector.
+    pub detection_latency: DetectionLatencyResponse,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DetectionLatencyResponse {
+    pub avg_ms: f64,
+    pub p95_ms: f64,
+    pub samples: usize,
+}
+
+impl From<LatencyStats> for DetectionLatencyResponse {
+    fn from(stats: LatencyStats) -> Self {
+        Self {
+            avg_ms: stats.avg_ms,
+            p95_ms: stats.p95_ms,
+            samples: stats.samples,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct CameraConfigResponse {
+    pub camera_id: Uuid,
+    /// Capture state, as in camera status events; `stopped` when not capturing.
+    pub state: String,
+    /// Settings the capture was started with; `None` when not capturing.
+    pub requested: Option<CaptureSettingsResponse>,
+    /// Frame size the camera actually delivers, which may differ from the
+    /// requested one.
+    pub actual_resolution: Option<FrameSizeResponse>,
+    /// Frames per second measured over recent frames; 0 when not capturing.
+    pub measured_fps: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CaptureSettingsResponse {
+    pub device_index: u32,
+    pub width: u32,
+    pub height: u32,
+    pub fps: u32,
+    pub pixel_format: PixelFormat,
+    pub frame_buffer: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FrameSizeResponse {
+    pub width: u32,
+    pub height: u32,
+}
+
+impl CameraConfigResponse {
+    fn new(camera_id: Uuid, status: Option<CaptureStatus>, measured_fps: f64) -> Self {
+        let Some(status) = status else {
+            return Self {
+                camera_id,
+                state: CaptureState::Stopped.as_str().to_string(),
+                requested: None,
+                actual_resolution: None,
+                measured_fps: 0.0,
+            };
+        };
+        let config = status.config;
+        Self {
+            camera_id,
+            state: status.state.as_str().to_string(),
+            requested: Some(CaptureSettingsResponse {
+                device_index: config.device_index,
+                width: config.width,
+                height: config.height,
+                fps: config.fps,
+                pixel_format: config.pixel_format,
+                frame_buffer: config.frame_buffer,
+            }),
+            actual_resolution: status
+                .resolution
+                .map(|(width, height)| FrameSizeResponse { width, height }),
+            measured_fps,
+        }
+    }
+}
+
 #[derive(Debug, Serialize)]
 pub struct AvailableCameraResponse {
     pub index: u32,
@@ -130,7 +231,22 @@ pub async fn list_cameras(
     Ok(Json(responses))
 }
 
+/// Resolves every camera's name in one lookup, for annotating sightings.
+pub(crate) async fn camera_names(
+    camera_repo: &dyn CameraRepository,
+) -> RepoResult<HashMap<Uuid, String>> {
+    Ok(camera_repo
+        .find_all()
+        .await?
+        .into_iter()
+        .map(|c| (c.id(), c.name().to_string()))
+        .collect())
+}
+
 /// POST /api/v1/cameras
+///
+/// Returns the existing camera with `200 OK` when one already uses the
+/// device ID.
 pub async fn create_camera(
     State(state): State<Arc<AppState>>,
     Json(body): Json<CreateCameraBody>,
@@ -145,13 +261,18 @@ pub async fn create_camera(
             .map(|l| GeoLocation::with_metadata(l.latitude, l.longitude, None, None, l.name)),
     };
 
-    let camera = state
+    let (camera, created) = state
         .manage_cameras
         .create_camera(request)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
-    Ok((StatusCode::CREATED, Json(camera.into())))
+    let status = if created {
+        StatusCode::CREATED
+    } else {
+        StatusCode::OK
+    };
+    Ok((status, Json(camera.into())))
 }
 
 /// GET /api/v1/cameras/:id
@@ -213,6 +334,108 @@ pub async fn delete_camera(
     }
 }
 
+/// GET /api/v1/cameras/:id/health
+pub async fn get_camera_health(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<CameraHealthResponse>, StatusCode> {
+    let camera = state
+        .manage_cameras
+        .get_camera(id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    // Live timings are fresher than the throttled `last_frame_at` column
+    let health = state.camera_service.camera_health(id).await;
+    let last_frame_at = health
+        .and_then(|h| chrono::DateTime::from_timestamp_millis(h.last_frame_ms))
+        .or(camera.last_frame_at());
+    let seconds_since_last_frame =
+        last_frame_at.map(|at| (chrono::Utc::now() - at).num_milliseconds().max(0) as f64 / 1000.0);
+
+    Ok(Json(CameraHealthResponse {
+        camera_id: id,
+        fps: health.map_or(0.0, |h| h.fps),
+        last_frame_at: last_frame_at.map(|t| t.to_rfc3339()),
+        seconds_since_last_frame,
+        detection_latency: state.face_detector.latency_stats().into(),
+    }))
+}
+
+/// GET /api/v1/cameras/:id/config
+pub async fn get_camera_config(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<CameraConfigResponse>, StatusCode> {
+    state
+        .manage_cameras
+        .get_camera(id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let status = state.camera_service.capture_status(id).await;
+    let fps = state
+        .camera_service
+        .camera_health(id)
+        .await
+        .map_or(0.0, |h| h.fps);
+    Ok(Json(CameraConfigResponse::new(id, status, fps)))
+}
+
+/// A sighting from a camera, with the name of the profile seen.
+#[derive(Debug, Serialize)]
+pub struct CameraSightingResponse {
+    #[serde(flatten)]
+    pub sighting: SightingResponse,
+    pub profile_name: Option<String>,
+}
+
+/// GET /api/v1/cameras/:id/sightings
+pub async fn get_camera_sightings(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<Uuid>,
+    page: PageParams,
+) -> Result<Json<Page<CameraSightingResponse>>, StatusCode> {
+    state
+        .manage_cameras
+        .get_camera(id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let sightings = state
+        .sighting_repo
+        .find_by_camera(id, page.limit(), page.offset())
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let total = state
+        .sighting_repo
+        .count_by_camera(id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let mut profile_ids: Vec<Uuid> = sightings.iter().map(|s| s.profile_id()).collect();
+    profile_ids.sort();
+    profile_ids.dedup();
+    let profile_names: HashMap<Uuid, String> = state
+        .profile_repo
+        .find_by_ids(&profile_ids)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .into_iter()
+        .filter_map(|p| Some((p.id(), p.name()?.to_string())))
+        .collect();
+
+    let page = Page::new(sightings, total, &page).map(|s| CameraSightingResponse {
+        profile_name: profile_names.get(&s.profile_id()).cloned(),
+        sighting: SightingResponse::new(s, &state.file_urls),
+    });
+
+    Ok(Json(page))
+}
+
 /// POST /api/v1/cameras/:id/stream/start
 pub async fn start_stream(
     State(state): State<Arc<AppState>>,
@@ -241,6 +464,65 @@ pub async fn stop_stream(
     Ok(StatusCode::OK)
 }
 
+/// POST /api/v1/cameras/:id/frames - Frame pushed by a browser camera
+///
+/// Bodies over `max_upload_bytes` are rejected by the router with 413.
+pub async fn push_camera_frame(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<Uuid>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<StatusCode, StatusCode> {
+    let camera = state
+        .manage_cameras
+        .get_camera(id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    if camera.camera_type() != CameraType::Browser {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok());
+    let content_type = content_type.map(str::to_string);
+    let frame =
+        tokio::task::spawn_blocking(move || decode_pushed_frame(content_type.as_deref(), &body))
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)??;
+    let (width, height) = frame.dimensions();
+
+    state
+        .camera_service
+        .push_frame(id, width, height, frame.into_raw())
+        .await;
+
+    Ok(StatusCode::ACCEPTED)
+}
+
+/// Validates and decodes a pushed JPEG or PNG frame to RGB, applying the
+/// JPEG's EXIF orientation.
+pub(super) fn decode_pushed_frame(
+    content_type: Option<&str>,
+    body: &[u8],
+) -> Result<image::RgbImage, StatusCode> {
+    let format = match content_type.map(|ct| ct.split(';').next().unwrap_or("").trim()) {
+        Some("image/jpeg") => image::ImageFormat::Jpeg,
+        Some("image/png") => image::ImageFormat::Png,
+        _ => return Err(StatusCode::UNSUPPORTED_MEDIA_TYPE),
+    };
+
+    let decoded = match format {
+        image::ImageFormat::Jpeg => decode_jpeg_upright(body),
+        _ => image::load_from_memory_with_format(body, format),
+    };
+    decoded
+        .map(|img| img.to_rgb8())
+        .map_err(|_| StatusCode::BAD_REQUEST)
+}
+
 /// GET /api/v1/cameras/available
 pub async fn list_available_cameras() -> Json<Vec<AvailableCameraResponse>> {
     let cameras = list_system_cameras();
@@ -257,13 +539,26 @@ pub async fn list_available_cameras() -> Json<Vec<AvailableCameraResponse>> {
     Json(responses)
 }
 
+#[derive(Debug, Deserialize)]
+pub struct MjpegQuery {
+    /// Highest frame rate to send; intermediate frames are dropped.
+    pub max_fps: Option<f64>,
+}
+
 /// GET /api/v1/cameras/:id/mjpeg - MJPEG video stream
 pub async fn mjpeg_stream(
     State(state): State<Arc<AppState>>,
     Path(id): Path<Uuid>,
+    Query(query): Query<MjpegQuery>,
 ) -> Result<Response, StatusCode> {
     tracing::info!("MJPEG stream requested for camera: {}", id);
 
+    let throttle = match query.max_fps {
+        Some(fps) if !(fps.is_finite() && fps > 0.0) => return Err(StatusCode::BAD_REQUEST),
+        Some(fps) => Some(FrameThrottle::new(fps)),
+        None => None,
+    };
+
     // Get the frame receiver from camera service
     let receiver = state
         .camera_service
@@ -276,22 +571,125 @@ pub async fn mjpeg_stream(
 
     tracing::info!("Successfully subscribed to camera {} frame stream", id);
 
+    // Display settings are read once, when the stream opens
+    let overlay = match state.settings_repo.get().await {
+        Ok(settings) => Some(OverlayOptions::from(&settings.display)),
+        Err(e) => {
+            tracing::warn!("Failed to load display settings for overlays: {}", e);
+            None
+        }
+    }
+    .filter(OverlayOptions::is_enabled)
+    .map(|options| StreamOverlay {
+        camera_service: state.camera_service.clone(),
+        camera_id: id,
+        options,
+    });
+
+    let camera_service = state.camera_service.clone();
+    let on_lag = move |skipped| camera_service.record_stream_lag(skipped);
+
+    Ok(mjpeg_response(receiver, overlay, throttle, on_lag))
+}
+
+/// Drops frames so a stream sends at most a target frame rate.
+///
+/// Frames are due on a fixed schedule rather than relative to the last frame
+/// sent, so capture jitter does not pull the output rate below the target.
+#[derive(Debug, Clone, Copy)]
+struct FrameThrottle {
+    interval_ms: f64,
+    next_due_ms: Option<f64>,
+}
+
+impl FrameThrottle {
+    fn new(max_fps: f64) -> Self {
+        Self {
+            interval_ms: 1000.0 / max_fps,
+            next_due_ms: None,
+        }
+    }
+
+    /// Returns whether the frame captured at `timestamp_ms` should be sent.
+    fn admit(&mut self, timestamp_ms: i64) -> bool {
+        let timestamp = timestamp_ms as f64;
+        // Tolerate frames arriving slightly early against the schedule
+        let slack = self.interval_ms / 10.0;
+        match self.next_due_ms {
+            Some(due) if timestamp + slack < due => false,
+            Some(due) if timestamp < due + self.interval_ms => {
+                self.next_due_ms = Some(due + self.interval_ms);
+                true
+            }
+            // First frame, or the stream fell a whole interval behind
+            _ => {
+                self.next_due_ms = Some(timestamp + self.interval_ms);
+                true
+            }
+        }
+    }
+}
+
+/// Draws a camera's latest detections onto its streamed frames.
+struct StreamOverlay {
+    camera_service: Arc<CameraService>,
+    camera_id: Uuid,
+    options: OverlayOptions,
+}
+
+/// Streams frames from `receiver` as a multipart MJPEG response.
+///
+/// With a `throttle`, frames above its rate are dropped before encoding.
+/// Frames skipped because the client fell behind are passed to `on_lag`.
+fn mjpeg_response(
+    receiver: broadcast::Receiver<CapturedFrame>,
+    overlay: Option<StreamOverlay>,
+    mut throttle: Option<FrameThrottle>,
+    on_lag: impl Fn(u64) + Send + 'static,
+) -> Response {
     // Create MJPEG boundary
     let boundary = "frame";
+    let overlay = overlay.map(Arc::new);
 
     // Convert frames to MJPEG stream
     let stream = BroadcastStream::new(receiver).filter_map(move |result| {
+        let overlay = overlay.clone();
+        let result = match result {
+            Ok(frame) => throttle
+                .as_mut()
+                .is_none_or(|t| t.admit(frame.timestamp_ms))
+                .then_some(frame),
+            Err(BroadcastStreamRecvError::Lagged(skipped)) => {
+                tracing::debug!("MJPEG client fell behind, skipped {} frames", skipped);
+                on_lag(skipped);
+                None
+            }
+        };
         async move {
             match result {
-                Ok(frame) => {
+                Some(frame) => {
                     tracing::debug!(
                         "Received frame: {}x{}, {} bytes",
                         frame.width,
                         frame.height,
                         frame.data.len()
                     );
-                    // Encode frame as JPEG
-                    match encode_jpeg(&frame.data, frame.width, frame.height) {
+                    let boxes = match overlay.as_deref() {
+                        Some(o) => o.camera_service.latest_detections(o.camera_id).await,
+                        None => Vec::new(),
+                    };
+                    // Encode frame as JPEG, with detection boxes when there are any
+                    let encoded = match overlay.as_deref() {
+                        Some(o) if !boxes.is_empty() => {
+                            decode_frame(&frame.data, frame.width, frame.height).and_then(|img| {
+                                let mut rgb = img.into_rgb8();
+                                draw_overlays(&mut rgb, &boxes, o.options);
+                                encode_image(&image::DynamicImage::ImageRgb8(rgb))
+                            })
+                        }
+                        _ => encode_jpeg(&frame.data, frame.width, frame.height),
+                    };
+                    match encoded {
                         Ok(jpeg_data) => {
                             tracing::debug!("Encoded JPEG: {} bytes", jpeg_data.len());
                             let header = format!(
@@ -310,17 +708,14 @@ pub async fn mjpeg_stream(
                         }
                     }
                 }
-                Err(e) => {
-                    tracing::warn!("Frame receive error: {:?}", e);
-                    None
-                }
+                None => None,
             }
         }
     });
 
     let body = Body::from_stream(stream);
 
-    Ok(Response::builder()
+    Response::builder()
         .status(StatusCode::OK)
         .header(
             "Content-Type",
@@ -329,17 +724,95 @@ pub async fn mjpeg_stream(
         .header("Cache-Control", "no-cache")
         .header("Connection", "keep-alive")
         .body(body)
+        .unwrap()
+}
+
+/// How long a snapshot request waits for the camera's next frame.
+const SNAPSHOT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// GET /api/v1/cameras/:id/snapshot - Current frame as a single JPEG
+pub async fn camera_snapshot(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<Uuid>,
+) -> Result<Response, StatusCode> {
+    let receiver = state
+        .camera_service
+        .subscribe_frames(id)
+        .await
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let jpeg_data = next_jpeg_frame(receiver, SNAPSHOT_TIMEOUT).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "image/jpeg")
+        .header("Cache-Control", "private, max-age=1")
+        .body(Body::from(jpeg_data))
         .unwrap())
 }
 
+/// Waits for the next frame and encodes it as JPEG.
+/// Returns 503 when the camera produces nothing within `timeout`.
+async fn next_jpeg_frame(
+    mut receiver: broadcast::Receiver<CapturedFrame>,
+    timeout: Duration,
+) -> Result<Vec<u8>, StatusCode> {
+    let frame = tokio::time::timeout(timeout, async {
+        loop {
+            match receiver.recv().await {
+                Ok(frame) => return Some(frame),
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    })
+    .await
+    .ok()
+    .flatten()
+    .ok_or(StatusCode::SERVICE_UNAVAILABLE)?;
+
+    encode_jpeg(&frame.data, frame.width, frame.height).map_err(|e| {
+        tracing::error!("Snapshot encoding failed: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })
+}
+
 /// Encode frame data to JPEG
-/// nokhwa returns data in various formats depending on the camera, so we need to handle this
 fn encode_jpeg(frame_data: &[u8], width: u32, height: u32) -> Result<Vec<u8>, String> {
+    encode_image(&decode_frame(frame_data, width, height)?)
+}
+
+/// Encodes an image as JPEG.
+fn encode_image(img: &image::DynamicImage) -> Result<Vec<u8>, String> {
+    let mut buffer = std::io::Cursor::new(Vec::new());
+    img.write_to(&mut buffer, image::ImageFormat::Jpeg)
+        .map_err(|e| format!("JPEG encoding failed: {}", e))?;
+
+    Ok(buffer.into_inner())
+}
+
+/// Largest frame side accepted for encoding, the JPEG format's limit.
+const MAX_FRAME_SIDE: u32 = u16::MAX as u32;
+
+/// Wraps raw frame data in an image.
+/// nokhwa returns data in various formats depending on the camera, so we need to handle this
+fn decode_frame(frame_data: &[u8], width: u32, height: u32) -> Result<image::DynamicImage, String> {
     use image::{DynamicImage, ImageBuffer, Rgb, Rgba};
-    use std::io::Cursor;
 
-    let expected_rgb = (width * height * 3) as usize;
-    let expected_rgba = (width * height * 4) as usize;
+    // Cameras can report 0x0 while starting up
+    if width == 0 || height == 0 {
+        return Err(format!("Frame has no pixels: {}x{}", width, height));
+    }
+    if width > MAX_FRAME_SIDE || height > MAX_FRAME_SIDE {
+        return Err(format!(
+            "Frame too large: {}x{} (at most {} per side)",
+            width, height, MAX_FRAME_SIDE
+        ));
+    }
+
+    let pixels = width as usize * height as usize;
+    let expected_rgb = pixels * 3;
+    let expected_rgba = pixels * 4;
 
     tracing::debug!(
         "encode_jpeg: data_len={}, width={}, height={}, expected_rgb={}, expected_rgba={}",
@@ -393,9 +866,248 @@ fn encode_jpeg(frame_data: &[u8], width: u32, height: u32) -> Result<Vec<u8>, St
         }
     };
 
-    let mut buffer = Cursor::new(Vec::new());
-    img.write_to(&mut buffer, image::ImageFormat::Jpeg)
-        .map_err(|e| format!("JPEG encoding failed: {}", e))?;
+    Ok(img)
+}
 
-    Ok(buffer.into_inner())
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::camera::{CaptureConfig, PixelFormat};
+    use crate::infrastructure::server::testing::{body_json, TestApp};
+
+    fn frame(width: u32, height: u32) -> CapturedFrame {
+        CapturedFrame {
+            camera_id: Uuid::new_v4(),
+            frame_number: 1,
+            timestamp_ms: 0,
+            width,
+            height,
+            pixel_format: PixelFormat::Rgb,
+            data: vec![128; (width * height * 3) as usize],
+        }
+    }
+
+    #[tokio::test]
+    async fn snapshot_encodes_next_frame_as_jpeg() {
+        let (tx, rx) = broadcast::channel(4);
+        tx.send(frame(32, 24)).unwrap();
+
+        let jpeg = next_jpeg_frame(rx, Duration::from_millis(100))
+            .await
+            .unwrap();
+
+        assert_eq!(&jpeg[..2], &[0xFF, 0xD8]);
+        let decoded = image::load_from_memory_with_format(&jpeg, image::ImageFormat::Jpeg).unwrap();
+        assert_eq!((decoded.width(), decoded.height()), (32, 24));
+    }
+
+    #[tokio::test]
+    async fn mjpeg_response_streams_jpeg_parts() {
+        let (tx, rx) = broadcast::channel(4);
+        let response = mjpeg_response(rx, None, None, |_| {});
+        tx.send(frame(16, 16)).unwrap();
+        drop(tx);
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()["Content-Type"],
+            "multipart/x-mixed-replace; boundary=frame"
+        );
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert!(body.starts_with(b"--frame\r\nContent-Type: image/jpeg\r\n"));
+    }
+
+    #[test]
+    fn throttle_keeps_target_rate_from_faster_camera() {
+        // One second of a jittery 30 fps camera, throttled to 10 fps
+        let mut throttle = FrameThrottle::new(10.0);
+        let sent = (0..30)
+            .map(|i| i * 1000 / 30 + if i % 2 == 0 { 2 } else { -2 })
+            .filter(|&ts| throttle.admit(ts))
+            .count();
+        assert_eq!(sent, 10);
+    }
+
+    #[test]
+    fn throttle_sends_latest_frame_after_falling_behind() {
+        let mut throttle = FrameThrottle::new(10.0);
+        assert!(throttle.admit(0));
+        assert!(!throttle.admit(50));
+
+        // After a gap the next frame is sent and the schedule restarts from it
+        assert!(throttle.admit(1_000));
+        assert!(!throttle.admit(1_050));
+        assert!(throttle.admit(1_100));
+    }
+
+    #[tokio::test]
+    async fn mjpeg_response_reports_skipped_frames() {
+        let (tx, rx) = broadcast::channel(2);
+        let skipped = Arc::new(std::sync::atomic::AtomicU64::new(0));
+        let counter = skipped.clone();
+        let response = mjpeg_response(rx, None, None, move |n| {
+            counter.fetch_add(n, std::sync::atomic::Ordering::Relaxed);
+        });
+        for _ in 0..5 {
+            tx.send(frame(8, 8)).unwrap();
+        }
+        drop(tx);
+
+        axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(skipped.load(std::sync::atomic::Ordering::Relaxed), 3);
+    }
+
+    #[test]
+    fn zero_or_oversized_frames_fail_to_encode() {
+        let err = encode_jpeg(&[], 0, 480).unwrap_err();
+        assert!(err.contains("no pixels"), "{}", err);
+        assert!(encode_jpeg(&[], 640, 0).is_err());
+
+        let err = encode_jpeg(&[0; 12], u32::MAX, u32::MAX).unwrap_err();
+        assert!(err.contains("too large"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn snapshot_times_out_when_camera_is_idle() {
+        let (_tx, rx) = broadcast::channel::<CapturedFrame>(4);
+        let result = next_jpeg_frame(rx, Duration::from_millis(20)).await;
+        assert_eq!(result, Err(StatusCode::SERVICE_UNAVAILABLE));
+    }
+
+    fn jpeg(width: u32, height: u32) -> Vec<u8> {
+        encode_jpeg(&vec![200; (width * height * 3) as usize], width, height).unwrap()
+    }
+
+    /// Creates a browser camera and pushes a frame, which starts its capture.
+    async fn pushing_camera(app: &TestApp) -> Uuid {
+        let id = app.browser_camera("browser-1").await;
+        let pushed = app.push_jpeg(id, jpeg(8, 6)).await;
+        assert_eq!(pushed.status(), StatusCode::ACCEPTED);
+        id
+    }
+
+    #[tokio::test]
+    async fn mjpeg_route_streams_active_cameras_only() {
+        let app = TestApp::new().await;
+
+        let unknown = app
+            .get(&format!("/api/v1/cameras/{}/mjpeg", Uuid::new_v4()))
+            .await;
+        assert_eq!(unknown.status(), StatusCode::NOT_FOUND);
+
+        let id = pushing_camera(&app).await;
+        let response = app.get(&format!("/api/v1/cameras/{}/mjpeg", id)).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "multipart/x-mixed-replace; boundary=frame"
+        );
+    }
+
+    #[tokio::test]
+    async fn pushed_jpeg_reaches_frame_pipeline() {
+        let app = TestApp::new().await;
+        let id = pushing_camera(&app).await;
+        let mut rx = app.state.camera_service.subscribe_frames(id).await.unwrap();
+
+        let pushed = app.push_jpeg(id, jpeg(10, 4)).await;
+        assert_eq!(pushed.status(), StatusCode::ACCEPTED);
+
+        let frame = rx.recv().await.unwrap();
+        assert_eq!((frame.width, frame.height), (10, 4));
+        assert_eq!(frame.pixel_format, PixelFormat::Rgb);
+        assert_eq!(frame.data.len(), 10 * 4 * 3);
+    }
+
+    #[tokio::test]
+    async fn config_reports_a_running_capture() {
+        let app = TestApp::new().await;
+        let id = pushing_camera(&app).await;
+
+        let response = app.get(&format!("/api/v1/cameras/{}/config", id)).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let config = body_json(response).await;
+        assert_eq!(config["state"], "streaming");
+        let requested = CaptureConfig::default();
+        assert_eq!(config["requested"]["width"], requested.width);
+        assert_eq!(config["requested"]["height"], requested.height);
+        assert_eq!(config["requested"]["fps"], requested.fps);
+        assert_eq!(config["actual_resolution"]["width"], 8);
+        assert_eq!(config["actual_resolution"]["height"], 6);
+    }
+
+    #[tokio::test]
+    async fn config_reports_stopped_and_unknown_cameras() {
+        let app = TestApp::new().await;
+        let id = app.browser_camera("browser-idle").await;
+
+        let config = body_json(app.get(&format!("/api/v1/cameras/{}/config", id)).await).await;
+        assert_eq!(config["state"], "stopped");
+        assert!(config["requested"].is_null());
+
+        let unknown = app
+            .get(&format!("/api/v1/cameras/{}/config", Uuid::new_v4()))
+            .await;
+        assert_eq!(unknown.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn camera_sightings_are_paged() {
+        use crate::domain::entities::Sighting;
+        use crate::domain::repositories::SightingRepository;
+        use crate::domain::value_objects::BoundingBox;
+
+        let app = TestApp::new().await;
+        let id = app.browser_camera("browser-paged").await;
+        for minutes_ago in [30, 10, 20] {
+            let sighting = Sighting::from_db(
+                Uuid::new_v4(),
+                Uuid::new_v4(),
+                id,
+                "snap.jpg".to_string(),
+                BoundingBox::new(0, 0, 10, 10),
+                0.9,
+                None,
+                None,
+                None,
+                None,
+                chrono::Utc::now() - chrono::Duration::minutes(minutes_ago),
+            );
+            app.sightings.save(&sighting).await.unwrap();
+        }
+
+        let uri = format!("/api/v1/cameras/{}/sightings?limit=2", id);
+        let page = body_json(app.get(&uri).await).await;
+        let items = page["items"].as_array().unwrap();
+        assert_eq!(items.len(), 2);
+        assert_eq!(page["total"], 3);
+        assert_eq!(page["has_more"], true);
+        assert!(items[0]["detected_at"].as_str() > items[1]["detected_at"].as_str());
+
+        let rest = body_json(app.get(&format!("{}&offset=2", uri)).await).await;
+        assert_eq!(rest["items"].as_array().unwrap().len(), 1);
+        assert_eq!(rest["has_more"], false);
+    }
+
+    #[test]
+    fn pushed_frame_is_validated() {
+        let body = jpeg(8, 6);
+        assert_eq!(
+            decode_pushed_frame(Some("text/plain"), &body).unwrap_err(),
+            StatusCode::UNSUPPORTED_MEDIA_TYPE
+        );
+        assert_eq!(
+            decode_pushed_frame(None, &body).unwrap_err(),
+            StatusCode::UNSUPPORTED_MEDIA_TYPE
+        );
+        assert_eq!(
+            decode_pushed_frame(Some("image/png"), &body).unwrap_err(),
+            StatusCode::BAD_REQUEST
+        );
+    }
 }
@@ -0,0 +1,141 @@
+//! Detection API Endpoint
+//!
+//! Runs the face detector on an uploaded image, so integrators can check
+//! detection without a live camera.
+
+use axum::{
+    body::Bytes,
+    extract::State,
+    http::{header, HeaderMap, StatusCode},
+    Json,
+};
+use chrono::Utc;
+use serde::Serialize;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::domain::entities::Detection;
+use crate::infrastructure::camera::{attach_embeddings, CapturedFrame, FaceDetector, PixelFormat};
+use crate::infrastructure::server::api::cameras::decode_pushed_frame;
+use crate::infrastructure::server::api::sightings::BoundingBoxResponse;
+use crate::infrastructure::server::AppState;
+
+#[derive(Debug, Serialize)]
+pub struct DetectResponse {
+    pub width: u32,
+    pub height: u32,
+    pub faces: Vec<DetectedFaceResponse>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DetectedFaceResponse {
+    pub bounding_box: BoundingBoxResponse,
+    pub confidence: f32,
+    /// Present when embedding extraction is enabled.
+    pub embedding: Option<Vec<f32>>,
+}
+
+impl From<Detection> for DetectedFaceResponse {
+    fn from(d: Detection) -> Self {
+        let bbox = d.bounding_box();
+        Self {
+            bounding_box: BoundingBoxResponse {
+                x: bbox.x(),
+                y: bbox.y(),
+                width: bbox.width(),
+                height: bbox.height(),
+            },
+            confidence: d.confidence(),
+            embedding: d.embedding().map(|e| e.values().to_vec()),
+        }
+    }
+}
+
+/// POST /api/v1/detect - Detect faces in an uploaded JPEG or PNG image
+pub async fn detect_faces(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<DetectResponse>, StatusCode> {
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok());
+    let image = decode_pushed_frame(content_type, &body)?;
+
+    Ok(Json(detect_image(&state.face_detector, image).await))
+}
+
+/// Runs an image through the detector and embedding extraction the same way
+/// the camera pipeline does.
+async fn detect_image(detector: &FaceDetector, image: image::RgbImage) -> DetectResponse {
+    let (width, height) = image.dimensions();
+    let frame = CapturedFrame {
+        camera_id: Uuid::nil(),
+        frame_number: 0,
+        timestamp_ms: Utc::now().timestamp_millis(),
+        width,
+        height,
+        pixel_format: PixelFormat::Rgb,
+        data: image.into_raw(),
+    };
+
+    let mut detections = detector.detect(&frame).await;
+    attach_embeddings(detector, &frame, &mut detections).await;
+
+    DetectResponse {
+        width,
+        height,
+        faces: detections.into_iter().map(Into::into).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::entities::Detection;
+    use crate::domain::value_objects::{embedding_dimension, BoundingBox, FaceEmbedding};
+    use crate::infrastructure::server::testing::{body_json, jpeg, TestApp};
+    use axum::body::Body;
+    use axum::http::{header, Request, StatusCode};
+
+    #[tokio::test]
+    async fn uploaded_image_reports_detected_faces() {
+        let mut face = Detection::new(BoundingBox::new(40, 30, 64, 64), 0.95);
+        face.set_embedding(FaceEmbedding::new(vec![0.5; embedding_dimension()]).normalized());
+        let app = TestApp::builder().detections(vec![face]).build().await;
+
+        let request = Request::post("/api/v1/detect")
+            .header(header::CONTENT_TYPE, "image/jpeg")
+            .body(Body::from(jpeg(320, 240)))
+            .unwrap();
+        let response = app.send(request).await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = body_json(response).await;
+        assert_eq!(
+            (body["width"].as_u64(), body["height"].as_u64()),
+            (Some(320), Some(240))
+        );
+        let faces = body["faces"].as_array().unwrap();
+        assert_eq!(faces.len(), 1);
+        assert_eq!(faces[0]["bounding_box"]["width"], 64);
+        assert_eq!(
+            faces[0]["embedding"].as_array().map(Vec::len),
+            Some(embedding_dimension())
+        );
+    }
+
+    #[tokio::test]
+    async fn unsupported_upload_is_rejected() {
+        let app = TestApp::new().await;
+
+        let request = Request::post("/api/v1/detect")
+            .header(header::CONTENT_TYPE, "text/plain")
+            .body(Body::from("not an image"))
+            .unwrap();
+
+        assert_eq!(
+            app.send(request).await.status(),
+            StatusCode::UNSUPPORTED_MEDIA_TYPE
+        );
+    }
+}
@@ -0,0 +1,65 @@
+//! Events API Endpoints
+//!
+//! Polling access to recent domain events for clients without a WebSocket.
+
+use axum::{extract::State, Json};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::application::services::RecordedEvent;
+use crate::domain::events::DomainEvent;
+use crate::infrastructure::server::api::extract::Query;
+use crate::infrastructure::server::AppState;
+
+/// Default and largest number of events returned per poll.
+const DEFAULT_EVENTS_LIMIT: usize = 100;
+const MAX_EVENTS_LIMIT: usize = 1000;
+
+#[derive(Debug, Deserialize)]
+pub struct RecentEventsQuery {
+    pub since_id: Option<u64>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RecentEventResponse {
+    pub id: u64,
+    #[serde(flatten)]
+    pub event: DomainEvent,
+}
+
+impl From<RecordedEvent> for RecentEventResponse {
+    fn from(recorded: RecordedEvent) -> Self {
+        Self {
+            id: recorded.id,
+            event: (*recorded.event).clone(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct RecentEventsResponse {
+    pub events: Vec<RecentEventResponse>,
+    pub next_since_id: u64,
+    pub missed_events: bool,
+}
+
+/// GET /api/v1/events/recent
+pub async fn get_recent_events(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<RecentEventsQuery>,
+) -> Json<RecentEventsResponse> {
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_EVENTS_LIMIT)
+        .clamp(1, MAX_EVENTS_LIMIT);
+    let page = state
+        .recent_events
+        .since(query.since_id.unwrap_or(0), limit);
+
+    Json(RecentEventsResponse {
+        events: page.events.into_iter().map(Into::into).collect(),
+        next_since_id: page.next_since_id,
+        missed_events: page.missed_events,
+    })
+}
@@ -0,0 +1,214 @@
+//! Request Extractors
+//!
+//! `Path` and `Query` extractors that reject malformed parameters with a JSON
+//! body naming the offending field, instead of axum's plain-text rejections.
+
+use axum::{
+    async_trait,
+    extract::{path::ErrorKind, rejection::PathRejection, FromRequestParts, RawPathParams},
+    http::{request::Parts, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Body returned when a path or query parameter cannot be parsed.
+#[derive(Debug, Serialize)]
+pub struct ParamError {
+    pub error: String,
+    pub field: Option<String>,
+}
+
+/// Rejection for malformed path or query parameters.
+#[derive(Debug)]
+pub struct ParamRejection {
+    status: StatusCode,
+    body: ParamError,
+}
+
+impl ParamRejection {
+    fn bad_request(error: impl Into<String>, field: Option<String>) -> Self {
+        Self {
+            status: StatusCode::BAD_REQUEST,
+            body: ParamError {
+                error: error.into(),
+                field,
+            },
+        }
+    }
+}
+
+impl IntoResponse for ParamRejection {
+    fn into_response(self) -> Response {
+        (self.status, Json(self.body)).into_response()
+    }
+}
+
+/// Path parameters, deserialized like [`axum::extract::Path`].
+#[derive(Debug)]
+pub struct Path<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequestParts<S> for Path<T>
+where
+    T: DeserializeOwned + Send,
+    S: Send + Sync,
+{
+    type Rejection = ParamRejection;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        match axum::extract::Path::<T>::from_request_parts(parts, state).await {
+            Ok(axum::extract::Path(value)) => Ok(Self(value)),
+            Err(PathRejection::FailedToDeserializePathParams(e)) => {
+                // Single-value paths such as `Path<Uuid>` report no key
+                let only_key = RawPathParams::from_request_parts(parts, state)
+                    .await
+                    .ok()
+                    .and_then(|params| match params.iter().collect::<Vec<_>>()[..] {
+                        [(key, _)] => Some(key.to_string()),
+                        _ => None,
+                    });
+                Err(path_error(e.into_kind(), only_key))
+            }
+            Err(e) => Err(ParamRejection {
+                status: e.status(),
+                body: ParamError {
+                    error: e.body_text(),
+                    field: None,
+                },
+            }),
+        }
+    }
+}
+
+fn path_error(kind: ErrorKind, only_key: Option<String>) -> ParamRejection {
+    match kind {
+        ErrorKind::ParseErrorAtKey {
+            key, expected_type, ..
+        } => ParamRejection::bad_request(invalid(expected_type), Some(key)),
+        ErrorKind::ParseError { expected_type, .. } => {
+            ParamRejection::bad_request(invalid(expected_type), only_key)
+        }
+        // `Uuid` deserializes through a custom error rather than a parse error
+        ErrorKind::Message(message) if message.starts_with("UUID parsing failed") => {
+            ParamRejection::bad_request("invalid uuid", only_key)
+        }
+        ErrorKind::InvalidUtf8InPathParam { key } => {
+            ParamRejection::bad_request("invalid utf-8", Some(key))
+        }
+        ErrorKind::UnsupportedType { .. } => ParamRejection {
+            status: StatusCode::INTERNAL_SERVER_ERROR,
+            body: ParamError {
+                error: "unsupported path parameter type".to_string(),
+                field: None,
+            },
+        },
+        other => ParamRejection::bad_request(other.to_string(), only_key),
+    }
+}
+
+/// Describes a failed parse by the type that was expected, e.g. "invalid uuid".
+fn invalid(expected_type: &str) -> String {
+    let name = expected_type.rsplit("::").next().unwrap_or(expected_type);
+    format!("invalid {}", name.to_lowercase())
+}
+
+/// Query parameters, deserialized like [`axum::extract::Query`].
+#[derive(Debug)]
+pub struct Query<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequestParts<S> for Query<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = ParamRejection;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let query = parts.uri.query().unwrap_or_default();
+        let deserializer =
+            serde_urlencoded::Deserializer::new(form_urlencoded::parse(query.as_bytes()));
+
+        serde_path_to_error::deserialize(deserializer)
+            .map(Self)
+            .map_err(|e| {
+                let field = match e.path().to_string() {
+                    path if path == "." => None,
+                    path => Some(path),
+                };
+                let error = e.into_inner().to_string();
+                // serde reports missing fields at the parent, naming them in the message
+                match error
+                    .strip_prefix("missing field `")
+                    .and_then(|rest| rest.strip_suffix('`'))
+                {
+                    Some(missing) => ParamRejection::bad_request(
+                        "missing required parameter",
+                        Some(missing.to_string()),
+                    ),
+                    None => ParamRejection::bad_request(error, field),
+                }
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, routing::get, Router};
+    use chrono::{DateTime, Utc};
+    use serde::Deserialize;
+    use tower::ServiceExt;
+    use uuid::Uuid;
+
+    #[derive(Deserialize)]
+    #[allow(dead_code)]
+    struct RangeQuery {
+        start: Option<DateTime<Utc>>,
+        limit: i64,
+    }
+
+    fn app() -> Router {
+        Router::new()
+            .route("/items/:id", get(|Path(_): Path<Uuid>| async { "ok" }))
+            .route("/range", get(|Query(_): Query<RangeQuery>| async { "ok" }))
+    }
+
+    async fn rejection(uri: &str) -> (StatusCode, serde_json::Value) {
+        let request = axum::http::Request::builder()
+            .uri(uri)
+            .body(Body::empty())
+            .unwrap();
+        let response = app().oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn malformed_uuid_names_the_path_field() {
+        let (status, body) = rejection("/items/not-a-uuid").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "invalid uuid");
+        assert_eq!(body["field"], "id");
+    }
+
+    #[tokio::test]
+    async fn malformed_date_names_the_query_field() {
+        let (status, body) = rejection("/range?start=yesterday&limit=5").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["field"], "start");
+        assert_ne!(body["error"], "missing required parameter");
+    }
+
+    #[tokio::test]
+    async fn missing_query_field_is_reported_as_missing() {
+        let (status, body) = rejection("/range?start=2024-12-24T08:15:00Z").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "missing required parameter");
+        assert_eq!(body["field"], "limit");
+    }
+}
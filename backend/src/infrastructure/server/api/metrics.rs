@@ -0,0 +1,278 @@
+//! Metrics Endpoint
+//!
+//! Prometheus text exposition of pipeline counters.
+//! Reference: https://prometheus.io/docs/instrumenting/exposition_formats/
+
+use axum::{
+    extract::State,
+    http::header,
+    response::{IntoResponse, Response},
+};
+use std::fmt::{Display, Write};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::infrastructure::camera::LatencyStats;
+use crate::infrastructure::server::AppState;
+
+/// Point-in-time view of the pipeline counters.
+#[derive(Debug, Clone, Default)]
+pub struct MetricsSnapshot {
+    pub frames_captured: Vec<(Uuid, u64)>,
+    pub faces_detected: u64,
+    pub profiles_created: u64,
+    pub sightings_recorded: u64,
+    pub rate_limited: u64,
+    pub events_lagged: u64,
+    pub events_dropped: u64,
+    pub ws_messages_lagged: u64,
+    pub ws_clients: usize,
+    pub ws_clients_rejected: u64,
+    pub stream_frames_lagged: u64,
+    pub capture_frames_lagged: u64,
+    pub detector_requests_dropped: u64,
+    pub matcher_cache_hits: u64,
+    pub matcher_cache_misses: u64,
+    pub detection_latency: LatencyStats,
+    pub active_captures: usize,
+}
+
+/// GET /metrics
+pub async fn get_metrics(State(state): State<Arc<AppState>>) -> Response {
+    let snapshot = MetricsSnapshot {
+        frames_captured: state.camera_service.frame_counts().await,
+        faces_detected: state.face_detector.detection_count().await,
+        profiles_created: state.detection_service.profiles_created_count(),
+        sightings_recorded: state.detection_service.sightings_recorded_count(),
+        rate_limited: state.detection_service.rate_limited_count(),
+        events_lagged: state.event_bus.lagged_count(),
+        events_dropped: state.event_bus.dropped_count(),
+        ws_messages_lagged: state.ws_broadcaster.lagged_count(),
+        ws_clients: state.ws_broadcaster.client_count(),
+        ws_clients_rejected: state.ws_broadcaster.rejected_count(),
+        stream_frames_lagged: state.camera_service.stream_frames_lagged(),
+        capture_frames_lagged: state.camera_service.capture_frames_lagged(),
+        detector_requests_dropped: state.face_detector.requests_dropped(),
+        matcher_cache_hits: state.face_matcher.cache_hits(),
+        matcher_cache_misses: state.face_matcher.cache_misses(),
+        detection_latency: state.face_detector.latency_stats(),
+        active_captures: state.camera_service.active_capture_count().await,
+    };
+
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        render_metrics(&snapshot),
+    )
+        .into_response()
+}
+
+/// Renders a snapshot in the Prometheus text format.
+pub fn render_metrics(snapshot: &MetricsSnapshot) -> String {
+    let mut out = String::new();
+
+    write_header(
+        &mut out,
+        "safelynx_frames_captured_total",
+        "Frames captured per camera.",
+        "counter",
+    );
+    let mut frames = snapshot.frames_captured.clone();
+    frames.sort_by_key(|(camera_id, _)| *camera_id);
+    for (camera_id, count) in frames {
+        let _ = writeln!(
+            out,
+            "safelynx_frames_captured_total{{camera_id=\"{}\"}} {}",
+            camera_id, count
+        );
+    }
+
+    write_sample(
+        &mut out,
+        "safelynx_faces_detected_total",
+        "Faces detected across all cameras.",
+        "counter",
+        snapshot.faces_detected,
+    );
+    write_sample(
+        &mut out,
+        "safelynx_profiles_created_total",
+        "Profiles created from new faces.",
+        "counter",
+        snapshot.profiles_created,
+    );
+    write_sample(
+        &mut out,
+        "safelynx_sightings_recorded_total",
+        "Sightings recorded for known profiles.",
+        "counter",
+        snapshot.sightings_recorded,
+    );
+    write_sample(
+        &mut out,
+        "safelynx_detections_rate_limited_total",
+        "Profile creations and sightings dropped by the per-camera rate limit.",
+        "counter",
+        snapshot.rate_limited,
+    );
+    write_sample(
+        &mut out,
+        "safelynx_events_lagged_total",
+        "Domain events skipped by event bus subscribers that fell behind.",
+        "counter",
+        snapshot.events_lagged,
+    );
+    write_sample(
+        &mut out,
+        "safelynx_events_dropped_total",
+        "Domain events evicted from the event bus before every subscriber read them.",
+        "counter",
+        snapshot.events_dropped,
+    );
+    write_sample(
+        &mut out,
+        "safelynx_ws_messages_lagged_total",
+        "WebSocket messages skipped by clients that fell behind.",
+        "counter",
+        snapshot.ws_messages_lagged,
+    );
+    write_sample(
+        &mut out,
+        "safelynx_ws_clients_rejected_total",
+        "WebSocket connections refused because the client limit was reached.",
+        "counter",
+        snapshot.ws_clients_rejected,
+    );
+    write_sample(
+        &mut out,
+        "safelynx_stream_frames_lagged_total",
+        "Frames skipped by MJPEG streams whose client fell behind.",
+        "counter",
+        snapshot.stream_frames_lagged,
+    );
+    write_sample(
+        &mut out,
+        "safelynx_capture_frames_lagged_total",
+        "Captured frames skipped by the detection pipeline because it fell behind.",
+        "counter",
+        snapshot.capture_frames_lagged,
+    );
+    write_sample(
+        &mut out,
+        "safelynx_detector_requests_dropped_total",
+        "Detection requests dropped because the detector queue was full.",
+        "counter",
+        snapshot.detector_requests_dropped,
+    );
+    write_sample(
+        &mut out,
+        "safelynx_matcher_cache_hits_total",
+        "Face matches found in the in-memory embedding cache.",
+        "counter",
+        snapshot.matcher_cache_hits,
+    );
+    write_sample(
+        &mut out,
+        "safelynx_matcher_cache_misses_total",
+        "Face lookups with no match in the in-memory embedding cache.",
+        "counter",
+        snapshot.matcher_cache_misses,
+    );
+    write_sample(
+        &mut out,
+        "safelynx_detection_latency_avg_seconds",
+        "Average face detection time over recent frames.",
+        "gauge",
+        snapshot.detection_latency.avg_ms / 1000.0,
+    );
+    write_sample(
+        &mut out,
+        "safelynx_detection_latency_p95_seconds",
+        "95th percentile face detection time over recent frames.",
+        "gauge",
+        snapshot.detection_latency.p95_ms / 1000.0,
+    );
+    write_sample(
+        &mut out,
+        "safelynx_active_captures",
+        "Camera captures currently running.",
+        "gauge",
+        snapshot.active_captures as u64,
+    );
+    write_sample(
+        &mut out,
+        "safelynx_ws_clients",
+        "WebSocket clients currently connected.",
+        "gauge",
+        snapshot.ws_clients as u64,
+    );
+
+    out
+}
+
+fn write_header(out: &mut String, name: &str, help: &str, kind: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+fn write_sample(out: &mut String, name: &str, help: &str, kind: &str, value: impl Display) {
+    write_header(out, name, help, kind);
+    let _ = writeln!(out, "{} {}", name, value);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exposition_contains_expected_metric_names() {
+        let camera_id = Uuid::new_v4();
+        let snapshot = MetricsSnapshot {
+            frames_captured: vec![(camera_id, 42)],
+            faces_detected: 7,
+            profiles_created: 2,
+            sightings_recorded: 5,
+            rate_limited: 3,
+            events_lagged: 4,
+            events_dropped: 10,
+            ws_messages_lagged: 11,
+            ws_clients: 3,
+            ws_clients_rejected: 14,
+            stream_frames_lagged: 6,
+            capture_frames_lagged: 12,
+            detector_requests_dropped: 13,
+            matcher_cache_hits: 8,
+            matcher_cache_misses: 9,
+            detection_latency: LatencyStats {
+                avg_ms: 12.5,
+                p95_ms: 40.0,
+                samples: 128,
+            },
+            active_captures: 1,
+        };
+
+        let text = render_metrics(&snapshot);
+
+        assert!(text.contains(&format!(
+            "safelynx_frames_captured_total{{camera_id=\"{}\"}} 42",
+            camera_id
+        )));
+        assert!(text.contains("safelynx_faces_detected_total 7"));
+        assert!(text.contains("safelynx_profiles_created_total 2"));
+        assert!(text.contains("safelynx_sightings_recorded_total 5"));
+        assert!(text.contains("safelynx_detections_rate_limited_total 3"));
+        assert!(text.contains("safelynx_events_lagged_total 4"));
+        assert!(text.contains("safelynx_events_dropped_total 10"));
+        assert!(text.contains("safelynx_ws_messages_lagged_total 11"));
+        assert!(text.contains("safelynx_stream_frames_lagged_total 6"));
+        assert!(text.contains("safelynx_capture_frames_lagged_total 12"));
+        assert!(text.contains("safelynx_detector_requests_dropped_total 13"));
+        assert!(text.contains("safelynx_matcher_cache_hits_total 8"));
+        assert!(text.contains("safelynx_matcher_cache_misses_total 9"));
+        assert!(text.contains("safelynx_detection_latency_avg_seconds 0.0125"));
+        assert!(text.contains("safelynx_detection_latency_p95_seconds 0.04"));
+        assert!(text.contains("safelynx_active_captures 1"));
+        assert!(text.contains("# TYPE safelynx_active_captures gauge"));
+        assert!(text.contains("safelynx_ws_clients 3"));
+        assert!(text.contains("safelynx_ws_clients_rejected_total 14"));
+    }
+}
@@ -4,50 +4,133 @@
 
 pub mod analytics;
 pub mod cameras;
+pub mod detect;
+pub mod events;
+mod extract;
 pub mod health;
+pub mod metrics;
+mod pagination;
 pub mod profiles;
 pub mod recordings;
 pub mod settings;
 pub mod sightings;
+pub mod system;
 
 use axum::{
+    extract::DefaultBodyLimit,
+    middleware,
     routing::{delete, get, post, put},
     Router,
 };
 use std::sync::Arc;
 
+use crate::infrastructure::server::idempotency::{replay_idempotent, IdempotencyStore};
 use crate::infrastructure::server::AppState;
 
 /// Creates all API routes.
-pub fn routes(_state: Arc<AppState>) -> Router<Arc<AppState>> {
+pub fn routes(state: Arc<AppState>) -> Router<Arc<AppState>> {
+    // Bodies larger than a JSON request: images and profile imports
+    let uploads = Router::new()
+        .route("/profiles/import", post(profiles::import_profiles))
+        .route("/cameras/:id/frames", post(cameras::push_camera_frame))
+        .route("/detect", post(detect::detect_faces));
+
+    with_body_limits(
+        json_routes(state.idempotency_keys.clone()),
+        uploads,
+        state.config.max_body_bytes,
+        state.config.max_upload_bytes,
+    )
+}
+
+/// Routes loaded straight by `<img>` and `<video>` tags, which cannot send
+/// an `Authorization` header.
+pub fn media_routes() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/cameras/:id/mjpeg", get(cameras::mjpeg_stream))
+        .route("/cameras/:id/snapshot", get(cameras::camera_snapshot))
+        .route("/recordings/:id/play", get(recordings::play_recording))
+        .route("/recordings/:id/stream", get(recordings::stream_recording))
+}
+
+/// Applies `json_limit` to `json` and `upload_limit` to `uploads`, so
+/// oversized bodies are rejected with 413 before they are buffered.
+fn with_body_limits<S>(
+    json: Router<S>,
+    uploads: Router<S>,
+    json_limit: usize,
+    upload_limit: usize,
+) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    json.layer(DefaultBodyLimit::max(json_limit))
+        .merge(uploads.layer(DefaultBodyLimit::max(upload_limit)))
+}
+
+/// `idempotency_keys` holds the responses of write endpoints that accept
+/// an `Idempotency-Key` header.
+fn json_routes(idempotency_keys: Arc<IdempotencyStore>) -> Router<Arc<AppState>> {
+    let idempotent = middleware::from_fn_with_state(idempotency_keys, replay_idempotent);
+
     Router::new()
         // Profiles
         .route("/profiles", get(profiles::list_profiles))
+        .route("/profiles/export", get(profiles::export_profiles))
+        .route("/profiles/bulk-classify", post(profiles::bulk_classify))
         .route("/profiles/:id", get(profiles::get_profile))
         .route("/profiles/:id", put(profiles::update_profile))
         .route("/profiles/:id", delete(profiles::delete_profile))
+        .route("/profiles/:id/hard", delete(profiles::hard_delete_profile))
+        .route(
+            "/profiles/:id/embedding-quality",
+            get(profiles::get_embedding_quality),
+        )
         .route(
             "/profiles/:id/sightings",
             get(profiles::get_profile_sightings),
         )
+        .route(
+            "/profiles/:id/sessions",
+            get(profiles::get_profile_sessions),
+        )
+        .route(
+            "/profiles/:id/timeline",
+            get(profiles::get_profile_timeline),
+        )
         // Cameras
         .route("/cameras", get(cameras::list_cameras))
-        .route("/cameras", post(cameras::create_camera))
+        .route(
+            "/cameras",
+            post(cameras::create_camera).layer(idempotent.clone()),
+        )
         .route("/cameras/:id", get(cameras::get_camera))
         .route("/cameras/:id", put(cameras::update_camera))
         .route("/cameras/:id", delete(cameras::delete_camera))
         .route("/cameras/:id/stream/start", post(cameras::start_stream))
         .route("/cameras/:id/stream/stop", post(cameras::stop_stream))
-        .route("/cameras/:id/mjpeg", get(cameras::mjpeg_stream))
+        .route("/cameras/:id/health", get(cameras::get_camera_health))
+        .route("/cameras/:id/config", get(cameras::get_camera_config))
+        .route("/cameras/:id/sightings", get(cameras::get_camera_sightings))
         .route("/cameras/available", get(cameras::list_available_cameras))
         // Sightings
         .route("/sightings", get(sightings::list_sightings))
+        .route("/sightings/export", get(sightings::export_sightings))
+        .route("/sightings/in-area", get(sightings::get_sightings_in_area))
         .route("/sightings/:id", get(sightings::get_sighting))
+        .route("/sightings/:id", delete(sightings::delete_sighting))
+        .route(
+            "/sightings/:id/thumbnail",
+            get(sightings::get_sighting_thumbnail),
+        )
         // Recordings
         .route("/recordings", get(recordings::list_recordings))
         .route("/recordings/:id", get(recordings::get_recording))
         .route("/recordings/:id", delete(recordings::delete_recording))
-        .route("/recordings/:id/play", get(recordings::play_recording))
+        .route(
+            "/recordings/:id/restore",
+            post(recordings::restore_recording),
+        )
         // Settings
         .route("/settings", get(settings::get_settings))
         .route("/settings", put(settings::update_settings))
@@ -56,8 +139,66 @@ pub fn routes(_state: Arc<AppState>) -> Router<Arc<AppState>> {
         .route("/analytics/heatmap", get(analytics::get_heatmap_data))
         .route("/analytics/timeline", get(analytics::get_timeline))
         .route("/analytics/storage", get(analytics::get_storage_stats))
+        .route("/analytics/top-profiles", get(analytics::get_top_profiles))
+        .route(
+            "/analytics/camera-activity",
+            get(analytics::get_camera_activity),
+        )
         .route(
             "/analytics/activity-chart",
             get(analytics::get_activity_chart),
         )
+        // Events
+        .route("/events/recent", get(events::get_recent_events))
+        // System
+        .route("/system/info", get(system::get_system_info))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, body::Bytes, http::StatusCode, Json};
+    use tower::ServiceExt;
+
+    fn app() -> Router {
+        let json = Router::new().route(
+            "/settings",
+            post(|Json(_): Json<serde_json::Value>| async { "ok" }),
+        );
+        let uploads = Router::new().route("/frames", post(|_: Bytes| async { "ok" }));
+        with_body_limits(json, uploads, 64, 256)
+    }
+
+    async fn post_status(uri: &str, body: Vec<u8>) -> StatusCode {
+        let request = axum::http::Request::builder()
+            .method("POST")
+            .uri(uri)
+            .header("content-type", "application/json")
+            .body(Body::from(body))
+            .unwrap();
+        app().oneshot(request).await.unwrap().status()
+    }
+
+    fn json_string(len: usize) -> Vec<u8> {
+        format!("\"{}\"", "x".repeat(len)).into_bytes()
+    }
+
+    #[tokio::test]
+    async fn oversized_bodies_are_rejected_with_413() {
+        assert_eq!(
+            post_status("/settings", json_string(10)).await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            post_status("/settings", json_string(100)).await,
+            StatusCode::PAYLOAD_TOO_LARGE
+        );
+
+        // Uploads get the larger limit, but are still capped
+        assert_eq!(post_status("/frames", vec![0; 100]).await, StatusCode::OK);
+        assert_eq!(
+            post_status("/frames", vec![0; 300]).await,
+            StatusCode::PAYLOAD_TOO_LARGE
+        );
+    }
 }
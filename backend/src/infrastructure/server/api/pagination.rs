@@ -0,0 +1,123 @@
+//! Pagination
+//!
+//! `limit`/`offset` query parameters and the page envelope list endpoints
+//! return, so every paginated endpoint behaves the same way.
+
+use axum::{async_trait, extract::FromRequestParts, http::request::Parts};
+use serde::{Deserialize, Serialize};
+
+use crate::infrastructure::server::api::extract::{ParamRejection, Query};
+
+/// Page size when `limit` is not given.
+pub const DEFAULT_PAGE_LIMIT: i64 = 100;
+
+/// Largest page size that may be requested.
+pub const MAX_PAGE_LIMIT: i64 = 500;
+
+/// `limit` and `offset` query parameters. Other parameters are ignored, so
+/// it can be extracted next to an endpoint's own query.
+#[derive(Debug, Default, Deserialize)]
+pub struct PageParams {
+    limit: Option<i64>,
+    offset: Option<i64>,
+}
+
+impl PageParams {
+    /// Page size, defaulting to [`DEFAULT_PAGE_LIMIT`] and clamped to
+    /// 1..=[`MAX_PAGE_LIMIT`].
+    pub fn limit(&self) -> i64 {
+        self.limit
+            .unwrap_or(DEFAULT_PAGE_LIMIT)
+            .clamp(1, MAX_PAGE_LIMIT)
+    }
+
+    /// Items skipped before the page, never negative.
+    pub fn offset(&self) -> i64 {
+        self.offset.unwrap_or(0).max(0)
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for PageParams
+where
+    S: Send + Sync,
+{
+    type Rejection = ParamRejection;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Query(params) = Query::<Self>::from_request_parts(parts, state).await?;
+        Ok(params)
+    }
+}
+
+/// One page of a list, with what clients need to fetch the next.
+#[derive(Debug, Serialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// Items across all pages.
+    pub total: i64,
+    pub limit: i64,
+    pub offset: i64,
+    pub has_more: bool,
+}
+
+impl<T> Page<T> {
+    /// Wraps the `items` fetched for `params` out of `total`.
+    pub fn new(items: Vec<T>, total: i64, params: &PageParams) -> Self {
+        let (limit, offset) = (params.limit(), params.offset());
+        Self {
+            has_more: offset.saturating_add(items.len() as i64) < total,
+            items,
+            total,
+            limit,
+            offset,
+        }
+    }
+
+    /// Converts every item, keeping the page metadata.
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> Page<U> {
+        Page {
+            items: self.items.into_iter().map(f).collect(),
+            total: self.total,
+            limit: self.limit,
+            offset: self.offset,
+            has_more: self.has_more,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(limit: Option<i64>, offset: Option<i64>) -> PageParams {
+        PageParams { limit, offset }
+    }
+
+    #[test]
+    fn missing_params_use_the_defaults() {
+        let page = params(None, None);
+        assert_eq!(page.limit(), DEFAULT_PAGE_LIMIT);
+        assert_eq!(page.offset(), 0);
+    }
+
+    #[test]
+    fn limit_and_offset_are_clamped() {
+        assert_eq!(params(Some(10_000), None).limit(), MAX_PAGE_LIMIT);
+        assert_eq!(params(Some(0), None).limit(), 1);
+        assert_eq!(params(None, Some(-5)).offset(), 0);
+    }
+
+    #[test]
+    fn has_more_is_set_until_the_last_page() {
+        let first = Page::new(vec![0, 1], 5, &params(Some(2), None));
+        assert_eq!(first.total, 5);
+        assert!(first.has_more);
+
+        let last = Page::new(vec![4], 5, &params(Some(2), Some(4)));
+        assert!(!last.has_more);
+
+        let past_the_end = Page::<i32>::new(Vec::new(), 5, &params(Some(2), Some(10)));
+        assert!(!past_the_end.has_more);
+    }
+}
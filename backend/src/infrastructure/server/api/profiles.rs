@@ -1,17 +1,20 @@
 //! Profile API Endpoints
 
-use axum::{
-    extract::{Path, Query, State},
-    http::StatusCode,
-    Json,
-};
+use axum::{extract::State, http::StatusCode, Json};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::sync::Arc;
 use uuid::Uuid;
 
-use crate::application::use_cases::{ProfileStats, UpdateProfileRequest};
+use crate::application::services::SNAPSHOTS_DIR;
+use crate::application::use_cases::{ProfileExport, ProfileStats, UpdateProfileRequest};
 use crate::domain::entities::{Profile, ProfileClassification, Sighting};
-use crate::infrastructure::server::AppState;
+use crate::domain::repositories::RepositoryError;
+use crate::infrastructure::server::api::cameras::camera_names;
+use crate::infrastructure::server::api::extract::{Path, Query};
+use crate::infrastructure::server::api::pagination::{Page, PageParams};
+use crate::infrastructure::server::signed_url::FileUrls;
+use crate::infrastructure::server::{AppState, FILES_ROUTE};
 
 #[derive(Debug, Serialize)]
 pub struct ProfileResponse {
@@ -26,37 +29,48 @@ pub struct ProfileResponse {
     pub last_seen_at: String,
     pub sighting_count: i64,
     pub is_active: bool,
+    /// False for profiles created without a face embedding, which are never
+    /// matched to new sightings.
+    pub has_embedding: bool,
 }
 
-impl From<Profile> for ProfileResponse {
-    fn from(p: Profile) -> Self {
+impl ProfileResponse {
+    pub fn new(p: Profile, urls: &FileUrls) -> Self {
         Self {
             id: p.id(),
             name: p.name().map(String::from),
             display_name: p.display_name(),
             classification: p.classification(),
-            thumbnail_url: p.thumbnail_path().map(|path| {
-                // Extract just the filename if it's an absolute path
-                let filename = std::path::Path::new(path)
-                    .file_name()
-                    .and_then(|n| n.to_str())
-                    .unwrap_or(path);
-                format!("/files/snapshots/{}", filename)
-            }),
+            thumbnail_url: p.thumbnail_path().map(|path| thumbnail_url(path, urls)),
             tags: p.tags().iter().map(|t| t.value().to_string()).collect(),
             notes: p.notes().map(String::from),
             first_seen_at: p.first_seen_at().to_rfc3339(),
             last_seen_at: p.last_seen_at().to_rfc3339(),
             sighting_count: p.sighting_count(),
             is_active: p.is_active(),
+            has_embedding: p.has_embedding(),
         }
     }
 }
 
+/// Maps a stored thumbnail or snapshot path to its URL under `/files/snapshots`,
+/// signed when `/files` is protected.
+///
+/// Files are written to `StorageManager::snapshots_dir()`, which `/files`
+/// serves from the same base directory.
+pub(crate) fn thumbnail_url(path: &str, urls: &FileUrls) -> String {
+    // Extract just the filename if it's an absolute path
+    let filename = std::path::Path::new(path)
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or(path);
+    urls.url(format!("{}/{}/{}", FILES_ROUTE, SNAPSHOTS_DIR, filename))
+}
+
 #[derive(Debug, Serialize)]
 pub struct ProfileListResponse {
-    pub profiles: Vec<ProfileResponse>,
-    pub total: usize,
+    #[serde(flatten)]
+    pub page: Page<ProfileResponse>,
     pub stats: ProfileStatsResponse,
 }
 
@@ -93,17 +107,39 @@ pub struct UpdateProfileBody {
 }
 
 #[derive(Debug, Deserialize)]
-pub struct SightingsQuery {
-    pub limit: Option<i64>,
+pub struct ImportQuery {
+    /// Keep the exported profile IDs (default) or assign new ones.
+    pub preserve_ids: Option<bool>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportResponse {
+    pub created: usize,
+    pub updated: usize,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SessionsQuery {
+    /// Seconds between sightings that start a new session (default: 300).
+    pub gap_secs: Option<u32>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SessionResponse {
+    pub start: String,
+    pub end: String,
+    pub count: usize,
+    pub camera_ids: Vec<Uuid>,
 }
 
 /// GET /api/v1/profiles
 pub async fn list_profiles(
     State(state): State<Arc<AppState>>,
+    page: PageParams,
 ) -> Result<Json<ProfileListResponse>, StatusCode> {
-    let profiles = state
+    let (profiles, total) = state
         .manage_profiles
-        .list_profiles()
+        .list_profiles(page.limit(), page.offset())
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
@@ -113,16 +149,88 @@ pub async fn list_profiles(
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
-    let total = profiles.len();
-    let profiles: Vec<ProfileResponse> = profiles.into_iter().map(Into::into).collect();
-
     Ok(Json(ProfileListResponse {
-        profiles,
-        total,
+        page: Page::new(profiles, total, &page).map(|p| ProfileResponse::new(p, &state.file_urls)),
         stats: stats.into(),
     }))
 }
 
+/// GET /api/v1/profiles/export
+pub async fn export_profiles(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<ProfileExport>>, StatusCode> {
+    let profiles = state
+        .manage_profiles
+        .export_profiles()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(profiles))
+}
+
+/// POST /api/v1/profiles/import
+pub async fn import_profiles(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ImportQuery>,
+    Json(body): Json<Vec<ProfileExport>>,
+) -> Result<Json<ImportResponse>, StatusCode> {
+    let summary = state
+        .manage_profiles
+        .import_profiles(body, query.preserve_ids.unwrap_or(true))
+        .await
+        .map_err(|e| match e {
+            RepositoryError::Serialization(_) => StatusCode::BAD_REQUEST,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        })?;
+
+    Ok(Json(ImportResponse {
+        created: summary.created,
+        updated: summary.updated,
+    }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BulkClassifyBody {
+    pub ids: Vec<Uuid>,
+    pub classification: ProfileClassification,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BulkClassifyItem {
+    pub id: Uuid,
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BulkClassifyResponse {
+    pub results: Vec<BulkClassifyItem>,
+}
+
+/// POST /api/v1/profiles/bulk-classify
+pub async fn bulk_classify(
+    State(state): State<Arc<AppState>>,
+    Json(body): Json<BulkClassifyBody>,
+) -> Result<Json<BulkClassifyResponse>, StatusCode> {
+    let results = state
+        .manage_profiles
+        .bulk_classify(&body.ids, body.classification)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(BulkClassifyResponse {
+        results: results
+            .into_iter()
+            .map(|r| BulkClassifyItem {
+                id: r.id,
+                success: r.updated,
+                error: (!r.updated).then(|| "Profile not found".to_string()),
+            })
+            .collect(),
+    }))
+}
+
 /// GET /api/v1/profiles/:id
 pub async fn get_profile(
     State(state): State<Arc<AppState>>,
@@ -135,7 +243,50 @@ pub async fn get_profile(
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
         .ok_or(StatusCode::NOT_FOUND)?;
 
-    Ok(Json(profile.into()))
+    Ok(Json(ProfileResponse::new(profile, &state.file_urls)))
+}
+
+#[derive(Debug, Serialize)]
+pub struct EmbeddingQualityResponse {
+    /// True for a placeholder embedding, which never matches.
+    pub is_zero_embedding: bool,
+    pub l2_norm: f32,
+    pub dimension: usize,
+    pub sighting_count: i64,
+    /// Distance to the closest other profile in the matcher cache; small
+    /// values mean the two are easily confused.
+    pub distance_to_nearest_other_profile: Option<f32>,
+}
+
+/// GET /api/v1/profiles/:id/embedding-quality
+pub async fn get_embedding_quality(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<EmbeddingQualityResponse>, StatusCode> {
+    let profile = state
+        .manage_profiles
+        .get_profile(id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let nearest = state.face_matcher.nearest_other_profile(&profile).await;
+    Ok(Json(embedding_quality(
+        &profile,
+        nearest.map(|(_, distance)| distance),
+    )))
+}
+
+/// Describes a profile's primary embedding.
+fn embedding_quality(profile: &Profile, nearest_distance: Option<f32>) -> EmbeddingQualityResponse {
+    let embedding = profile.embedding();
+    EmbeddingQualityResponse {
+        is_zero_embedding: embedding.is_zero(),
+        l2_norm: embedding.l2_norm(),
+        dimension: embedding.dimension(),
+        sighting_count: profile.sighting_count(),
+        distance_to_nearest_other_profile: nearest_distance,
+    }
 }
 
 /// PUT /api/v1/profiles/:id
@@ -159,7 +310,7 @@ pub async fn update_profile(
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
         .ok_or(StatusCode::NOT_FOUND)?;
 
-    Ok(Json(profile.into()))
+    Ok(Json(ProfileResponse::new(profile, &state.file_urls)))
 }
 
 /// DELETE /api/v1/profiles/:id
@@ -180,25 +331,156 @@ pub async fn delete_profile(
     }
 }
 
+#[derive(Debug, Deserialize)]
+pub struct HardDeleteQuery {
+    /// Must be `true`; guards against accidental purges.
+    pub confirm: Option<bool>,
+}
+
+/// DELETE /api/v1/profiles/:id/hard
+pub async fn hard_delete_profile(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<HardDeleteQuery>,
+) -> Result<StatusCode, StatusCode> {
+    if query.confirm != Some(true) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let snapshots_dir = state.storage_manager.snapshots_dir().await;
+    let deleted = state
+        .manage_profiles
+        .purge_profile(id, &snapshots_dir)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    if deleted {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(StatusCode::NOT_FOUND)
+    }
+}
+
 /// GET /api/v1/profiles/:id/sightings
 pub async fn get_profile_sightings(
     State(state): State<Arc<AppState>>,
     Path(id): Path<Uuid>,
-    Query(query): Query<SightingsQuery>,
-) -> Result<Json<Vec<SightingResponse>>, StatusCode> {
-    let limit = query.limit.unwrap_or(100);
-
+    page: PageParams,
+) -> Result<Json<Page<SightingResponse>>, StatusCode> {
     let sightings = state
         .sighting_repo
-        .find_by_profile(id, limit)
+        .find_recent_by_profile_page(id, page.limit(), page.offset())
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let total = state
+        .sighting_repo
+        .count_by_profile(id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let page =
+        Page::new(sightings, total, &page).map(|s| SightingResponse::new(s, &state.file_urls));
+
+    Ok(Json(page))
+}
 
-    let responses: Vec<SightingResponse> = sightings.into_iter().map(Into::into).collect();
+/// GET /api/v1/profiles/:id/sessions
+pub async fn get_profile_sessions(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<SessionsQuery>,
+) -> Result<Json<Vec<SessionResponse>>, StatusCode> {
+    let gap_secs = query.gap_secs.unwrap_or(300);
+
+    let sessions = state
+        .query_analytics
+        .get_sighting_sessions(id, gap_secs.into())
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let responses = sessions
+        .into_iter()
+        .map(|s| SessionResponse {
+            start: s.start.to_rfc3339(),
+            end: s.end.to_rfc3339(),
+            count: s.count,
+            camera_ids: s.camera_ids,
+        })
+        .collect();
 
     Ok(Json(responses))
 }
 
+/// GET /api/v1/profiles/:id/timeline
+pub async fn get_profile_timeline(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<Uuid>,
+    page: PageParams,
+) -> Result<Json<Page<ProfileTimelineEntry>>, StatusCode> {
+    state
+        .profile_repo
+        .find_by_id(id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let sightings = state
+        .sighting_repo
+        .find_by_profile_page(id, page.limit(), page.offset())
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let total = state
+        .sighting_repo
+        .count_by_profile(id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let camera_names = camera_names(state.camera_repo.as_ref())
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let entries = build_profile_timeline(sightings, &camera_names, &state.file_urls);
+
+    Ok(Json(Page::new(entries, total, &page)))
+}
+
+/// Orders a profile's sightings by time and annotates them with camera names.
+fn build_profile_timeline(
+    mut sightings: Vec<Sighting>,
+    camera_names: &HashMap<Uuid, String>,
+    urls: &FileUrls,
+) -> Vec<ProfileTimelineEntry> {
+    sightings.sort_by_key(|s| s.detected_at());
+    sightings
+        .into_iter()
+        .map(|s| ProfileTimelineEntry {
+            sighting_id: s.id(),
+            camera_id: s.camera_id(),
+            camera_name: camera_names.get(&s.camera_id()).cloned(),
+            snapshot_url: thumbnail_url(s.snapshot_path(), urls),
+            confidence: s.confidence(),
+            match_confidence: s.match_confidence(),
+            recording_id: s.recording_id(),
+            recording_timestamp_ms: s.recording_timestamp_ms(),
+            detected_at: s.detected_at().to_rfc3339(),
+        })
+        .collect()
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProfileTimelineEntry {
+    pub sighting_id: Uuid,
+    pub camera_id: Uuid,
+    /// `None` when the camera has since been deleted.
+    pub camera_name: Option<String>,
+    pub snapshot_url: String,
+    pub confidence: f32,
+    pub match_confidence: Option<f32>,
+    /// Recording the sighting happened in, and its offset into it.
+    pub recording_id: Option<Uuid>,
+    pub recording_timestamp_ms: Option<i64>,
+    pub detected_at: String,
+}
+
 #[derive(Debug, Serialize)]
 pub struct SightingResponse {
     pub id: Uuid,
@@ -206,7 +488,10 @@ pub struct SightingResponse {
     pub camera_id: Uuid,
     pub snapshot_url: String,
     pub confidence: f32,
+    pub match_confidence: Option<f32>,
     pub location: Option<LocationResponse>,
+    pub recording_id: Option<Uuid>,
+    pub recording_timestamp_ms: Option<i64>,
     pub detected_at: String,
 }
 
@@ -216,25 +501,158 @@ pub struct LocationResponse {
     pub longitude: f64,
 }
 
-impl From<Sighting> for SightingResponse {
-    fn from(s: Sighting) -> Self {
-        // Extract just the filename if it's an absolute path
-        let snapshot_path = s.snapshot_path();
-        let filename = std::path::Path::new(snapshot_path)
-            .file_name()
-            .and_then(|n| n.to_str())
-            .unwrap_or(snapshot_path);
+impl SightingResponse {
+    pub fn new(s: Sighting, urls: &FileUrls) -> Self {
         Self {
             id: s.id(),
             profile_id: s.profile_id(),
             camera_id: s.camera_id(),
-            snapshot_url: format!("/files/snapshots/{}", filename),
+            snapshot_url: thumbnail_url(s.snapshot_path(), urls),
             confidence: s.confidence(),
+            match_confidence: s.match_confidence(),
             location: s.location().map(|l| LocationResponse {
                 latitude: l.latitude(),
                 longitude: l.longitude(),
             }),
+            recording_id: s.recording_id(),
+            recording_timestamp_ms: s.recording_timestamp_ms(),
             detected_at: s.detected_at().to_rfc3339(),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::repositories::SightingRepository;
+    use crate::domain::value_objects::{embedding_dimension, BoundingBox, FaceEmbedding};
+    use crate::infrastructure::server::testing::{body_json, TestApp};
+    use chrono::{Duration, Utc};
+
+    fn sighting_at(camera_id: Uuid, minutes_ago: i64) -> Sighting {
+        profile_sighting_at(Uuid::new_v4(), camera_id, minutes_ago)
+    }
+
+    fn profile_sighting_at(profile_id: Uuid, camera_id: Uuid, minutes_ago: i64) -> Sighting {
+        Sighting::from_db(
+            Uuid::new_v4(),
+            profile_id,
+            camera_id,
+            "/data/snapshots/snap.jpg".to_string(),
+            BoundingBox::new(0, 0, 10, 10),
+            0.9,
+            None,
+            None,
+            None,
+            None,
+            Utc::now() - Duration::minutes(minutes_ago),
+        )
+    }
+
+    #[test]
+    fn timeline_is_time_ordered_with_camera_names() {
+        let front = Uuid::new_v4();
+        let back = Uuid::new_v4();
+        let deleted = Uuid::new_v4();
+        let camera_names = HashMap::from([
+            (front, "Front Door".to_string()),
+            (back, "Back Yard".to_string()),
+        ]);
+        let sightings = vec![
+            sighting_at(back, 5),
+            sighting_at(front, 30),
+            sighting_at(deleted, 1),
+            sighting_at(front, 10),
+        ];
+
+        let entries = build_profile_timeline(sightings, &camera_names, &FileUrls::default());
+
+        let names: Vec<Option<&str>> = entries.iter().map(|e| e.camera_name.as_deref()).collect();
+        assert_eq!(
+            names,
+            vec![
+                Some("Front Door"),
+                Some("Front Door"),
+                Some("Back Yard"),
+                None
+            ]
+        );
+        assert!(entries
+            .windows(2)
+            .all(|pair| pair[0].detected_at <= pair[1].detected_at));
+        assert_eq!(entries[0].snapshot_url, "/files/snapshots/snap.jpg");
+    }
+
+    #[test]
+    fn zero_embedding_is_reported_as_placeholder() {
+        let quality = embedding_quality(&Profile::without_embedding(None), None);
+        assert!(quality.is_zero_embedding);
+        assert_eq!(quality.l2_norm, 0.0);
+        assert_eq!(quality.dimension, embedding_dimension());
+        assert_eq!(quality.distance_to_nearest_other_profile, None);
+
+        let enrolled = Profile::new(FaceEmbedding::new(vec![0.5; embedding_dimension()]), None);
+        let quality = embedding_quality(&enrolled, Some(0.4));
+        assert!(!quality.is_zero_embedding);
+        assert!(quality.l2_norm > 0.0);
+        assert_eq!(quality.sighting_count, enrolled.sighting_count());
+    }
+
+    #[tokio::test]
+    async fn profiles_and_their_sightings_are_paged() {
+        let app = TestApp::new().await;
+        let mut flagged = Profile::new(FaceEmbedding::new(vec![0.5; embedding_dimension()]), None);
+        flagged.set_classification(ProfileClassification::Flagged);
+        app.state.profile_repo.save(&flagged).await.unwrap();
+        for _ in 0..2 {
+            let profile = Profile::new(FaceEmbedding::new(vec![0.5; embedding_dimension()]), None);
+            app.state.profile_repo.save(&profile).await.unwrap();
+        }
+        for minutes_ago in [30, 10, 20] {
+            let sighting = profile_sighting_at(flagged.id(), Uuid::new_v4(), minutes_ago);
+            app.sightings.save(&sighting).await.unwrap();
+        }
+
+        let profiles = body_json(app.get("/api/v1/profiles?limit=2&offset=1").await).await;
+        assert_eq!(profiles["items"].as_array().unwrap().len(), 2);
+        assert_eq!(profiles["total"], 3);
+        assert_eq!(profiles["has_more"], false);
+        assert_eq!(profiles["stats"]["total"], 3);
+        assert_eq!(profiles["stats"]["flagged"], 1);
+        assert_eq!(profiles["stats"]["unknown"], 2);
+
+        let uri = format!("/api/v1/profiles/{}/sightings?limit=2", flagged.id());
+        let sightings = body_json(app.get(&uri).await).await;
+        let items = sightings["items"].as_array().unwrap();
+        assert_eq!(items.len(), 2);
+        assert_eq!(sightings["total"], 3);
+        assert_eq!(sightings["has_more"], true);
+        assert!(items[0]["detected_at"].as_str() > items[1]["detected_at"].as_str());
+    }
+
+    #[tokio::test]
+    async fn timeline_is_a_page_of_entries() {
+        let app = TestApp::new().await;
+        let profile = Profile::new(FaceEmbedding::new(vec![0.5; embedding_dimension()]), None);
+        app.state.profile_repo.save(&profile).await.unwrap();
+        for minutes_ago in [30, 10, 20] {
+            let sighting = profile_sighting_at(profile.id(), Uuid::new_v4(), minutes_ago);
+            app.sightings.save(&sighting).await.unwrap();
+        }
+
+        let uri = format!(
+            "/api/v1/profiles/{}/timeline?limit=2&offset=1",
+            profile.id()
+        );
+        let timeline = body_json(app.get(&uri).await).await;
+        let items = timeline["items"].as_array().unwrap();
+        assert_eq!(items.len(), 2);
+        assert_eq!(timeline["total"], 3);
+        assert_eq!(timeline["offset"], 1);
+        assert_eq!(timeline["has_more"], false);
+        assert!(items[0]["detected_at"].as_str() < items[1]["detected_at"].as_str());
+
+        let missing = format!("/api/v1/profiles/{}/timeline", Uuid::new_v4());
+        assert_eq!(app.get(&missing).await.status(), StatusCode::NOT_FOUND);
+    }
+}
@@ -1,16 +1,25 @@
 //! Recordings API Endpoints
 
 use axum::{
-    extract::{Path, Query, State},
-    http::StatusCode,
+    body::Body,
+    extract::State,
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
     Json,
 };
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::io::SeekFrom;
 use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
 use uuid::Uuid;
 
 use crate::domain::entities::{Recording, RecordingStatus};
-use crate::infrastructure::server::AppState;
+use crate::domain::repositories::RecordingFilter;
+use crate::infrastructure::server::api::extract::{Path, Query};
+use crate::infrastructure::server::api::pagination::{Page, PageParams};
+use crate::infrastructure::server::signed_url::FileUrls;
+use crate::infrastructure::server::{AppState, FILES_ROUTE};
 
 #[derive(Debug, Serialize)]
 pub struct RecordingResponse {
@@ -24,19 +33,20 @@ pub struct RecordingResponse {
     pub frame_count: i64,
     pub status: RecordingStatus,
     pub has_detections: bool,
+    /// Whether the recording file is present on disk.
+    pub file_available: bool,
     pub started_at: String,
     pub ended_at: Option<String>,
 }
 
-impl From<Recording> for RecordingResponse {
-    fn from(r: Recording) -> Self {
+impl RecordingResponse {
+    /// Builds the response, checking for the file on disk.
+    pub async fn new(r: Recording, urls: &FileUrls) -> Self {
+        let file_available = file_available(&r).await;
         Self {
             id: r.id(),
             camera_id: r.camera_id(),
-            file_url: format!(
-                "/files/recordings/{}",
-                r.file_path().split('/').last().unwrap_or("")
-            ),
+            file_url: recording_url(&r, urls),
             file_size_bytes: r.file_size_bytes(),
             file_size_human: format_bytes(r.file_size_bytes()),
             duration_ms: r.duration_ms(),
@@ -44,12 +54,50 @@ impl From<Recording> for RecordingResponse {
             frame_count: r.frame_count(),
             status: r.status(),
             has_detections: r.has_detections(),
+            file_available,
             started_at: r.started_at().to_rfc3339(),
             ended_at: r.ended_at().map(|t| t.to_rfc3339()),
         }
     }
 }
 
+/// URL of the recording's file under `/files/recordings`, signed when
+/// `/files` is protected.
+fn recording_url(recording: &Recording, urls: &FileUrls) -> String {
+    let filename = recording.file_path().split('/').last().unwrap_or("");
+    urls.url(format!("{}/recordings/{}", FILES_ROUTE, filename))
+}
+
+/// Checks whether the recording file exists on disk.
+async fn file_available(recording: &Recording) -> bool {
+    tokio::fs::metadata(recording.file_path())
+        .await
+        .is_ok_and(|metadata| metadata.is_file())
+}
+
+/// Finds a recording by id. Recordings marked for deletion are not found;
+/// only [`restore_recording`] reaches them.
+async fn find_recording(state: &AppState, id: Uuid) -> Result<Recording, StatusCode> {
+    state
+        .recording_repo
+        .find_by_id(id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .filter(|r| r.status() != RecordingStatus::Deleting)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Fails with `410 Gone` when a finished recording's file has disappeared.
+/// The row is left alone; maintenance marks it for deletion once the file
+/// stays missing.
+async fn require_file(recording: &Recording) -> Result<(), StatusCode> {
+    if recording.is_active() || file_available(recording).await {
+        Ok(())
+    } else {
+        Err(StatusCode::GONE)
+    }
+}
+
 fn format_bytes(bytes: i64) -> String {
     const KB: i64 = 1024;
     const MB: i64 = KB * 1024;
@@ -84,40 +132,58 @@ fn format_duration(ms: i64) -> String {
 pub struct RecordingsQuery {
     pub camera_id: Option<Uuid>,
     pub has_detections: Option<bool>,
-    pub limit: Option<i64>,
+    pub start: Option<DateTime<Utc>>,
+    pub end: Option<DateTime<Utc>>,
+}
+
+impl RecordingsQuery {
+    /// Combines every given parameter for one page; with none, matches the
+    /// most recent recordings.
+    fn filter(&self, page: &PageParams) -> RecordingFilter {
+        let mut filter = RecordingFilter::new(page.limit()).offset(page.offset());
+        if let Some(camera_id) = self.camera_id {
+            filter = filter.camera(camera_id);
+        }
+        if let Some(has_detections) = self.has_detections {
+            filter = filter.has_detections(has_detections);
+        }
+        if let Some(start) = self.start {
+            filter = filter.started_after(start);
+        }
+        if let Some(end) = self.end {
+            filter = filter.started_before(end);
+        }
+        filter
+    }
 }
 
 /// GET /api/v1/recordings
 pub async fn list_recordings(
     State(state): State<Arc<AppState>>,
     Query(query): Query<RecordingsQuery>,
-) -> Result<Json<Vec<RecordingResponse>>, StatusCode> {
-    let limit = query.limit.unwrap_or(50);
-
-    let recordings = if let Some(camera_id) = query.camera_id {
-        state
-            .recording_repo
-            .find_by_camera(camera_id, limit)
-            .await
-            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-    } else if query.has_detections == Some(true) {
-        state
-            .recording_repo
-            .find_with_detections(limit)
-            .await
-            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-    } else {
-        // Return recent recordings
-        state
-            .recording_repo
-            .find_with_detections(limit)
-            .await
-            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-    };
+    page: PageParams,
+) -> Result<Json<Page<RecordingResponse>>, StatusCode> {
+    let filter = query.filter(&page);
+    let recordings = state
+        .recording_repo
+        .query(&filter)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let total = state
+        .recording_repo
+        .count_matching(&filter)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
-    let responses: Vec<RecordingResponse> = recordings.into_iter().map(Into::into).collect();
+    let items = futures::future::join_all(
+        recordings
+            .into_iter()
+            .map(|r| RecordingResponse::new(r, &state.file_urls)),
+    )
+    .await;
+    let page = Page::new(items, total, &page);
 
-    Ok(Json(responses))
+    Ok(Json(page))
 }
 
 /// GET /api/v1/recordings/:id
@@ -125,47 +191,76 @@ pub async fn get_recording(
     State(state): State<Arc<AppState>>,
     Path(id): Path<Uuid>,
 ) -> Result<Json<RecordingResponse>, StatusCode> {
-    let recording = state
-        .recording_repo
-        .find_by_id(id)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-        .ok_or(StatusCode::NOT_FOUND)?;
+    let recording = find_recording(&state, id).await?;
 
-    Ok(Json(recording.into()))
+    require_file(&recording).await?;
+
+    Ok(Json(
+        RecordingResponse::new(recording, &state.file_urls).await,
+    ))
 }
 
-/// DELETE /api/v1/recordings/:id
+/// DELETE /api/v1/recordings/:id - Marks a recording for deletion; its file
+/// is purged once the deletion grace period passes
 pub async fn delete_recording(
     State(state): State<Arc<AppState>>,
     Path(id): Path<Uuid>,
 ) -> Result<StatusCode, StatusCode> {
+    let mut recording = find_recording(&state, id).await?;
+
+    if recording.is_active() {
+        return Err(StatusCode::CONFLICT);
+    }
+
+    recording.mark_for_deletion();
     state
         .recording_repo
-        .delete(id)
+        .update(&recording)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
     Ok(StatusCode::NO_CONTENT)
 }
 
-/// GET /api/v1/recordings/:id/play
-pub async fn play_recording(
+/// POST /api/v1/recordings/:id/restore - Cancels a pending deletion
+pub async fn restore_recording(
     State(state): State<Arc<AppState>>,
     Path(id): Path<Uuid>,
-) -> Result<Json<PlaybackResponse>, StatusCode> {
-    let recording = state
+) -> Result<Json<RecordingResponse>, StatusCode> {
+    let mut recording = state
         .recording_repo
         .find_by_id(id)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
         .ok_or(StatusCode::NOT_FOUND)?;
 
-    let filename = recording.file_path().split('/').last().unwrap_or("");
+    if !recording.restore() {
+        return Err(StatusCode::CONFLICT);
+    }
+
+    state
+        .recording_repo
+        .update(&recording)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(
+        RecordingResponse::new(recording, &state.file_urls).await,
+    ))
+}
+
+/// GET /api/v1/recordings/:id/play
+pub async fn play_recording(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<PlaybackResponse>, StatusCode> {
+    let recording = find_recording(&state, id).await?;
+
+    require_file(&recording).await?;
 
     Ok(Json(PlaybackResponse {
         id: recording.id(),
-        url: format!("/files/recordings/{}", filename),
+        url: recording_url(&recording, &state.file_urls),
         duration_ms: recording.duration_ms(),
     }))
 }
@@ -176,3 +271,309 @@ pub struct PlaybackResponse {
     pub url: String,
     pub duration_ms: i64,
 }
+
+/// GET /api/v1/recordings/:id/stream
+///
+/// Serves the recording file with HTTP Range support for seeking.
+pub async fn stream_recording(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<Uuid>,
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
+    let recording = find_recording(&state, id).await?;
+
+    let range = headers
+        .get(header::RANGE)
+        .and_then(|value| value.to_str().ok());
+
+    stream_file(std::path::Path::new(recording.file_path()), range).await
+}
+
+/// Chunk size used when streaming file contents.
+const STREAM_CHUNK_BYTES: usize = 64 * 1024;
+
+/// Parses a single `bytes=` range against a file of `len` bytes.
+///
+/// Returns `Ok(None)` when the whole file should be served (no header, or a
+/// form we don't handle such as multiple ranges), `Ok(Some((start, end)))`
+/// with an inclusive end for a satisfiable range, and `Err(())` when the
+/// range lies outside the file.
+fn parse_range(header: Option<&str>, len: u64) -> Result<Option<(u64, u64)>, ()> {
+    let Some(spec) = header.and_then(|h| h.trim().strip_prefix("bytes=")) else {
+        return Ok(None);
+    };
+    if spec.contains(',') {
+        return Ok(None);
+    }
+    let Some((start, end)) = spec.split_once('-') else {
+        return Ok(None);
+    };
+
+    let (start, end) = match (start.trim(), end.trim()) {
+        ("", suffix) => {
+            let Ok(suffix) = suffix.parse::<u64>() else {
+                return Ok(None);
+            };
+            if suffix == 0 || len == 0 {
+                return Err(());
+            }
+            (len.saturating_sub(suffix), len - 1)
+        }
+        (start, end) => {
+            let Ok(start) = start.parse::<u64>() else {
+                return Ok(None);
+            };
+            let end = if end.is_empty() {
+                len.saturating_sub(1)
+            } else {
+                match end.parse::<u64>() {
+                    Ok(end) => end.min(len.saturating_sub(1)),
+                    Err(_) => return Ok(None),
+                }
+            };
+            if start >= len || start > end {
+                return Err(());
+            }
+            (start, end)
+        }
+    };
+
+    Ok(Some((start, end)))
+}
+
+/// Streams `path`, honoring an optional `Range` header value.
+async fn stream_file(path: &std::path::Path, range: Option<&str>) -> Result<Response, StatusCode> {
+    let mut file = tokio::fs::File::open(path).await.map_err(|e| {
+        if e.kind() == std::io::ErrorKind::NotFound {
+            StatusCode::NOT_FOUND
+        } else {
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    })?;
+    let len = file
+        .metadata()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .len();
+
+    let (status, start, end) = match parse_range(range, len) {
+        Ok(Some((start, end))) => (StatusCode::PARTIAL_CONTENT, start, end),
+        Ok(None) => (StatusCode::OK, 0, len.saturating_sub(1)),
+        Err(()) => {
+            return Ok((
+                StatusCode::RANGE_NOT_SATISFIABLE,
+                [
+                    (header::CONTENT_RANGE, format!("bytes */{}", len)),
+                    (header::ACCEPT_RANGES, "bytes".to_string()),
+                ],
+            )
+                .into_response());
+        }
+    };
+    let content_length = if len == 0 { 0 } else { end - start + 1 };
+
+    file.seek(SeekFrom::Start(start))
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let stream =
+        futures::stream::unfold((file, content_length), |(mut file, remaining)| async move {
+            if remaining == 0 {
+                return None;
+            }
+            let mut buf = vec![0u8; remaining.min(STREAM_CHUNK_BYTES as u64) as usize];
+            match file.read(&mut buf).await {
+                Ok(0) => None,
+                Ok(n) => {
+                    buf.truncate(n);
+                    Some((Ok::<_, std::io::Error>(buf), (file, remaining - n as u64)))
+                }
+                Err(e) => Some((Err(e), (file, 0))),
+            }
+        });
+
+    let mut response = Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, "video/mp4")
+        .header(header::ACCEPT_RANGES, "bytes")
+        .header(header::CONTENT_LENGTH, content_length);
+    if status == StatusCode::PARTIAL_CONTENT {
+        response = response.header(
+            header::CONTENT_RANGE,
+            format!("bytes {}-{}/{}", start, end, len),
+        );
+    }
+
+    response
+        .body(Body::from_stream(stream))
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::server::testing::TestApp;
+    use axum::http::Method;
+    use serde_json::Value;
+    use std::io::Write;
+
+    fn fixture() -> tempfile::NamedTempFile {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        let data: Vec<u8> = (0..=255u8).cycle().take(1000).collect();
+        file.write_all(&data).unwrap();
+        file
+    }
+
+    async fn body_bytes(response: Response) -> Vec<u8> {
+        axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap()
+            .to_vec()
+    }
+
+    fn parse_filter(query: &str) -> RecordingFilter {
+        let page: PageParams = serde_urlencoded::from_str(query).unwrap();
+        serde_urlencoded::from_str::<RecordingsQuery>(query)
+            .unwrap()
+            .filter(&page)
+    }
+
+    #[test]
+    fn recordings_query_without_filters_lists_recent_recordings() {
+        // Not only those with detections
+        assert_eq!(parse_filter(""), RecordingFilter::new(100));
+    }
+
+    #[test]
+    fn recordings_query_combines_filters() {
+        let camera_id = Uuid::new_v4();
+        let filter = parse_filter(&format!(
+            "camera_id={}&has_detections=true&start=2024-12-24T00:00:00Z&end=2024-12-25T00:00:00Z&limit=10&offset=20",
+            camera_id
+        ));
+
+        let start = "2024-12-24T00:00:00Z".parse().unwrap();
+        let end = "2024-12-25T00:00:00Z".parse().unwrap();
+        assert_eq!(
+            filter,
+            RecordingFilter::new(10)
+                .offset(20)
+                .camera(camera_id)
+                .has_detections(true)
+                .started_after(start)
+                .started_before(end)
+        );
+    }
+
+    #[tokio::test]
+    async fn stream_without_range_returns_whole_file() {
+        let file = fixture();
+
+        let response = stream_file(file.path(), None).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::ACCEPT_RANGES], "bytes");
+        assert_eq!(response.headers()[header::CONTENT_LENGTH], "1000");
+        assert_eq!(body_bytes(response).await.len(), 1000);
+    }
+
+    #[tokio::test]
+    async fn stream_with_mid_file_range_returns_partial_content() {
+        let file = fixture();
+
+        let response = stream_file(file.path(), Some("bytes=100-199"))
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(
+            response.headers()[header::CONTENT_RANGE],
+            "bytes 100-199/1000"
+        );
+        assert_eq!(response.headers()[header::CONTENT_LENGTH], "100");
+        let body = body_bytes(response).await;
+        assert_eq!(body.len(), 100);
+        assert_eq!(body[0], 100);
+        assert_eq!(body[99], 199);
+    }
+
+    #[tokio::test]
+    async fn stream_with_out_of_range_start_is_not_satisfiable() {
+        let file = fixture();
+
+        let response = stream_file(file.path(), Some("bytes=5000-")).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+        assert_eq!(response.headers()[header::CONTENT_RANGE], "bytes */1000");
+    }
+
+    #[tokio::test]
+    async fn stream_missing_file_is_not_found() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let result = stream_file(&dir.path().join("missing.mp4"), None).await;
+
+        assert_eq!(result.unwrap_err(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn recording_marked_for_deletion_is_not_streamed_until_restored() {
+        let app = TestApp::new().await;
+        let file = fixture();
+        let mut recording =
+            Recording::new(Uuid::new_v4(), file.path().to_string_lossy().to_string());
+        recording.complete(1000, 5000, 150);
+        app.state.recording_repo.save(&recording).await.unwrap();
+        let uri = format!("/api/v1/recordings/{}", recording.id());
+        let stream = format!("{}/stream", uri);
+        assert_eq!(app.get(&stream).await.status(), StatusCode::OK);
+
+        let deleted = app.json(Method::DELETE, &uri, Value::Null).await;
+        assert_eq!(deleted.status(), StatusCode::NO_CONTENT);
+
+        // The file is kept for the grace period, but no longer served
+        assert!(file.path().exists());
+        assert_eq!(app.get(&stream).await.status(), StatusCode::NOT_FOUND);
+        assert_eq!(app.get(&uri).await.status(), StatusCode::NOT_FOUND);
+        assert_eq!(
+            app.get(&format!("{}/play", uri)).await.status(),
+            StatusCode::NOT_FOUND
+        );
+
+        let restored = app
+            .json(Method::POST, &format!("{}/restore", uri), Value::Null)
+            .await;
+        assert_eq!(restored.status(), StatusCode::OK);
+        assert_eq!(app.get(&stream).await.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn response_reports_present_file_as_available() {
+        let file = fixture();
+        let recording = Recording::new(Uuid::new_v4(), file.path().to_string_lossy().to_string());
+
+        let response = RecordingResponse::new(recording, &FileUrls::default()).await;
+
+        assert!(response.file_available);
+    }
+
+    #[tokio::test]
+    async fn response_reports_missing_file_as_unavailable() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("gone.mp4");
+        let recording = Recording::new(Uuid::new_v4(), path.to_string_lossy().to_string());
+
+        let response = RecordingResponse::new(recording, &FileUrls::default()).await;
+
+        assert!(!response.file_available);
+    }
+
+    #[test]
+    fn parse_range_handles_open_and_suffix_forms() {
+        assert_eq!(parse_range(Some("bytes=900-"), 1000), Ok(Some((900, 999))));
+        assert_eq!(parse_range(Some("bytes=-100"), 1000), Ok(Some((900, 999))));
+        assert_eq!(parse_range(Some("bytes=0-5000"), 1000), Ok(Some((0, 999))));
+        assert_eq!(parse_range(Some("bytes=0-1,5-6"), 1000), Ok(None));
+        assert_eq!(parse_range(None, 1000), Ok(None));
+    }
+}
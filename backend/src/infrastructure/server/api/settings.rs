@@ -1,10 +1,24 @@
 //! Settings API Endpoints
 
-use axum::{extract::State, http::StatusCode, Json};
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::Utc;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::sync::Arc;
+use uuid::Uuid;
 
-use crate::domain::entities::{DetectionSettings, DisplaySettings, NotificationSettings, Settings};
+use crate::application::services::RecordingConfig;
+use crate::domain::entities::{
+    ClassificationSettings, DetectionSettings, DisplaySettings, NotificationSettings, Settings,
+    SettingsValidationError, SightingCooldownScope, StoredSettings,
+};
+use crate::domain::events::{DomainEvent, SettingsChangedEvent};
+use crate::domain::repositories::RepositoryError;
 use crate::infrastructure::server::AppState;
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -13,6 +27,11 @@ pub struct SettingsResponse {
     pub recording: RecordingSettingsResponse,
     pub notification: NotificationSettingsResponse,
     pub display: DisplaySettingsResponse,
+    pub classification: ClassificationSettings,
+    /// Send back with updates; a stale version is rejected with 409.
+    pub version: i64,
+    pub created_at: String,
+    pub updated_at: String,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -20,8 +39,20 @@ pub struct DetectionSettingsResponse {
     pub min_confidence: f32,
     pub match_threshold: f32,
     pub sighting_cooldown_secs: i32,
+    #[serde(default)]
+    pub sighting_cooldown_scope: SightingCooldownScope,
+    #[serde(default)]
+    pub min_face_area_px: u32,
     pub motion_detection_enabled: bool,
     pub motion_sensitivity: f32,
+    #[serde(default = "default_detection_sample_every")]
+    pub detection_sample_every: u64,
+    #[serde(default)]
+    pub detection_only: bool,
+}
+
+fn default_detection_sample_every() -> u64 {
+    DetectionSettings::default().detection_sample_every
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -30,9 +61,15 @@ pub struct RecordingSettingsResponse {
     pub pre_trigger_buffer_secs: i32,
     pub post_trigger_buffer_secs: i32,
     pub max_segment_duration_secs: i32,
+    pub min_recording_duration_secs: i32,
     pub max_storage_bytes: i64,
     pub max_storage_human: String,
     pub auto_cleanup_enabled: bool,
+    pub cleanup_target_percent: f64,
+    pub min_retention_days: i32,
+    pub detection_retention_days: i32,
+    #[serde(default)]
+    pub camera_overrides: HashMap<Uuid, bool>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -51,24 +88,34 @@ pub struct DisplaySettingsResponse {
     pub dark_mode: bool,
 }
 
-impl From<Settings> for SettingsResponse {
-    fn from(s: Settings) -> Self {
+impl From<StoredSettings> for SettingsResponse {
+    fn from(stored: StoredSettings) -> Self {
+        let s = stored.settings;
         Self {
             detection: DetectionSettingsResponse {
                 min_confidence: s.detection.min_confidence,
                 match_threshold: s.detection.match_threshold,
                 sighting_cooldown_secs: s.detection.sighting_cooldown_secs,
+                sighting_cooldown_scope: s.detection.sighting_cooldown_scope,
+                min_face_area_px: s.detection.min_face_area_px,
                 motion_detection_enabled: s.detection.motion_detection_enabled,
                 motion_sensitivity: s.detection.motion_sensitivity,
+                detection_sample_every: s.detection.detection_sample_every,
+                detection_only: s.detection.detection_only,
             },
             recording: RecordingSettingsResponse {
                 detection_triggered: s.recording.detection_triggered,
                 pre_trigger_buffer_secs: s.recording.pre_trigger_buffer_secs,
                 post_trigger_buffer_secs: s.recording.post_trigger_buffer_secs,
                 max_segment_duration_secs: s.recording.max_segment_duration_secs,
+                min_recording_duration_secs: s.recording.min_recording_duration_secs,
                 max_storage_bytes: s.recording.max_storage_bytes,
                 max_storage_human: format_bytes(s.recording.max_storage_bytes),
                 auto_cleanup_enabled: s.recording.auto_cleanup_enabled,
+                cleanup_target_percent: s.recording.cleanup_target_percent,
+                min_retention_days: s.recording.min_retention_days,
+                detection_retention_days: s.recording.detection_retention_days,
+                camera_overrides: s.recording.camera_overrides,
             },
             notification: NotificationSettingsResponse {
                 desktop_notifications: s.notification.desktop_notifications,
@@ -82,6 +129,10 @@ impl From<Settings> for SettingsResponse {
                 show_names: s.display.show_names,
                 dark_mode: s.display.dark_mode,
             },
+            classification: s.classification,
+            version: stored.version,
+            created_at: stored.created_at.to_rfc3339(),
+            updated_at: stored.updated_at.to_rfc3339(),
         }
     }
 }
@@ -93,10 +144,15 @@ fn format_bytes(bytes: i64) -> String {
 
 #[derive(Debug, Deserialize)]
 pub struct UpdateSettingsBody {
+    /// Version the client last read. When omitted, the update applies on top
+    /// of whatever is current.
+    pub version: Option<i64>,
     pub detection: Option<DetectionSettingsResponse>,
     pub recording: Option<UpdateRecordingSettings>,
     pub notification: Option<NotificationSettingsResponse>,
     pub display: Option<DisplaySettingsResponse>,
+    /// Replaces all classification rules when present.
+    pub classification: Option<ClassificationSettings>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -105,45 +161,77 @@ pub struct UpdateRecordingSettings {
     pub pre_trigger_buffer_secs: Option<i32>,
     pub post_trigger_buffer_secs: Option<i32>,
     pub max_segment_duration_secs: Option<i32>,
+    pub min_recording_duration_secs: Option<i32>,
     pub max_storage_gb: Option<i64>,
     pub auto_cleanup_enabled: Option<bool>,
+    pub cleanup_target_percent: Option<f64>,
+    pub min_retention_days: Option<i32>,
+    pub detection_retention_days: Option<i32>,
+    /// Replaces all per-camera overrides when present.
+    pub camera_overrides: Option<HashMap<Uuid, bool>>,
+}
+
+/// Body returned when an update is rejected.
+#[derive(Debug, Serialize)]
+pub struct SettingsErrorResponse {
+    pub field: String,
+    pub error: String,
+}
+
+impl From<SettingsValidationError> for SettingsErrorResponse {
+    fn from(e: SettingsValidationError) -> Self {
+        Self {
+            field: e.field.to_string(),
+            error: e.reason,
+        }
+    }
 }
 
 /// GET /api/v1/settings
 pub async fn get_settings(
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<SettingsResponse>, StatusCode> {
-    let settings = state
+    let stored = state
         .settings_repo
-        .get()
+        .get_stored()
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
-    Ok(Json(settings.into()))
+    Ok(Json(stored.into()))
 }
 
 /// PUT /api/v1/settings
 pub async fn update_settings(
     State(state): State<Arc<AppState>>,
     Json(body): Json<UpdateSettingsBody>,
-) -> Result<Json<SettingsResponse>, StatusCode> {
-    let mut settings = state
+) -> Result<Json<SettingsResponse>, Response> {
+    let stored = state
         .settings_repo
-        .get()
+        .get_stored()
         .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())?;
+    let expected_version = body.version.unwrap_or(stored.version);
+    let mut settings = stored.settings;
+
+    let mut changed = Vec::new();
 
     if let Some(detection) = body.detection {
+        changed.push("detection");
         settings.detection = DetectionSettings {
             min_confidence: detection.min_confidence,
             match_threshold: detection.match_threshold,
             sighting_cooldown_secs: detection.sighting_cooldown_secs,
+            sighting_cooldown_scope: detection.sighting_cooldown_scope,
+            min_face_area_px: detection.min_face_area_px,
             motion_detection_enabled: detection.motion_detection_enabled,
             motion_sensitivity: detection.motion_sensitivity,
+            detection_sample_every: detection.detection_sample_every,
+            detection_only: detection.detection_only,
         };
     }
 
     if let Some(recording) = body.recording {
+        changed.push("recording");
         if let Some(v) = recording.detection_triggered {
             settings.recording.detection_triggered = v;
         }
@@ -156,15 +244,31 @@ pub async fn update_settings(
         if let Some(v) = recording.max_segment_duration_secs {
             settings.recording.max_segment_duration_secs = v;
         }
+        if let Some(v) = recording.min_recording_duration_secs {
+            settings.recording.min_recording_duration_secs = v;
+        }
         if let Some(gb) = recording.max_storage_gb {
-            settings.recording.max_storage_bytes = gb * 1024 * 1024 * 1024;
+            settings.recording.max_storage_bytes = gb.saturating_mul(1024 * 1024 * 1024);
         }
         if let Some(v) = recording.auto_cleanup_enabled {
             settings.recording.auto_cleanup_enabled = v;
         }
+        if let Some(v) = recording.cleanup_target_percent {
+            settings.recording.cleanup_target_percent = v;
+        }
+        if let Some(v) = recording.min_retention_days {
+            settings.recording.min_retention_days = v;
+        }
+        if let Some(v) = recording.detection_retention_days {
+            settings.recording.detection_retention_days = v;
+        }
+        if let Some(v) = recording.camera_overrides {
+            settings.recording.camera_overrides = v;
+        }
     }
 
     if let Some(notification) = body.notification {
+        changed.push("notification");
         settings.notification = NotificationSettings {
             desktop_notifications: notification.desktop_notifications,
             notify_new_profile: notification.notify_new_profile,
@@ -174,6 +278,7 @@ pub async fn update_settings(
     }
 
     if let Some(display) = body.display {
+        changed.push("display");
         settings.display = DisplaySettings {
             show_bounding_boxes: display.show_bounding_boxes,
             show_confidence: display.show_confidence,
@@ -182,11 +287,98 @@ pub async fn update_settings(
         };
     }
 
+    if let Some(classification) = body.classification {
+        changed.push("classification");
+        settings.classification = classification;
+    }
+
+    if let Err(e) = settings.validate() {
+        let body = SettingsErrorResponse::from(e);
+        return Err((StatusCode::BAD_REQUEST, Json(body)).into_response());
+    }
+
+    let saved = match state.settings_repo.save(&settings, expected_version).await {
+        Ok(saved) => saved,
+        Err(RepositoryError::Conflict(_)) => {
+            let body = SettingsErrorResponse {
+                field: "version".to_string(),
+                error: "settings were changed by another client; reload and retry".to_string(),
+            };
+            return Err((StatusCode::CONFLICT, Json(body)).into_response());
+        }
+        Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR.into_response()),
+    };
+
+    apply_settings(&state, &settings).await;
+
+    for category in changed {
+        state
+            .event_bus
+            .publish(DomainEvent::SettingsChanged(SettingsChangedEvent {
+                category: category.to_string(),
+                timestamp: Utc::now(),
+            }));
+    }
+
+    Ok(Json(saved.into()))
+}
+
+/// Pushes validated settings to the running services.
+async fn apply_settings(state: &AppState, settings: &Settings) {
     state
-        .settings_repo
-        .save(&settings)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        .detection_service
+        .apply_settings(&settings.detection)
+        .await;
+    state
+        .camera_service
+        .set_detection_sample_every(settings.detection.detection_sample_every);
 
-    Ok(Json(settings.into()))
+    let recording = state.recording_service.config().await;
+    state
+        .recording_service
+        .update_config(RecordingConfig {
+            detection_triggered: settings.recording.detection_triggered,
+            pre_trigger_buffer_secs: settings.recording.pre_trigger_buffer_secs,
+            post_trigger_buffer_secs: settings.recording.post_trigger_buffer_secs,
+            max_segment_duration_secs: settings.recording.max_segment_duration_secs,
+            min_recording_duration_secs: settings.recording.min_recording_duration_secs,
+            camera_overrides: settings.recording.camera_overrides.clone(),
+            ..recording
+        })
+        .await;
+
+    state
+        .profile_classifier
+        .set_rules(settings.classification.rules.clone())
+        .await;
+
+    let storage = state.storage_manager.config().await;
+    state
+        .storage_manager
+        .update_config(storage.with_settings(&settings.recording))
+        .await;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::infrastructure::server::testing::{body_json, TestApp};
+    use axum::http::{Method, StatusCode};
+
+    #[tokio::test]
+    async fn updating_match_threshold_changes_the_running_matcher() {
+        let app = TestApp::new().await;
+        let mut detection = body_json(app.get("/api/v1/settings").await).await["detection"].clone();
+        detection["match_threshold"] = 0.35.into();
+
+        let response = app
+            .json(
+                Method::PUT,
+                "/api/v1/settings",
+                serde_json::json!({ "detection": detection }),
+            )
+            .await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(app.state.face_matcher.threshold().await, 0.35);
+    }
 }
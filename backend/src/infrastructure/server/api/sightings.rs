@@ -1,17 +1,27 @@
 //! Sightings API Endpoints
 
 use axum::{
-    extract::{Path, Query, State},
-    http::StatusCode,
+    body::{Body, Bytes},
+    extract::State,
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
     Json,
 };
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::sync::Arc;
 use uuid::Uuid;
 
+use crate::application::services::{remove_snapshot_files, SnapshotFormat, THUMBS_DIR};
 use crate::application::use_cases::TimeRange;
-use crate::domain::entities::Sighting;
+use crate::domain::entities::{Profile, Sighting};
+use crate::domain::repositories::{ProfileRepository, SightingRepository};
+use crate::infrastructure::server::api::cameras::camera_names;
+use crate::infrastructure::server::api::extract::{Path, Query};
+use crate::infrastructure::server::api::pagination::{DEFAULT_PAGE_LIMIT, MAX_PAGE_LIMIT};
+use crate::infrastructure::server::api::profiles::thumbnail_url;
+use crate::infrastructure::server::signed_url::FileUrls;
 use crate::infrastructure::server::AppState;
 
 #[derive(Debug, Serialize)]
@@ -21,7 +31,10 @@ pub struct SightingResponse {
     pub camera_id: Uuid,
     pub snapshot_url: String,
     pub bounding_box: BoundingBoxResponse,
+    /// Detector score for the face.
     pub confidence: f32,
+    /// How confidently the face was matched to the profile.
+    pub match_confidence: Option<f32>,
     pub location: Option<LocationResponse>,
     pub recording_id: Option<Uuid>,
     pub recording_timestamp_ms: Option<i64>,
@@ -42,20 +55,14 @@ pub struct LocationResponse {
     pub longitude: f64,
 }
 
-impl From<Sighting> for SightingResponse {
-    fn from(s: Sighting) -> Self {
+impl SightingResponse {
+    pub fn new(s: Sighting, urls: &FileUrls) -> Self {
         let bbox = s.bounding_box();
-        // Extract just the filename if it's an absolute path
-        let snapshot_path = s.snapshot_path();
-        let filename = std::path::Path::new(snapshot_path)
-            .file_name()
-            .and_then(|n| n.to_str())
-            .unwrap_or(snapshot_path);
         Self {
             id: s.id(),
             profile_id: s.profile_id(),
             camera_id: s.camera_id(),
-            snapshot_url: format!("/files/snapshots/{}", filename),
+            snapshot_url: thumbnail_url(s.snapshot_path(), urls),
             bounding_box: BoundingBoxResponse {
                 x: bbox.x(),
                 y: bbox.y(),
@@ -63,6 +70,7 @@ impl From<Sighting> for SightingResponse {
                 height: bbox.height(),
             },
             confidence: s.confidence(),
+            match_confidence: s.match_confidence(),
             location: s.location().map(|l| LocationResponse {
                 latitude: l.latitude(),
                 longitude: l.longitude(),
@@ -111,11 +119,71 @@ pub async fn list_sightings(
             .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
     };
 
-    let responses: Vec<SightingResponse> = sightings.into_iter().map(Into::into).collect();
+    let responses: Vec<SightingResponse> = sightings
+        .into_iter()
+        .map(|s| SightingResponse::new(s, &state.file_urls))
+        .collect();
 
     Ok(Json(responses))
 }
 
+#[derive(Debug, Deserialize)]
+pub struct InAreaQuery {
+    pub min_lat: f64,
+    pub min_lon: f64,
+    pub max_lat: f64,
+    pub max_lon: f64,
+    pub limit: Option<i64>,
+}
+
+impl InAreaQuery {
+    /// Returns whether the box has real coordinates and its minimums don't
+    /// exceed its maximums.
+    fn is_valid(&self) -> bool {
+        let lat = -90.0..=90.0;
+        let lon = -180.0..=180.0;
+        lat.contains(&self.min_lat)
+            && lat.contains(&self.max_lat)
+            && lon.contains(&self.min_lon)
+            && lon.contains(&self.max_lon)
+            && self.min_lat <= self.max_lat
+            && self.min_lon <= self.max_lon
+    }
+}
+
+/// GET /api/v1/sightings/in-area
+pub async fn get_sightings_in_area(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<InAreaQuery>,
+) -> Result<Json<Vec<SightingResponse>>, StatusCode> {
+    if !query.is_valid() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_PAGE_LIMIT)
+        .clamp(1, MAX_PAGE_LIMIT);
+
+    let sightings = state
+        .sighting_repo
+        .find_in_bbox(
+            query.min_lat,
+            query.min_lon,
+            query.max_lat,
+            query.max_lon,
+            limit,
+        )
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(
+        sightings
+            .into_iter()
+            .map(|s| SightingResponse::new(s, &state.file_urls))
+            .collect(),
+    ))
+}
+
 /// GET /api/v1/sightings/:id
 pub async fn get_sighting(
     State(state): State<Arc<AppState>>,
@@ -128,5 +196,515 @@ pub async fn get_sighting(
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
         .ok_or(StatusCode::NOT_FOUND)?;
 
-    Ok(Json(sighting.into()))
+    Ok(Json(SightingResponse::new(sighting, &state.file_urls)))
+}
+
+/// DELETE /api/v1/sightings/:id
+pub async fn delete_sighting(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, StatusCode> {
+    let sighting = state
+        .sighting_repo
+        .delete(id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let snapshots_dir = state.storage_manager.snapshots_dir().await;
+    remove_snapshot_files(&snapshots_dir, sighting.snapshot_path()).await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Default bound of a sighting thumbnail, in pixels.
+const DEFAULT_THUMBNAIL_SIZE: u32 = 128;
+
+/// Smallest and largest thumbnail bounds that may be requested.
+const THUMBNAIL_SIZE_RANGE: (u32, u32) = (16, 1024);
+
+#[derive(Debug, Deserialize)]
+pub struct ThumbnailQuery {
+    /// Longest side of the thumbnail, in pixels.
+    pub size: Option<u32>,
+}
+
+/// GET /api/v1/sightings/:id/thumbnail
+pub async fn get_sighting_thumbnail(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<ThumbnailQuery>,
+) -> Result<Response, StatusCode> {
+    let (min_size, max_size) = THUMBNAIL_SIZE_RANGE;
+    let size = query
+        .size
+        .unwrap_or(DEFAULT_THUMBNAIL_SIZE)
+        .clamp(min_size, max_size);
+
+    let sighting = state
+        .sighting_repo
+        .find_by_id(id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    // Only the file name is trusted, so stored paths can't escape the directory
+    let name = std::path::Path::new(sighting.snapshot_path())
+        .file_name()
+        .ok_or(StatusCode::NOT_FOUND)?
+        .to_owned();
+    let snapshots_dir = state.storage_manager.snapshots_dir().await;
+    let snapshot = snapshots_dir.join(&name);
+    let cached = snapshots_dir
+        .join(THUMBS_DIR)
+        .join(&name)
+        .join(format!("{}.jpg", size));
+
+    let jpeg = thumbnail(&snapshot, &cached, size)
+        .await
+        .map_err(|e| {
+            tracing::warn!("Failed to create thumbnail for sighting {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "image/jpeg"),
+            (header::CACHE_CONTROL, "max-age=86400"),
+        ],
+        jpeg,
+    )
+        .into_response())
+}
+
+/// Returns `snapshot` shrunk to fit within `size` pixels as JPEG, reading it
+/// from `cached` or writing it there on first use. Returns `None` when the
+/// snapshot file is missing.
+async fn thumbnail(
+    snapshot: &std::path::Path,
+    cached: &std::path::Path,
+    size: u32,
+) -> anyhow::Result<Option<Vec<u8>>> {
+    if !tokio::fs::try_exists(snapshot).await? {
+        return Ok(None);
+    }
+    if let Ok(jpeg) = tokio::fs::read(cached).await {
+        return Ok(Some(jpeg));
+    }
+
+    let original = match tokio::fs::read(snapshot).await {
+        Ok(original) => original,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    let jpeg = tokio::task::spawn_blocking(move || -> anyhow::Result<Vec<u8>> {
+        let img = image::load_from_memory(&original)?;
+        Ok(SnapshotFormat::default().encode(&img.thumbnail(size, size))?)
+    })
+    .await??;
+
+    // A failed cache write still serves the thumbnail
+    if let Some(parent) = cached.parent() {
+        if let Err(e) = tokio::fs::create_dir_all(parent).await {
+            tracing::warn!("Failed to cache thumbnail {}: {}", cached.display(), e);
+            return Ok(Some(jpeg));
+        }
+    }
+    if let Err(e) = tokio::fs::write(cached, &jpeg).await {
+        tracing::warn!("Failed to cache thumbnail {}: {}", cached.display(), e);
+    }
+
+    Ok(Some(jpeg))
+}
+
+/// Sightings fetched per query while streaming an export.
+const EXPORT_PAGE_SIZE: i64 = 500;
+
+const CSV_HEADER: &str = "detected_at,profile_id,profile_name,classification,camera_name,confidence,lat,lon,snapshot_url\n";
+
+#[derive(Debug, Deserialize)]
+pub struct ExportQuery {
+    pub start: Option<DateTime<Utc>>,
+    pub end: Option<DateTime<Utc>>,
+    pub camera_id: Option<Uuid>,
+}
+
+/// GET /api/v1/sightings/export
+pub async fn export_sightings(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ExportQuery>,
+) -> Result<Response, StatusCode> {
+    let camera_names = camera_names(state.camera_repo.as_ref())
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let export = SightingExport {
+        sighting_repo: state.sighting_repo.clone(),
+        profile_repo: state.profile_repo.clone(),
+        camera_names,
+        profiles: HashMap::new(),
+        start: query
+            .start
+            .unwrap_or_else(|| Utc::now() - chrono::Duration::days(1)),
+        end: query.end.unwrap_or_else(Utc::now),
+        camera_id: query.camera_id,
+        cursor: None,
+        header_sent: false,
+        done: false,
+    };
+
+    let stream = futures::stream::unfold(export, |mut export| async move {
+        export
+            .next_chunk()
+            .await
+            .transpose()
+            .map(|chunk| (chunk, export))
+    });
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/csv; charset=utf-8"),
+            (
+                header::CONTENT_DISPOSITION,
+                "attachment; filename=\"sightings.csv\"",
+            ),
+        ],
+        Body::from_stream(stream),
+    )
+        .into_response())
+}
+
+/// Pages backwards through sightings, newest first, rendering each page as CSV.
+struct SightingExport {
+    sighting_repo: Arc<dyn SightingRepository>,
+    profile_repo: Arc<dyn ProfileRepository>,
+    camera_names: HashMap<Uuid, String>,
+    /// Profiles resolved so far, so each is loaded once.
+    profiles: HashMap<Uuid, Profile>,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    camera_id: Option<Uuid>,
+    /// Time and id of the last row written; the next page starts after it.
+    cursor: Option<(DateTime<Utc>, Uuid)>,
+    header_sent: bool,
+    done: bool,
+}
+
+impl SightingExport {
+    /// Returns the next piece of the CSV, or `None` once every row is written.
+    async fn next_chunk(&mut self) -> Result<Option<Bytes>, std::io::Error> {
+        if !self.header_sent {
+            self.header_sent = true;
+            return Ok(Some(Bytes::from_static(CSV_HEADER.as_bytes())));
+        }
+        if self.done {
+            return Ok(None);
+        }
+
+        let page = self
+            .sighting_repo
+            .find_page_before(
+                self.start,
+                self.end,
+                self.camera_id,
+                self.cursor,
+                EXPORT_PAGE_SIZE,
+            )
+            .await
+            .map_err(std::io::Error::other)?;
+
+        self.done = (page.len() as i64) < EXPORT_PAGE_SIZE;
+        let Some(last) = page.last() else {
+            return Ok(None);
+        };
+        self.cursor = Some((last.detected_at(), last.id()));
+
+        self.resolve_profiles(&page).await?;
+
+        let mut csv = String::new();
+        for sighting in &page {
+            csv.push_str(&csv_row(
+                sighting,
+                self.profiles.get(&sighting.profile_id()),
+                self.camera_names
+                    .get(&sighting.camera_id())
+                    .map(String::as_str),
+            ));
+        }
+        Ok(Some(Bytes::from(csv)))
+    }
+
+    /// Loads the page's unseen profiles in one query.
+    async fn resolve_profiles(&mut self, sightings: &[Sighting]) -> Result<(), std::io::Error> {
+        let mut missing: Vec<Uuid> = sightings
+            .iter()
+            .map(|s| s.profile_id())
+            .filter(|id| !self.profiles.contains_key(id))
+            .collect();
+        missing.sort();
+        missing.dedup();
+        if missing.is_empty() {
+            return Ok(());
+        }
+
+        let profiles = self
+            .profile_repo
+            .find_by_ids(&missing)
+            .await
+            .map_err(std::io::Error::other)?;
+        self.profiles
+            .extend(profiles.into_iter().map(|p| (p.id(), p)));
+        Ok(())
+    }
+}
+
+/// Renders one sighting as a CSV line.
+fn csv_row(sighting: &Sighting, profile: Option<&Profile>, camera_name: Option<&str>) -> String {
+    let (lat, lon) = sighting
+        .location()
+        .map(|l| (l.latitude().to_string(), l.longitude().to_string()))
+        .unwrap_or_default();
+    let fields = [
+        sighting.detected_at().to_rfc3339(),
+        sighting.profile_id().to_string(),
+        formula_safe(profile.and_then(|p| p.name()).unwrap_or_default()),
+        profile
+            .map(|p| format!("{:?}", p.classification()).to_lowercase())
+            .unwrap_or_default(),
+        formula_safe(camera_name.unwrap_or_default()),
+        sighting.confidence().to_string(),
+        lat,
+        lon,
+        // Unsigned: an export outlives any signature, so the link takes the
+        // API token like the rest of the API
+        thumbnail_url(sighting.snapshot_path(), &FileUrls::default()),
+    ];
+
+    let mut line = fields
+        .iter()
+        .map(|f| csv_field(f))
+        .collect::<Vec<_>>()
+        .join(",");
+    line.push('\n');
+    line
+}
+
+/// Quotes a CSV field when it contains a delimiter, quote, or line break.
+fn csv_field(value: &str) -> std::borrow::Cow<'_, str> {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\"")).into()
+    } else {
+        value.into()
+    }
+}
+
+/// Prefixes user-entered text with `'` when it starts with `=`, `+`, `-` or
+/// `@`, so a spreadsheet opens it as text instead of running it as a formula.
+fn formula_safe(value: &str) -> String {
+    if value.starts_with(['=', '+', '-', '@']) {
+        format!("'{}", value)
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::entities::Detection;
+    use crate::domain::value_objects::{
+        BoundingBox, FaceEmbedding, GeoLocation, EMBEDDING_DIMENSION,
+    };
+    use crate::infrastructure::server::testing::{jpeg, TestApp};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn thumbnail_fits_requested_size_and_is_cached() {
+        let dir = tempfile::tempdir().unwrap();
+        let snapshot = dir.path().join("snap.jpg");
+        let cached = dir.path().join(THUMBS_DIR).join("snap.jpg").join("128.jpg");
+        image::RgbImage::from_pixel(640, 360, image::Rgb([90, 120, 200]))
+            .save(&snapshot)
+            .unwrap();
+
+        let jpeg = thumbnail(&snapshot, &cached, 128).await.unwrap().unwrap();
+
+        let thumb = image::load_from_memory_with_format(&jpeg, image::ImageFormat::Jpeg).unwrap();
+        assert!(thumb.width() <= 128 && thumb.height() <= 128);
+        assert_eq!(thumb.width(), 128);
+        assert_eq!(std::fs::read(&cached).unwrap(), jpeg);
+
+        // Later requests are served from the cache
+        std::fs::write(&cached, b"cached").unwrap();
+        assert_eq!(
+            thumbnail(&snapshot, &cached, 128).await.unwrap(),
+            Some(b"cached".to_vec())
+        );
+    }
+
+    #[tokio::test]
+    async fn detection_snapshot_is_served_as_a_thumbnail() {
+        let face = Detection::new(BoundingBox::new(200, 150, 160, 160), 0.99);
+        let app = TestApp::builder().detections(vec![face]).build().await;
+        let camera = app.browser_camera("browser-1").await;
+
+        // Only sampled frames reach the detector, so keep pushing
+        let sighting = tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                let pushed = app.push_jpeg(camera, jpeg(640, 480)).await;
+                assert_eq!(pushed.status(), StatusCode::ACCEPTED);
+                if let Some(sighting) = app.sightings.all().pop() {
+                    return sighting;
+                }
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("the pushed frame produces a sighting");
+
+        let response = app
+            .get(&format!(
+                "/api/v1/sightings/{}/thumbnail?size=64",
+                sighting.id()
+            ))
+            .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "image/jpeg");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let thumb = image::load_from_memory_with_format(&body, image::ImageFormat::Jpeg).unwrap();
+        assert_eq!(thumb.width(), 64);
+    }
+
+    #[tokio::test]
+    async fn thumbnail_of_missing_snapshot_is_none() {
+        let dir = tempfile::tempdir().unwrap();
+        let cached = dir.path().join("thumb.jpg");
+        std::fs::write(&cached, b"stale").unwrap();
+
+        let result = thumbnail(&dir.path().join("missing.jpg"), &cached, 128).await;
+        assert!(result.unwrap().is_none());
+    }
+
+    #[test]
+    fn in_area_query_rejects_inverted_or_out_of_range_boxes() {
+        let area = |min_lat, min_lon, max_lat, max_lon| InAreaQuery {
+            min_lat,
+            min_lon,
+            max_lat,
+            max_lon,
+            limit: None,
+        };
+
+        assert!(area(38.70, -9.15, 38.72, -9.10).is_valid());
+        assert!(area(-90.0, -180.0, 90.0, 180.0).is_valid());
+        assert!(!area(38.72, -9.15, 38.70, -9.10).is_valid());
+        assert!(!area(38.70, -9.10, 38.72, -9.15).is_valid());
+        assert!(!area(-91.0, 0.0, 0.0, 0.0).is_valid());
+        assert!(!area(0.0, 0.0, 0.0, 181.0).is_valid());
+        assert!(!area(f64::NAN, 0.0, 0.0, 0.0).is_valid());
+    }
+
+    #[test]
+    fn export_row_matches_header_columns() {
+        let mut profile = Profile::new(FaceEmbedding::new(vec![0.1; EMBEDDING_DIMENSION]), None);
+        profile.set_name(Some("Doe, Jane".to_string()));
+        let sighting = Sighting::new(
+            profile.id(),
+            Uuid::new_v4(),
+            "/data/snapshots/abc.jpg".to_string(),
+            BoundingBox::new(0, 0, 10, 10),
+            0.5,
+            Some(GeoLocation::new(51.5, -0.25)),
+        );
+
+        let csv = format!(
+            "{}{}",
+            CSV_HEADER,
+            csv_row(&sighting, Some(&profile), Some("Front Door"))
+        );
+        let lines: Vec<&str> = csv.lines().collect();
+
+        assert_eq!(
+            lines[0],
+            "detected_at,profile_id,profile_name,classification,camera_name,confidence,lat,lon,snapshot_url"
+        );
+        assert_eq!(
+            lines[1],
+            format!(
+                "{},{},\"Doe, Jane\",unknown,Front Door,0.5,51.5,-0.25,/files/snapshots/abc.jpg",
+                sighting.detected_at().to_rfc3339(),
+                profile.id()
+            )
+        );
+    }
+
+    #[test]
+    fn formula_like_names_are_exported_as_text() {
+        let mut profile = Profile::new(FaceEmbedding::new(vec![0.1; EMBEDDING_DIMENSION]), None);
+        profile.set_name(Some("=HYPERLINK(\"http://x\")".to_string()));
+        let sighting = Sighting::new(
+            profile.id(),
+            Uuid::new_v4(),
+            "abc.jpg".to_string(),
+            BoundingBox::new(0, 0, 10, 10),
+            0.5,
+            Some(GeoLocation::new(51.5, -0.25)),
+        );
+
+        let row = csv_row(&sighting, Some(&profile), Some("@cam"));
+
+        assert!(
+            row.contains(",\"'=HYPERLINK(\"\"http://x\"\")\","),
+            "{}",
+            row
+        );
+        assert!(row.contains(",'@cam,"), "{}", row);
+        // Numbers keep their sign
+        assert!(row.contains(",51.5,-0.25,"), "{}", row);
+    }
+
+    #[tokio::test]
+    async fn export_pages_through_rows_sharing_a_timestamp() {
+        let app = TestApp::new().await;
+        let at = Utc::now() - chrono::Duration::minutes(1);
+        let count = EXPORT_PAGE_SIZE as usize + 100;
+        for _ in 0..count {
+            let sighting = Sighting::from_db(
+                Uuid::new_v4(),
+                Uuid::new_v4(),
+                Uuid::new_v4(),
+                "snap.jpg".to_string(),
+                BoundingBox::new(0, 0, 10, 10),
+                0.9,
+                None,
+                None,
+                None,
+                None,
+                at,
+            );
+            app.sightings.save(&sighting).await.unwrap();
+        }
+
+        let response = app.get("/api/v1/sightings/export").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "text/csv; charset=utf-8"
+        );
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let csv = std::str::from_utf8(&body).unwrap();
+
+        let rows: Vec<&str> = csv.lines().skip(1).collect();
+        assert_eq!(rows.len(), count);
+        let profiles: std::collections::HashSet<&str> = rows
+            .iter()
+            .map(|row| row.split(',').nth(1).unwrap())
+            .collect();
+        assert_eq!(profiles.len(), count);
+    }
 }
@@ -2,30 +2,41 @@
 //!
 //! Shared state for the HTTP server.
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use sqlx::PgPool;
 use std::sync::Arc;
+use std::time::Duration;
 use tracing::info;
 
+use super::api::analytics::DashboardStats;
+use super::idempotency::IdempotencyStore;
+use super::signed_url::{FileUrls, UrlSigner};
 use super::websocket::WsBroadcaster;
 use crate::application::services::{
-    DetectionConfig, DetectionService, EventBus, FaceMatcher, RecordingConfig, RecordingService,
-    StorageConfig, StorageManager,
+    DetectionConfig, DetectionService, EventBus, FaceMatcher, Maintenance, ProfileClassifier,
+    ProfileRetention, RecentEvents, RecordingConfig, RecordingService, StorageConfig,
+    StorageManager, TtlCache, PURGE_INTERVAL,
 };
 use crate::application::use_cases::{
     ManageCamerasUseCase, ManageProfilesUseCase, ProcessFrameUseCase, QueryAnalyticsUseCase,
 };
+use crate::domain::events::DomainEvent;
 use crate::domain::repositories::{
     CameraRepository, ProfileRepository, RecordingRepository, SettingsRepository,
     SightingRepository,
 };
-use crate::infrastructure::camera::{CameraService, FaceDetector};
+use crate::domain::value_objects::set_embedding_dimension;
+use crate::infrastructure::camera::{CameraService, FaceDetector, PipelineConfig};
 use crate::infrastructure::config::AppConfig;
 use crate::infrastructure::database::{
     create_pool, run_migrations, PgCameraRepository, PgProfileRepository, PgRecordingRepository,
     PgSettingsRepository, PgSightingRepository,
 };
 
+/// How long a resumed camera gets to start delivering frames before it is
+/// marked as failed.
+const CAMERA_RESUME_TIMEOUT: Duration = Duration::from_secs(10);
+
 /// Application state shared across handlers.
 pub struct AppState {
     pub config: AppConfig,
@@ -33,11 +44,16 @@ pub struct AppState {
     pub event_bus: Arc<EventBus>,
     pub face_matcher: Arc<FaceMatcher>,
     pub detection_service: Arc<DetectionService>,
+    pub profile_classifier: Arc<ProfileClassifier>,
     pub recording_service: Arc<RecordingService>,
     pub storage_manager: Arc<StorageManager>,
     pub face_detector: Arc<FaceDetector>,
     pub ws_broadcaster: Arc<WsBroadcaster>,
+    pub recent_events: Arc<RecentEvents>,
     pub camera_service: Arc<CameraService>,
+    pub dashboard_cache: Arc<TtlCache<DashboardStats>>,
+    pub idempotency_keys: Arc<IdempotencyStore>,
+    pub file_urls: FileUrls,
 
     // Repositories
     pub profile_repo: Arc<dyn ProfileRepository>,
@@ -53,46 +69,148 @@ pub struct AppState {
     pub query_analytics: Arc<QueryAnalyticsUseCase>,
 }
 
+/// Signs `/files` URLs when the API requires a token and files aren't
+/// public, with `URL_SIGNING_KEY` or else a key derived from the token.
+fn file_urls(config: &AppConfig) -> FileUrls {
+    let token = config.api_token.as_deref().filter(|t| !t.is_empty());
+    let signer = token.filter(|_| !config.files_public).map(|token| {
+        let ttl = config.signed_url_ttl_secs;
+        Arc::new(match &config.url_signing_key {
+            Some(key) => UrlSigner::new(key.as_bytes(), ttl),
+            None => UrlSigner::from_token(token, ttl),
+        })
+    });
+    FileUrls::new(signer)
+}
+
+/// Repositories the state is built on.
+pub(crate) struct Repositories {
+    pub profile: Arc<dyn ProfileRepository>,
+    pub sighting: Arc<dyn SightingRepository>,
+    pub camera: Arc<dyn CameraRepository>,
+    pub recording: Arc<dyn RecordingRepository>,
+    pub settings: Arc<dyn SettingsRepository>,
+}
+
 impl AppState {
     /// Creates new application state.
     pub async fn new(config: &AppConfig) -> Result<Self> {
+        set_embedding_dimension(config.embedding_dimension);
+
         // Database
         let pool = create_pool(&config.database_url).await?;
         run_migrations(&pool).await?;
 
         // Repositories
-        let profile_repo: Arc<dyn ProfileRepository> =
-            Arc::new(PgProfileRepository::new(pool.clone()));
-        let sighting_repo: Arc<dyn SightingRepository> =
-            Arc::new(PgSightingRepository::new(pool.clone()));
-        let camera_repo: Arc<dyn CameraRepository> =
-            Arc::new(PgCameraRepository::new(pool.clone()));
-        let recording_repo: Arc<dyn RecordingRepository> =
-            Arc::new(PgRecordingRepository::new(pool.clone()));
-        let settings_repo: Arc<dyn SettingsRepository> =
-            Arc::new(PgSettingsRepository::new(pool.clone()));
+        let repos = Repositories {
+            profile: Arc::new(PgProfileRepository::new(pool.clone())),
+            sighting: Arc::new(PgSightingRepository::new(pool.clone())),
+            camera: Arc::new(PgCameraRepository::new(pool.clone())),
+            recording: Arc::new(PgRecordingRepository::new(pool.clone())),
+            settings: Arc::new(PgSettingsRepository::new(pool.clone())),
+        };
+
+        // Face detector
+        let face_detector = Arc::new(
+            FaceDetector::new((&config.detector).into())
+                .context("Failed to start face detector (set DETECTOR_MODEL_PATH)")?,
+        );
+
+        let state = Self::assemble(config, pool, repos, face_detector).await?;
+
+        // Reconcile recordings left in progress by an unclean shutdown
+        match state.recording_service.recover_interrupted().await {
+            Ok(0) => {}
+            Ok(count) => info!("Recovered {} interrupted recording(s)", count),
+            Err(e) => tracing::warn!("Failed to recover interrupted recordings: {}", e),
+        }
+
+        state.storage_manager.ensure_directories().await?;
+        state.storage_manager.spawn_purge_task(PURGE_INTERVAL);
+
+        // Stop quiet recordings and clean up storage even when no frames arrive
+        let mut maintenance = Maintenance::new(
+            state.recording_service.clone(),
+            state.storage_manager.clone(),
+        );
+        if config.unknown_retention_days > 0 {
+            maintenance = maintenance.with_profile_retention(ProfileRetention::new(
+                state.profile_repo.clone(),
+                state.face_matcher.clone(),
+                config.unknown_retention_max_sightings,
+                chrono::Duration::days(config.unknown_retention_days.into()),
+            ));
+        }
+        maintenance.spawn(Duration::from_secs(config.maintenance_interval_secs.max(1)));
+
+        // Opening cameras can take a while, so serving doesn't wait for it
+        tokio::spawn(start_cameras(
+            state.camera_service.clone(),
+            state.manage_cameras.clone(),
+        ));
+        Ok(state)
+    }
+
+    /// Wires services and use cases onto `repos`, without starting cameras
+    /// or background tasks.
+    pub(crate) async fn assemble(
+        config: &AppConfig,
+        pool: PgPool,
+        repos: Repositories,
+        face_detector: Arc<FaceDetector>,
+    ) -> Result<Self> {
+        let Repositories {
+            profile: profile_repo,
+            sighting: sighting_repo,
+            camera: camera_repo,
+            recording: recording_repo,
+            settings: settings_repo,
+        } = repos;
+
+        // Persisted settings, so restarts keep the operator's tuning
+        let settings = settings_repo.get().await?;
 
         // Event bus
         let event_bus = Arc::new(EventBus::new());
 
         // Face matcher
-        let face_matcher = Arc::new(FaceMatcher::new(profile_repo.clone(), 0.6));
+        let face_matcher = Arc::new(
+            FaceMatcher::new(profile_repo.clone(), settings.detection.match_threshold)
+                .with_cache_capacity(config.matcher_cache_capacity),
+        );
         face_matcher.load_cache().await?;
 
         // Services
-        let detection_service = Arc::new(DetectionService::new(
-            profile_repo.clone(),
+        let profile_classifier = Arc::new(ProfileClassifier::new(
             sighting_repo.clone(),
-            face_matcher.clone(),
-            event_bus.clone(),
-            DetectionConfig::default(),
+            settings.classification.rules.clone(),
         ));
 
+        let detection_service = Arc::new(
+            DetectionService::new(
+                profile_repo.clone(),
+                sighting_repo.clone(),
+                face_matcher.clone(),
+                event_bus.clone(),
+                DetectionConfig {
+                    snapshot_format: config.snapshot_format,
+                    thumbnail_margin: config.thumbnail_margin,
+                    track_iou_threshold: config.track_iou_threshold,
+                    track_lifetime_ms: config.track_lifetime_ms,
+                    ..Default::default()
+                },
+            )
+            .with_classifier(profile_classifier.clone()),
+        );
+        detection_service.apply_settings(&settings.detection).await;
+
         let recording_service = Arc::new(RecordingService::new(
             recording_repo.clone(),
             event_bus.clone(),
             RecordingConfig {
                 recordings_dir: config.recordings_dir(),
+                min_recording_duration_secs: settings.recording.min_recording_duration_secs,
+                camera_overrides: settings.recording.camera_overrides.clone(),
                 ..Default::default()
             },
         ));
@@ -101,16 +219,41 @@ impl AppState {
             recording_repo.clone(),
             StorageConfig {
                 base_dir: config.data_dir.clone(),
+                deletion_grace_secs: config.recording_deletion_grace_secs,
                 ..Default::default()
-            },
+            }
+            .with_settings(&settings.recording),
         ));
-        storage_manager.ensure_directories().await?;
+        // WebSocket broadcaster, fed with every domain event
+        let ws_broadcaster =
+            Arc::new(WsBroadcaster::new(1024).with_max_clients(config.ws_max_clients));
+        let ws_events = ws_broadcaster.clone();
+        event_bus.register_handler(move |event| ws_events.broadcast_domain_event((*event).clone()));
 
-        // Face detector
-        let face_detector = Arc::new(FaceDetector::new(Default::default())?);
+        // Recent events, for clients that poll instead of holding a WebSocket
+        let recent_events = Arc::new(RecentEvents::default());
+        let recorded_events = recent_events.clone();
+        event_bus.register_handler(move |event| {
+            recorded_events.push(event);
+        });
 
-        // WebSocket broadcaster
-        let ws_broadcaster = Arc::new(WsBroadcaster::new(1024));
+        // Dashboard stats are cached briefly and dropped when profiles,
+        // cameras or settings change. Sightings only age them out, so a busy
+        // camera doesn't defeat the cache.
+        let dashboard_cache = Arc::new(TtlCache::new(Duration::from_secs(
+            config.dashboard_cache_ttl_secs,
+        )));
+        let stale_dashboard = dashboard_cache.clone();
+        event_bus.register_handler(move |event| {
+            if matches!(
+                *event,
+                DomainEvent::ProfileCreated(_)
+                    | DomainEvent::CameraStatusChanged(_)
+                    | DomainEvent::SettingsChanged(_)
+            ) {
+                stale_dashboard.invalidate();
+            }
+        });
 
         // Use cases
         let process_frame = Arc::new(ProcessFrameUseCase::new(
@@ -119,11 +262,15 @@ impl AppState {
             storage_manager.clone(),
         ));
 
-        let manage_profiles = Arc::new(ManageProfilesUseCase::new(
-            profile_repo.clone(),
-            sighting_repo.clone(),
-            face_matcher.clone(),
-        ));
+        let manage_profiles = Arc::new(
+            ManageProfilesUseCase::new(
+                profile_repo.clone(),
+                sighting_repo.clone(),
+                face_matcher.clone(),
+            )
+            .with_classifier(profile_classifier.clone())
+            .with_event_bus(event_bus.clone()),
+        );
 
         let manage_cameras = Arc::new(ManageCamerasUseCase::new(camera_repo.clone()));
 
@@ -133,39 +280,49 @@ impl AppState {
             recording_repo.clone(),
         ));
 
-        // Camera service - manages capture and processing
-        let camera_service = Arc::new(CameraService::new(
-            face_detector.clone(),
-            process_frame.clone(),
-            camera_repo.clone(),
-        ));
+        // Frame pipeline tuning comes from persisted settings
+        let pipeline = PipelineConfig::from_settings(&settings.detection).unwrap_or_else(|e| {
+            tracing::warn!("Invalid pipeline settings: {}. Using defaults.", e);
+            PipelineConfig::default()
+        });
+        let pipeline = PipelineConfig {
+            frame_buffer: config.capture_frame_buffer,
+            permission_preflight: config.camera_permission_preflight,
+            pixel_format: config.capture_pixel_format,
+            detection_batch_size: config.detection_batch_size,
+            detection_batch_timeout: Duration::from_millis(config.detection_batch_timeout_ms),
+            ..pipeline
+        };
 
-        // Start built-in camera capture automatically
-        info!("Starting built-in camera capture...");
-        match camera_service.start_builtin_camera().await {
-            Ok(camera_id) => {
-                info!("Built-in camera started with ID: {}", camera_id);
-            }
-            Err(e) => {
-                // Log but don't fail - camera might not be available
-                tracing::warn!(
-                    "Failed to start built-in camera: {}. Camera features will be limited.",
-                    e
-                );
-            }
-        }
+        // Camera service - manages capture and processing
+        let camera_service = Arc::new(
+            CameraService::new(
+                face_detector.clone(),
+                process_frame.clone(),
+                camera_repo.clone(),
+                event_bus.clone(),
+            )
+            .with_pipeline_config(pipeline),
+        );
 
         Ok(Self {
+            file_urls: file_urls(config),
             config: config.clone(),
             pool,
             event_bus,
             face_matcher,
             detection_service,
+            profile_classifier,
             recording_service,
             storage_manager,
             face_detector,
             ws_broadcaster,
+            recent_events,
             camera_service,
+            dashboard_cache,
+            idempotency_keys: Arc::new(IdempotencyStore::new(Duration::from_secs(
+                config.idempotency_key_ttl_secs,
+            ))),
             profile_repo,
             sighting_repo,
             camera_repo,
@@ -178,3 +335,79 @@ impl AppState {
         })
     }
 }
+
+/// Starts the built-in camera and resumes the other enabled cameras.
+async fn start_cameras(
+    camera_service: Arc<CameraService>,
+    manage_cameras: Arc<ManageCamerasUseCase>,
+) {
+    // Start built-in camera capture automatically
+    info!("Starting built-in camera capture...");
+    match camera_service.start_builtin_camera().await {
+        Ok(Some(camera_id)) => {
+            info!("Built-in camera started with ID: {}", camera_id);
+        }
+        Ok(None) => {}
+        Err(e) => {
+            // Log but don't fail - camera might not be available
+            tracing::warn!(
+                "Failed to start built-in camera: {}. Camera features will be limited.",
+                e
+            );
+        }
+    }
+
+    // Resume the other enabled cameras that were running before a restart
+    let resumed = manage_cameras
+        .reconcile_on_startup(|camera| {
+            let camera_service = camera_service.clone();
+            async move {
+                camera_service
+                    .start_camera_and_wait(&camera, CAMERA_RESUME_TIMEOUT)
+                    .await
+            }
+        })
+        .await;
+    match resumed {
+        Ok(count) if count > 0 => info!("Resumed {} enabled camera(s)", count),
+        Ok(_) => {}
+        Err(e) => tracing::warn!("Failed to reconcile cameras: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::entities::Settings;
+    use crate::infrastructure::server::testing::TestApp;
+
+    #[tokio::test]
+    async fn matcher_starts_with_the_persisted_threshold() {
+        let mut settings = Settings::default();
+        settings.detection.match_threshold = 0.42;
+
+        let app = TestApp::builder().settings(settings).build().await;
+
+        assert_eq!(app.state.face_matcher.threshold().await, 0.42);
+    }
+
+    #[test]
+    fn file_urls_are_signed_only_when_files_are_protected() {
+        let mut config = AppConfig::default();
+        assert!(file_urls(&config).signer().is_none());
+
+        config.api_token = Some("secret".to_string());
+        let url = file_urls(&config).url("/files/snapshots/a.jpg".to_string());
+        let (path, query) = url.split_once('?').unwrap();
+        // A restart builds the same key from the token
+        let restarted = file_urls(&config);
+        assert!(restarted
+            .signer()
+            .unwrap()
+            .verify_url(path, Some(query))
+            .is_ok());
+
+        config.files_public = true;
+        assert!(file_urls(&config).signer().is_none());
+    }
+}
@@ -0,0 +1,233 @@
+//! API Authentication
+//!
+//! Bearer-token middleware guarding the REST API, WebSocket and stored files.
+
+use axum::{
+    extract::{Query, Request, State},
+    http::{header, StatusCode, Uri},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde::Deserialize;
+use std::sync::Arc;
+
+/// Shared secret clients must present. `None` disables authentication.
+#[derive(Debug, Clone, Default)]
+pub struct ApiAuth {
+    token: Option<Arc<str>>,
+}
+
+impl ApiAuth {
+    /// Creates an authenticator for the configured token.
+    pub fn new(token: Option<String>) -> Self {
+        Self {
+            token: token.filter(|t| !t.is_empty()).map(Into::into),
+        }
+    }
+
+    /// Whether requests must carry a token.
+    pub fn is_enabled(&self) -> bool {
+        self.token.is_some()
+    }
+
+    /// Whether `candidate` grants access.
+    pub(crate) fn accepts(&self, candidate: Option<&str>) -> bool {
+        match (&self.token, candidate) {
+            (None, _) => true,
+            (Some(expected), Some(candidate)) => {
+                constant_time_eq(expected.as_bytes(), candidate.as_bytes())
+            }
+            (Some(_), None) => false,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenQuery {
+    token: Option<String>,
+}
+
+/// Rejects requests without a valid `Authorization: Bearer` header.
+pub async fn require_token(State(auth): State<ApiAuth>, request: Request, next: Next) -> Response {
+    authorize(&auth, bearer_token(&request), request, next).await
+}
+
+/// Like [`require_token`], but also accepts a `token` query parameter.
+///
+/// Only for the WebSocket upgrade and media streams, since browsers cannot
+/// set headers there. Query strings end up in logs, so the rest of the API
+/// takes the header alone.
+pub async fn require_token_or_query(
+    State(auth): State<ApiAuth>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let token = bearer_token(&request).or_else(|| query_token(request.uri()));
+    authorize(&auth, token, request, next).await
+}
+
+async fn authorize(
+    auth: &ApiAuth,
+    token: Option<String>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if auth.accepts(token.as_deref()) {
+        next.run(request).await
+    } else {
+        (
+            StatusCode::UNAUTHORIZED,
+            [(header::WWW_AUTHENTICATE, "Bearer")],
+        )
+            .into_response()
+    }
+}
+
+/// Returns the token from the `Authorization: Bearer` header.
+pub(crate) fn bearer_token(request: &Request) -> Option<String> {
+    request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(|token| token.trim().to_string())
+}
+
+fn query_token(uri: &Uri) -> Option<String> {
+    Query::<TokenQuery>::try_from_uri(uri)
+        .ok()
+        .and_then(|Query(q)| q.token)
+}
+
+/// Renders `uri` for logging with the value of any `token` parameter masked.
+pub(crate) fn redacted_uri(uri: &Uri) -> String {
+    let Some(query) = uri.query() else {
+        return uri.to_string();
+    };
+    let query = query
+        .split('&')
+        .map(|pair| match pair.split_once('=') {
+            Some(("token", _)) => "token=redacted",
+            _ => pair,
+        })
+        .collect::<Vec<_>>()
+        .join("&");
+    format!("{}?{}", uri.path(), query)
+}
+
+/// Compares two byte strings without short-circuiting on the first mismatch.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, middleware, routing::get, Router};
+    use tower::ServiceExt;
+
+    fn app(auth: ApiAuth) -> Router {
+        Router::new()
+            .route("/protected", get(|| async { "ok" }))
+            .route_layer(middleware::from_fn_with_state(auth.clone(), require_token))
+            .merge(
+                Router::new()
+                    .route("/stream", get(|| async { "ok" }))
+                    .route_layer(middleware::from_fn_with_state(auth, require_token_or_query)),
+            )
+    }
+
+    async fn status(auth: ApiAuth, request: axum::http::Request<Body>) -> StatusCode {
+        app(auth).oneshot(request).await.unwrap().status()
+    }
+
+    fn request(uri: &str, bearer: Option<&str>) -> axum::http::Request<Body> {
+        let mut builder = axum::http::Request::builder().uri(uri);
+        if let Some(token) = bearer {
+            builder = builder.header(header::AUTHORIZATION, format!("Bearer {}", token));
+        }
+        builder.body(Body::empty()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn accepts_valid_bearer_token() {
+        let auth = ApiAuth::new(Some("secret".to_string()));
+        let status = status(auth, request("/protected", Some("secret"))).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn accepts_query_token_only_where_headers_cannot_be_set() {
+        let auth = ApiAuth::new(Some("secret".to_string()));
+        let stream = status(auth.clone(), request("/stream?token=secret", None)).await;
+        assert_eq!(stream, StatusCode::OK);
+
+        let api = status(auth, request("/protected?token=secret", None)).await;
+        assert_eq!(api, StatusCode::UNAUTHORIZED);
+    }
+
+    #[test]
+    fn redacts_token_from_logged_uri() {
+        let uri: Uri = "/ws?camera=1&token=secret&x=2".parse().unwrap();
+        assert_eq!(redacted_uri(&uri), "/ws?camera=1&token=redacted&x=2");
+
+        let uri: Uri = "/api/v1/cameras?limit=5".parse().unwrap();
+        assert_eq!(redacted_uri(&uri), "/api/v1/cameras?limit=5");
+    }
+
+    #[tokio::test]
+    async fn rejects_missing_token() {
+        let auth = ApiAuth::new(Some("secret".to_string()));
+        let status = status(auth, request("/protected", None)).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn rejects_invalid_token() {
+        let auth = ApiAuth::new(Some("secret".to_string()));
+        let status = status(auth, request("/protected", Some("guess"))).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn rejects_websocket_upgrade_without_token() {
+        use axum::extract::ws::WebSocketUpgrade;
+
+        let app = Router::new()
+            .route(
+                "/ws",
+                get(|ws: WebSocketUpgrade| async move { ws.on_upgrade(|_| async {}) }),
+            )
+            .route_layer(middleware::from_fn_with_state(
+                ApiAuth::new(Some("secret".to_string())),
+                require_token_or_query,
+            ));
+        let upgrade = |uri: &str| {
+            axum::http::Request::builder()
+                .uri(uri)
+                .header(header::CONNECTION, "upgrade")
+                .header(header::UPGRADE, "websocket")
+                .header(header::SEC_WEBSOCKET_VERSION, "13")
+                .header(header::SEC_WEBSOCKET_KEY, "dGhlIHNhbXBsZSBub25jZQ==")
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let rejected = app.clone().oneshot(upgrade("/ws")).await.unwrap();
+        assert_eq!(rejected.status(), StatusCode::UNAUTHORIZED);
+
+        let admitted = app.oneshot(upgrade("/ws?token=secret")).await.unwrap();
+        assert_ne!(admitted.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn allows_all_requests_when_disabled() {
+        let auth = ApiAuth::new(None);
+        assert!(!auth.is_enabled());
+        let status = status(auth, request("/protected", None)).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+}
@@ -0,0 +1,293 @@
+//! Idempotency Keys
+//!
+//! Lets clients retry a write safely: a request carrying an
+//! `Idempotency-Key` header runs once, and repeats of it within the TTL get
+//! the first response back instead of running again.
+
+use axum::{
+    body::{Body, Bytes},
+    extract::{Request, State},
+    http::{HeaderMap, HeaderName, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+/// Header carrying the client's idempotency key.
+pub const IDEMPOTENCY_KEY_HEADER: HeaderName = HeaderName::from_static("idempotency-key");
+
+/// Header set on responses replayed from an earlier request.
+pub const IDEMPOTENT_REPLAYED_HEADER: HeaderName = HeaderName::from_static("idempotent-replayed");
+
+/// Longest idempotency key accepted.
+const MAX_KEY_LEN: usize = 255;
+
+/// Largest response body kept for replay; larger ones aren't cached.
+const MAX_CACHED_BODY: usize = 1024 * 1024;
+
+/// A response kept for replay.
+#[derive(Debug, Clone)]
+struct CachedResponse {
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Bytes,
+}
+
+impl CachedResponse {
+    fn replay(&self) -> Response {
+        let mut response = Response::new(Body::from(self.body.clone()));
+        *response.status_mut() = self.status;
+        *response.headers_mut() = self.headers.clone();
+        response
+            .headers_mut()
+            .insert(IDEMPOTENT_REPLAYED_HEADER, HeaderValue::from_static("true"));
+        response
+    }
+}
+
+#[derive(Debug)]
+struct Entry {
+    stored_at: Instant,
+    /// `None` while the first request is still running.
+    response: Option<CachedResponse>,
+}
+
+/// Responses to requests that carried an idempotency key, kept in memory.
+#[derive(Debug)]
+pub struct IdempotencyStore {
+    ttl: Duration,
+    entries: Mutex<HashMap<String, Entry>>,
+}
+
+/// What to do with a keyed request.
+enum Lookup {
+    /// First time the key is seen; run the request.
+    Claimed,
+    Replay(CachedResponse),
+    InFlight,
+}
+
+impl IdempotencyStore {
+    /// Creates a store keeping responses for `ttl`.
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn lookup(&self, key: &str, now: Instant) -> Lookup {
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|_, entry| now.saturating_duration_since(entry.stored_at) < self.ttl);
+
+        match entries.get(key) {
+            Some(Entry {
+                response: Some(response),
+                ..
+            }) => Lookup::Replay(response.clone()),
+            Some(_) => Lookup::InFlight,
+            None => {
+                entries.insert(
+                    key.to_string(),
+                    Entry {
+                        stored_at: now,
+                        response: None,
+                    },
+                );
+                Lookup::Claimed
+            }
+        }
+    }
+
+    fn complete(&self, key: &str, response: CachedResponse, now: Instant) {
+        self.entries.lock().unwrap().insert(
+            key.to_string(),
+            Entry {
+                stored_at: now,
+                response: Some(response),
+            },
+        );
+    }
+
+    fn release(&self, key: &str) {
+        self.entries.lock().unwrap().remove(key);
+    }
+}
+
+/// Releases a claimed key unless its response was stored, so a request that
+/// failed or was cancelled can be retried.
+struct Claim<'a> {
+    store: &'a IdempotencyStore,
+    key: &'a str,
+    stored: bool,
+}
+
+impl Drop for Claim<'_> {
+    fn drop(&mut self) {
+        if !self.stored {
+            self.store.release(self.key);
+        }
+    }
+}
+
+/// Runs a request carrying an `Idempotency-Key` once, replaying its response
+/// to repeats. Requests without the header pass through.
+///
+/// Server errors aren't kept, so they can be retried. A repeat arriving
+/// while the first request is still running gets `409 Conflict`.
+pub async fn replay_idempotent(
+    State(store): State<Arc<IdempotencyStore>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(value) = request.headers().get(&IDEMPOTENCY_KEY_HEADER) else {
+        return next.run(request).await;
+    };
+    let key = match value.to_str() {
+        Ok(key) if !key.is_empty() && key.len() <= MAX_KEY_LEN => key,
+        _ => return StatusCode::BAD_REQUEST.into_response(),
+    };
+    // Keys are scoped to the endpoint, so one key can't replay another's response
+    let key = format!("{} {} {}", request.method(), request.uri().path(), key);
+
+    match store.lookup(&key, Instant::now()) {
+        Lookup::Replay(response) => return response.replay(),
+        Lookup::InFlight => return StatusCode::CONFLICT.into_response(),
+        Lookup::Claimed => {}
+    }
+    let mut claim = Claim {
+        store: &store,
+        key: &key,
+        stored: false,
+    };
+
+    let response = next.run(request).await;
+    if response.status().is_server_error() {
+        return response;
+    }
+
+    let (parts, body) = response.into_parts();
+    let body = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(body) => body,
+        Err(e) => {
+            warn!("Failed to read response for idempotency key: {}", e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+    if body.len() <= MAX_CACHED_BODY {
+        store.complete(
+            &key,
+            CachedResponse {
+                status: parts.status,
+                headers: parts.headers.clone(),
+                body: body.clone(),
+            },
+            Instant::now(),
+        );
+        claim.stored = true;
+    }
+    Response::from_parts(parts, Body::from(body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{middleware, routing::post, Json, Router};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tower::ServiceExt;
+
+    /// A create endpoint that counts what it created and answers with a new id.
+    fn router(store: Arc<IdempotencyStore>, created: Arc<AtomicUsize>) -> Router {
+        Router::new()
+            .route(
+                "/cameras",
+                post(move || {
+                    created.fetch_add(1, Ordering::SeqCst);
+                    async {
+                        (
+                            StatusCode::CREATED,
+                            Json(serde_json::json!({ "id": uuid::Uuid::new_v4() })),
+                        )
+                    }
+                }),
+            )
+            .route(
+                "/fail",
+                post(|| async { StatusCode::INTERNAL_SERVER_ERROR }),
+            )
+            .layer(middleware::from_fn_with_state(store, replay_idempotent))
+    }
+
+    async fn post_with_key(app: &Router, uri: &str, key: Option<&str>) -> (Response, Bytes) {
+        let mut builder = axum::http::Request::post(uri);
+        if let Some(key) = key {
+            builder = builder.header(&IDEMPOTENCY_KEY_HEADER, key);
+        }
+        let response = app
+            .clone()
+            .oneshot(builder.body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let (parts, body) = response.into_parts();
+        let body = axum::body::to_bytes(body, usize::MAX).await.unwrap();
+        (Response::from_parts(parts, Body::empty()), body)
+    }
+
+    #[tokio::test]
+    async fn repeated_key_creates_once_and_replays_the_response() {
+        let created = Arc::new(AtomicUsize::new(0));
+        let store = Arc::new(IdempotencyStore::new(Duration::from_secs(60)));
+        let app = router(store, created.clone());
+
+        let (first, first_body) = post_with_key(&app, "/cameras", Some("retry-1")).await;
+        let (second, second_body) = post_with_key(&app, "/cameras", Some("retry-1")).await;
+
+        assert_eq!(created.load(Ordering::SeqCst), 1);
+        assert_eq!(first.status(), StatusCode::CREATED);
+        assert_eq!(second.status(), StatusCode::CREATED);
+        assert_eq!(first_body, second_body);
+        assert!(first.headers().get(&IDEMPOTENT_REPLAYED_HEADER).is_none());
+        assert_eq!(second.headers()[&IDEMPOTENT_REPLAYED_HEADER], "true");
+        assert_eq!(
+            second.headers()["content-type"],
+            first.headers()["content-type"]
+        );
+
+        // Another key, or none, runs the request again
+        post_with_key(&app, "/cameras", Some("retry-2")).await;
+        post_with_key(&app, "/cameras", None).await;
+        post_with_key(&app, "/cameras", None).await;
+        assert_eq!(created.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn server_errors_and_expired_keys_are_not_replayed() {
+        let store = Arc::new(IdempotencyStore::new(Duration::from_secs(60)));
+        let app = router(store.clone(), Arc::new(AtomicUsize::new(0)));
+
+        post_with_key(&app, "/fail", Some("key")).await;
+        assert!(store.entries.lock().unwrap().is_empty());
+
+        let expired = Arc::new(IdempotencyStore::new(Duration::ZERO));
+        let created = Arc::new(AtomicUsize::new(0));
+        let app = router(expired, created.clone());
+        post_with_key(&app, "/cameras", Some("key")).await;
+        post_with_key(&app, "/cameras", Some("key")).await;
+        assert_eq!(created.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn repeat_of_a_running_request_conflicts() {
+        let store = IdempotencyStore::new(Duration::from_secs(60));
+        let now = Instant::now();
+
+        assert!(matches!(store.lookup("key", now), Lookup::Claimed));
+        assert!(matches!(store.lookup("key", now), Lookup::InFlight));
+
+        store.release("key");
+        assert!(matches!(store.lookup("key", now), Lookup::Claimed));
+    }
+}
@@ -4,19 +4,31 @@
 
 mod api;
 mod app_state;
+mod auth;
+mod idempotency;
+mod request_id;
+mod signed_url;
+#[cfg(test)]
+pub(crate) mod testing;
 mod websocket;
 
 pub use app_state::AppState;
+pub use websocket::DEFAULT_WS_MAX_CLIENTS;
 
 use anyhow::Result;
-use axum::{routing::get, Router};
+use axum::{http::HeaderValue, middleware, routing::get, Router};
+use std::path::Path;
 use std::sync::Arc;
-use tower_http::cors::{Any, CorsLayer};
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
+use tower_http::services::ServeDir;
 use tower_http::trace::TraceLayer;
-use tracing::info;
+use tracing::{info, warn};
 
 use crate::infrastructure::config::AppConfig;
 
+/// Route the storage base directory is served under.
+pub const FILES_ROUTE: &str = "/files";
+
 /// HTTP server for the Safelynx API.
 pub struct Server {
     config: AppConfig,
@@ -26,6 +38,7 @@ pub struct Server {
 impl Server {
     /// Creates a new server instance.
     pub async fn new(config: AppConfig) -> Result<Self> {
+        config.validate()?;
         let state = AppState::new(&config).await?;
 
         Ok(Self {
@@ -36,26 +49,7 @@ impl Server {
 
     /// Runs the server.
     pub async fn run(self) -> Result<()> {
-        let cors = CorsLayer::new()
-            .allow_origin(Any)
-            .allow_methods(Any)
-            .allow_headers(Any);
-
-        let app = Router::new()
-            // Health check
-            .route("/health", get(api::health::health_check))
-            // API routes
-            .nest("/api/v1", api::routes(self.state.clone()))
-            // WebSocket
-            .route("/ws", get(websocket::ws_handler))
-            // Static files for recordings/snapshots
-            .nest_service(
-                "/files",
-                tower_http::services::ServeDir::new(&self.config.data_dir),
-            )
-            .layer(cors)
-            .layer(TraceLayer::new_for_http())
-            .with_state(self.state);
+        let app = app(self.state).await;
 
         let addr = format!("{}:{}", self.config.host, self.config.port);
         let listener = tokio::net::TcpListener::bind(&addr).await?;
@@ -68,3 +62,275 @@ impl Server {
         Ok(())
     }
 }
+
+/// Builds the full application router: API, WebSocket and files, behind
+/// authentication, CORS and request tracing.
+async fn app(state: Arc<AppState>) -> Router {
+    let config = &state.config;
+    let cors = CorsLayer::new()
+        .allow_origin(allowed_origins(&config.cors_origin))
+        .allow_methods(Any)
+        .allow_headers(Any);
+
+    let auth = auth::ApiAuth::new(config.api_token.clone());
+    if !auth.is_enabled() {
+        warn!("API_TOKEN is not set; the API and WebSocket are unauthenticated");
+    } else {
+        if config.files_public {
+            warn!("FILES_PUBLIC is set; snapshots and recordings are served without a token");
+        }
+        if config.metrics_public {
+            warn!("METRICS_PUBLIC is set; /metrics is served without a token");
+        }
+    }
+    let require_token = middleware::from_fn_with_state(auth.clone(), auth::require_token);
+    // Browsers can't set headers on these, so they take `?token=` too
+    let require_token_or_query =
+        middleware::from_fn_with_state(auth.clone(), auth::require_token_or_query);
+
+    // Protected files are reached through signed URLs the API hands out
+    let files_access = state
+        .file_urls
+        .signer()
+        .map(|signer| signed_url::FilesAccess {
+            auth: auth.clone(),
+            signer: signer.clone(),
+        });
+
+    // Serve the directory snapshots and recordings are written to
+    let files = files_router(&state.storage_manager.base_dir().await, files_access);
+
+    // Prometheus metrics, which name cameras and their activity
+    let metrics = Router::new().route("/metrics", get(api::metrics::get_metrics));
+    let metrics = if config.metrics_public {
+        metrics
+    } else {
+        metrics.route_layer(require_token.clone())
+    };
+
+    Router::new()
+        // Health check
+        .route("/health", get(api::health::health_check))
+        .merge(metrics)
+        // API routes
+        .nest(
+            "/api/v1",
+            api::routes(state.clone())
+                .route_layer(require_token)
+                .merge(api::media_routes().route_layer(require_token_or_query.clone())),
+        )
+        // WebSocket
+        .route(
+            "/ws",
+            get(websocket::ws_handler).route_layer(require_token_or_query),
+        )
+        // Static files for recordings/snapshots
+        .merge(files)
+        .layer(cors)
+        .layer(TraceLayer::new_for_http().make_span_with(
+            |request: &axum::http::Request<axum::body::Body>| {
+                tracing::debug_span!(
+                    "request",
+                    method = %request.method(),
+                    uri = %auth::redacted_uri(request.uri()),
+                    version = ?request.version(),
+                )
+            },
+        ))
+        .layer(middleware::from_fn(request_id::propagate_request_id))
+        .with_state(state.clone())
+}
+
+/// Serves files from the storage base directory.
+fn files_service(base_dir: &Path) -> ServeDir {
+    ServeDir::new(base_dir)
+}
+
+/// Mounts [`files_service`] at [`FILES_ROUTE`], requiring a signed URL or
+/// the API token when `access` is given.
+fn files_router<S: Clone + Send + Sync + 'static>(
+    base_dir: &Path,
+    access: Option<signed_url::FilesAccess>,
+) -> Router<S> {
+    let router = Router::new().nest_service(FILES_ROUTE, files_service(base_dir));
+    match access {
+        Some(access) => router.layer(middleware::from_fn_with_state(
+            access,
+            signed_url::require_signature_or_token,
+        )),
+        None => router,
+    }
+}
+
+/// Parses the comma-separated `CORS_ORIGIN` list; `*` allows any origin.
+fn allowed_origins(config: &str) -> AllowOrigin {
+    let origins: Vec<&str> = config
+        .split(',')
+        .map(str::trim)
+        .filter(|o| !o.is_empty())
+        .collect();
+    if origins.contains(&"*") {
+        return AllowOrigin::any();
+    }
+
+    let origins: Vec<HeaderValue> = origins
+        .into_iter()
+        .filter_map(|origin| match HeaderValue::from_str(origin) {
+            Ok(value) => Some(value),
+            Err(_) => {
+                warn!("Ignoring invalid CORS origin {:?}", origin);
+                None
+            }
+        })
+        .collect();
+    AllowOrigin::list(origins)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::application::services::{StorageConfig, StorageManager};
+    use crate::domain::repositories::fakes::InMemoryRecordingRepo;
+    use crate::infrastructure::server::api::profiles::thumbnail_url;
+    use crate::infrastructure::server::testing::TestApp;
+    use axum::body::{to_bytes, Body};
+    use axum::http::{Request, StatusCode};
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn written_snapshot_is_served_at_its_url() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = StorageManager::new(
+            Arc::new(InMemoryRecordingRepo::default()),
+            StorageConfig {
+                base_dir: dir.path().to_path_buf(),
+                ..Default::default()
+            },
+        );
+        storage.ensure_directories().await.unwrap();
+
+        let written = storage.snapshots_dir().await.join("thumb_test.jpg");
+        std::fs::write(&written, b"snapshot bytes").unwrap();
+
+        let url = thumbnail_url(&written.to_string_lossy(), &signed_url::FileUrls::default());
+        let app: Router = files_router(&storage.base_dir().await, None);
+        let response = app
+            .oneshot(Request::builder().uri(&url).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(&body[..], b"snapshot bytes");
+    }
+
+    #[tokio::test]
+    async fn files_require_a_signed_url_or_the_token_when_auth_is_enabled() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("snapshots")).unwrap();
+        std::fs::write(dir.path().join("snapshots/thumb.jpg"), b"snapshot bytes").unwrap();
+        let signer = Arc::new(signed_url::UrlSigner::new(b"key", 60));
+        let access = signed_url::FilesAccess {
+            auth: auth::ApiAuth::new(Some("secret".to_string())),
+            signer: signer.clone(),
+        };
+
+        let status = |uri: &str, access: Option<signed_url::FilesAccess>| {
+            let app: Router = files_router(dir.path(), access);
+            let request = Request::builder().uri(uri).body(Body::empty()).unwrap();
+            async move { app.oneshot(request).await.unwrap().status() }
+        };
+        let with_bearer = |uri: &str, access: signed_url::FilesAccess| {
+            let app: Router = files_router(dir.path(), Some(access));
+            let request = Request::builder()
+                .uri(uri)
+                .header("authorization", "Bearer secret")
+                .body(Body::empty())
+                .unwrap();
+            async move { app.oneshot(request).await.unwrap().status() }
+        };
+
+        let path = "/files/snapshots/thumb.jpg";
+        assert_eq!(
+            status(path, Some(access.clone())).await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            status("/files/snapshots/missing.jpg", Some(access.clone())).await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            status(&format!("{}?token=secret", path), Some(access.clone())).await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(with_bearer(path, access.clone()).await, StatusCode::OK);
+        assert_eq!(
+            status(&signer.sign_url(path), Some(access.clone())).await,
+            StatusCode::OK
+        );
+
+        // A signature for one file doesn't open another
+        let signed = signer.sign_url(path);
+        let other = signed.replace("thumb.jpg", "other.jpg");
+        assert_eq!(status(&other, Some(access)).await, StatusCode::FORBIDDEN);
+
+        // FILES_PUBLIC serves them without either
+        assert_eq!(status(path, None).await, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn metrics_require_the_token_unless_public() {
+        let with_token = |public: bool| {
+            TestApp::builder()
+                .config(move |config| {
+                    config.api_token = Some("secret".to_string());
+                    config.metrics_public = public;
+                })
+                .build()
+        };
+
+        let app = with_token(false).await;
+        assert_eq!(app.get("/metrics").await.status(), StatusCode::UNAUTHORIZED);
+        let authorized = Request::get("/metrics")
+            .header("authorization", "Bearer secret")
+            .body(Body::empty())
+            .unwrap();
+        assert_eq!(app.send(authorized).await.status(), StatusCode::OK);
+
+        let app = with_token(true).await;
+        assert_eq!(app.get("/metrics").await.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn cors_allows_only_configured_origins() {
+        let allowed_origin = |config: &str, origin: &'static str| {
+            let app: Router = Router::new()
+                .route("/", get(|| async { "ok" }))
+                .layer(CorsLayer::new().allow_origin(allowed_origins(config)));
+            let request = Request::builder()
+                .uri("/")
+                .header("origin", origin)
+                .body(Body::empty())
+                .unwrap();
+            async move {
+                app.oneshot(request)
+                    .await
+                    .unwrap()
+                    .headers()
+                    .get("access-control-allow-origin")
+                    .cloned()
+            }
+        };
+
+        let config = "http://a.test, http://b.test";
+        assert_eq!(
+            allowed_origin(config, "http://b.test").await,
+            Some(HeaderValue::from_static("http://b.test"))
+        );
+        assert_eq!(allowed_origin(config, "http://evil.test").await, None);
+        assert_eq!(
+            allowed_origin("*", "http://evil.test").await,
+            Some(HeaderValue::from_static("*"))
+        );
+    }
+}
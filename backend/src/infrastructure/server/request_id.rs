@@ -0,0 +1,106 @@
+//! Request IDs
+//!
+//! Tags each request with an `x-request-id` so its log lines can be
+//! correlated, and echoes the id on the response, including error responses.
+
+use axum::{
+    extract::Request,
+    http::{HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use tracing::{info_span, Instrument};
+use uuid::Uuid;
+
+use crate::infrastructure::server::auth::redacted_uri;
+
+/// Header carrying the request id.
+pub const REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Longest client-supplied id that is propagated rather than replaced.
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// Id of the current request, available to handlers as an extension.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestId(pub HeaderValue);
+
+/// Reuses the client's `x-request-id`, or generates one, and runs the rest of
+/// the stack inside a span carrying it.
+pub async fn propagate_request_id(mut request: Request, next: Next) -> Response {
+    let id = request
+        .headers()
+        .get(&REQUEST_ID_HEADER)
+        .filter(|value| is_valid_id(value))
+        .cloned()
+        .unwrap_or_else(generate_id);
+
+    let span = info_span!(
+        "request",
+        request_id = %String::from_utf8_lossy(id.as_bytes()),
+        method = %request.method(),
+        uri = %redacted_uri(request.uri()),
+    );
+
+    request.extensions_mut().insert(RequestId(id.clone()));
+    let mut response = next.run(request).instrument(span).await;
+    response.headers_mut().insert(REQUEST_ID_HEADER, id);
+    response
+}
+
+fn is_valid_id(value: &HeaderValue) -> bool {
+    !value.is_empty()
+        && value.len() <= MAX_REQUEST_ID_LEN
+        && value
+            .to_str()
+            .is_ok_and(|s| s.bytes().all(|b| b.is_ascii_graphic()))
+}
+
+fn generate_id() -> HeaderValue {
+    HeaderValue::from_str(&Uuid::new_v4().to_string()).expect("UUIDs are valid header values")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, http::StatusCode, middleware, routing::get, Router};
+    use tower::ServiceExt;
+
+    fn app() -> Router {
+        Router::new()
+            .route("/ok", get(|| async { "ok" }))
+            .route("/fail", get(|| async { StatusCode::INTERNAL_SERVER_ERROR }))
+            .layer(middleware::from_fn(propagate_request_id))
+    }
+
+    async fn response_id(uri: &str, id: Option<&str>) -> Option<HeaderValue> {
+        let mut builder = axum::http::Request::builder().uri(uri);
+        if let Some(id) = id {
+            builder = builder.header(&REQUEST_ID_HEADER, id);
+        }
+        let response = app()
+            .oneshot(builder.body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        response.headers().get(&REQUEST_ID_HEADER).cloned()
+    }
+
+    #[tokio::test]
+    async fn echoes_provided_request_id() {
+        let id = response_id("/ok", Some("abc-123")).await.unwrap();
+        assert_eq!(id, "abc-123");
+
+        // Error responses carry it too
+        let id = response_id("/fail", Some("abc-456")).await.unwrap();
+        assert_eq!(id, "abc-456");
+    }
+
+    #[tokio::test]
+    async fn generates_request_id_when_absent_or_invalid() {
+        let id = response_id("/ok", None).await.unwrap();
+        assert!(Uuid::parse_str(id.to_str().unwrap()).is_ok());
+
+        let too_long = "x".repeat(MAX_REQUEST_ID_LEN + 1);
+        let id = response_id("/ok", Some(&too_long)).await.unwrap();
+        assert!(Uuid::parse_str(id.to_str().unwrap()).is_ok());
+    }
+}
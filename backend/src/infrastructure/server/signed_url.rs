@@ -0,0 +1,228 @@
+//! Signed File URLs
+//!
+//! HMAC-signed, expiring links to snapshots and recordings, so `<img>` and
+//! `<video>` tags can load them from `/files` without sending the API token.
+
+use axum::{
+    extract::{Request, State},
+    http::{header, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD as BASE64, Engine as _};
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::sync::Arc;
+use thiserror::Error;
+
+use crate::infrastructure::server::auth::{self, ApiAuth};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Builds the `/files` URLs the API returns, signed when `/files` is
+/// protected.
+#[derive(Clone, Default)]
+pub struct FileUrls {
+    signer: Option<Arc<UrlSigner>>,
+}
+
+impl FileUrls {
+    /// URLs signed by `signer`, or left unsigned when `/files` is open.
+    pub fn new(signer: Option<Arc<UrlSigner>>) -> Self {
+        Self { signer }
+    }
+
+    /// The signer `/files` requests are verified against, if any.
+    pub fn signer(&self) -> Option<&Arc<UrlSigner>> {
+        self.signer.as_ref()
+    }
+
+    /// Returns `path` signed, or unchanged when `/files` is open.
+    pub fn url(&self, path: String) -> String {
+        match &self.signer {
+            Some(signer) => signer.sign_url(&path),
+            None => path,
+        }
+    }
+}
+
+/// Why a signed URL was rejected.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum SignedUrlError {
+    #[error("URL is not signed")]
+    Missing,
+    #[error("URL signature has expired")]
+    Expired,
+    #[error("URL signature is invalid")]
+    Invalid,
+}
+
+/// Signs and verifies file URLs with a secret key.
+pub struct UrlSigner {
+    key: Vec<u8>,
+    ttl_secs: i64,
+}
+
+impl UrlSigner {
+    /// Creates a signer whose URLs stay valid for `ttl_secs`.
+    pub fn new(key: &[u8], ttl_secs: u64) -> Self {
+        Self {
+            key: key.to_vec(),
+            ttl_secs: i64::try_from(ttl_secs).unwrap_or(i64::MAX),
+        }
+    }
+
+    /// Creates a signer keyed from the API token, so URLs survive restarts
+    /// and stop working when the token changes.
+    pub fn from_token(token: &str, ttl_secs: u64) -> Self {
+        let mut mac =
+            HmacSha256::new_from_slice(token.as_bytes()).expect("HMAC accepts any key length");
+        mac.update(b"safelynx file urls");
+        Self::new(&mac.finalize().into_bytes(), ttl_secs)
+    }
+
+    /// Appends an expiry and signature to `path`.
+    pub fn sign_url(&self, path: &str) -> String {
+        let expires = Utc::now().timestamp().saturating_add(self.ttl_secs);
+        self.sign_url_until(path, expires)
+    }
+
+    fn sign_url_until(&self, path: &str, expires: i64) -> String {
+        format!(
+            "{}?expires={}&sig={}",
+            path,
+            expires,
+            BASE64.encode(self.mac(path, expires).finalize().into_bytes())
+        )
+    }
+
+    /// Checks that `query` carries an unexpired signature for `path`.
+    pub fn verify_url(&self, path: &str, query: Option<&str>) -> Result<(), SignedUrlError> {
+        let (mut expires, mut sig) = (None, None);
+        for (key, value) in form_urlencoded::parse(query.unwrap_or("").as_bytes()) {
+            match key.as_ref() {
+                "expires" => expires = Some(value.into_owned()),
+                "sig" => sig = Some(value.into_owned()),
+                _ => {}
+            }
+        }
+        let (Some(expires), Some(sig)) = (expires, sig) else {
+            return Err(SignedUrlError::Missing);
+        };
+
+        let expires: i64 = expires.parse().map_err(|_| SignedUrlError::Invalid)?;
+        let sig = BASE64.decode(sig).map_err(|_| SignedUrlError::Invalid)?;
+        self.mac(path, expires)
+            .verify_slice(&sig)
+            .map_err(|_| SignedUrlError::Invalid)?;
+
+        if expires < Utc::now().timestamp() {
+            return Err(SignedUrlError::Expired);
+        }
+        Ok(())
+    }
+
+    fn mac(&self, path: &str, expires: i64) -> HmacSha256 {
+        let mut mac = HmacSha256::new_from_slice(&self.key).expect("HMAC accepts any key length");
+        mac.update(path.as_bytes());
+        mac.update(b"\n");
+        mac.update(expires.to_string().as_bytes());
+        mac
+    }
+}
+
+/// What grants access to `/files`.
+#[derive(Clone)]
+pub struct FilesAccess {
+    pub auth: ApiAuth,
+    pub signer: Arc<UrlSigner>,
+}
+
+/// Serves requests carrying a valid signed URL or the API token as a bearer
+/// header.
+pub async fn require_signature_or_token(
+    State(access): State<FilesAccess>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let uri = request.uri();
+    let signature = access.signer.verify_url(uri.path(), uri.query());
+    if signature.is_ok() || access.auth.accepts(auth::bearer_token(&request).as_deref()) {
+        return next.run(request).await;
+    }
+
+    let status = match signature {
+        Err(SignedUrlError::Missing) => StatusCode::UNAUTHORIZED,
+        _ => StatusCode::FORBIDDEN,
+    };
+    (status, [(header::WWW_AUTHENTICATE, "Bearer")]).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PATH: &str = "/files/snapshots/thumb_a.jpg";
+
+    fn query(url: &str) -> Option<&str> {
+        url.split_once('?').map(|(_, query)| query)
+    }
+
+    #[test]
+    fn valid_signature_is_accepted() {
+        let signer = UrlSigner::new(b"key", 60);
+        let url = signer.sign_url(PATH);
+
+        assert!(url.starts_with(PATH));
+        assert_eq!(signer.verify_url(PATH, query(&url)), Ok(()));
+    }
+
+    #[test]
+    fn expired_signature_is_rejected() {
+        let signer = UrlSigner::new(b"key", 60);
+        let url = signer.sign_url_until(PATH, Utc::now().timestamp() - 1);
+
+        assert_eq!(
+            signer.verify_url(PATH, query(&url)),
+            Err(SignedUrlError::Expired)
+        );
+    }
+
+    #[test]
+    fn tampered_url_is_rejected() {
+        let signer = UrlSigner::new(b"key", 60);
+        let url = signer.sign_url(PATH);
+
+        // Another file, a pushed-back expiry, or another key
+        assert_eq!(
+            signer.verify_url("/files/snapshots/thumb_b.jpg", query(&url)),
+            Err(SignedUrlError::Invalid)
+        );
+        let extended = url.replace("expires=", "expires=9");
+        assert_eq!(
+            signer.verify_url(PATH, query(&extended)),
+            Err(SignedUrlError::Invalid)
+        );
+        assert_eq!(
+            UrlSigner::new(b"other", 60).verify_url(PATH, query(&url)),
+            Err(SignedUrlError::Invalid)
+        );
+        assert_eq!(signer.verify_url(PATH, None), Err(SignedUrlError::Missing));
+    }
+
+    #[test]
+    fn token_derived_key_outlives_a_restart() {
+        let url = UrlSigner::from_token("secret", 60).sign_url(PATH);
+
+        // A new signer from the same token, as after a restart
+        let restarted = UrlSigner::from_token("secret", 60);
+        assert_eq!(restarted.verify_url(PATH, query(&url)), Ok(()));
+
+        let rotated = UrlSigner::from_token("rotated", 60);
+        assert_eq!(
+            rotated.verify_url(PATH, query(&url)),
+            Err(SignedUrlError::Invalid)
+        );
+    }
+}
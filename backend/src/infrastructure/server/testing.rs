@@ -0,0 +1,193 @@
+//! Test Application
+//!
+//! The full router over in-memory repositories and a stub face detector, so
+//! handlers can be tested through their routes without Postgres or the model.
+
+use axum::body::{to_bytes, Body};
+use axum::http::{header, Method, Request, StatusCode};
+use axum::response::Response;
+use axum::Router;
+use serde_json::Value;
+use sqlx::postgres::PgPoolOptions;
+use std::future::IntoFuture;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tempfile::TempDir;
+use tokio::net::TcpListener;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+use super::app_state::Repositories;
+use super::AppState;
+use crate::application::services::SnapshotFormat;
+use crate::domain::entities::{Detection, Settings};
+use crate::domain::repositories::fakes::{
+    InMemoryCameraRepo, InMemoryProfileRepo, InMemoryRecordingRepo, InMemorySettingsRepo,
+    InMemorySightingRepo,
+};
+use crate::infrastructure::camera::FaceDetector;
+use crate::infrastructure::config::AppConfig;
+
+/// Builds a [`TestApp`].
+#[derive(Default)]
+pub(crate) struct TestAppBuilder {
+    config: AppConfig,
+    settings: Settings,
+    detections: Vec<Detection>,
+}
+
+impl TestAppBuilder {
+    /// Adjusts the configuration the state is built from.
+    pub fn config(mut self, configure: impl FnOnce(&mut AppConfig)) -> Self {
+        configure(&mut self.config);
+        self
+    }
+
+    /// Faces the stub detector reports in every frame.
+    pub fn detections(mut self, detections: Vec<Detection>) -> Self {
+        self.detections = detections;
+        self
+    }
+
+    /// Seeds the settings repository.
+    pub fn settings(mut self, settings: Settings) -> Self {
+        self.settings = settings;
+        self
+    }
+
+    pub async fn build(self) -> TestApp {
+        let data_dir = tempfile::tempdir().unwrap();
+        let mut config = self.config;
+        config.data_dir = data_dir.path().to_path_buf();
+
+        let profiles = Arc::new(InMemoryProfileRepo::default());
+        let sightings = Arc::new(InMemorySightingRepo::default());
+        let cameras = Arc::new(InMemoryCameraRepo::default());
+        let recordings = Arc::new(InMemoryRecordingRepo::default());
+        let settings = Arc::new(InMemorySettingsRepo::new(self.settings));
+
+        // Nothing under test touches the pool; it never connects
+        let pool = PgPoolOptions::new()
+            .connect_lazy(&config.database_url)
+            .unwrap();
+        let state = AppState::assemble(
+            &config,
+            pool,
+            Repositories {
+                profile: profiles.clone(),
+                sighting: sightings.clone(),
+                camera: cameras.clone(),
+                recording: recordings.clone(),
+                settings: settings.clone(),
+            },
+            Arc::new(FaceDetector::stub(self.detections)),
+        )
+        .await
+        .unwrap();
+        state.storage_manager.ensure_directories().await.unwrap();
+        let state = Arc::new(state);
+
+        TestApp {
+            router: super::app(state.clone()).await,
+            state,
+            sightings,
+            _data_dir: data_dir,
+        }
+    }
+}
+
+/// The application router and the repositories behind it.
+pub(crate) struct TestApp {
+    pub router: Router,
+    pub state: Arc<AppState>,
+    pub sightings: Arc<InMemorySightingRepo>,
+    _data_dir: TempDir,
+}
+
+impl TestApp {
+    pub fn builder() -> TestAppBuilder {
+        TestAppBuilder::default()
+    }
+
+    /// An app with default configuration and settings and no faces.
+    pub async fn new() -> Self {
+        Self::builder().build().await
+    }
+
+    /// Sends `request` through the router.
+    pub async fn send(&self, request: Request<Body>) -> Response {
+        self.router.clone().oneshot(request).await.unwrap()
+    }
+
+    /// Serves the router on a local port, for clients that need a real
+    /// connection, such as WebSocket upgrades.
+    pub async fn serve(&self) -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(axum::serve(listener, self.router.clone()).into_future());
+        addr
+    }
+
+    pub async fn get(&self, uri: &str) -> Response {
+        self.send(Request::get(uri).body(Body::empty()).unwrap())
+            .await
+    }
+
+    /// Sends `body` as JSON.
+    pub async fn json(&self, method: Method, uri: &str, body: Value) -> Response {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        self.send(request).await
+    }
+}
+
+impl TestApp {
+    /// Creates a browser camera through the API and returns its id.
+    pub async fn browser_camera(&self, device_id: &str) -> Uuid {
+        let created = self
+            .json(
+                Method::POST,
+                "/api/v1/cameras",
+                serde_json::json!({
+                    "name": device_id,
+                    "camera_type": "browser",
+                    "device_id": device_id,
+                }),
+            )
+            .await;
+        assert_eq!(created.status(), StatusCode::CREATED);
+        body_json(created).await["id"]
+            .as_str()
+            .unwrap()
+            .parse()
+            .unwrap()
+    }
+
+    /// Pushes `jpeg` as the next frame of a browser camera, starting its
+    /// capture on the first push.
+    pub async fn push_jpeg(&self, camera_id: Uuid, jpeg: Vec<u8>) -> Response {
+        let request = Request::post(format!("/api/v1/cameras/{}/frames", camera_id))
+            .header(header::CONTENT_TYPE, "image/jpeg")
+            .body(Body::from(jpeg))
+            .unwrap();
+        self.send(request).await
+    }
+}
+
+/// Encodes a flat grey JPEG frame.
+pub(crate) fn jpeg(width: u32, height: u32) -> Vec<u8> {
+    let frame = image::RgbImage::from_pixel(width, height, image::Rgb([200, 200, 200]));
+    SnapshotFormat::default()
+        .encode(&image::DynamicImage::ImageRgb8(frame))
+        .unwrap()
+}
+
+/// Reads a response body as JSON.
+pub(crate) async fn body_json(response: Response) -> Value {
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    serde_json::from_slice(&body).unwrap()
+}
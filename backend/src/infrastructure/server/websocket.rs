@@ -7,16 +7,21 @@ use axum::{
         ws::{Message, WebSocket, WebSocketUpgrade},
         State,
     },
-    response::Response,
+    http::StatusCode,
+    response::{IntoResponse, Response},
 };
 use chrono::{DateTime, Utc};
 use futures_util::{SinkExt, StreamExt};
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tokio::sync::broadcast;
 use uuid::Uuid;
 
+use crate::domain::entities::ProfileClassification;
 use crate::domain::events::DomainEvent;
+use crate::domain::value_objects::BoundingBox;
 use crate::infrastructure::server::AppState;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -27,6 +32,7 @@ pub enum WsMessage {
     NewSighting(SightingPayload),
     NewProfile(ProfilePayload),
     ProfileUpdated(ProfilePayload),
+    ProfileClassificationChanged(ClassificationChangedPayload),
     CameraStatusChanged(CameraStatusPayload),
     RecordingStarted(RecordingPayload),
     RecordingStopped(RecordingPayload),
@@ -47,6 +53,7 @@ pub struct FaceDetectedPayload {
     pub timestamp: DateTime<Utc>,
 }
 
+/// Face box as fractions of the frame size, so clients can draw it at any resolution.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BoundingBoxPayload {
     pub x: f32,
@@ -55,6 +62,19 @@ pub struct BoundingBoxPayload {
     pub height: f32,
 }
 
+impl BoundingBoxPayload {
+    /// Normalizes a pixel bounding box against its frame size.
+    pub fn normalized(bbox: &BoundingBox, frame_width: u32, frame_height: u32) -> Self {
+        let (x, y, width, height) = bbox.normalized(frame_width, frame_height);
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SightingPayload {
     pub id: Uuid,
@@ -76,12 +96,22 @@ pub struct ProfilePayload {
     pub sightings_count: i32,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClassificationChangedPayload {
+    pub profile_id: Uuid,
+    pub previous: ProfileClassification,
+    pub classification: ProfileClassification,
+    pub timestamp: DateTime<Utc>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CameraStatusPayload {
     pub camera_id: Uuid,
     pub camera_name: String,
     pub status: String,
     pub streaming: bool,
+    #[serde(default)]
+    pub message: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -101,14 +131,90 @@ pub struct StorageWarningPayload {
     pub message: String,
 }
 
+/// Default limit on concurrently connected WebSocket clients.
+pub const DEFAULT_WS_MAX_CLIENTS: usize = 64;
+
+/// Fans messages out to connected WebSocket clients.
+///
+/// Broadcasting never waits on clients. A client that falls more than the
+/// channel capacity behind skips the oldest messages and stays connected;
+/// the skipped messages are counted in [`WsBroadcaster::lagged_count`].
 pub struct WsBroadcaster {
     tx: broadcast::Sender<WsMessage>,
+    lagged: AtomicU64,
+    clients: Arc<AtomicUsize>,
+    max_clients: usize,
+    rejected: AtomicU64,
 }
 
 impl WsBroadcaster {
     pub fn new(capacity: usize) -> Self {
         let (tx, _) = broadcast::channel(capacity);
-        Self { tx }
+        Self {
+            tx,
+            lagged: AtomicU64::new(0),
+            clients: Arc::new(AtomicUsize::new(0)),
+            max_clients: DEFAULT_WS_MAX_CLIENTS,
+            rejected: AtomicU64::new(0),
+        }
+    }
+
+    /// Sets how many clients may be connected at once.
+    pub fn with_max_clients(mut self, max_clients: usize) -> Self {
+        self.max_clients = max_clients.max(1);
+        self
+    }
+
+    /// Claims a place for a new client, or returns `None` if the limit is
+    /// reached. The place is freed when the returned slot is dropped.
+    pub fn try_connect(&self) -> Option<ClientSlot> {
+        let max_clients = self.max_clients;
+        let admitted = self
+            .clients
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |count| {
+                (count < max_clients).then_some(count + 1)
+            })
+            .is_ok();
+
+        if admitted {
+            Some(ClientSlot {
+                clients: self.clients.clone(),
+            })
+        } else {
+            self.rejected.fetch_add(1, Ordering::Relaxed);
+            None
+        }
+    }
+
+    /// Returns the number of connected clients.
+    pub fn client_count(&self) -> usize {
+        self.clients.load(Ordering::Acquire)
+    }
+
+    /// Returns the number of clients turned away by the client limit.
+    pub fn rejected_count(&self) -> u64 {
+        self.rejected.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of messages skipped by clients that fell behind.
+    pub fn lagged_count(&self) -> u64 {
+        self.lagged.load(Ordering::Relaxed)
+    }
+
+    /// Receives the next message for a client, skipping past any it missed.
+    ///
+    /// Returns `None` only once the broadcaster is gone.
+    pub async fn recv(&self, rx: &mut broadcast::Receiver<WsMessage>) -> Option<WsMessage> {
+        loop {
+            match rx.recv().await {
+                Ok(msg) => return Some(msg),
+                Err(broadcast::error::RecvError::Closed) => return None,
+                Err(broadcast::error::RecvError::Lagged(count)) => {
+                    tracing::warn!("WebSocket client lagged by {} messages", count);
+                    self.lagged.fetch_add(count, Ordering::Relaxed);
+                }
+            }
+        }
     }
 
     pub fn subscribe(&self) -> broadcast::Receiver<WsMessage> {
@@ -128,12 +234,11 @@ impl WsBroadcaster {
                     profile_id: e.profile_id,
                     profile_name: e.profile_name,
                     confidence: e.confidence,
-                    bounding_box: BoundingBoxPayload {
-                        x: e.bounding_box.x() as f32,
-                        y: e.bounding_box.y() as f32,
-                        width: e.bounding_box.width() as f32,
-                        height: e.bounding_box.height() as f32,
-                    },
+                    bounding_box: BoundingBoxPayload::normalized(
+                        &e.bounding_box,
+                        e.frame_width,
+                        e.frame_height,
+                    ),
                     timestamp: e.timestamp,
                 }));
             }
@@ -158,12 +263,32 @@ impl WsBroadcaster {
                     sightings_count: 1,
                 }));
             }
+            DomainEvent::ProfileUpdated(e) => {
+                self.broadcast(WsMessage::ProfileUpdated(ProfilePayload {
+                    id: e.profile_id,
+                    name: e.name,
+                    classification: format!("{:?}", e.classification).to_lowercase(),
+                    tags: e.tags,
+                    sightings_count: i32::try_from(e.sighting_count).unwrap_or(i32::MAX),
+                }));
+            }
+            DomainEvent::ProfileClassificationChanged(e) => {
+                self.broadcast(WsMessage::ProfileClassificationChanged(
+                    ClassificationChangedPayload {
+                        profile_id: e.profile_id,
+                        previous: e.previous,
+                        classification: e.classification,
+                        timestamp: e.timestamp,
+                    },
+                ));
+            }
             DomainEvent::CameraStatusChanged(e) => {
                 self.broadcast(WsMessage::CameraStatusChanged(CameraStatusPayload {
                     camera_id: e.camera_id,
                     camera_name: e.camera_name,
                     status: e.status.clone(),
                     streaming: e.status == "streaming",
+                    message: e.message,
                 }));
             }
             DomainEvent::RecordingStarted(e) => {
@@ -189,12 +314,61 @@ impl WsBroadcaster {
     }
 }
 
-/// WebSocket upgrade handler
+/// A connected client's place under the client limit, freed on drop.
+pub struct ClientSlot {
+    clients: Arc<AtomicUsize>,
+}
+
+impl Drop for ClientSlot {
+    fn drop(&mut self) {
+        self.clients.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// Tracks whether a client is still answering pings.
+#[derive(Debug, Clone, Copy)]
+pub struct Heartbeat {
+    interval: Duration,
+    timeout: Duration,
+    last_seen: Instant,
+}
+
+impl Heartbeat {
+    /// Starts tracking a client that was last heard from at `now`.
+    pub fn new(interval: Duration, timeout: Duration, now: Instant) -> Self {
+        Self {
+            interval,
+            timeout,
+            last_seen: now,
+        }
+    }
+
+    /// Records a pong, or any other frame, from the client.
+    pub fn record_pong(&mut self, now: Instant) {
+        self.last_seen = now;
+    }
+
+    /// Whether the client missed the pong deadline of the last ping.
+    pub fn is_expired(&self, now: Instant) -> bool {
+        now.saturating_duration_since(self.last_seen) > self.interval + self.timeout
+    }
+}
+
+/// WebSocket upgrade handler. Once `WS_MAX_CLIENTS` clients are connected,
+/// further upgrades get `429 Too Many Requests`.
 pub async fn ws_handler(ws: WebSocketUpgrade, State(state): State<Arc<AppState>>) -> Response {
-    ws.on_upgrade(move |socket| handle_socket(socket, state))
+    let Some(slot) = state.ws_broadcaster.try_connect() else {
+        tracing::warn!(
+            "Rejecting WebSocket client: {} clients already connected",
+            state.ws_broadcaster.client_count()
+        );
+        return StatusCode::TOO_MANY_REQUESTS.into_response();
+    };
+    ws.on_upgrade(move |socket| handle_socket(socket, state, slot))
 }
 
-async fn handle_socket(socket: WebSocket, state: Arc<AppState>) {
+/// Serves a connected client, holding its `slot` until it disconnects.
+async fn handle_socket(socket: WebSocket, state: Arc<AppState>, slot: ClientSlot) {
     let (mut sender, mut receiver) = socket.split();
 
     let client_id = Uuid::new_v4().to_string();
@@ -206,13 +380,42 @@ async fn handle_socket(socket: WebSocket, state: Arc<AppState>) {
         let _ = sender.send(Message::Text(json.into())).await;
     }
 
-    let mut rx = state.ws_broadcaster.subscribe();
+    let broadcaster = state.ws_broadcaster.clone();
+    let mut rx = broadcaster.subscribe();
+
+    let period = Duration::from_secs(state.config.ws_ping_interval_secs.max(1));
+    let heartbeat = Arc::new(Mutex::new(Heartbeat::new(
+        period,
+        Duration::from_secs(state.config.ws_pong_timeout_secs),
+        Instant::now(),
+    )));
 
+    let send_heartbeat = heartbeat.clone();
+    let send_client_id = client_id.clone();
     let mut send_task = tokio::spawn(async move {
-        while let Ok(msg) = rx.recv().await {
-            if let Ok(json) = serde_json::to_string(&msg) {
-                if sender.send(Message::Text(json.into())).await.is_err() {
-                    break;
+        let mut ticker = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+        loop {
+            tokio::select! {
+                msg = broadcaster.recv(&mut rx) => {
+                    let Some(msg) = msg else { break };
+                    if let Ok(json) = serde_json::to_string(&msg) {
+                        if sender.send(Message::Text(json.into())).await.is_err() {
+                            break;
+                        }
+                    }
+                }
+                _ = ticker.tick() => {
+                    if send_heartbeat.lock().unwrap().is_expired(Instant::now()) {
+                        tracing::warn!(
+                            "WebSocket client {} missed its pong deadline; closing",
+                            send_client_id
+                        );
+                        let _ = sender.send(Message::Close(None)).await;
+                        break;
+                    }
+                    if sender.send(Message::Ping(Vec::new())).await.is_err() {
+                        break;
+                    }
                 }
             }
         }
@@ -220,18 +423,11 @@ async fn handle_socket(socket: WebSocket, state: Arc<AppState>) {
 
     let mut recv_task = tokio::spawn(async move {
         while let Some(Ok(msg)) = receiver.next().await {
-            match msg {
-                Message::Text(text) => {
-                    if let Ok(ws_msg) = serde_json::from_str::<WsMessage>(&text) {
-                        match ws_msg {
-                            WsMessage::Ping => {}
-                            _ => {}
-                        }
-                    }
-                }
-                Message::Close(_) => break,
-                _ => {}
+            if let Message::Close(_) = msg {
+                break;
             }
+            // Pongs, and any other frame, show the client is still there
+            heartbeat.lock().unwrap().record_pong(Instant::now());
         }
     });
 
@@ -240,5 +436,137 @@ async fn handle_socket(socket: WebSocket, state: Arc<AppState>) {
         _ = &mut recv_task => send_task.abort(),
     };
 
+    drop(slot);
     tracing::info!("WebSocket client {} disconnected", client_id);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::events::ProfileClassificationChangedEvent;
+    use crate::infrastructure::server::testing::TestApp;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpStream;
+
+    fn heartbeat(now: Instant) -> Heartbeat {
+        Heartbeat::new(Duration::from_secs(30), Duration::from_secs(10), now)
+    }
+
+    #[test]
+    fn heartbeat_is_alive_within_deadline() {
+        let start = Instant::now();
+        let hb = heartbeat(start);
+        assert!(!hb.is_expired(start + Duration::from_secs(40)));
+    }
+
+    #[test]
+    fn heartbeat_expires_without_pong() {
+        let start = Instant::now();
+        let hb = heartbeat(start);
+        assert!(hb.is_expired(start + Duration::from_secs(41)));
+    }
+
+    #[test]
+    fn pong_extends_deadline() {
+        let start = Instant::now();
+        let mut hb = heartbeat(start);
+        hb.record_pong(start + Duration::from_secs(30));
+        assert!(!hb.is_expired(start + Duration::from_secs(65)));
+        assert!(hb.is_expired(start + Duration::from_secs(71)));
+    }
+
+    #[tokio::test]
+    async fn lagging_client_skips_ahead_instead_of_disconnecting() {
+        let broadcaster = WsBroadcaster::new(4);
+        let mut rx = broadcaster.subscribe();
+
+        for i in 0..10 {
+            broadcaster.broadcast(WsMessage::Connected {
+                client_id: i.to_string(),
+            });
+        }
+
+        let Some(WsMessage::Connected { client_id }) = broadcaster.recv(&mut rx).await else {
+            panic!("expected a message after lagging");
+        };
+        assert_eq!(client_id, "6");
+        assert_eq!(broadcaster.lagged_count(), 6);
+    }
+
+    #[tokio::test]
+    async fn classification_changes_are_forwarded_to_clients() {
+        let broadcaster = WsBroadcaster::new(4);
+        let mut rx = broadcaster.subscribe();
+        let profile_id = Uuid::new_v4();
+
+        broadcaster.broadcast_domain_event(DomainEvent::ProfileClassificationChanged(
+            ProfileClassificationChangedEvent {
+                profile_id,
+                previous: ProfileClassification::Unknown,
+                classification: ProfileClassification::Flagged,
+                timestamp: Utc::now(),
+            },
+        ));
+
+        let Some(WsMessage::ProfileClassificationChanged(payload)) =
+            broadcaster.recv(&mut rx).await
+        else {
+            panic!("expected a classification change");
+        };
+        assert_eq!(payload.profile_id, profile_id);
+        assert_eq!(payload.classification, ProfileClassification::Flagged);
+    }
+
+    #[test]
+    fn clients_over_the_limit_are_rejected_until_one_leaves() {
+        let broadcaster = WsBroadcaster::new(4).with_max_clients(2);
+
+        let first = broadcaster.try_connect().expect("first client admitted");
+        let _second = broadcaster.try_connect().expect("second client admitted");
+        assert!(broadcaster.try_connect().is_none());
+        assert_eq!(broadcaster.client_count(), 2);
+        assert_eq!(broadcaster.rejected_count(), 1);
+
+        drop(first);
+        assert_eq!(broadcaster.client_count(), 1);
+        assert!(broadcaster.try_connect().is_some());
+    }
+
+    /// Opens a WebSocket upgrade to `path` and returns the connection with
+    /// the response status.
+    async fn upgrade(addr: std::net::SocketAddr, path: &str) -> (TcpStream, u16) {
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        let request = format!(
+            "GET {} HTTP/1.1\r\nHost: {}\r\nConnection: Upgrade\r\nUpgrade: websocket\r\n\
+             Sec-WebSocket-Version: 13\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\r\n",
+            path, addr
+        );
+        stream.write_all(request.as_bytes()).await.unwrap();
+
+        let mut head = [0; 12];
+        stream.read_exact(&mut head).await.unwrap();
+        let status = std::str::from_utf8(&head[9..12]).unwrap().parse().unwrap();
+        (stream, status)
+    }
+
+    #[tokio::test]
+    async fn upgrade_requires_the_token_and_a_free_slot() {
+        let app = TestApp::builder()
+            .config(|config| {
+                config.api_token = Some("secret".to_string());
+                config.ws_max_clients = 1;
+            })
+            .build()
+            .await;
+        let addr = app.serve().await;
+
+        let (_, status) = upgrade(addr, "/ws").await;
+        assert_eq!(status, 401);
+
+        let (_connected, status) = upgrade(addr, "/ws?token=secret").await;
+        assert_eq!(status, 101);
+
+        let (_, status) = upgrade(addr, "/ws?token=secret").await;
+        assert_eq!(status, 429);
+    }
+}
@@ -14,6 +14,15 @@ export interface ApiResponse<T> {
   ok: boolean
 }
 
+/** One page of a paginated list endpoint. */
+export interface Page<T> {
+  items: T[]
+  total: number
+  limit: number
+  offset: number
+  has_more: boolean
+}
+
 export interface ApiError {
   message: string
   status: number
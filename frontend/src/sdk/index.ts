@@ -23,7 +23,7 @@
 
 // Import modules
 import { httpClient, HttpClient, SafelynxApiError } from './client'
-import type { ApiResponse, ApiError, RequestConfig } from './client'
+import type { ApiResponse, ApiError, Page, RequestConfig } from './client'
 
 import { profilesSdk } from './profiles'
 import type { 
@@ -64,7 +64,7 @@ import type { HealthResponse } from './health'
 
 // Re-export everything
 export { httpClient, HttpClient, SafelynxApiError }
-export type { ApiResponse, ApiError, RequestConfig }
+export type { ApiResponse, ApiError, Page, RequestConfig }
 export { profilesSdk }
 export type { ProfileListResponse, ProfileResponse, ProfileStatsResponse, UpdateProfileRequest }
 export { camerasSdk }
@@ -5,13 +5,11 @@
  * Matches Rust backend: ProfileResponse, ProfileListResponse
  */
 
-import { httpClient, type ApiResponse } from './client'
+import { httpClient, type ApiResponse, type Page } from './client'
 import type { Profile, Sighting } from '@/types'
 
 // Backend response types (matching Rust structs)
-export interface ProfileListResponse {
-  profiles: ProfileResponse[]
-  total: number
+export interface ProfileListResponse extends Page<ProfileResponse> {
   stats: ProfileStatsResponse
 }
 
@@ -92,7 +90,7 @@ export const profilesSdk = {
     })
     return {
       ...response,
-      data: response.data.profiles.map(toProfile),
+      data: response.data.items.map(toProfile),
     }
   },
 
@@ -137,9 +135,13 @@ export const profilesSdk = {
   /**
    * Get sightings for a profile
    */
-  async getSightings(id: string, limit = 50): Promise<ApiResponse<Sighting[]>> {
-    return httpClient.get<Sighting[]>(`/profiles/${id}/sightings`, {
-      params: { limit },
+  async getSightings(id: string, limit = 50, offset = 0): Promise<ApiResponse<Sighting[]>> {
+    const response = await httpClient.get<Page<Sighting>>(`/profiles/${id}/sightings`, {
+      params: { limit, offset },
     })
+    return {
+      ...response,
+      data: response.data.items,
+    }
   },
 }
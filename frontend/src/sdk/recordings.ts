@@ -5,7 +5,7 @@
  * Matches Rust backend: Recording, RecordingStatus
  */
 
-import { httpClient, type ApiResponse } from './client'
+import { httpClient, type ApiResponse, type Page } from './client'
 import type { Recording } from '@/types'
 
 const API_BASE = import.meta.env.VITE_API_URL || 'http://localhost:7889'
@@ -25,9 +25,13 @@ export const recordingsSdk = {
    * List recordings with optional filters
    */
   async list(query?: RecordingQuery): Promise<ApiResponse<Recording[]>> {
-    return httpClient.get<Recording[]>('/recordings', {
+    const response = await httpClient.get<Page<Recording>>('/recordings', {
       params: query as Record<string, string | number | boolean | undefined>,
     })
+    return {
+      ...response,
+      data: response.data.items,
+    }
   },
 
   /**
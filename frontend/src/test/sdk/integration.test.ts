@@ -191,7 +191,9 @@ describe('SDK Integration', () => {
         },
       ]
 
-      mockFetch.mockResolvedValueOnce(mockResponse(mockRecordings))
+      mockFetch.mockResolvedValueOnce(
+        mockResponse({ items: mockRecordings, total: 1, limit: 100, offset: 0, has_more: false })
+      )
 
       const result = await sdk.recordings.list()
 
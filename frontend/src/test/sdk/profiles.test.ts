@@ -38,7 +38,7 @@ describe('profilesSdk', () => {
   describe('list', () => {
     it('should fetch profiles with pagination', async () => {
       const mockProfiles = {
-        profiles: [
+        items: [
           {
             id: '123',
             name: 'John Doe',
@@ -54,6 +54,9 @@ describe('profilesSdk', () => {
           },
         ],
         total: 1,
+        limit: 100,
+        offset: 0,
+        has_more: false,
         stats: {
           total: 1,
           trusted: 0,
@@ -81,8 +84,11 @@ describe('profilesSdk', () => {
   describe('listWithStats', () => {
     it('should return profiles with stats', async () => {
       const mockData = {
-        profiles: [],
+        items: [],
         total: 0,
+        limit: 100,
+        offset: 0,
+        has_more: false,
         stats: {
           total: 100,
           trusted: 20,
@@ -202,7 +208,9 @@ describe('profilesSdk', () => {
         },
       ]
 
-      mockFetch.mockResolvedValueOnce(mockResponse(mockSightings))
+      mockFetch.mockResolvedValueOnce(
+        mockResponse({ items: mockSightings, total: 1, limit: 50, offset: 0, has_more: false })
+      )
 
       const result = await profilesSdk.getSightings('123', 50)
 
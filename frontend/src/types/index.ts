@@ -10,6 +10,7 @@ export interface Profile {
   first_seen_at: string
   last_seen_at: string
   is_active: boolean
+  has_embedding: boolean
 }
 
 export interface Camera {
@@ -44,6 +45,7 @@ export interface Sighting {
   profile_id: string
   camera_id: string
   confidence: number
+  match_confidence: number | null
   bounding_box: BoundingBox
   snapshot_path: string | null
   detected_at: string